@@ -6,6 +6,18 @@ fn main() {
     println!("cargo:rerun-if-changed=weston/ivi-shell/ivi-layout-export.h");
     println!("cargo:rerun-if-changed=weston/libweston/plugin-registry.h");
 
+    // Default socket path, shared with ivi-client. Packagers can change it
+    // with WESTON_IVI_DEFAULT_SOCKET_PATH at build time
+    println!("cargo:rerun-if-env-changed=WESTON_IVI_DEFAULT_SOCKET_PATH");
+    let default_socket_path = env::var("WESTON_IVI_DEFAULT_SOCKET_PATH")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| "/tmp/weston-ivi-controller.sock".to_string());
+    println!(
+        "cargo:rustc-env=WESTON_IVI_DEFAULT_SOCKET_PATH={}",
+        default_socket_path
+    );
+
     let out_path = PathBuf::from(env::var("OUT_DIR").unwrap());
     let bindings_path = out_path.join("ivi_bindings.rs");
     let weston_bindings_path = out_path.join("weston_bindings.rs");
@@ -34,6 +46,13 @@ fn main() {
         .generate()
         .unwrap();
 
+    // surface_set_orientation was dropped from the IVI layout interface of
+    // newer Weston releases, so only bind it when the headers declare it
+    println!("cargo:rustc-check-cfg=cfg(ivi_surface_set_orientation)");
+    if bindings_ivi.to_string().contains("surface_set_orientation") {
+        println!("cargo:rustc-cfg=ivi_surface_set_orientation");
+    }
+
     // Handle IVI bindings
     bindings_ivi
         .write_to_file(&bindings_path)
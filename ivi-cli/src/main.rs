@@ -5,11 +5,40 @@
 
 mod output;
 
-use clap::{ArgAction, Parser, Subcommand};
-use ivi_client::{IviClient, IviError, Result};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use ivi_client::{
+    EventType, IviClient, IviError, NotificationListener, Result, SceneSnapshot, WireDirection,
+    WireLogger, SCENE_SNAPSHOT_VERSION,
+};
 #[allow(unused_imports)]
 use jlogger_tracing::{jdebug, jerror, jinfo, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat};
+use output::OutputStyle;
 use std::result::Result as StdResult;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Exit code for a successful command
+const EXIT_SUCCESS: i32 = 0;
+/// Exit code for errors not covered by a more specific code
+const EXIT_FAILURE: i32 = 1;
+/// Exit code when the surface or layer does not exist
+const EXIT_NOT_FOUND: i32 = 2;
+/// Exit code when the arguments were rejected
+const EXIT_INVALID_PARAMS: i32 = 3;
+/// Exit code when the controller could not be reached
+const EXIT_CONNECTION_FAILED: i32 = 4;
+/// Exit code when the controller did not answer in time
+const EXIT_TIMEOUT: i32 = 5;
+
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Success
+  1  Other error
+  2  Surface or layer not found
+  3  Invalid parameters or usage
+  4  Connection to the controller failed
+  5  Timed out waiting for the controller";
 
 /// Command-line interface for Weston IVI Controller
 #[derive(Parser)]
@@ -17,6 +46,7 @@ use std::result::Result as StdResult;
 #[command(version = "0.1.0")]
 #[command(about = "Command-line interface for Weston IVI Controller", long_about = None)]
 #[command(author = "Weston IVI Controller Project")]
+#[command(after_help = EXIT_CODES_HELP)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
@@ -29,9 +59,30 @@ struct Cli {
     #[arg(long, default_value = "/tmp/ivi_cli.log")]
     log_file: String,
 
-    /// Verbosity level (can be used multiple times for increased verbosity)
+    /// Verbosity level (can be used multiple times for increased verbosity).
+    /// Also prints the JSON-RPC requests (>>) and responses (<<) to stderr
     #[arg(short = 'v', long = "verbose", action = ArgAction::Count)]
     pub verbose: u8,
+
+    /// Prefix messages with OK:/ERROR: instead of ✓/✗.
+    /// This is the default when stdout is not a terminal
+    #[arg(long, visible_alias = "plain", default_value_t = false)]
+    no_color: bool,
+
+    /// Milliseconds to wait for each answer from the controller before
+    /// failing, 0 to wait forever
+    #[arg(long, value_name = "MS", default_value_t = 5000)]
+    timeout: u64,
+
+    /// Only check changes with the controller instead of applying them.
+    /// Commands that read state run as usual
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Output format of commands that read state, and of the `apply` report.
+    /// Messages of commands that change state stay human-readable
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Pretty)]
+    format: OutputFormat,
 }
 
 /// Available commands
@@ -52,10 +103,77 @@ enum Commands {
         #[command(subcommand)]
         command: ScreenCommands,
     },
-    /// Display complete scene hierarchy
-    Scene,
+    /// Display complete scene hierarchy, or dump/load a scene snapshot
+    Scene {
+        #[command(subcommand)]
+        command: Option<SceneCommands>,
+    },
     /// Commit pending changes atomically
     Commit,
+    /// Print notifications as they arrive
+    Watch {
+        /// Comma-separated list of event types to watch (default: all)
+        #[arg(long, value_delimiter = ',')]
+        events: Vec<String>,
+        /// Exit after receiving this many notifications
+        #[arg(long)]
+        count: Option<usize>,
+        /// Exit after this many milliseconds, whether or not `--count` was reached
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+    /// Run the commands of a file, one per line (`-` reads standard input)
+    ///
+    /// Each line holds a command as given to ivi_cli, e.g. `surface set-opacity 1000 0.5`.
+    /// Blank lines and lines starting with `#` are ignored.
+    Apply {
+        /// File with one command per line
+        file: String,
+        /// Run the remaining lines after a failure and exit with success.
+        /// By default, apply stops at the first failing line
+        #[arg(long, default_value_t = false)]
+        continue_on_error: bool,
+    },
+    /// Show a one-line summary of the controller state
+    Status,
+    /// Measure the round-trip latency of `ping` requests
+    Bench {
+        /// Number of pings to send
+        #[arg(long, default_value_t = 100)]
+        count: usize,
+    },
+}
+
+/// Output format selected with `--format`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Text meant for people; for `apply`, the output of each command
+    #[value(alias = "text")]
+    Pretty,
+    /// JSON meant for scripts; for `apply`, a report of each line and a summary
+    Json,
+}
+
+/// A single line of an `apply` file, parsed as an ivi_cli command
+#[derive(Parser)]
+#[command(name = "apply", no_binary_name = true)]
+struct ApplyLine {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// How often `watch` checks whether the controller closed the connection
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Event types watched when `watch` is given no `--events`
+///
+/// `RepaintComplete` fires on every frame and must be asked for explicitly.
+fn default_event_types() -> Vec<EventType> {
+    EventType::ALL
+        .iter()
+        .filter(|event_type| **event_type != EventType::RepaintComplete)
+        .cloned()
+        .collect()
 }
 
 /// Surface management commands
@@ -72,18 +190,20 @@ enum SurfaceCommands {
         /// Surface ID
         id: u32,
     },
-    /// Set surface visibility
+    /// Set the visibility of one or more surfaces
     SetVisibility {
-        /// Surface ID
-        id: u32,
+        /// Surface IDs
+        #[arg(required = true, num_args = 1..)]
+        ids: Vec<u32>,
         /// Visibility (true or false)
         #[arg(action = clap::ArgAction::Set)]
         visible: bool,
     },
-    /// Set surface opacity
+    /// Set the opacity of one or more surfaces
     SetOpacity {
-        /// Surface ID
-        id: u32,
+        /// Surface IDs
+        #[arg(required = true, num_args = 1..)]
+        ids: Vec<u32>,
         /// Opacity value (0.0 to 1.0)
         opacity: f32,
     },
@@ -120,11 +240,36 @@ enum SurfaceCommands {
         /// Z-order value
         z_order: i32,
     },
+    /// Move a surface by an offset and print its new position
+    Nudge {
+        /// Surface ID
+        id: u32,
+        /// Horizontal offset in pixels (e.g. 10 or -10)
+        #[arg(allow_hyphen_values = true)]
+        dx: i32,
+        /// Vertical offset in pixels (e.g. 10 or -10)
+        #[arg(allow_hyphen_values = true)]
+        dy: i32,
+    },
+    /// Move a surface to the top of every layer it is on
+    ToFront {
+        /// Surface ID
+        id: u32,
+    },
+    /// Move a surface to the bottom of every layer it is on
+    ToBack {
+        /// Surface ID
+        id: u32,
+    },
     /// Set surface focus
     SetFocus {
         /// Surface ID
         id: u32,
     },
+    /// Show the currently focused surface
+    GetFocus,
+    /// Clear surface focus
+    ClearFocus,
 }
 
 /// Layer management commands
@@ -181,6 +326,17 @@ enum LayerCommands {
         /// Height in pixels
         height: i32,
     },
+    /// Move a layer by an offset and print its new position
+    Nudge {
+        /// Layer ID
+        id: u32,
+        /// Horizontal offset in pixels (e.g. 10 or -10)
+        #[arg(allow_hyphen_values = true)]
+        dx: i32,
+        /// Vertical offset in pixels (e.g. 10 or -10)
+        #[arg(allow_hyphen_values = true)]
+        dy: i32,
+    },
     /// Set layer visibility
     SetVisibility {
         /// Layer ID
@@ -188,6 +344,9 @@ enum LayerCommands {
         /// Visibility (true or false)
         #[arg(action = clap::ArgAction::Set)]
         visible: bool,
+        /// Stage the change without committing it
+        #[arg(long)]
+        no_commit: bool,
     },
     /// Set layer opacity
     SetOpacity {
@@ -195,6 +354,20 @@ enum LayerCommands {
         id: u32,
         /// Opacity value (0.0 to 1.0)
         opacity: f32,
+        /// Stage the change without committing it
+        #[arg(long)]
+        no_commit: bool,
+    },
+    /// Adjust layer opacity relative to its current value
+    AdjustOpacity {
+        /// Layer ID
+        id: u32,
+        /// Amount to add to the current opacity (e.g. 0.1 or -0.25)
+        #[arg(allow_hyphen_values = true)]
+        delta: f32,
+        /// Stage the change without committing it
+        #[arg(long)]
+        no_commit: bool,
     },
     /// Set surfaces on a layer (replaces all existing surfaces)
     SetSurfaces {
@@ -223,13 +396,22 @@ enum LayerCommands {
         /// Layer ID
         layer_id: u32,
     },
+    /// Print the surface render order of a layer in the form taken by set-surfaces
+    GetRenderOrder {
+        /// Layer ID
+        id: u32,
+    },
 }
 
 /// Screen management commands
 #[derive(Subcommand)]
 enum ScreenCommands {
     /// List all available screens
-    List,
+    List {
+        /// Show the output index and geometry as used for layout
+        #[arg(long, default_value_t = false)]
+        detailed: bool,
+    },
     /// Get properties of a specific screen
     GetProps {
         /// Screen name (e.g., "HDMI-A-1")
@@ -240,6 +422,14 @@ enum ScreenCommands {
         /// Screen name
         name: String,
     },
+    /// List the surfaces shown on an output, topmost first
+    Surfaces {
+        /// Output index as shown by `screen list --detailed`
+        index: u32,
+        /// Show only surface IDs
+        #[arg(long, default_value_t = false)]
+        ids_only: bool,
+    },
     /// Get screens assigned to a layer
     GetScreensForLayer {
         /// Layer ID
@@ -262,6 +452,21 @@ enum ScreenCommands {
     },
 }
 
+/// Scene snapshot commands
+#[derive(Subcommand)]
+enum SceneCommands {
+    /// Write the current scene (surfaces, layers, render orders) to a JSON file
+    Dump {
+        /// Output file path
+        file: String,
+    },
+    /// Restore a scene previously written by `scene dump` and commit it
+    Load {
+        /// Input file path
+        file: String,
+    },
+}
+
 /// Validation error type
 #[derive(Debug)]
 struct ValidationError {
@@ -285,6 +490,51 @@ impl std::fmt::Display for ValidationError {
 
 impl std::error::Error for ValidationError {}
 
+/// Parse event type names as accepted by the `subscribe` RPC, e.g. `SurfaceCreated`
+fn parse_event_types(names: &[String]) -> StdResult<Vec<EventType>, ValidationError> {
+    if names.is_empty() {
+        return Ok(default_event_types());
+    }
+
+    names
+        .iter()
+        .map(|name| {
+            serde_json::from_value(serde_json::Value::String(name.clone())).map_err(|_| {
+                ValidationError {
+                    message: format!("Unknown event type: {}", name),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Parse one line of an `apply` file
+///
+/// Returns `None` for blank lines and comments.
+fn parse_apply_line(line: &str) -> Option<StdResult<Commands, ValidationError>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let command = match ApplyLine::try_parse_from(line.split_whitespace()) {
+        Ok(parsed) => parsed.command,
+        Err(e) => {
+            let message = e.to_string();
+            return Some(Err(ValidationError {
+                message: message.lines().next().unwrap_or_default().to_string(),
+            }));
+        }
+    };
+
+    match command {
+        Commands::Apply { .. } | Commands::Watch { .. } => Some(Err(ValidationError {
+            message: format!("Command cannot be used in apply: {}", line),
+        })),
+        command => Some(Ok(command)),
+    }
+}
+
 /// Validate opacity value is in range [0.0, 1.0]
 fn validate_opacity(opacity: f32) -> StdResult<(), ValidationError> {
     if !(0.0..=1.0).contains(&opacity) {
@@ -296,32 +546,72 @@ fn validate_opacity(opacity: f32) -> StdResult<(), ValidationError> {
     }
 }
 
+/// Map an error to the process exit code, so scripts can branch on the kind of failure
+fn exit_code(error: &IviError) -> i32 {
+    if error.is_not_found() {
+        EXIT_NOT_FOUND
+    } else if error.is_invalid_params() {
+        EXIT_INVALID_PARAMS
+    } else if error.is_timeout() {
+        EXIT_TIMEOUT
+    } else if error.is_connection_failure() {
+        EXIT_CONNECTION_FAILED
+    } else {
+        EXIT_FAILURE
+    }
+}
+
 struct IviCli {
     client: IviClient,
+    wire_logger: Option<WireLogger>,
+    style: OutputStyle,
+    format: OutputFormat,
 }
 
 impl IviCli {
-    fn new(remote: Option<&str>) -> Result<Self> {
+    fn new(remote: Option<&str>, style: OutputStyle) -> Result<Self> {
         Ok(IviCli {
             client: IviClient::new(remote)?,
+            wire_logger: None,
+            style,
+            format: OutputFormat::Pretty,
         })
     }
+
+    /// Whether commands that read state print JSON
+    fn json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Set the wire logger used by the client and by `watch`
+    fn set_wire_logger(&mut self, logger: Option<WireLogger>) {
+        self.client.set_wire_logger(logger.clone());
+        self.wire_logger = logger;
+    }
     /// Handle surface list command
     fn handle_surface_list(&mut self, ids_only: bool) -> Result<String> {
         let surfaces = self.client.list_surfaces()?;
+        if self.json() {
+            return Ok(output::format_surface_list_json(&surfaces, ids_only));
+        }
         Ok(output::format_surface_list(&surfaces, ids_only))
     }
 
     /// Handle surface get-props command
     fn handle_surface_get_properties(&mut self, id: u32) -> Result<String> {
         let surface = self.client.get_surface(id)?;
+        if self.json() {
+            return Ok(output::format_json(&surface));
+        }
         Ok(output::format_surface_properties(&surface))
     }
 
     /// Handle surface set-visibility command
     fn handle_surface_set_visibility(&mut self, id: u32, visible: bool) -> Result<String> {
         self.client.set_surface_visibility(id, visible, true)?;
-        Ok(output::format_surface_visibility_success(id, visible))
+        Ok(output::format_surface_visibility_success(
+            self.style, id, visible,
+        ))
     }
 
     /// Handle surface set-opacity command
@@ -329,7 +619,69 @@ impl IviCli {
         validate_opacity(opacity)?;
 
         self.client.set_surface_opacity(id, opacity, true)?;
-        Ok(output::format_surface_opacity_success(id, opacity))
+        Ok(output::format_surface_opacity_success(
+            self.style, id, opacity,
+        ))
+    }
+
+    /// Handle surface set-visibility command with several IDs
+    fn handle_surfaces_set_visibility(&mut self, ids: &[u32], visible: bool) -> Result<String> {
+        let action = format!("visibility set to {}", visible);
+        self.handle_surfaces_bulk(ids, &action, |client| {
+            client.set_surfaces_visibility(ids, visible, true)
+        })
+    }
+
+    /// Handle surface set-opacity command with several IDs
+    fn handle_surfaces_set_opacity(&mut self, ids: &[u32], opacity: f32) -> Result<String> {
+        validate_opacity(opacity)?;
+
+        let action = format!("opacity set to {:.2}", opacity);
+        self.handle_surfaces_bulk(ids, &action, |client| {
+            client.set_surfaces_opacity(ids, opacity, true)
+        })
+    }
+
+    /// Run a bulk surface request and report the outcome for each ID
+    ///
+    /// The controller changes either all surfaces or none. When one is
+    /// missing, every ID is looked up to tell which ones caused the failure;
+    /// the table is printed and the error returned for a non-zero exit code.
+    fn handle_surfaces_bulk(
+        &mut self,
+        ids: &[u32],
+        action: &str,
+        request: impl FnOnce(&mut IviClient) -> Result<()>,
+    ) -> Result<String> {
+        match request(&mut self.client) {
+            Ok(()) => {
+                let results: Vec<output::BulkResult> = ids
+                    .iter()
+                    .map(|&id| output::BulkResult { id, error: None })
+                    .collect();
+                Ok(output::format_bulk_report(self.style, action, &results))
+            }
+            Err(e) if e.is_not_found() => {
+                let mut results = Vec::new();
+                for &id in ids {
+                    let error = if self.client.surface_exists(id)? {
+                        "not changed"
+                    } else {
+                        "not found"
+                    };
+                    results.push(output::BulkResult {
+                        id,
+                        error: Some(error.to_string()),
+                    });
+                }
+                println!(
+                    "{}",
+                    output::format_bulk_report(self.style, action, &results)
+                );
+                Err(e)
+            }
+            Err(e) => Err(e),
+        }
     }
 
     /// Handle surface set-source-rect command
@@ -344,7 +696,7 @@ impl IviCli {
         self.client
             .set_surface_source_rectangle(id, x, y, width, height, true)?;
         Ok(output::format_surface_source_rect_success(
-            id, x, y, width, height,
+            self.style, id, x, y, width, height,
         ))
     }
 
@@ -359,44 +711,89 @@ impl IviCli {
         self.client
             .set_surface_destination_rectangle(id, x, y, width, height, true)?;
         Ok(output::format_surface_dest_rect_success(
-            id, x, y, width, height,
+            self.style, id, x, y, width, height,
         ))
     }
 
     /// Handle surface set-z-order command
     fn handle_surface_set_z_order(&mut self, id: u32, z_order: i32) -> Result<String> {
-        self.client.set_surface_z_order(id, z_order, true)?;
-        Ok(output::format_surface_z_order_success(id, z_order))
+        let resolved = self.client.set_surface_z_order(id, z_order, true)?;
+        Ok(output::format_surface_z_order_success(
+            self.style, id, z_order, resolved,
+        ))
+    }
+
+    /// Handle surface to-front and to-back commands
+    fn handle_surface_restack(&mut self, id: u32, to_front: bool) -> Result<String> {
+        let layer_ids = if to_front {
+            self.client.bring_surface_to_front(id, true)?
+        } else {
+            self.client.send_surface_to_back(id, true)?
+        };
+        Ok(output::format_surface_restack_success(
+            self.style, id, to_front, &layer_ids,
+        ))
+    }
+
+    /// Handle surface nudge command
+    fn handle_surface_nudge(&mut self, id: u32, dx: i32, dy: i32) -> Result<String> {
+        let (x, y) = self.client.nudge_surface(id, dx, dy)?;
+        Ok(output::format_nudge_success(
+            self.style, "Surface", id, x, y,
+        ))
     }
 
     /// Handle surface set-focus command
     fn handle_surface_set_focus(&mut self, id: u32) -> Result<String> {
         self.client.set_surface_focus(id, true)?;
-        Ok(output::format_surface_focus_success(id))
+        Ok(output::format_surface_focus_success(self.style, id))
+    }
+
+    /// Handle surface get-focus command
+    fn handle_surface_get_focus(&mut self) -> Result<String> {
+        let focused = self.client.get_focus()?;
+        if self.json() {
+            return Ok(output::format_json(
+                &serde_json::json!({ "surface_id": focused }),
+            ));
+        }
+        Ok(output::format_surface_focus(focused))
+    }
+
+    /// Handle surface clear-focus command
+    fn handle_surface_clear_focus(&mut self) -> Result<String> {
+        self.client.clear_focus()?;
+        Ok(output::format_surface_clear_focus_success(self.style))
     }
 
     /// Handle layer list command
     fn handle_layer_list(&mut self, ids_only: bool) -> Result<String> {
         let layers = self.client.list_layers()?;
+        if self.json() {
+            return Ok(output::format_layer_list_json(&layers, ids_only));
+        }
         Ok(output::format_layer_list(&layers, ids_only))
     }
 
     /// Handle layer get-props command
     fn handle_layer_get_properties(&mut self, id: u32) -> Result<String> {
         let layer = self.client.get_layer(id)?;
+        if self.json() {
+            return Ok(output::format_json(&layer));
+        }
         Ok(output::format_layer_properties(&layer))
     }
 
     /// Handle layer create-layer command
     fn handle_layer_create_layer(&mut self, id: u32, width: i32, height: i32) -> Result<String> {
         self.client.create_layer(id, width, height, true)?;
-        Ok(output::format_layer_create_success(id))
+        Ok(output::format_layer_create_success(self.style, id))
     }
 
     /// Handle layer destroy command
     fn handle_layer_destroy(&mut self, id: u32) -> Result<String> {
         self.client.destroy_layer(id, true)?;
-        Ok(output::format_layer_destroy_success(id))
+        Ok(output::format_layer_destroy_success(self.style, id))
     }
 
     /// Handle layer set-source-rect command
@@ -411,7 +808,7 @@ impl IviCli {
         self.client
             .set_layer_source_rectangle(id, x, y, width, height, true)?;
         Ok(output::format_layer_source_rect_success(
-            id, x, y, width, height,
+            self.style, id, x, y, width, height,
         ))
     }
 
@@ -427,22 +824,45 @@ impl IviCli {
         self.client
             .set_layer_destination_rectangle(id, x, y, width, height, true)?;
         Ok(output::format_layer_dest_rect_success(
-            id, x, y, width, height,
+            self.style, id, x, y, width, height,
         ))
     }
 
+    /// Handle layer nudge command
+    fn handle_layer_nudge(&mut self, id: u32, dx: i32, dy: i32) -> Result<String> {
+        let (x, y) = self.client.nudge_layer(id, dx, dy)?;
+        Ok(output::format_nudge_success(self.style, "Layer", id, x, y))
+    }
+
     /// Handle layer set-visibility command
-    fn handle_layer_set_visibility(&mut self, id: u32, visible: bool) -> Result<String> {
-        self.client.set_layer_visibility(id, visible, true)?;
-        Ok(output::format_layer_visibility_success(id, visible))
+    fn handle_layer_set_visibility(
+        &mut self,
+        id: u32,
+        visible: bool,
+        commit: bool,
+    ) -> Result<String> {
+        self.client.set_layer_visibility(id, visible, commit)?;
+        Ok(output::format_layer_visibility_success(
+            self.style, id, visible, commit,
+        ))
     }
 
     /// Handle layer set-opacity command
-    fn handle_layer_set_opacity(&mut self, id: u32, opacity: f32) -> Result<String> {
+    fn handle_layer_set_opacity(&mut self, id: u32, opacity: f32, commit: bool) -> Result<String> {
         validate_opacity(opacity)?;
 
-        self.client.set_layer_opacity(id, opacity, true)?;
-        Ok(output::format_layer_opacity_success(id, opacity))
+        self.client.set_layer_opacity(id, opacity, commit)?;
+        Ok(output::format_layer_opacity_success(
+            self.style, id, opacity, commit,
+        ))
+    }
+
+    /// Handle layer adjust-opacity command
+    fn handle_layer_adjust_opacity(&mut self, id: u32, delta: f32, commit: bool) -> Result<String> {
+        let opacity = self.client.adjust_layer_opacity(id, delta, commit)?;
+        Ok(output::format_layer_opacity_success(
+            self.style, id, opacity, commit,
+        ))
     }
 
     /// Handle layer set surfaces command
@@ -450,6 +870,7 @@ impl IviCli {
         self.client
             .set_surfaces_on_layer(layer_id, surface_ids, true)?;
         Ok(output::format_layer_set_surfaces_success(
+            self.style,
             layer_id,
             surface_ids,
             true,
@@ -461,7 +882,7 @@ impl IviCli {
         self.client
             .add_surface_to_layer(layer_id, surface_id, true)?;
         Ok(output::format_layer_add_surface_success(
-            layer_id, surface_id, true,
+            self.style, layer_id, surface_id, true,
         ))
     }
 
@@ -470,37 +891,78 @@ impl IviCli {
         self.client
             .remove_surface_from_layer(layer_id, surface_id, true)?;
         Ok(output::format_layer_remove_surface_success(
-            layer_id, surface_id, true,
+            self.style, layer_id, surface_id, true,
         ))
     }
 
     /// Handle layer get surfaces command
     fn handle_layer_get_surfaces(&mut self, layer_id: u32) -> Result<String> {
         let surface_ids = self.client.get_layer_surfaces(layer_id)?;
+        if self.json() {
+            return Ok(output::format_json(&surface_ids));
+        }
         Ok(output::format_layer_surfaces(layer_id, &surface_ids))
     }
 
+    /// Handle layer get-render-order command
+    fn handle_layer_get_render_order(&mut self, id: u32) -> Result<String> {
+        let surface_ids = self.client.get_layer_render_order(id)?;
+        if self.json() {
+            return Ok(output::format_json(&surface_ids));
+        }
+        Ok(output::format_render_order(&surface_ids))
+    }
+
     /// Handle screen list command
-    fn handle_screen_list(&mut self) -> Result<String> {
+    fn handle_screen_list(&mut self, detailed: bool) -> Result<String> {
+        if detailed {
+            let outputs = self.client.list_outputs()?;
+            if self.json() {
+                return Ok(output::format_json(&outputs));
+            }
+            return Ok(output::format_output_list(&outputs));
+        }
+
         let screens = self.client.list_screens()?;
+        if self.json() {
+            return Ok(output::format_json(&screens));
+        }
         Ok(output::format_screen_list(&screens))
     }
 
     /// Handle screen get properties command
     fn handle_screen_get_properties(&mut self, name: &str) -> Result<String> {
         let screen = self.client.get_screen(name)?;
+        if self.json() {
+            return Ok(output::format_json(&screen));
+        }
         Ok(output::format_screen_properties(&screen))
     }
 
     /// Handle screen get layers command
     fn handle_screen_get_layers(&mut self, name: &str) -> Result<String> {
         let layer_ids = self.client.get_screen_layers(name)?;
+        if self.json() {
+            return Ok(output::format_json(&layer_ids));
+        }
         Ok(output::format_screen_layers(name, &layer_ids))
     }
 
+    /// Handle screen surfaces command
+    fn handle_screen_surfaces(&mut self, index: u32, ids_only: bool) -> Result<String> {
+        let surfaces = self.client.surfaces_on_output(index)?;
+        if self.json() {
+            return Ok(output::format_surface_list_json(&surfaces, ids_only));
+        }
+        Ok(output::format_surface_list(&surfaces, ids_only))
+    }
+
     /// Handle get screens for layer command
     fn handle_screen_get_screens_for_layer(&mut self, layer_id: u32) -> Result<String> {
         let screen_names = self.client.get_layer_screens(layer_id)?;
+        if self.json() {
+            return Ok(output::format_json(&screen_names));
+        }
         Ok(output::format_layer_screens(layer_id, &screen_names))
     }
 
@@ -508,7 +970,7 @@ impl IviCli {
     fn handle_screen_set_layers(&mut self, name: &str, layer_ids: &[u32]) -> Result<String> {
         self.client.add_layers_to_screen(name, layer_ids, true)?;
         Ok(output::format_screen_set_layers_success(
-            name, layer_ids, true,
+            self.style, name, layer_ids, true,
         ))
     }
 
@@ -516,12 +978,19 @@ impl IviCli {
     fn handle_screen_remove_layer(&mut self, name: &str, layer_id: u32) -> Result<String> {
         self.client.remove_layer_from_screen(name, layer_id, true)?;
         Ok(output::format_screen_remove_layer_success(
-            name, layer_id, true,
+            self.style, name, layer_id, true,
         ))
     }
 
     /// Handle hierarchical scene command
+    ///
+    /// With `--format json`, prints the scene snapshot written by `scene dump`.
     fn handle_scene(&mut self) -> Result<String> {
+        if self.json() {
+            let scene = self.client.get_scene()?;
+            return Ok(output::format_json(&scene));
+        }
+
         jinfo!("Building hierarchical scene representation");
 
         jdebug!("Fetching screens from IVI Controller");
@@ -553,16 +1022,334 @@ impl IviCli {
         Ok(output::format_hierarchical_scene(&hierarchy))
     }
 
+    /// Handle scene dump command
+    fn handle_scene_dump(&mut self, file: &str) -> Result<String> {
+        let scene = self.client.get_scene()?;
+        let json = serde_json::to_string_pretty(&scene)?;
+        std::fs::write(file, json)?;
+        Ok(output::format_scene_dump_success(self.style, file, &scene))
+    }
+
+    /// Handle scene load command
+    ///
+    /// Layers missing from the compositor are created. Surfaces are owned by
+    /// applications, so surfaces that no longer exist are skipped.
+    fn handle_scene_load(&mut self, file: &str) -> Result<String> {
+        let json = std::fs::read_to_string(file)?;
+        let scene: SceneSnapshot = serde_json::from_str(&json)?;
+
+        if scene.version > SCENE_SNAPSHOT_VERSION {
+            return Err(IviError::DeserializationError(format!(
+                "Unsupported scene version {} (supported up to {})",
+                scene.version, SCENE_SNAPSHOT_VERSION
+            )));
+        }
+
+        let existing_surfaces: Vec<u32> =
+            self.client.list_surfaces()?.iter().map(|s| s.id).collect();
+        let existing_layers: Vec<u32> = self.client.list_layers()?.iter().map(|l| l.id).collect();
+
+        let mut skipped = Vec::new();
+        for surface in &scene.surfaces {
+            if !existing_surfaces.contains(&surface.id) {
+                jwarn!("Surface {} no longer exists, skipping", surface.id);
+                skipped.push(surface.id);
+                continue;
+            }
+
+            let src = &surface.src_rect;
+            if src.width > 0 && src.height > 0 {
+                self.client.set_surface_source_rectangle(
+                    surface.id, src.x, src.y, src.width, src.height, false,
+                )?;
+            }
+            let dest = &surface.dest_rect;
+            if dest.width > 0 && dest.height > 0 {
+                self.client.set_surface_destination_rectangle(
+                    surface.id,
+                    dest.x,
+                    dest.y,
+                    dest.width,
+                    dest.height,
+                    false,
+                )?;
+            }
+            self.client
+                .set_surface_visibility(surface.id, surface.visibility, false)?;
+            self.client
+                .set_surface_opacity(surface.id, surface.opacity, false)?;
+        }
+
+        for scene_layer in &scene.layers {
+            let layer = &scene_layer.layer;
+            if !existing_layers.contains(&layer.id) {
+                self.client.create_layer(
+                    layer.id,
+                    layer.dest_rect.width,
+                    layer.dest_rect.height,
+                    false,
+                )?;
+            }
+
+            let src = &layer.src_rect;
+            if src.width > 0 && src.height > 0 {
+                self.client.set_layer_source_rectangle(
+                    layer.id, src.x, src.y, src.width, src.height, false,
+                )?;
+            }
+            let dest = &layer.dest_rect;
+            if dest.width > 0 && dest.height > 0 {
+                self.client.set_layer_destination_rectangle(
+                    layer.id,
+                    dest.x,
+                    dest.y,
+                    dest.width,
+                    dest.height,
+                    false,
+                )?;
+            }
+            self.client
+                .set_layer_visibility(layer.id, layer.visibility, false)?;
+            self.client
+                .set_layer_opacity(layer.id, layer.opacity, false)?;
+
+            let surface_ids: Vec<u32> = scene_layer
+                .surface_ids
+                .iter()
+                .copied()
+                .filter(|id| existing_surfaces.contains(id))
+                .collect();
+            self.client
+                .set_surfaces_on_layer(layer.id, &surface_ids, false)?;
+        }
+
+        for screen in &scene.screens {
+            self.client
+                .add_layers_to_screen(&screen.name, &screen.layer_ids, false)?;
+        }
+
+        self.client.commit()?;
+
+        Ok(output::format_scene_load_success(
+            self.style, file, &scene, &skipped,
+        ))
+    }
+
     /// Handle commit command
     fn handle_commit(&mut self) -> Result<String> {
         self.client.commit()?;
-        Ok(output::format_commit_success())
+        Ok(output::format_commit_success(self.style))
+    }
+
+    /// Handle status command
+    fn handle_status(&mut self) -> Result<String> {
+        let stats = self.client.stats()?;
+        if self.json() {
+            return Ok(output::format_json(&stats));
+        }
+        Ok(output::format_stats(&stats))
+    }
+
+    /// Handle bench command
+    fn handle_bench(&mut self, count: usize) -> Result<String> {
+        let stats = self.client.round_trip_latency(count)?;
+        Ok(output::format_latency_stats(&stats))
+    }
+
+    /// Handle watch command
+    ///
+    /// Prints each notification as it arrives until `count` notifications were
+    /// received or `timeout_ms` elapsed. Without either, runs until interrupted.
+    fn handle_watch(
+        &mut self,
+        events: &[String],
+        count: Option<usize>,
+        timeout_ms: Option<u64>,
+    ) -> Result<String> {
+        let event_types = parse_event_types(events)?;
+        let deadline = timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+
+        let mut listener = NotificationListener::new(None)?;
+        listener.set_wire_logger(self.wire_logger.clone());
+
+        let (sender, receiver) = mpsc::channel();
+        listener.on_all(move |notification| {
+            let _ = sender.send(output::format_notification(
+                &notification.event_type,
+                &notification.params,
+            ));
+        });
+        let ignored = listener.start(&event_types)?;
+        // Watching everything is expected to include disabled event types
+        if !events.is_empty() && !ignored.is_empty() {
+            eprintln!("{}", output::format_ignored_events_warning(&ignored));
+        }
+
+        let mut received = 0;
+        loop {
+            if count.is_some_and(|count| received >= count) {
+                break;
+            }
+
+            // Wake up periodically to notice the controller closing the socket
+            let mut wait = WATCH_POLL_INTERVAL;
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                wait = wait.min(remaining);
+            }
+
+            let line = match receiver.recv_timeout(wait) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) if listener.is_running() => continue,
+                Err(RecvTimeoutError::Timeout) => {
+                    eprintln!("Connection to the controller closed");
+                    break;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            println!("{}", line);
+            received += 1;
+        }
+
+        listener.stop();
+
+        Ok(output::format_watch_summary(self.style, received))
+    }
+
+    /// Handle apply command
+    ///
+    /// Runs the commands of `file` in order. Unless `continue_on_error` is
+    /// set, stops at the first failing line and returns its error after
+    /// printing the report of the lines run so far.
+    fn handle_apply(&mut self, file: &str, continue_on_error: bool) -> Result<String> {
+        let content = if file == "-" {
+            std::io::read_to_string(std::io::stdin())?
+        } else {
+            std::fs::read_to_string(file)?
+        };
+
+        let lines: Vec<(usize, &str)> = content
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| parse_apply_line(line).is_some())
+            .collect();
+
+        let mut results = Vec::new();
+        let mut outputs = Vec::new();
+        let mut first_error = None;
+        for &(line, command) in &lines {
+            let result = match parse_apply_line(command) {
+                Some(Ok(parsed)) => execute(self, parsed),
+                Some(Err(e)) => Err(e.into()),
+                None => continue,
+            };
+
+            match result {
+                Ok(output) => {
+                    outputs.push(output);
+                    results.push(output::ApplyResult {
+                        line,
+                        command: command.to_string(),
+                        error: None,
+                    });
+                }
+                Err(e) => {
+                    let message = e.to_string();
+                    if continue_on_error {
+                        outputs.push(format!(
+                            "line {}: {}",
+                            line,
+                            output::format_error(self.style, &e)
+                        ));
+                    }
+                    results.push(output::ApplyResult {
+                        line,
+                        command: command.to_string(),
+                        error: Some(message),
+                    });
+                    if first_error.is_none() {
+                        first_error = Some(e);
+                    }
+                    if !continue_on_error {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let report = match self.format {
+            OutputFormat::Pretty => {
+                outputs.push(output::format_apply_summary(
+                    self.style,
+                    lines.len(),
+                    &results,
+                ));
+                outputs.join("\n")
+            }
+            OutputFormat::Json => output::format_apply_report(lines.len(), &results),
+        };
+
+        match first_error {
+            Some(e) if !continue_on_error => {
+                println!("{}", report);
+                Err(e)
+            }
+            _ => Ok(report),
+        }
     }
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let mut ivi_cli = IviCli::new(None)?;
+fn main() {
+    // clap exits with 2 on usage errors, which would read as "not found"
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            std::process::exit(if e.use_stderr() {
+                EXIT_INVALID_PARAMS
+            } else {
+                EXIT_SUCCESS
+            });
+        }
+    };
+
+    let mut style = OutputStyle::detect(cli.no_color);
+    style.dry_run = cli.dry_run;
+    let code = match run(cli, style) {
+        Ok(()) => EXIT_SUCCESS,
+        Err(e) => {
+            eprintln!("{}", output::format_error(style, &e));
+            exit_code(&e)
+        }
+    };
+
+    std::process::exit(code);
+}
+
+fn run(cli: Cli, style: OutputStyle) -> Result<()> {
+    let mut ivi_cli = IviCli::new(None, style)?;
+    if cli.timeout > 0 {
+        ivi_cli
+            .client
+            .set_request_timeout(Some(Duration::from_millis(cli.timeout)));
+    }
+    ivi_cli.client.set_dry_run(cli.dry_run);
+    ivi_cli.format = cli.format;
+
+    if cli.verbose > 0 {
+        ivi_cli.set_wire_logger(Some(Arc::new(|direction, bytes: &[u8]| {
+            let prefix = match direction {
+                WireDirection::Outgoing => ">>",
+                WireDirection::Incoming => "<<",
+            };
+            eprintln!("{} {}", prefix, String::from_utf8_lossy(bytes));
+        })));
+    }
 
     if cli.log {
         let log_level = match cli.verbose {
@@ -581,16 +1368,23 @@ fn main() -> Result<()> {
 
     jinfo!("Starting IVI CLI");
 
-    match cli.command {
+    execute(&mut ivi_cli, cli.command).map(|r| println!("{}", r))
+}
+
+/// Run a single command and return its output
+fn execute(ivi_cli: &mut IviCli, command: Commands) -> Result<String> {
+    match command {
         Commands::Surface { command } => match command {
             SurfaceCommands::List { ids_only } => ivi_cli.handle_surface_list(ids_only),
             SurfaceCommands::GetProps { id } => ivi_cli.handle_surface_get_properties(id),
-            SurfaceCommands::SetVisibility { id, visible } => {
-                ivi_cli.handle_surface_set_visibility(id, visible)
-            }
-            SurfaceCommands::SetOpacity { id, opacity } => {
-                ivi_cli.handle_surface_set_opacity(id, opacity)
-            }
+            SurfaceCommands::SetVisibility { ids, visible } => match ids[..] {
+                [id] => ivi_cli.handle_surface_set_visibility(id, visible),
+                _ => ivi_cli.handle_surfaces_set_visibility(&ids, visible),
+            },
+            SurfaceCommands::SetOpacity { ids, opacity } => match ids[..] {
+                [id] => ivi_cli.handle_surface_set_opacity(id, opacity),
+                _ => ivi_cli.handle_surfaces_set_opacity(&ids, opacity),
+            },
             SurfaceCommands::SetSrcRect {
                 id,
                 x,
@@ -608,7 +1402,12 @@ fn main() -> Result<()> {
             SurfaceCommands::SetZOrder { id, z_order } => {
                 ivi_cli.handle_surface_set_z_order(id, z_order)
             }
+            SurfaceCommands::Nudge { id, dx, dy } => ivi_cli.handle_surface_nudge(id, dx, dy),
+            SurfaceCommands::ToFront { id } => ivi_cli.handle_surface_restack(id, true),
+            SurfaceCommands::ToBack { id } => ivi_cli.handle_surface_restack(id, false),
             SurfaceCommands::SetFocus { id } => ivi_cli.handle_surface_set_focus(id),
+            SurfaceCommands::GetFocus => ivi_cli.handle_surface_get_focus(),
+            SurfaceCommands::ClearFocus => ivi_cli.handle_surface_clear_focus(),
         },
         Commands::Layer { command } => match command {
             LayerCommands::List { ids_only } => ivi_cli.handle_layer_list(ids_only),
@@ -631,12 +1430,22 @@ fn main() -> Result<()> {
                 width,
                 height,
             } => ivi_cli.handle_layer_set_dest_rect(id, x, y, width, height),
-            LayerCommands::SetVisibility { id, visible } => {
-                ivi_cli.handle_layer_set_visibility(id, visible)
-            }
-            LayerCommands::SetOpacity { id, opacity } => {
-                ivi_cli.handle_layer_set_opacity(id, opacity)
-            }
+            LayerCommands::Nudge { id, dx, dy } => ivi_cli.handle_layer_nudge(id, dx, dy),
+            LayerCommands::SetVisibility {
+                id,
+                visible,
+                no_commit,
+            } => ivi_cli.handle_layer_set_visibility(id, visible, !no_commit),
+            LayerCommands::SetOpacity {
+                id,
+                opacity,
+                no_commit,
+            } => ivi_cli.handle_layer_set_opacity(id, opacity, !no_commit),
+            LayerCommands::AdjustOpacity {
+                id,
+                delta,
+                no_commit,
+            } => ivi_cli.handle_layer_adjust_opacity(id, delta, !no_commit),
             LayerCommands::SetSurfaces {
                 layer_id,
                 surface_ids,
@@ -650,11 +1459,15 @@ fn main() -> Result<()> {
                 surface_id,
             } => ivi_cli.handle_layer_remove_surface(layer_id, surface_id),
             LayerCommands::GetSurfaces { layer_id } => ivi_cli.handle_layer_get_surfaces(layer_id),
+            LayerCommands::GetRenderOrder { id } => ivi_cli.handle_layer_get_render_order(id),
         },
         Commands::Screen { command } => match command {
-            ScreenCommands::List => ivi_cli.handle_screen_list(),
+            ScreenCommands::List { detailed } => ivi_cli.handle_screen_list(detailed),
             ScreenCommands::GetProps { name } => ivi_cli.handle_screen_get_properties(&name),
             ScreenCommands::GetLayers { name } => ivi_cli.handle_screen_get_layers(&name),
+            ScreenCommands::Surfaces { index, ids_only } => {
+                ivi_cli.handle_screen_surfaces(index, ids_only)
+            }
             ScreenCommands::GetScreensForLayer { layer_id } => {
                 ivi_cli.handle_screen_get_screens_for_layer(layer_id)
             }
@@ -665,22 +1478,54 @@ fn main() -> Result<()> {
                 ivi_cli.handle_screen_remove_layer(&name, layer_id)
             }
         },
-        Commands::Scene => ivi_cli.handle_scene(),
+        Commands::Scene { command } => match command {
+            None => ivi_cli.handle_scene(),
+            Some(SceneCommands::Dump { file }) => ivi_cli.handle_scene_dump(&file),
+            Some(SceneCommands::Load { file }) => ivi_cli.handle_scene_load(&file),
+        },
         Commands::Commit => ivi_cli.handle_commit(),
+        Commands::Watch {
+            events,
+            count,
+            timeout,
+        } => ivi_cli.handle_watch(&events, count, timeout),
+        Commands::Apply {
+            file,
+            continue_on_error,
+        } => ivi_cli.handle_apply(&file, continue_on_error),
+        Commands::Status => ivi_cli.handle_status(),
+        Commands::Bench { count } => ivi_cli.handle_bench(count),
     }
-    .map(|r| println!("{}", r))
-    .map_err(|e| {
-        eprintln!("{}", output::format_error(&e));
-
-        // Avoid printing error twice
-        std::process::exit(1);
-    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_exit_code_classification() {
+        let not_found = IviError::RequestFailed {
+            code: -32000,
+            message: "Surface not found: 1000".to_string(),
+        };
+        assert_eq!(exit_code(&not_found), EXIT_NOT_FOUND);
+
+        let invalid: IviError = validate_opacity(2.0).unwrap_err().into();
+        assert_eq!(exit_code(&invalid), EXIT_INVALID_PARAMS);
+
+        let refused = IviError::ConnectionFailed("refused".to_string());
+        assert_eq!(exit_code(&refused), EXIT_CONNECTION_FAILED);
+
+        let timeout = IviError::from(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "timed out",
+        ));
+        assert_eq!(exit_code(&timeout), EXIT_TIMEOUT);
+
+        let other = IviError::DeserializationError("bad".to_string());
+        assert_eq!(exit_code(&other), EXIT_FAILURE);
+    }
+
     #[test]
     fn test_validate_opacity_valid() {
         assert!(validate_opacity(0.0).is_ok());
@@ -688,6 +1533,66 @@ mod tests {
         assert!(validate_opacity(1.0).is_ok());
     }
 
+    #[test]
+    fn test_parse_event_types() {
+        assert_eq!(parse_event_types(&[]).unwrap(), default_event_types());
+
+        let names = vec!["SurfaceCreated".to_string(), "FocusChanged".to_string()];
+        assert_eq!(
+            parse_event_types(&names).unwrap(),
+            vec![EventType::SurfaceCreated, EventType::FocusChanged]
+        );
+
+        assert!(parse_event_types(&["Bogus".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_apply_line() {
+        assert!(parse_apply_line("").is_none());
+        assert!(parse_apply_line("   # comment").is_none());
+
+        match parse_apply_line("  surface nudge 1000 -10 5 ") {
+            Some(Ok(Commands::Surface {
+                command: SurfaceCommands::Nudge { id, dx, dy },
+            })) => assert_eq!((id, dx, dy), (1000, -10, 5)),
+            _ => panic!("expected a surface nudge command"),
+        }
+
+        match parse_apply_line("surface set-visibility 1000 1001 1002 false") {
+            Some(Ok(Commands::Surface {
+                command: SurfaceCommands::SetVisibility { ids, visible },
+            })) => assert_eq!((ids, visible), (vec![1000, 1001, 1002], false)),
+            _ => panic!("expected a surface set-visibility command"),
+        }
+        assert!(matches!(
+            parse_apply_line("surface set-opacity 0.5"),
+            Some(Err(_))
+        ));
+
+        assert!(matches!(
+            parse_apply_line("surface bogus 1000"),
+            Some(Err(_))
+        ));
+        assert!(matches!(parse_apply_line("apply other.txt"), Some(Err(_))));
+        assert!(matches!(parse_apply_line("watch"), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_format_flag() {
+        let cli = Cli::try_parse_from(["ivi_cli", "surface", "list"]).unwrap();
+        assert!(cli.format == OutputFormat::Pretty);
+
+        let cli = Cli::try_parse_from(["ivi_cli", "surface", "list", "--format", "json"]).unwrap();
+        assert!(cli.format == OutputFormat::Json);
+
+        // `apply --format text` predates the global flag
+        let cli =
+            Cli::try_parse_from(["ivi_cli", "apply", "setup.txt", "--format", "text"]).unwrap();
+        assert!(cli.format == OutputFormat::Pretty);
+
+        assert!(Cli::try_parse_from(["ivi_cli", "--format", "yaml", "status"]).is_err());
+    }
+
     #[test]
     fn test_validate_opacity_invalid() {
         assert!(validate_opacity(-0.1).is_err());
@@ -4,7 +4,10 @@
 //!
 //! This module provides functions to format CLI output in a consistent,
 //! human-readable manner.
-use ivi_client::{IviLayer, IviScreen, IviSurface};
+use ivi_client::{EventType, IviLayer, IviScreen, IviSurface, LatencyStats, SceneSnapshot, Stats};
+use serde::Serialize;
+use serde_json::Value;
+use std::io::IsTerminal;
 
 /// Format a list of surfaces
 ///
@@ -13,7 +16,8 @@ use ivi_client::{IviLayer, IviScreen, IviSurface};
 /// * `ids_only` - If true, only show surface IDs
 ///
 /// # Returns
-/// A formatted string with surface information, or a message if no surfaces exist
+/// A table with one row per surface, its columns as wide as their widest
+/// value, or a message if no surfaces exist
 pub fn format_surface_list(surfaces: &[IviSurface], ids_only: bool) -> String {
     if surfaces.is_empty() {
         return "No surfaces available".to_string();
@@ -24,18 +28,50 @@ pub fn format_surface_list(surfaces: &[IviSurface], ids_only: bool) -> String {
         return ids.join(" ");
     }
 
-    let mut output = format!("Found {} surface(s):\n", surfaces.len());
-    for surface in surfaces {
-        output.push_str(&format!("  Surface {}:\n", surface.id));
-        output.push_str(&format!("    OrigSize: {}\n", surface.orig_size));
-        output.push_str(&format!("    SrcRect: {}\n", surface.src_rect));
-        output.push_str(&format!("    DestRect: {}\n", surface.dest_rect));
-        output.push_str(&format!("    Visibility: {}\n", surface.visibility));
-        output.push_str(&format!("    Opacity: {:.2}\n", surface.opacity));
-        output.push_str(&format!("    Orientation: {}\n", surface.orientation));
-        output.push_str(&format!("    Z-Order: {}\n", surface.z_order));
+    let rows: Vec<Vec<String>> = surfaces
+        .iter()
+        .map(|surface| {
+            vec![
+                surface.id.to_string(),
+                surface.dest_rect.to_string(),
+                surface.visibility.to_string(),
+                format!("{:.2}", surface.opacity),
+                surface.orientation.to_string(),
+                surface.z_order.to_string(),
+            ]
+        })
+        .collect();
+    format_table(
+        &["ID", "DEST", "VISIBLE", "OPACITY", "ORIENTATION", "Z-ORDER"],
+        &rows,
+    )
+}
+
+/// Format rows as a table with a header line
+///
+/// Columns are separated by two spaces and padded to their widest cell.
+fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
     }
-    output.trim_end().to_string()
+
+    let format_row = |cells: Vec<&str>| -> String {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        line.join("  ").trim_end().to_string()
+    };
+
+    let mut lines = vec![format_row(headers.to_vec())];
+    for row in rows {
+        lines.push(format_row(row.iter().map(String::as_str).collect()));
+    }
+    lines.join("\n")
 }
 
 /// Format a list of layers
@@ -68,18 +104,46 @@ pub fn format_layer_list(layers: &[IviLayer], ids_only: bool) -> String {
     output.trim_end().to_string()
 }
 
-pub fn format_layer_create_success(id: u32) -> String {
-    format_success(&format!("Layer {} created", id))
+/// Format a value as pretty-printed JSON, for `--format json`
+pub fn format_json<T: Serialize + ?Sized>(value: &T) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+/// Format a list of surfaces as a JSON array
+///
+/// With `ids_only`, the array holds only the surface IDs. An empty list is
+/// `[]` rather than a message, so scripts can parse it either way.
+pub fn format_surface_list_json(surfaces: &[IviSurface], ids_only: bool) -> String {
+    if ids_only {
+        let ids: Vec<u32> = surfaces.iter().map(|s| s.id).collect();
+        return format_json(&ids);
+    }
+    format_json(surfaces)
+}
+
+/// Format a list of layers as a JSON array
+///
+/// With `ids_only`, the array holds only the layer IDs.
+pub fn format_layer_list_json(layers: &[IviLayer], ids_only: bool) -> String {
+    if ids_only {
+        let ids: Vec<u32> = layers.iter().map(|l| l.id).collect();
+        return format_json(&ids);
+    }
+    format_json(layers)
+}
+
+pub fn format_layer_create_success(style: OutputStyle, id: u32) -> String {
+    format_success(style, &format!("Layer {} created", id))
 }
 
-pub fn format_layer_destroy_success(id: u32) -> String {
-    format_success(&format!("Layer {} destroyed", id))
+pub fn format_layer_destroy_success(style: OutputStyle, id: u32) -> String {
+    format_success(style, &format!("Layer {} destroyed", id))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ivi_client::{IviOrientation, IviSize, Rectangle};
+    use ivi_client::{IviOrientation, IviSize, IviSurfaceType, Rectangle};
 
     #[test]
     fn test_format_surface_list_empty() {
@@ -118,10 +182,51 @@ mod tests {
             opacity: 1.0,
             orientation: IviOrientation::Normal,
             z_order: 0,
+            on_screen: false,
+            surface_type: IviSurfaceType::Ivi,
         }];
         assert_eq!(format_surface_list(&surfaces, true), "1000");
     }
 
+    #[test]
+    fn test_format_surface_list_json() {
+        let surfaces = vec![IviSurface {
+            id: 1000,
+            orig_size: IviSize {
+                width: 100,
+                height: 100,
+            },
+            src_rect: Rectangle {
+                x: 0,
+                y: 0,
+                width: 100,
+                height: 100,
+            },
+            dest_rect: Rectangle {
+                x: 10,
+                y: 20,
+                width: 100,
+                height: 100,
+            },
+            visibility: true,
+            opacity: 0.5,
+            orientation: IviOrientation::Normal,
+            z_order: 0,
+            on_screen: true,
+            surface_type: IviSurfaceType::Ivi,
+        }];
+
+        let output = format_surface_list_json(&surfaces, false);
+        let parsed: Vec<IviSurface> = serde_json::from_str(&output).unwrap();
+        assert_eq!(parsed, surfaces);
+
+        assert_eq!(
+            serde_json::from_str::<Vec<u32>>(&format_surface_list_json(&surfaces, true)).unwrap(),
+            vec![1000]
+        );
+        assert_eq!(format_surface_list_json(&[], false), "[]");
+    }
+
     #[test]
     fn test_format_surface_list_detailed() {
         let surfaces = vec![IviSurface {
@@ -146,14 +251,64 @@ mod tests {
             opacity: 1.0,
             orientation: IviOrientation::Normal,
             z_order: 0,
+            on_screen: false,
+            surface_type: IviSurfaceType::Ivi,
         }];
+        assert_eq!(
+            format_surface_list(&surfaces, false),
+            "ID    DEST            VISIBLE  OPACITY  ORIENTATION  Z-ORDER\n\
+             1000  100x100@(0, 0)  true     1.00     0 degrees    0"
+        );
+    }
+
+    #[test]
+    fn test_format_surface_list_table_alignment() {
+        let surface = |id: u32, x: i32, z_order: i32| IviSurface {
+            id,
+            orig_size: IviSize {
+                width: 1920,
+                height: 1080,
+            },
+            src_rect: Rectangle::default(),
+            dest_rect: Rectangle {
+                x,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            visibility: id % 2 == 0,
+            opacity: 0.5,
+            orientation: IviOrientation::Rotate90,
+            z_order,
+            on_screen: true,
+            surface_type: IviSurfaceType::Ivi,
+        };
+        let surfaces = vec![
+            surface(7, 0, 0),
+            surface(4294967295, -1920, 12),
+            surface(1000, 5, 3),
+        ];
+
         let output = format_surface_list(&surfaces, false);
-        assert!(output.contains("Found 1 surface(s):"));
-        assert!(output.contains("Surface 1000:"));
-        assert!(output.contains("OrigSize: 100x100"));
-        assert!(output.contains("Visibility: true"));
-        assert!(output.contains("Opacity: 1.00"));
-        assert!(output.contains("Z-Order: 0"));
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "ID          DEST                  VISIBLE  OPACITY  ORIENTATION  Z-ORDER"
+        );
+        assert_eq!(
+            lines[2],
+            "4294967295  1920x1080@(-1920, 0)  false    0.50     90 degrees   12"
+        );
+
+        // Every column starts at the same offset on every line
+        for header in ["DEST", "VISIBLE", "OPACITY", "ORIENTATION", "Z-ORDER"] {
+            let column = lines[0].find(header).unwrap();
+            for line in &lines[1..] {
+                assert_eq!(line.as_bytes()[column - 1], b' ', "{}", line);
+                assert_ne!(line.as_bytes()[column], b' ', "{}", line);
+            }
+        }
     }
 
     #[test]
@@ -181,6 +336,8 @@ mod tests {
                 opacity: 1.0,
                 orientation: IviOrientation::Normal,
                 z_order: 0,
+                on_screen: false,
+                surface_type: IviSurfaceType::Ivi,
             },
             IviSurface {
                 id: 1001,
@@ -204,6 +361,8 @@ mod tests {
                 opacity: 0.5,
                 orientation: IviOrientation::Rotate90,
                 z_order: 1,
+                on_screen: false,
+                surface_type: IviSurfaceType::Ivi,
             },
             IviSurface {
                 id: 1002,
@@ -227,6 +386,8 @@ mod tests {
                 opacity: 0.75,
                 orientation: IviOrientation::Rotate180,
                 z_order: 2,
+                on_screen: false,
+                surface_type: IviSurfaceType::Ivi,
             },
         ];
         assert_eq!(format_surface_list(&surfaces, true), "1000 1001 1002");
@@ -257,6 +418,8 @@ mod tests {
                 opacity: 1.0,
                 orientation: IviOrientation::Normal,
                 z_order: 0,
+                on_screen: false,
+                surface_type: IviSurfaceType::Ivi,
             },
             IviSurface {
                 id: 1001,
@@ -280,15 +443,18 @@ mod tests {
                 opacity: 0.5,
                 orientation: IviOrientation::Rotate90,
                 z_order: 1,
+                on_screen: false,
+                surface_type: IviSurfaceType::Ivi,
             },
         ];
         let output = format_surface_list(&surfaces, false);
-        assert!(output.contains("Found 2 surface(s):"));
-        assert!(output.contains("Surface 1000:"));
-        assert!(output.contains("Surface 1001:"));
-        assert!(output.contains("Visibility: false"));
-        assert!(output.contains("Opacity: 0.50"));
-        assert!(output.contains("Orientation: 90 degrees"));
+        assert_eq!(output.lines().count(), 3);
+        assert!(output.lines().nth(1).unwrap().starts_with("1000 "));
+        let second = output.lines().nth(2).unwrap();
+        assert!(second.starts_with("1001 "));
+        assert!(second.contains("false"));
+        assert!(second.contains("0.50"));
+        assert!(second.contains("90 degrees"));
     }
 
     #[test]
@@ -433,7 +599,7 @@ mod tests {
 /// ```
 pub fn format_surface_properties(surface: &IviSurface) -> String {
     format!(
-        "Surface {}:\n  OrigSize: {}\n  SrcRect: {}\n  DestRect: {}\n Visibility: {}\n  Opacity: {:.2}\n  Orientation: {}\n  Z-Order: {}",
+        "Surface {}:\n  OrigSize: {}\n  SrcRect: {}\n  DestRect: {}\n Visibility: {}\n  Opacity: {:.2}\n  Orientation: {}\n  Z-Order: {}\n  On-Screen: {}\n  Type: {}",
         surface.id,
         surface.orig_size,
         surface.src_rect,
@@ -441,7 +607,9 @@ pub fn format_surface_properties(surface: &IviSurface) -> String {
         surface.visibility,
         surface.opacity,
         surface.orientation,
-        surface.z_order
+        surface.z_order,
+        surface.on_screen,
+        surface.surface_type
     )
 }
 
@@ -477,7 +645,7 @@ pub fn format_layer_properties(layer: &IviLayer) -> String {
 #[cfg(test)]
 mod properties_tests {
     use super::*;
-    use ivi_client::{IviOrientation, IviSize, Rectangle};
+    use ivi_client::{IviOrientation, IviSize, IviSurfaceType, Rectangle};
 
     #[test]
     fn test_format_surface_properties() {
@@ -503,6 +671,8 @@ mod properties_tests {
             opacity: 1.0,
             orientation: IviOrientation::Normal,
             z_order: 0,
+            on_screen: false,
+            surface_type: IviSurfaceType::Ivi,
         };
 
         let output = format_surface_properties(&surface);
@@ -514,6 +684,7 @@ mod properties_tests {
         assert!(output.contains("Opacity: 1.00"));
         assert!(output.contains("Orientation: 0 degrees"));
         assert!(output.contains("Z-Order: 0"));
+        assert!(output.contains("On-Screen: false"));
     }
 
     #[test]
@@ -540,6 +711,8 @@ mod properties_tests {
             opacity: 0.5,
             orientation: IviOrientation::Rotate90,
             z_order: -1,
+            on_screen: false,
+            surface_type: IviSurfaceType::Ivi,
         };
 
         let output = format_surface_properties(&surface);
@@ -577,6 +750,8 @@ mod properties_tests {
             opacity: 0.123456,
             orientation: IviOrientation::Normal,
             z_order: 0,
+            on_screen: false,
+            surface_type: IviSurfaceType::Ivi,
         };
 
         let output = format_surface_properties(&surface);
@@ -665,9 +840,45 @@ mod properties_tests {
     }
 }
 
-/// Format a success message with a check mark
+/// How success and error messages are marked
+///
+/// The `✓`/`✗` glyphs are only readable on a UTF-8 terminal, so output that is
+/// piped to a file or another program uses ASCII prefixes instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputStyle {
+    /// Use `OK:`/`ERROR:` instead of `✓`/`✗`
+    pub plain: bool,
+    /// Say that successful changes were only checked, for `--dry-run`
+    pub dry_run: bool,
+}
+
+impl OutputStyle {
+    /// `✓`/`✗` glyphs, for terminals
+    pub const GLYPHS: OutputStyle = OutputStyle {
+        plain: false,
+        dry_run: false,
+    };
+    /// `OK:`/`ERROR:` prefixes, for logs and pipes
+    pub const PLAIN: OutputStyle = OutputStyle {
+        plain: true,
+        dry_run: false,
+    };
+
+    /// Pick the style for stdout: plain if requested or if stdout is not a terminal
+    pub fn detect(plain: bool) -> Self {
+        OutputStyle {
+            plain: plain || !std::io::stdout().is_terminal(),
+            dry_run: false,
+        }
+    }
+}
+
+/// Format a success message with a check mark, or `OK:` in plain style
+///
+/// In a dry run, the message says that the change was only checked.
 ///
 /// # Arguments
+/// * `style` - Whether to use glyphs or plain ASCII prefixes
 /// * `message` - The success message to format
 ///
 /// # Returns
@@ -675,16 +886,29 @@ mod properties_tests {
 ///
 /// # Examples
 /// ```
-/// let msg = format_success("Operation completed");
+/// let msg = format_success(OutputStyle::GLYPHS, "Operation completed");
 /// assert_eq!(msg, "✓ Operation completed");
+/// let msg = format_success(OutputStyle::PLAIN, "Operation completed");
+/// assert_eq!(msg, "OK: Operation completed");
 /// ```
-pub fn format_success(message: &str) -> String {
-    format!("✓ {}", message)
+pub fn format_success(style: OutputStyle, message: &str) -> String {
+    let message = if style.dry_run {
+        format!("Dry run: {} (valid, not applied)", message)
+    } else {
+        message.to_string()
+    };
+
+    if style.plain {
+        format!("OK: {}", message)
+    } else {
+        format!("✓ {}", message)
+    }
 }
 
-/// Format an error message with a cross mark
+/// Format an error message with a cross mark, or `ERROR:` in plain style
 ///
 /// # Arguments
+/// * `style` - Whether to use glyphs or plain ASCII prefixes
 /// * `error` - The error to format
 ///
 /// # Returns
@@ -692,91 +916,323 @@ pub fn format_success(message: &str) -> String {
 ///
 /// # Examples
 /// ```
-/// let msg = format_error("Connection failed");
+/// let msg = format_error(OutputStyle::GLYPHS, "Connection failed");
 /// assert_eq!(msg, "✗ Error: Connection failed");
+/// let msg = format_error(OutputStyle::PLAIN, "Connection failed");
+/// assert_eq!(msg, "ERROR: Connection failed");
 /// ```
-pub fn format_error(error: &dyn std::error::Error) -> String {
-    format!("✗ Error: {}", error)
+pub fn format_error(style: OutputStyle, error: &dyn std::error::Error) -> String {
+    if style.plain {
+        format!("ERROR: {}", error)
+    } else {
+        format!("✗ Error: {}", error)
+    }
 }
 
 /// Format a success message for setting surface visibility
-pub fn format_surface_visibility_success(id: u32, visible: bool) -> String {
-    format_success(&format!("Surface {} visibility set to {}", id, visible))
+pub fn format_surface_visibility_success(style: OutputStyle, id: u32, visible: bool) -> String {
+    format_success(
+        style,
+        &format!("Surface {} visibility set to {}", id, visible),
+    )
 }
 
 /// Format a success message for setting surface opacity
-pub fn format_surface_opacity_success(id: u32, opacity: f32) -> String {
-    format_success(&format!("Surface {} opacity set to {:.2}", id, opacity))
+pub fn format_surface_opacity_success(style: OutputStyle, id: u32, opacity: f32) -> String {
+    format_success(
+        style,
+        &format!("Surface {} opacity set to {:.2}", id, opacity),
+    )
 }
 
 /// Format a success message for setting surface source rectangle
 pub fn format_surface_source_rect_success(
+    style: OutputStyle,
     id: u32,
     x: i32,
     y: i32,
     width: i32,
     height: i32,
 ) -> String {
-    format_success(&format!(
-        "Surface {} source rectangle set to position ({}, {}) and size {}x{}",
-        id, x, y, width, height
-    ))
+    format_success(
+        style,
+        &format!(
+            "Surface {} source rectangle set to position ({}, {}) and size {}x{}",
+            id, x, y, width, height
+        ),
+    )
 }
 
 /// Format a success message for setting surface destination rectangle
 pub fn format_surface_dest_rect_success(
+    style: OutputStyle,
     id: u32,
     x: i32,
     y: i32,
     width: i32,
     height: i32,
 ) -> String {
-    format_success(&format!(
-        "Surface {} destination rectangle set to position ({}, {}) and size {}x{}",
-        id, x, y, width, height
-    ))
+    format_success(
+        style,
+        &format!(
+            "Surface {} destination rectangle set to position ({}, {}) and size {}x{}",
+            id, x, y, width, height
+        ),
+    )
+}
+
+/// Outcome for one surface of a command given several surface IDs
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkResult {
+    /// Surface ID
+    pub id: u32,
+    /// Why the surface was not changed, if it was not
+    pub error: Option<String>,
+}
+
+/// Format the per-surface table of a command given several surface IDs
+///
+/// `action` describes the change, e.g. `visibility set to false`.
+pub fn format_bulk_report(style: OutputStyle, action: &str, results: &[BulkResult]) -> String {
+    let mut output = format!("{:<12}RESULT\n", "ID");
+    for result in results {
+        output.push_str(&format!(
+            "{:<12}{}\n",
+            result.id,
+            result.error.as_deref().unwrap_or("OK")
+        ));
+    }
+
+    let changed = results.iter().filter(|r| r.error.is_none()).count();
+    let message = format!(
+        "Surface {} on {} of {} surfaces",
+        action,
+        changed,
+        results.len()
+    );
+    if changed == results.len() {
+        output.push_str(&format_success(style, &message));
+    } else {
+        output.push_str(&format!("Warning: {}", message));
+    }
+    output
+}
+
+/// Result of one line of an `apply` file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApplyResult {
+    /// Line number in the file, starting at 1
+    pub line: usize,
+    /// The command as written on the line
+    pub command: String,
+    /// The error message if the command failed
+    pub error: Option<String>,
+}
+
+/// Format the summary printed after the output of an `apply` run
+pub fn format_apply_summary(style: OutputStyle, total: usize, results: &[ApplyResult]) -> String {
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    let succeeded = results.len() - failed;
+    let message = format!(
+        "Applied {} of {} commands, {} failed",
+        succeeded, total, failed
+    );
+    if failed == 0 {
+        format_success(style, &message)
+    } else {
+        format!("Warning: {}", message)
+    }
+}
+
+/// Format the JSON report of an `apply` run
+///
+/// `total` counts the commands in the file, including those not run after a failure.
+pub fn format_apply_report(total: usize, results: &[ApplyResult]) -> String {
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    let lines: Vec<serde_json::Value> = results
+        .iter()
+        .map(|r| {
+            let mut entry = serde_json::json!({
+                "line": r.line,
+                "command": r.command,
+                "ok": r.error.is_none(),
+            });
+            if let Some(error) = &r.error {
+                entry["error"] = serde_json::json!(error);
+            }
+            entry
+        })
+        .collect();
+
+    let report = serde_json::json!({
+        "results": lines,
+        "summary": {
+            "total": total,
+            "succeeded": results.len() - failed,
+            "failed": failed,
+        },
+    });
+    serde_json::to_string_pretty(&report).unwrap_or_default()
+}
+
+/// Format the result of a `bench` run, in milliseconds
+pub fn format_latency_stats(stats: &LatencyStats) -> String {
+    let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+    format!(
+        "Ping round trip over {} request(s):\n  min: {:.3} ms\n  avg: {:.3} ms\n  max: {:.3} ms",
+        stats.samples,
+        ms(stats.min),
+        ms(stats.avg),
+        ms(stats.max)
+    )
+}
+
+/// Format the result of `status` on one line, for status bars
+pub fn format_stats(stats: &Stats) -> String {
+    format!(
+        "surfaces: {} | layers: {} | clients: {} | subscriptions: {} | pending: {}",
+        stats.surfaces,
+        stats.layers,
+        stats.connected_clients,
+        stats.subscriptions,
+        stats.pending_changes
+    )
+}
+
+/// Format a success message for moving a surface or layer by an offset
+pub fn format_nudge_success(style: OutputStyle, kind: &str, id: u32, x: i32, y: i32) -> String {
+    format_success(style, &format!("{} {} moved to ({}, {})", kind, id, x, y))
 }
 
 /// Format a success message for setting surface z-order
-pub fn format_surface_z_order_success(id: u32, z_order: i32) -> String {
-    format_success(&format!("Surface {} z-order set to {}", id, z_order))
+pub fn format_surface_z_order_success(
+    style: OutputStyle,
+    id: u32,
+    requested: i32,
+    resolved: i32,
+) -> String {
+    if requested == resolved {
+        format_success(
+            style,
+            &format!("Surface {} z-order set to {}", id, resolved),
+        )
+    } else {
+        format_success(
+            style,
+            &format!(
+                "Surface {} z-order set to {} (requested {})",
+                id, resolved, requested
+            ),
+        )
+    }
+}
+
+/// Format a success message for moving a surface to the front or back of its layers
+pub fn format_surface_restack_success(
+    style: OutputStyle,
+    id: u32,
+    to_front: bool,
+    layer_ids: &[u32],
+) -> String {
+    let position = if to_front { "front" } else { "back" };
+    if layer_ids.is_empty() {
+        return format_success(
+            style,
+            &format!("Surface {} is not on any layer, nothing to move", id),
+        );
+    }
+    let ids: Vec<String> = layer_ids.iter().map(|id| id.to_string()).collect();
+    format_success(
+        style,
+        &format!(
+            "Surface {} moved to the {} of layer(s) {}",
+            id,
+            position,
+            ids.join(", ")
+        ),
+    )
 }
 
 /// Format a success message for setting surface focus
-pub fn format_surface_focus_success(id: u32) -> String {
-    format_success(&format!("Surface {} focus set", id))
+pub fn format_surface_focus_success(style: OutputStyle, id: u32) -> String {
+    format_success(style, &format!("Surface {} focus set", id))
+}
+
+/// Format the currently focused surface
+pub fn format_surface_focus(focused: Option<u32>) -> String {
+    match focused {
+        Some(id) => format!("Surface {} has focus", id),
+        None => "No surface has focus".to_string(),
+    }
+}
+
+/// Format a success message for clearing surface focus
+pub fn format_surface_clear_focus_success(style: OutputStyle) -> String {
+    format_success(style, "Surface focus cleared")
 }
 
 /// Format a success message for setting layer visibility
-pub fn format_layer_visibility_success(id: u32, visible: bool) -> String {
-    format_success(&format!("Layer {} visibility set to {}", id, visible))
+pub fn format_layer_visibility_success(
+    style: OutputStyle,
+    id: u32,
+    visible: bool,
+    auto_commit: bool,
+) -> String {
+    let commit_msg = if auto_commit { " and committed" } else { "" };
+    format_success(
+        style,
+        &format!("Layer {} visibility set to {}{}", id, visible, commit_msg),
+    )
 }
 
 /// Format a success message for setting layer opacity
-pub fn format_layer_opacity_success(id: u32, opacity: f32) -> String {
-    format_success(&format!("Layer {} opacity set to {:.2}", id, opacity))
+pub fn format_layer_opacity_success(
+    style: OutputStyle,
+    id: u32,
+    opacity: f32,
+    auto_commit: bool,
+) -> String {
+    let commit_msg = if auto_commit { " and committed" } else { "" };
+    format_success(
+        style,
+        &format!("Layer {} opacity set to {:.2}{}", id, opacity, commit_msg),
+    )
 }
 
 /// Format a success message for setting layer source rectangle
 pub fn format_layer_source_rect_success(
+    style: OutputStyle,
     id: u32,
     x: i32,
     y: i32,
     width: i32,
     height: i32,
 ) -> String {
-    format_success(&format!(
-        "Layer {} source rectangle set to position ({}, {}) and size {}x{}",
-        id, x, y, width, height
-    ))
+    format_success(
+        style,
+        &format!(
+            "Layer {} source rectangle set to position ({}, {}) and size {}x{}",
+            id, x, y, width, height
+        ),
+    )
 }
 
 /// Format a success message for setting layer destination rectangle
-pub fn format_layer_dest_rect_success(id: u32, x: i32, y: i32, width: i32, height: i32) -> String {
-    format_success(&format!(
-        "Layer {} destination rectangle set to position ({}, {}) and size {}x{}",
-        id, x, y, width, height
-    ))
+pub fn format_layer_dest_rect_success(
+    style: OutputStyle,
+    id: u32,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+) -> String {
+    format_success(
+        style,
+        &format!(
+            "Layer {} destination rectangle set to position ({}, {}) and size {}x{}",
+            id, x, y, width, height
+        ),
+    )
 }
 
 /// Format screen list output
@@ -806,6 +1262,22 @@ pub fn format_screen_list(screens: &[ivi_client::IviScreen]) -> String {
     output.trim_end().to_string()
 }
 
+/// Format the output list shown by `screen list --detailed`
+pub fn format_output_list(outputs: &[ivi_client::IviOutput]) -> String {
+    if outputs.is_empty() {
+        return String::from("No screens found");
+    }
+
+    let mut output = format!("Found {} output(s):\n", outputs.len());
+    for o in outputs {
+        output.push_str(&format!(
+            "  [{}] {} - {}x{} at ({}, {})\n",
+            o.index, o.name, o.width, o.height, o.x, o.y
+        ));
+    }
+    output.trim_end().to_string()
+}
+
 /// Format screen properties output
 pub fn format_screen_properties(screen: &ivi_client::IviScreen) -> String {
     format!(
@@ -857,6 +1329,7 @@ pub fn format_layer_screens(layer_id: u32, screen_names: &[String]) -> String {
 
 /// Format success message for screen set layers operation
 pub fn format_screen_set_layers_success(
+    style: OutputStyle,
     screen_name: &str,
     layer_ids: &[u32],
     auto_commit: bool,
@@ -867,23 +1340,30 @@ pub fn format_screen_set_layers_success(
         .collect::<Vec<_>>()
         .join(", ");
     let commit_msg = if auto_commit { " and committed" } else { "" };
-    format_success(&format!(
-        "Screen '{}' layers set to [{}]{}",
-        screen_name, layer_list, commit_msg
-    ))
+    format_success(
+        style,
+        &format!(
+            "Screen '{}' layers set to [{}]{}",
+            screen_name, layer_list, commit_msg
+        ),
+    )
 }
 
 /// Format success message for screen remove layer operation
 pub fn format_screen_remove_layer_success(
+    style: OutputStyle,
     screen_name: &str,
     layer_id: u32,
     auto_commit: bool,
 ) -> String {
     let commit_msg = if auto_commit { " and committed" } else { "" };
-    format_success(&format!(
-        "Layer {} removed from screen '{}'{}",
-        layer_id, screen_name, commit_msg
-    ))
+    format_success(
+        style,
+        &format!(
+            "Layer {} removed from screen '{}'{}",
+            layer_id, screen_name, commit_msg
+        ),
+    )
 }
 
 /// Format layer surfaces list output
@@ -903,8 +1383,17 @@ pub fn format_layer_surfaces(layer_id: u32, surface_ids: &[u32]) -> String {
     output.trim_end().to_string()
 }
 
+/// Format a render order as comma-separated IDs, bottommost first
+///
+/// The output can be passed back to `layer set-surfaces`.
+pub fn format_render_order(surface_ids: &[u32]) -> String {
+    let ids: Vec<String> = surface_ids.iter().map(|id| id.to_string()).collect();
+    ids.join(",")
+}
+
 /// Format success message for layer set surfaces operation
 pub fn format_layer_set_surfaces_success(
+    style: OutputStyle,
     layer_id: u32,
     surface_ids: &[u32],
     auto_commit: bool,
@@ -915,41 +1404,182 @@ pub fn format_layer_set_surfaces_success(
         .collect::<Vec<_>>()
         .join(", ");
     let commit_msg = if auto_commit { " and committed" } else { "" };
-    format_success(&format!(
-        "Layer {} surfaces set to [{}]{}",
-        layer_id, surface_list, commit_msg
-    ))
+    format_success(
+        style,
+        &format!(
+            "Layer {} surfaces set to [{}]{}",
+            layer_id, surface_list, commit_msg
+        ),
+    )
 }
 
 /// Format success message for layer add surface operation
 pub fn format_layer_add_surface_success(
+    style: OutputStyle,
     layer_id: u32,
     surface_id: u32,
     auto_commit: bool,
 ) -> String {
     let commit_msg = if auto_commit { " and committed" } else { "" };
-    format_success(&format!(
-        "Surface {} added to layer {}{}",
-        surface_id, layer_id, commit_msg
-    ))
+    format_success(
+        style,
+        &format!(
+            "Surface {} added to layer {}{}",
+            surface_id, layer_id, commit_msg
+        ),
+    )
 }
 
 /// Format success message for layer remove surface operation
 pub fn format_layer_remove_surface_success(
+    style: OutputStyle,
     layer_id: u32,
     surface_id: u32,
     auto_commit: bool,
 ) -> String {
     let commit_msg = if auto_commit { " and committed" } else { "" };
-    format_success(&format!(
-        "Surface {} removed from layer {}{}",
-        surface_id, layer_id, commit_msg
-    ))
+    format_success(
+        style,
+        &format!(
+            "Surface {} removed from layer {}{}",
+            surface_id, layer_id, commit_msg
+        ),
+    )
 }
 
 /// Format a success message for commit operation
-pub fn format_commit_success() -> String {
-    format_success("Changes committed")
+pub fn format_commit_success(style: OutputStyle) -> String {
+    format_success(style, "Changes committed")
+}
+
+/// Format a notification received by `watch` as a single line
+///
+/// Change events read as `[surface 1000] visibility false -> true`. Events
+/// without a known old/new pair fall back to the event name and its params.
+pub fn format_notification(event_type: &EventType, params: &Value) -> String {
+    let subject = if let Some(id) = params.get("surface_id") {
+        format!("[surface {}] ", id)
+    } else if let Some(id) = params.get("layer_id") {
+        format!("[layer {}] ", id)
+    } else {
+        String::new()
+    };
+
+    let change = match event_type {
+        EventType::SurfaceCreated | EventType::LayerCreated => Some("created".to_string()),
+        EventType::SurfaceDestroyed | EventType::LayerDestroyed => Some("destroyed".to_string()),
+        EventType::VisibilityChanged | EventType::LayerVisibilityChanged => {
+            format_change(params, "visibility", "old_visibility", "new_visibility")
+        }
+        EventType::OpacityChanged | EventType::LayerOpacityChanged => {
+            format_change(params, "opacity", "old_opacity", "new_opacity")
+        }
+        EventType::OrientationChanged => {
+            format_change(params, "orientation", "old_orientation", "new_orientation")
+        }
+        EventType::ZOrderChanged => format_change(params, "z-order", "old_z_order", "new_z_order"),
+        EventType::SourceGeometryChanged => format_change(params, "source", "old_rect", "new_rect"),
+        EventType::DestinationGeometryChanged => {
+            format_change(params, "destination", "old_rect", "new_rect")
+        }
+        EventType::FocusChanged => format_change(
+            params,
+            "focus",
+            "old_focused_surface",
+            "new_focused_surface",
+        ),
+        _ => None,
+    };
+
+    match change {
+        Some(change) => format!("{}{}", subject, change),
+        None => format!("{}{:?} {}", subject, event_type, params),
+    }
+}
+
+/// Format `label old -> new` from two fields of a notification
+fn format_change(params: &Value, label: &str, old: &str, new: &str) -> Option<String> {
+    Some(format!(
+        "{} {} -> {}",
+        label,
+        format_notification_value(params.get(old)?),
+        format_notification_value(params.get(new)?)
+    ))
+}
+
+/// Format a notification field: strings unquoted, rectangles as `WxH+X+Y`
+fn format_notification_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "none".to_string(),
+        Value::Object(rect) => match (
+            rect.get("x"),
+            rect.get("y"),
+            rect.get("width"),
+            rect.get("height"),
+        ) {
+            (Some(x), Some(y), Some(width), Some(height)) => {
+                format!("{}x{}+{}+{}", width, height, x, y)
+            }
+            _ => value.to_string(),
+        },
+        _ => value.to_string(),
+    }
+}
+
+/// Format the warning printed when `watch` requests events the controller has disabled
+pub fn format_ignored_events_warning(ignored: &[EventType]) -> String {
+    let names: Vec<String> = ignored.iter().map(|e| format!("{:?}", e)).collect();
+    format!(
+        "Warning: disabled in the controller configuration, will not be received: {}",
+        names.join(", ")
+    )
+}
+
+/// Format the summary printed when `watch` exits
+pub fn format_watch_summary(style: OutputStyle, received: usize) -> String {
+    format_success(style, &format!("Received {} event(s)", received))
+}
+
+/// Format a success message for scene dump
+pub fn format_scene_dump_success(style: OutputStyle, file: &str, scene: &SceneSnapshot) -> String {
+    format_success(
+        style,
+        &format!(
+            "Scene with {} surface(s), {} layer(s) and {} screen(s) written to {}",
+            scene.surfaces.len(),
+            scene.layers.len(),
+            scene.screens.len(),
+            file
+        ),
+    )
+}
+
+/// Format a success message for scene load
+pub fn format_scene_load_success(
+    style: OutputStyle,
+    file: &str,
+    scene: &SceneSnapshot,
+    skipped: &[u32],
+) -> String {
+    let mut output = format_success(
+        style,
+        &format!(
+            "Scene from {} restored ({} surface(s), {} layer(s), {} screen(s)) and committed",
+            file,
+            scene.surfaces.len() - skipped.len(),
+            scene.layers.len(),
+            scene.screens.len()
+        ),
+    );
+    if !skipped.is_empty() {
+        let ids: Vec<String> = skipped.iter().map(|id| id.to_string()).collect();
+        output.push_str(&format!(
+            "\n  Skipped missing surface(s): {}",
+            ids.join(", ")
+        ));
+    }
+    output
 }
 
 type HierarchicalScene = Vec<(IviScreen, Vec<(IviLayer, Vec<IviSurface>)>)>;
@@ -1072,17 +1702,50 @@ mod message_tests {
 
     #[test]
     fn test_format_success() {
-        assert_eq!(format_success("Test message"), "✓ Test message");
         assert_eq!(
-            format_success("Operation completed successfully"),
+            format_success(OutputStyle::GLYPHS, "Test message"),
+            "✓ Test message"
+        );
+        assert_eq!(
+            format_success(OutputStyle::GLYPHS, "Operation completed successfully"),
             "✓ Operation completed successfully"
         );
     }
 
+    #[test]
+    fn test_format_dry_run_success() {
+        let style = OutputStyle {
+            dry_run: true,
+            ..OutputStyle::PLAIN
+        };
+        assert_eq!(
+            format_surface_opacity_success(style, 1000, 0.5),
+            "OK: Dry run: Surface 1000 opacity set to 0.50 (valid, not applied)"
+        );
+    }
+
+    #[test]
+    fn test_format_plain_style() {
+        assert_eq!(
+            format_success(OutputStyle::PLAIN, "Test message"),
+            "OK: Test message"
+        );
+        assert_eq!(
+            format_commit_success(OutputStyle::PLAIN),
+            "OK: Changes committed"
+        );
+
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "File not found");
+        assert_eq!(
+            format_error(OutputStyle::PLAIN, &error),
+            "ERROR: File not found"
+        );
+    }
+
     #[test]
     fn test_format_error() {
         let error = std::io::Error::new(std::io::ErrorKind::NotFound, "File not found");
-        let formatted = format_error(&error);
+        let formatted = format_error(OutputStyle::GLYPHS, &error);
         assert!(formatted.starts_with("✗ Error:"));
         assert!(formatted.contains("File not found"));
     }
@@ -1090,11 +1753,11 @@ mod message_tests {
     #[test]
     fn test_format_surface_visibility_success() {
         assert_eq!(
-            format_surface_visibility_success(1000, true),
+            format_surface_visibility_success(OutputStyle::GLYPHS, 1000, true),
             "✓ Surface 1000 visibility set to true"
         );
         assert_eq!(
-            format_surface_visibility_success(1001, false),
+            format_surface_visibility_success(OutputStyle::GLYPHS, 1001, false),
             "✓ Surface 1001 visibility set to false"
         );
     }
@@ -1102,15 +1765,15 @@ mod message_tests {
     #[test]
     fn test_format_surface_opacity_success() {
         assert_eq!(
-            format_surface_opacity_success(1000, 1.0),
+            format_surface_opacity_success(OutputStyle::GLYPHS, 1000, 1.0),
             "✓ Surface 1000 opacity set to 1.00"
         );
         assert_eq!(
-            format_surface_opacity_success(1001, 0.5),
+            format_surface_opacity_success(OutputStyle::GLYPHS, 1001, 0.5),
             "✓ Surface 1001 opacity set to 0.50"
         );
         assert_eq!(
-            format_surface_opacity_success(1002, 0.123456),
+            format_surface_opacity_success(OutputStyle::GLYPHS, 1002, 0.123456),
             "✓ Surface 1002 opacity set to 0.12"
         );
     }
@@ -1118,11 +1781,11 @@ mod message_tests {
     #[test]
     fn test_format_surface_source_rect_success() {
         assert_eq!(
-            format_surface_source_rect_success(1000, 0, 0, 1920, 1080),
+            format_surface_source_rect_success(OutputStyle::GLYPHS, 1000, 0, 0, 1920, 1080),
             "✓ Surface 1000 source rectangle set to position (0, 0) and size 1920x1080"
         );
         assert_eq!(
-            format_surface_source_rect_success(1001, 100, 200, 800, 600),
+            format_surface_source_rect_success(OutputStyle::GLYPHS, 1001, 100, 200, 800, 600),
             "✓ Surface 1001 source rectangle set to position (100, 200) and size 800x600"
         );
     }
@@ -1130,11 +1793,11 @@ mod message_tests {
     #[test]
     fn test_format_surface_dest_rect_success() {
         assert_eq!(
-            format_surface_dest_rect_success(1000, 100, 200, 1920, 1080),
+            format_surface_dest_rect_success(OutputStyle::GLYPHS, 1000, 100, 200, 1920, 1080),
             "✓ Surface 1000 destination rectangle set to position (100, 200) and size 1920x1080"
         );
         assert_eq!(
-            format_surface_dest_rect_success(1001, -50, -100, 800, 600),
+            format_surface_dest_rect_success(OutputStyle::GLYPHS, 1001, -50, -100, 800, 600),
             "✓ Surface 1001 destination rectangle set to position (-50, -100) and size 800x600"
         );
     }
@@ -1142,59 +1805,250 @@ mod message_tests {
     #[test]
     fn test_format_surface_z_order_success() {
         assert_eq!(
-            format_surface_z_order_success(1000, 0),
+            format_surface_z_order_success(OutputStyle::GLYPHS, 1000, 0, 0),
             "✓ Surface 1000 z-order set to 0"
         );
         assert_eq!(
-            format_surface_z_order_success(1001, -5),
+            format_surface_z_order_success(OutputStyle::GLYPHS, 1001, -5, -5),
             "✓ Surface 1001 z-order set to -5"
         );
         assert_eq!(
-            format_surface_z_order_success(1002, 10),
+            format_surface_z_order_success(OutputStyle::GLYPHS, 1002, 10, 10),
             "✓ Surface 1002 z-order set to 10"
         );
+        assert_eq!(
+            format_surface_z_order_success(OutputStyle::GLYPHS, 1003, 10, 2),
+            "✓ Surface 1003 z-order set to 2 (requested 10)"
+        );
     }
 
     #[test]
     fn test_format_surface_focus_success() {
         assert_eq!(
-            format_surface_focus_success(1000),
+            format_surface_focus_success(OutputStyle::GLYPHS, 1000),
             "✓ Surface 1000 focus set"
         );
     }
 
+    #[test]
+    fn test_format_surface_focus() {
+        assert_eq!(format_surface_focus(Some(1000)), "Surface 1000 has focus");
+        assert_eq!(format_surface_focus(None), "No surface has focus");
+    }
+
+    #[test]
+    fn test_format_scene_success() {
+        let scene = SceneSnapshot {
+            version: ivi_client::SCENE_SNAPSHOT_VERSION,
+            surfaces: vec![],
+            layers: vec![],
+            screens: vec![],
+        };
+        assert_eq!(
+            format_scene_load_success(OutputStyle::GLYPHS, "scene.json", &scene, &[]),
+            "✓ Scene from scene.json restored (0 surface(s), 0 layer(s), 0 screen(s)) and committed"
+        );
+        assert_eq!(
+            format_scene_dump_success(OutputStyle::GLYPHS, "scene.json", &scene),
+            "✓ Scene with 0 surface(s), 0 layer(s) and 0 screen(s) written to scene.json"
+        );
+    }
+
+    #[test]
+    fn test_format_surface_clear_focus_success() {
+        assert_eq!(
+            format_surface_clear_focus_success(OutputStyle::GLYPHS),
+            "✓ Surface focus cleared"
+        );
+    }
+
     #[test]
     fn test_format_layer_visibility_success() {
         assert_eq!(
-            format_layer_visibility_success(2000, true),
+            format_layer_visibility_success(OutputStyle::GLYPHS, 2000, true, false),
             "✓ Layer 2000 visibility set to true"
         );
         assert_eq!(
-            format_layer_visibility_success(2001, false),
+            format_layer_visibility_success(OutputStyle::GLYPHS, 2001, false, false),
             "✓ Layer 2001 visibility set to false"
         );
+        assert_eq!(
+            format_layer_visibility_success(OutputStyle::GLYPHS, 2000, true, true),
+            "✓ Layer 2000 visibility set to true and committed"
+        );
+    }
+
+    #[test]
+    fn test_format_output_list() {
+        assert_eq!(format_output_list(&[]), "No screens found");
+
+        let outputs = vec![
+            ivi_client::IviOutput {
+                index: 0,
+                name: "HDMI-A-1".to_string(),
+                width: 1920,
+                height: 1080,
+                x: 0,
+                y: 0,
+            },
+            ivi_client::IviOutput {
+                index: 1,
+                name: "DP-1".to_string(),
+                width: 1280,
+                height: 720,
+                x: 1920,
+                y: 0,
+            },
+        ];
+        assert_eq!(
+            format_output_list(&outputs),
+            "Found 2 output(s):\n  [0] HDMI-A-1 - 1920x1080 at (0, 0)\n  [1] DP-1 - 1280x720 at (1920, 0)"
+        );
+    }
+
+    #[test]
+    fn test_format_apply_report() {
+        let results = vec![
+            ApplyResult {
+                line: 1,
+                command: "surface set-visibility 1000 true".to_string(),
+                error: None,
+            },
+            ApplyResult {
+                line: 3,
+                command: "surface set-opacity 1000 2".to_string(),
+                error: Some("Opacity must be between 0.0 and 1.0, got: 2".to_string()),
+            },
+        ];
+
+        let report: serde_json::Value =
+            serde_json::from_str(&format_apply_report(3, &results)).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "results": [
+                    {"line": 1, "command": "surface set-visibility 1000 true", "ok": true},
+                    {
+                        "line": 3,
+                        "command": "surface set-opacity 1000 2",
+                        "ok": false,
+                        "error": "Opacity must be between 0.0 and 1.0, got: 2"
+                    }
+                ],
+                "summary": {"total": 3, "succeeded": 1, "failed": 1}
+            })
+        );
+
+        assert_eq!(
+            format_apply_summary(OutputStyle::PLAIN, 3, &results),
+            "Warning: Applied 1 of 3 commands, 1 failed"
+        );
+        assert_eq!(
+            format_apply_summary(OutputStyle::PLAIN, 1, &results[..1]),
+            "OK: Applied 1 of 1 commands, 0 failed"
+        );
+    }
+
+    #[test]
+    fn test_format_bulk_report() {
+        let results = vec![
+            BulkResult {
+                id: 1000,
+                error: None,
+            },
+            BulkResult {
+                id: 1001,
+                error: None,
+            },
+        ];
+        assert_eq!(
+            format_bulk_report(OutputStyle::PLAIN, "visibility set to false", &results),
+            "ID          RESULT\n1000        OK\n1001        OK\nOK: Surface visibility set to false on 2 of 2 surfaces"
+        );
+
+        let results = vec![
+            BulkResult {
+                id: 1000,
+                error: Some("not changed".to_string()),
+            },
+            BulkResult {
+                id: 1001,
+                error: Some("not found".to_string()),
+            },
+        ];
+        assert_eq!(
+            format_bulk_report(OutputStyle::PLAIN, "opacity set to 0.50", &results),
+            "ID          RESULT\n1000        not changed\n1001        not found\nWarning: Surface opacity set to 0.50 on 0 of 2 surfaces"
+        );
+    }
+
+    #[test]
+    fn test_format_latency_stats() {
+        use std::time::Duration;
+
+        let stats = LatencyStats {
+            samples: 100,
+            min: Duration::from_micros(41),
+            avg: Duration::from_micros(67),
+            max: Duration::from_micros(1412),
+        };
+        assert_eq!(
+            format_latency_stats(&stats),
+            "Ping round trip over 100 request(s):\n  min: 0.041 ms\n  avg: 0.067 ms\n  max: 1.412 ms"
+        );
+    }
+
+    #[test]
+    fn test_format_stats() {
+        let stats = Stats {
+            surfaces: 5,
+            layers: 3,
+            connected_clients: 2,
+            subscriptions: 7,
+            pending_changes: 0,
+        };
+        assert_eq!(
+            format_stats(&stats),
+            "surfaces: 5 | layers: 3 | clients: 2 | subscriptions: 7 | pending: 0"
+        );
+    }
+
+    #[test]
+    fn test_format_nudge_success() {
+        assert_eq!(
+            format_nudge_success(OutputStyle::PLAIN, "Surface", 1000, 110, -10),
+            "OK: Surface 1000 moved to (110, -10)"
+        );
+        assert_eq!(
+            format_nudge_success(OutputStyle::PLAIN, "Layer", 2000, 0, 0),
+            "OK: Layer 2000 moved to (0, 0)"
+        );
     }
 
     #[test]
     fn test_format_layer_opacity_success() {
         assert_eq!(
-            format_layer_opacity_success(2000, 1.0),
+            format_layer_opacity_success(OutputStyle::GLYPHS, 2000, 1.0, false),
             "✓ Layer 2000 opacity set to 1.00"
         );
         assert_eq!(
-            format_layer_opacity_success(2001, 0.75),
+            format_layer_opacity_success(OutputStyle::GLYPHS, 2001, 0.75, false),
             "✓ Layer 2001 opacity set to 0.75"
         );
+        assert_eq!(
+            format_layer_opacity_success(OutputStyle::GLYPHS, 2001, 0.5, true),
+            "✓ Layer 2001 opacity set to 0.50 and committed"
+        );
     }
 
     #[test]
     fn test_format_layer_source_rect_success() {
         assert_eq!(
-            format_layer_source_rect_success(2000, 0, 0, 1920, 1080),
+            format_layer_source_rect_success(OutputStyle::GLYPHS, 2000, 0, 0, 1920, 1080),
             "✓ Layer 2000 source rectangle set to position (0, 0) and size 1920x1080"
         );
         assert_eq!(
-            format_layer_source_rect_success(2001, 100, 200, 800, 600),
+            format_layer_source_rect_success(OutputStyle::GLYPHS, 2001, 100, 200, 800, 600),
             "✓ Layer 2001 source rectangle set to position (100, 200) and size 800x600"
         );
     }
@@ -1202,17 +2056,110 @@ mod message_tests {
     #[test]
     fn test_format_layer_dest_rect_success() {
         assert_eq!(
-            format_layer_dest_rect_success(2000, 100, 200, 1920, 1080),
+            format_layer_dest_rect_success(OutputStyle::GLYPHS, 2000, 100, 200, 1920, 1080),
             "✓ Layer 2000 destination rectangle set to position (100, 200) and size 1920x1080"
         );
         assert_eq!(
-            format_layer_dest_rect_success(2001, -50, -100, 800, 600),
+            format_layer_dest_rect_success(OutputStyle::GLYPHS, 2001, -50, -100, 800, 600),
             "✓ Layer 2001 destination rectangle set to position (-50, -100) and size 800x600"
         );
     }
 
     #[test]
     fn test_format_commit_success() {
-        assert_eq!(format_commit_success(), "✓ Changes committed");
+        assert_eq!(
+            format_commit_success(OutputStyle::GLYPHS),
+            "✓ Changes committed"
+        );
+    }
+
+    #[test]
+    fn test_format_watch_summary() {
+        assert_eq!(
+            format_watch_summary(OutputStyle::GLYPHS, 0),
+            "✓ Received 0 event(s)"
+        );
+        assert_eq!(
+            format_watch_summary(OutputStyle::GLYPHS, 3),
+            "✓ Received 3 event(s)"
+        );
+    }
+
+    #[test]
+    fn test_format_surface_restack_success() {
+        assert_eq!(
+            format_surface_restack_success(OutputStyle::GLYPHS, 1000, true, &[2000, 2001]),
+            "✓ Surface 1000 moved to the front of layer(s) 2000, 2001"
+        );
+        assert_eq!(
+            format_surface_restack_success(OutputStyle::GLYPHS, 1000, false, &[2000]),
+            "✓ Surface 1000 moved to the back of layer(s) 2000"
+        );
+        assert_eq!(
+            format_surface_restack_success(OutputStyle::PLAIN, 1000, true, &[]),
+            "OK: Surface 1000 is not on any layer, nothing to move"
+        );
+    }
+
+    #[test]
+    fn test_format_render_order() {
+        assert_eq!(format_render_order(&[1002, 1000, 1001]), "1002,1000,1001");
+        assert_eq!(format_render_order(&[]), "");
+    }
+
+    #[test]
+    fn test_format_ignored_events_warning() {
+        assert_eq!(
+            format_ignored_events_warning(&[EventType::SurfaceCreated, EventType::LayerCreated]),
+            "Warning: disabled in the controller configuration, will not be received: \
+             SurfaceCreated, LayerCreated"
+        );
+    }
+
+    #[test]
+    fn test_format_notification() {
+        let params = serde_json::json!({"event_type": "SurfaceDestroyed", "surface_id": 1000});
+        assert_eq!(
+            format_notification(&EventType::SurfaceDestroyed, &params),
+            "[surface 1000] destroyed"
+        );
+
+        let params = serde_json::json!({
+            "event_type": "VisibilityChanged",
+            "surface_id": 268435456,
+            "old_visibility": false,
+            "new_visibility": true
+        });
+        assert_eq!(
+            format_notification(&EventType::VisibilityChanged, &params),
+            "[surface 268435456] visibility false -> true"
+        );
+
+        let params = serde_json::json!({
+            "event_type": "DestinationGeometryChanged",
+            "surface_id": 1000,
+            "old_rect": {"x": 0, "y": 0, "width": 800, "height": 600},
+            "new_rect": {"x": 100, "y": 50, "width": 1920, "height": 1080}
+        });
+        assert_eq!(
+            format_notification(&EventType::DestinationGeometryChanged, &params),
+            "[surface 1000] destination 800x600+0+0 -> 1920x1080+100+50"
+        );
+
+        let params = serde_json::json!({
+            "event_type": "FocusChanged",
+            "old_focused_surface": null,
+            "new_focused_surface": 1000
+        });
+        assert_eq!(
+            format_notification(&EventType::FocusChanged, &params),
+            "focus none -> 1000"
+        );
+
+        let params = serde_json::json!({"event_type": "RepaintComplete"});
+        assert_eq!(
+            format_notification(&EventType::RepaintComplete, &params),
+            r#"RepaintComplete {"event_type":"RepaintComplete"}"#
+        );
     }
 }
@@ -42,6 +42,10 @@ typedef enum IviErrorCode {
      Invalid parameter (null pointer, etc.)
      */
     INVALID_PARAM = -6,
+    /*
+     The controller did not answer within the request timeout
+     */
+    TIMEOUT = -7,
 } IviErrorCode;
 
 /*
@@ -63,14 +67,18 @@ typedef enum IviEventType {
     LAYER_OPACITY_CHANGED = 12,
     SURFACE_CONTENT_READY = 13,
     SURFACE_CONTENT_SIZE_CHANGED = 14,
+    REPAINT_COMPLETE = 15,
+    INPUT_PANEL_SHOWN = 16,
+    INPUT_PANEL_HIDDEN = 17,
 } IviEventType;
 
 /*
- Indicates whether a notification refers to a surface or a layer.
+ Indicates whether a notification refers to a surface, a layer or an output.
  */
 typedef enum IviObjectType {
     SURFACE = 0,
     LAYER = 1,
+    OUTPUT = 2,
 } IviObjectType;
 
 /*
@@ -87,6 +95,24 @@ typedef enum IviOrientation {
     FLIPPED270 = 7,
 } IviOrientation;
 
+/*
+ C-compatible shell surface kind enum
+ */
+typedef enum IviSurfaceType {
+    /*
+     Created through the ivi_application protocol
+     */
+    IVI = 0,
+    /*
+     Desktop (xdg-shell) surface adopted by ivi-shell
+     */
+    DESKTOP = 1,
+    /*
+     Input panel surface, such as a virtual keyboard
+     */
+    INPUT_PANEL = 2,
+} IviSurfaceType;
+
 typedef struct IviClient IviClient;
 
 /*
@@ -146,6 +172,14 @@ typedef struct IviSurface {
     float opacity;
     enum IviOrientation orientation;
     int32_t z_order;
+    /*
+     True if the surface is visible, on a visible layer, and that layer is on a screen
+     */
+    bool on_screen;
+    /*
+     Shell surface kind, `Ivi` until the surface has been configured
+     */
+    enum IviSurfaceType surface_type;
 } IviSurface;
 
 typedef uint32_t LayerId;
@@ -225,7 +259,7 @@ typedef struct IviContentSizeChange {
 
  Only the fields relevant to `event_type` are populated; all others are
  zero-initialised. Check `object_type` to determine whether `object_id`
- refers to a surface or a layer.
+ refers to a surface, a layer or an output.
 
  For `FocusChanged` events:
    - `object_type`   = `SURFACE`
@@ -236,7 +270,7 @@ typedef struct IviNotification {
     enum IviEventType event_type;
     enum IviObjectType object_type;
     /*
-     Current object ID (surface or layer). For focus events: new focused surface.
+     Current object ID (surface, layer or output). For focus events: new focused surface.
      */
     uint32_t object_id;
     /*
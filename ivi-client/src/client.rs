@@ -11,17 +11,22 @@ pub mod ipcon;
 
 use crate::error::{IviError, Result};
 use crate::ffi::*;
-use crate::protocol::{EventType, JsonRpcRequest, JsonRpcResponse, Notification};
+use crate::protocol::{
+    is_notification_frame, schema_version, EventType, JsonRpcRequest, JsonRpcResponse,
+    Notification, PROTOCOL_VERSION, SCHEMA_VERSION,
+};
+use crate::scene::SceneSnapshot;
 #[allow(unused)]
 use jlogger_tracing::{jdebug, jerror, jinfo, jtrace, jwarn};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io::ErrorKind;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[cfg(not(feature = "enable-ipcon"))]
 use unix_domain::UnixDomainIviClient;
@@ -35,26 +40,282 @@ pub enum IviRequestResult {
     GetLayer(IviLayer),
 }
 
+/// Warn when the controller sends surface or layer objects with a newer
+/// schema than this client understands. Unknown fields are ignored, so
+/// parsing still succeeds. Objects of an older schema lack the newer fields,
+/// which then take their defaults, such as `surface_type` before schema 2.
+fn check_schema_version(value: &Value) {
+    let version = schema_version(value);
+    if version > SCHEMA_VERSION {
+        jwarn!(
+            "Controller schema version {} is newer than supported version {}",
+            version,
+            SCHEMA_VERSION
+        );
+    } else if version < SCHEMA_VERSION {
+        jdebug!(
+            "Controller schema version {} is older than {}, missing fields take their defaults",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+}
+
+/// Extract the `exists` flag from a surface_exists or layer_exists response.
+fn parse_exists(value: &Value) -> Result<bool> {
+    value
+        .get("exists")
+        .and_then(|v| v.as_bool())
+        .ok_or_else(|| {
+            IviError::DeserializationError("Missing 'exists' field in response".to_string())
+        })
+}
+
+/// Number of surfaces fetched per request by [`IviClient::surfaces_iter`]
+const SURFACES_PAGE_SIZE: usize = 64;
+
+/// A page of surfaces returned by [`IviClient::list_surfaces_page`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurfacePage {
+    /// Surfaces in this page, ordered by surface ID
+    pub surfaces: Vec<IviSurface>,
+    /// Total number of surfaces known to the controller
+    pub total: usize,
+}
+
+/// Round-trip latency of `ping` requests, measured by
+/// [`IviClient::round_trip_latency`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyStats {
+    /// Number of pings sent
+    pub samples: usize,
+    /// Fastest round trip
+    pub min: Duration,
+    /// Mean round trip
+    pub avg: Duration,
+    /// Slowest round trip
+    pub max: Duration,
+}
+
+/// Limits of the controller, reported by [`IviClient::limits`].
+///
+/// Each limit is `None` if the controller does not report it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub struct Limits {
+    /// Largest frame payload, in bytes, the controller accepts
+    pub max_frame_bytes: Option<u64>,
+    /// Maximum number of event types one client may subscribe to
+    #[serde(rename = "max_subscriptions_per_client")]
+    pub max_subscriptions: Option<usize>,
+    /// Notifications buffered per client before the oldest is dropped
+    pub notify_queue_depth: Option<usize>,
+    /// Largest width or height accepted for surfaces and layers
+    pub max_surface_size: Option<i32>,
+}
+
+/// Capabilities of the controller, read by [`IviClient::handshake`].
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+pub struct Capabilities {
+    /// Wire protocol version, `None` if the controller predates it
+    pub protocol: Option<u32>,
+    /// Version of the controller plugin
+    pub version: Option<String>,
+    /// Transport the controller serves, `"unix"` or `"ipcon"`
+    pub transport: Option<String>,
+    /// Names of the RPC methods the controller accepts
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Limits of the controller
+    #[serde(flatten)]
+    pub limits: Limits,
+}
+
+impl Capabilities {
+    /// Whether the controller accepts `method`
+    pub fn supports(&self, method: &str) -> bool {
+        self.methods.iter().any(|m| m == method)
+    }
+}
+
+/// Position of a surface in the render order of one layer, reported by
+/// [`IviClient::get_surface_z_index`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct SurfaceZIndex {
+    /// Layer the surface is on
+    pub layer_id: u32,
+    /// Position in the render order of the layer, 0 being the bottommost
+    pub index: usize,
+    /// Number of surfaces on the layer
+    pub count: usize,
+}
+
+/// Summary of the controller state, reported by [`IviClient::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    /// Number of surfaces the controller knows
+    pub surfaces: usize,
+    /// Number of layers the controller knows
+    pub layers: usize,
+    /// Clients connected to the main socket
+    pub connected_clients: usize,
+    /// Subscribed event types, summed over all clients
+    pub subscriptions: usize,
+    /// Staged surface changes waiting for a commit, from all clients
+    pub pending_changes: usize,
+}
+
+/// Direction of a frame passed to a [`WireLogger`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireDirection {
+    /// A frame sent to the controller
+    Outgoing,
+    /// A frame received from the controller
+    Incoming,
+}
+
+/// Callback that observes the raw JSON bytes sent to and received from the controller.
+///
+/// The bytes are the JSON payload of a frame, without the length prefix.
+pub type WireLogger = Arc<dyn Fn(WireDirection, &[u8]) + Send + Sync + 'static>;
+
+/// Source of the ids of the requests an [`IviClient`] sends.
+pub type RequestIdGenerator = Box<dyn FnMut() -> u64 + Send + 'static>;
+
 trait IviClientTransport: Send {
     fn send_request(&mut self, request: &[u8]) -> Result<()>;
     fn receive_response(&mut self) -> Result<Vec<u8>>;
     fn disconnect(&mut self) -> Result<()>;
     fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<()>;
+
+    /// Bounds how long sending a request may block, `None` to block forever.
+    /// Transports without send timeouts ignore it
+    fn set_write_timeout(&mut self, _timeout: Option<Duration>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Address of the controller end of the connection, if the transport has one
+    fn peer_addr(&self) -> Option<String> {
+        None
+    }
+
+    /// Address of this end of the connection, if the transport has one
+    fn local_addr(&self) -> Option<String> {
+        None
+    }
+}
+
+/// How long `flush_notifications` waits for another frame before it stops
+const FLUSH_READ_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Error returned when a request could not be sent or its response did not
+/// arrive in time
+fn request_timeout(timeout: Duration) -> IviError {
+    IviError::Timeout(format!(
+        "Timed out talking to controller after {} ms",
+        timeout.as_millis()
+    ))
+}
+
+/// Builds an idempotency key prefix that no other client uses
+///
+/// The process id and start time tell processes apart, also across pid
+/// reuse; the counter tells apart clients of one process.
+fn idempotency_prefix() -> String {
+    static NEXT_CLIENT: AtomicU64 = AtomicU64::new(0);
+
+    let started = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!(
+        "{:x}-{:x}-{:x}",
+        std::process::id(),
+        started,
+        NEXT_CLIENT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Error returned by `subscribe_once` when no matching notification arrived
+fn notification_timeout(event_type: &EventType) -> IviError {
+    IviError::Timeout(format!(
+        "No matching {:?} notification before the timeout",
+        event_type
+    ))
 }
 
 pub struct IviClient {
     transport: Option<Box<dyn IviClientTransport>>,
 
+    /// Socket path (or IPCON server name) passed to `new` or
+    /// `set_socket_path`, used by `reconnect` and `try_clone`
+    remote: Option<String>,
+
     /// Atomic counter for generating unique request IDs
     request_id: AtomicU64,
+
+    /// Replaces `request_id` as the source of request IDs when set
+    id_generator: Option<RequestIdGenerator>,
+
+    /// How long to wait for the response to a request, `None` to wait forever
+    request_timeout: Option<Duration>,
+
+    /// Whether to reconnect and resend a request once when the connection
+    /// is lost, see `set_auto_reconnect`
+    auto_reconnect: bool,
+
+    /// Whether to send rectangles as `[x, y, width, height]` arrays, see
+    /// `set_compact_rectangles`
+    compact_rectangles: bool,
+
+    /// Whether to ask the controller to only check changes, see
+    /// `set_dry_run`
+    dry_run: bool,
+
+    /// Prefix of the idempotency keys of this client, unique per client
+    idempotency_prefix: String,
+
+    /// Counter for the idempotency keys of this client
+    next_idempotency_key: u64,
+
+    /// Capabilities read by `handshake`, kept across reconnects
+    capabilities: Option<Capabilities>,
+
+    /// Changes this client has made since its last commit
+    pending_changes: usize,
+
+    /// Observer for the raw request and response frames
+    wire_logger: Option<WireLogger>,
+
+    /// Notifications that arrived while waiting for a response, returned
+    /// by `next_notification` before reading from the connection
+    queued_notifications: VecDeque<Notification>,
+}
+
+impl std::fmt::Debug for IviClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let transport = self.transport.as_ref();
+        f.debug_struct("IviClient")
+            .field("connected", &transport.is_some())
+            .field("remote", &self.remote)
+            .field("peer_addr", &transport.and_then(|t| t.peer_addr()))
+            .field("local_addr", &transport.and_then(|t| t.local_addr()))
+            .field("next_request_id", &self.request_id.load(Ordering::SeqCst))
+            .field("id_generator", &self.id_generator.is_some())
+            .field("request_timeout", &self.request_timeout)
+            .field("auto_reconnect", &self.auto_reconnect)
+            .field("compact_rectangles", &self.compact_rectangles)
+            .field("dry_run", &self.dry_run)
+            .field("protocol_version", &self.protocol_version())
+            .field("pending_changes", &self.pending_changes)
+            .field("wire_logger", &self.wire_logger.is_some())
+            .field("queued_notifications", &self.queued_notifications.len())
+            .finish()
+    }
 }
 
 impl IviClient {
     pub fn new(remote: Option<&str>) -> Result<Self> {
-        let mut client = IviClient {
-            transport: None,
-            request_id: AtomicU64::new(1),
-        };
+        let mut client = Self::unconnected(remote);
 
         #[cfg(not(feature = "enable-ipcon"))]
         client.ud_connect(remote)?;
@@ -65,6 +326,160 @@ impl IviClient {
         Ok(client)
     }
 
+    /// A client with default settings and no connection yet
+    fn unconnected(remote: Option<&str>) -> Self {
+        IviClient {
+            transport: None,
+            remote: remote.map(str::to_string),
+            request_id: AtomicU64::new(1),
+            id_generator: None,
+            request_timeout: None,
+            auto_reconnect: false,
+            compact_rectangles: false,
+            dry_run: false,
+            idempotency_prefix: idempotency_prefix(),
+            next_idempotency_key: 1,
+            capabilities: None,
+            pending_changes: 0,
+            wire_logger: None,
+            queued_notifications: VecDeque::new(),
+        }
+    }
+
+    /// Connects to the controller and fails requests that take longer than `timeout`.
+    ///
+    /// Like [`new`](Self::new) followed by
+    /// [`set_request_timeout`](Self::set_request_timeout), so a hung
+    /// controller makes each request fail with [`IviError::Timeout`] instead
+    /// of blocking forever, both while sending it and while waiting for its
+    /// response.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IviError::ConnectionFailed` if the controller cannot be reached.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::connect_with_timeout(
+    ///     Some("/tmp/weston-ivi-controller.sock"),
+    ///     Duration::from_secs(2),
+    /// )?;
+    /// client.list_surfaces()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn connect_with_timeout(remote: Option<&str>, timeout: Duration) -> Result<Self> {
+        let mut client = Self::new(remote)?;
+        client.set_request_timeout(Some(timeout));
+        Ok(client)
+    }
+
+    /// Connects to the controller and reads its capabilities right away.
+    ///
+    /// Like [`new`](Self::new) followed by [`handshake`](Self::handshake),
+    /// so [`capabilities`](Self::capabilities) and
+    /// [`protocol_version`](Self::protocol_version) are known without
+    /// further requests and a controller speaking a newer protocol is
+    /// reported at connect time. `new` skips the extra round trip for
+    /// latency-sensitive users.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the controller cannot be reached or does not
+    /// answer `get_capabilities`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let client = IviClient::connect_with_handshake(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// if !client.capabilities().unwrap().supports("stats") {
+    ///     println!("Controller is too old for stats");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn connect_with_handshake(remote: Option<&str>) -> Result<Self> {
+        let mut client = Self::new(remote)?;
+        client.handshake()?;
+        Ok(client)
+    }
+
+    /// Reads the capabilities of the controller and keeps them on the client.
+    ///
+    /// Logs a warning if the controller speaks a newer protocol than
+    /// [`PROTOCOL_VERSION`](crate::PROTOCOL_VERSION), since frames of newer
+    /// protocol features may then be misread. After a handshake,
+    /// [`reconnect`](Self::reconnect) repeats it, as the controller may
+    /// have been replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    pub fn handshake(&mut self) -> Result<&Capabilities> {
+        let result = self.send_request("get_capabilities", json!({}))?;
+        let capabilities: Capabilities = serde_json::from_value(result)
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+
+        match capabilities.protocol {
+            Some(protocol) if protocol > PROTOCOL_VERSION => jwarn!(
+                "Controller speaks protocol {}, this client only protocol {}",
+                protocol,
+                PROTOCOL_VERSION
+            ),
+            Some(protocol) => jdebug!("Controller speaks protocol {}", protocol),
+            None => jdebug!("Controller predates protocol versions"),
+        }
+
+        Ok(self.capabilities.insert(capabilities))
+    }
+
+    /// Capabilities read by the last [`handshake`](Self::handshake), if any.
+    pub fn capabilities(&self) -> Option<&Capabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Wire protocol version of the controller, known after a
+    /// [`handshake`](Self::handshake) with a controller that reports it.
+    pub fn protocol_version(&self) -> Option<u32> {
+        self.capabilities.as_ref().and_then(|c| c.protocol)
+    }
+
+    /// Connects to a controller listening on a Linux abstract socket.
+    ///
+    /// Same as `IviClient::new(Some("@name"))`: a socket path starting with
+    /// `@` names an abstract socket everywhere a path is accepted, including
+    /// [`set_socket_path`](Self::set_socket_path) and the C API. Abstract sockets leave
+    /// no socket file behind, so a controller that crashed never blocks the
+    /// next one, which suits containers sharing a network namespace.
+    ///
+    /// # Errors
+    ///
+    /// Returns `IviError::ConnectionFailed` if no controller listens on `name`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// // Controller started with --socket-path=@weston-ivi-controller
+    /// let client = IviClient::connect_abstract("weston-ivi-controller")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(not(feature = "enable-ipcon"))]
+    pub fn connect_abstract(name: &str) -> Result<Self> {
+        Self::new(Some(&format!("@{}", name)))
+    }
+
     #[cfg(not(feature = "enable-ipcon"))]
     /// Connect to the IVI controller via a UNIX domain socket at the specified path.
     /// Ipcon connection is not supported via this method.
@@ -108,123 +523,92 @@ impl IviClient {
         }
     }
 
-    /// Generates the next unique request ID.
-    ///
-    /// This method uses an atomic counter to ensure thread-safe ID generation.
+    /// Sets the socket path (or IPCON server name) used by
+    /// [`reconnect`](Self::reconnect) and [`try_clone`](Self::try_clone).
     ///
-    /// # Returns
+    /// The current connection is left open; call `reconnect` to switch to
+    /// the new controller.
     ///
-    /// Returns a unique u64 request ID.
-    fn next_request_id(&self) -> u64 {
-        self.request_id.fetch_add(1, Ordering::SeqCst)
-    }
-
-    /// Sends a JSON-RPC request to the IVI controller and receives the response.
+    /// # Errors
     ///
-    /// This is an internal helper method that handles the low-level communication:
-    /// 1. Generates a unique request ID
-    /// 2. Serializes the request to JSON
-    /// 3. Sends the request over the socket with newline termination
-    /// 4. Receives the response from the socket
-    /// 5. Deserializes and validates the response
+    /// Returns `IviError::IoError` with `InvalidInput` if `path` is empty.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `method` - The JSON-RPC method name to invoke
-    /// * `params` - The parameters to pass to the method as a JSON value
+    /// ```no_run
+    /// use ivi_client::IviClient;
     ///
-    /// # Returns
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_socket_path("/run/compositor-2/weston-ivi-controller.sock")?;
+    /// client.reconnect()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_socket_path(&mut self, path: &str) -> Result<()> {
+        if path.is_empty() {
+            return Err(IviError::IoError(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                "Socket path must not be empty",
+            )));
+        }
+        self.remote = Some(path.to_string());
+        Ok(())
+    }
+
+    /// Closes the current connection, if any, and connects again to the
+    /// socket path set by [`set_socket_path`](Self::set_socket_path) or
+    /// given to [`new`](Self::new).
     ///
-    /// Returns the result value from a successful response, or an error if:
-    /// - Serialization fails
-    /// - Network communication fails
-    /// - Deserialization fails
-    /// - The server returns an error response
+    /// Notifications queued on the old connection are dropped and the
+    /// pending change count is reset, since the controller tracks both per
+    /// connection. Subscriptions are not carried over. If the client did a
+    /// [`handshake`](Self::handshake), it is repeated on the new connection.
     ///
     /// # Errors
     ///
-    /// - `IviError::SerializationError` - Failed to serialize the request
-    /// - `IviError::IoError` - Network communication error
-    /// - `IviError::DeserializationError` - Failed to deserialize the response
-    /// - `IviError::RequestFailed` - The server returned an error response
-    pub(crate) fn send_request(&mut self, method: &str, params: Value) -> Result<Value> {
-        // Generate unique request ID
-        let request_id = self.next_request_id();
-
-        // Create JSON-RPC request
-        let request = JsonRpcRequest::new(request_id, method, params);
-
-        jtrace!(
-            event = "ivi_client_send_request",
-            request_id = request_id,
-            method = method,
-            request = format!("{:?}", request.params)
-        );
-
-        // Serialize request to JSON (as bytes for length-prefix protocol)
-        let request_json = serde_json::to_vec(&request)
-            .map_err(|e| IviError::SerializationError(e.to_string()))?;
-
-        let transport = self.transport.as_mut().ok_or_else(|| {
-            IviError::ConnectionFailed("No active connection to send request.".to_string())
-        })?;
-
-        transport.send_request(&request_json)?;
-        let response_buf = transport.receive_response()?;
+    /// Returns `IviError::ConnectionFailed` if the controller cannot be reached.
+    pub fn reconnect(&mut self) -> Result<()> {
+        if let Err(e) = self.disconnect() {
+            jwarn!("Failed to close the old connection: {}", e);
+        }
+        self.queued_notifications.clear();
+        self.pending_changes = 0;
+        let handshake = self.capabilities.take().is_some();
 
-        // Deserialize response
-        let response: JsonRpcResponse = serde_json::from_slice(&response_buf)
-            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        let remote = self.remote.clone();
 
-        jtrace!(
-            event = "ivi_client_receive_response",
-            request_id = request_id,
-            result = response
-                .result
-                .as_ref()
-                .map(|r| format!("{:?}", r))
-                .unwrap_or("None".to_string()),
-            error = response
-                .error
-                .as_ref()
-                .map(|e| format!("{:?}", e))
-                .unwrap_or("None".to_string()),
-        );
+        #[cfg(not(feature = "enable-ipcon"))]
+        self.ud_connect(remote.as_deref())?;
 
-        // Verify response ID matches request ID
-        if response.id != request_id {
-            return Err(IviError::DeserializationError(format!(
-                "Response ID mismatch: expected {}, got {}",
-                request_id, response.id
-            )));
-        }
+        #[cfg(feature = "enable-ipcon")]
+        self.ipcon_connect(None, remote.as_deref())?;
 
-        // Check for error response
-        if let Some(error) = response.error {
-            return Err(IviError::RequestFailed {
-                code: error.code,
-                message: error.message,
-            });
+        if handshake {
+            self.handshake()?;
         }
-
-        // Return the result value
-        response.result.ok_or_else(|| {
-            IviError::DeserializationError("Response missing both result and error".to_string())
-        })
+        Ok(())
     }
 
-    /// Lists all available surfaces in the IVI compositor.
+    /// Opens a second, independent connection to the same controller.
+    ///
+    /// The new client connects to the current socket path of this client
+    /// and has its own request-id counter and frame reader, so the two can be
+    /// used from different threads without sharing a lock. The wire logger, if
+    /// any, is shared with the new client.
+    ///
+    /// The controller sees the two connections as separate clients: each gets
+    /// its own `ClientId`, subscriptions, and count of uncommitted changes.
+    /// Changes made through one client become visible to the other only once
+    /// they are committed.
     ///
     /// # Returns
     ///
-    /// Returns a vector of `Surface` objects representing all surfaces currently
-    /// managed by the IVI controller.
+    /// Returns a new connected `IviClient`.
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - Communication with the controller fails
-    /// - The response cannot be parsed
+    /// Returns `IviError::ConnectionFailed` if the controller can no longer be reached.
     ///
     /// # Example
     ///
@@ -233,88 +617,52 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// let surfaces = client.list_surfaces()?;
-    /// for surface in surfaces {
-    ///     println!("Surface ID: {}", surface.id);
-    /// }
+    /// let mut watcher = client.try_clone()?;
+    ///
+    /// let handle = std::thread::spawn(move || watcher.list_surfaces());
+    /// let layers = client.list_layers()?;
+    /// let surfaces = handle.join().unwrap()?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn list_surfaces(&mut self) -> Result<Vec<IviSurface>> {
-        let result = self.send_request("list_surfaces", json!({}))?;
-        jdebug!("list_surfaces result: {}", result);
+    pub fn try_clone(&self) -> Result<Self> {
+        let mut client = Self::new(self.remote.as_deref())?;
+        client.wire_logger = self.wire_logger.clone();
+        client.request_timeout = self.request_timeout;
+        client.auto_reconnect = self.auto_reconnect;
+        client.compact_rectangles = self.compact_rectangles;
+        client.dry_run = self.dry_run;
+        client.capabilities = self.capabilities.clone();
+        Ok(client)
+    }
 
-        // Extract the "surfaces" array from the result object
-        let surfaces: Vec<IviSurface> = serde_json::from_value(
-            result
-                .get("surfaces")
-                .ok_or_else(|| {
-                    IviError::DeserializationError(
-                        "Missing 'surfaces' field in response".to_string(),
-                    )
-                })?
-                .clone(),
-        )
-        .map_err(|e| IviError::DeserializationError(format!("Failed to parse surfaces: {}", e)))?;
-
-        Ok(surfaces)
+    /// Socket path (or IPCON server name) this client connects to.
+    pub(crate) fn remote(&self) -> Option<&str> {
+        self.remote.as_deref()
     }
 
-    /// Gets detailed properties of a specific surface.
-    ///
-    /// # Arguments
+    /// Generates the next unique request ID.
     ///
-    /// * `id` - The surface ID to query
+    /// This method uses the generator set with `set_id_generator`, or an
+    /// atomic counter to ensure thread-safe ID generation.
     ///
     /// # Returns
     ///
-    /// Returns a `Surface` object containing all properties of the specified surface.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if:
-    /// - The surface ID does not exist
-    /// - Communication with the controller fails
-    /// - The response cannot be parsed
-    ///
-    /// # Example
-    ///
-    /// ```no_run
-    /// use ivi_client::IviClient;
-    ///
-    /// # fn main() -> ivi_client::Result<()> {
-    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// let surface = client.get_surface(1000)?;
-    /// println!("Surface destination rectangle: {}", surface.dest_rect);
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn get_surface(&mut self, id: u32) -> Result<IviSurface> {
-        let result = self.send_request("get_surface", json!({ "id": id }))?;
-
-        // Parse the result as a surface
-        let surface: IviSurface = serde_json::from_value(result).map_err(|e| {
-            IviError::DeserializationError(format!("Failed to parse surface: {}", e))
-        })?;
-
-        Ok(surface)
+    /// Returns a u64 request ID.
+    fn next_request_id(&mut self) -> u64 {
+        match &mut self.id_generator {
+            Some(generator) => generator(),
+            None => self.request_id.fetch_add(1, Ordering::SeqCst),
+        }
     }
 
-    /// Sets the source rectangle of a surface (which part of the application buffer to display).
-    ///
-    /// # Arguments
-    ///
-    /// * `id` - The surface ID to modify
-    /// * `x` - The X coordinate in the source buffer
-    /// * `y` - The Y coordinate in the source buffer
-    /// * `width` - The width of the source rectangle in pixels
-    /// * `height` - The height of the source rectangle in pixels
-    ///
-    /// # Errors
+    /// Sets the function that generates the id of each request.
     ///
-    /// Returns an error if:
-    /// - The surface ID does not exist
-    /// - Communication with the controller fails
+    /// Use it to correlate requests with an external system, or to get
+    /// deterministic ids in tests. Pass `None` to go back to the built-in
+    /// counter. Responses are still matched against the id that was sent, so
+    /// a generator that repeats ids may accept a late response to an earlier
+    /// request that timed out. `try_clone` does not copy the generator.
     ///
     /// # Example
     ///
@@ -323,49 +671,34 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_surface_source_rectangle(1000, 0, 0, 1920, 1080, false)?;
+    /// let mut next = 1000;
+    /// client.set_id_generator(Some(Box::new(move || {
+    ///     next += 1;
+    ///     next
+    /// })));
+    /// client.list_surfaces()?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_surface_source_rectangle(
-        &mut self,
-        id: u32,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<()> {
-        use serde_json::json;
-
-        let value = json!({
-            "id": id,
-            "x": x,
-            "y": y,
-            "width": width,
-            "height": height,
-            "auto_commit": auto_commit
-        });
-
-        self.send_request("set_surface_source_rectangle", value)?;
-        Ok(())
+    pub fn set_id_generator(&mut self, generator: Option<RequestIdGenerator>) {
+        self.id_generator = generator;
     }
 
-    /// Sets the destination rectangle of a surface (where and at what size to display on screen).
-    ///
-    /// # Arguments
-    ///
-    /// * `id` - The surface ID to modify
-    /// * `x` - The X coordinate on screen
-    /// * `y` - The Y coordinate on screen
-    /// * `width` - The width on screen in pixels
-    /// * `height` - The height on screen in pixels
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if:
-    /// - The surface ID does not exist
-    /// - Communication with the controller fails
+    /// Sets whether a request is resent on a new connection when the
+    /// connection to the controller is lost.
+    ///
+    /// When enabled, a request that fails because the connection broke is
+    /// retried once after [`reconnect`](Self::reconnect). Every request then
+    /// carries an `idempotency_key`, and the retry reuses it, so the
+    /// controller answers a mutation it already applied with the result of
+    /// the first attempt instead of applying it twice. This makes retrying
+    /// safe for relative changes such as [`nudge_surface`](Self::nudge_surface).
+    /// The controller remembers keys for a minute.
+    ///
+    /// A reconnect starts a new controller session: subscriptions, queued
+    /// notifications and the pending change count are not carried over, so
+    /// enable this on connections used for requests only. Disabled by
+    /// default; `try_clone` copies the setting.
     ///
     /// # Example
     ///
@@ -374,37 +707,38 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_surface_destination_rectangle(1000, 100, 200, 1280, 720, false)?;
+    /// client.set_auto_reconnect(true);
+    /// client.nudge_surface(1000, 10, 0)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_surface_destination_rectangle(
-        &mut self,
-        id: u32,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<()> {
-        let value = json!({ "id": id, "x": x, "y": y, "width": width, "height": height, "auto_commit": auto_commit });
+    pub fn set_auto_reconnect(&mut self, enabled: bool) {
+        self.auto_reconnect = enabled;
+    }
 
-        self.send_request("set_surface_destination_rectangle", value)
-            .map(|_| ())
+    /// Sends rectangles in the compact `[x, y, width, height]` form.
+    ///
+    /// Rectangle setters such as
+    /// [`set_surface_destination_rectangle`](Self::set_surface_destination_rectangle)
+    /// and [`set_surface_rectangles`](Self::set_surface_rectangles) then
+    /// send each rectangle as a four-number array instead of an object with
+    /// named fields, which shortens frequent geometry updates such as
+    /// dragging. Controllers older than this form reject such requests, so
+    /// it is disabled by default; `try_clone` copies the setting.
+    pub fn set_compact_rectangles(&mut self, enabled: bool) {
+        self.compact_rectangles = enabled;
     }
 
-    /// Sets the visibility of a surface.
-    ///
-    /// # Arguments
-    ///
-    /// * `id` - The surface ID to modify
-    /// * `visible` - Whether the surface should be visible (true) or hidden (false)
+    /// Only checks changes instead of applying them.
     ///
-    /// # Errors
-    ///
-    /// Returns an error if:
-    /// - The surface ID does not exist
-    /// - Communication with the controller fails
+    /// Every request is sent with `dry_run: true`. The controller answers
+    /// requests that change the layout, including `commit`, after checking
+    /// them without applying anything: a valid request succeeds and an
+    /// invalid one fails with the error it would fail with when applied.
+    /// Requests that only read state run as usual. Setters that return a
+    /// value read it from the request, so e.g. a dry run of
+    /// [`create_layer`](Self::create_layer) returns the requested id.
+    /// `try_clone` copies the setting.
     ///
     /// # Example
     ///
@@ -413,168 +747,375 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_surface_visibility(1000, true, false)?;
+    /// client.set_dry_run(true);
+    /// // Fails if surface 1000 does not exist, changes nothing otherwise
+    /// client.set_surface_opacity(1000, 0.5, true)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_surface_visibility(
-        &mut self,
-        id: u32,
-        visible: bool,
-        auto_commit: bool,
-    ) -> Result<()> {
-        let value = json!({ "id": id, "visible": visible , "auto_commit": auto_commit});
+    pub fn set_dry_run(&mut self, enabled: bool) {
+        self.dry_run = enabled;
+    }
 
-        self.send_request("set_surface_visibility", value)
-            .map(|_| ())
+    /// Parameters of a single-rectangle setter for `id`
+    fn rectangle_params(&self, id: u32, rect: Rectangle, auto_commit: bool) -> Value {
+        if self.compact_rectangles {
+            json!({ "id": id, "rect": self.rectangle_value(rect), "auto_commit": auto_commit })
+        } else {
+            json!({
+                "id": id,
+                "x": rect.x,
+                "y": rect.y,
+                "width": rect.width,
+                "height": rect.height,
+                "auto_commit": auto_commit
+            })
+        }
     }
 
-    /// Sets the opacity of a surface.
-    ///
-    /// # Arguments
-    ///
-    /// * `id` - The surface ID to modify
-    /// * `opacity` - The opacity value (0.0 = fully transparent, 1.0 = fully opaque)
-    ///
-    /// # Errors
+    /// A rectangle parameter in the form chosen by `set_compact_rectangles`
+    fn rectangle_value(&self, rect: Rectangle) -> Value {
+        if self.compact_rectangles {
+            json!([rect.x, rect.y, rect.width, rect.height])
+        } else {
+            json!(rect)
+        }
+    }
+
+    /// Sets how long to wait for the response to each request.
     ///
-    /// Returns an error if:
-    /// - The surface ID does not exist
-    /// - The opacity value is outside the range 0.0 to 1.0
-    /// - Communication with the controller fails
+    /// A request that gets no response in time fails with
+    /// [`IviError::Timeout`], instead of blocking forever on a controller
+    /// that stopped answering. Its response may still arrive later; it is
+    /// then skipped as a response to another request. Sending a request to a
+    /// controller that stopped reading is bounded by the same timeout; a
+    /// request that was only partly sent leaves the connection unusable, so
+    /// [`reconnect`](Self::reconnect) after such a failure. `None`, the
+    /// default, waits forever. Waiting for notifications is not affected.
     ///
     /// # Example
     ///
     /// ```no_run
     /// use ivi_client::IviClient;
+    /// use std::time::Duration;
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_surface_opacity(1000, 0.75, false)?;
+    /// client.set_request_timeout(Some(Duration::from_secs(5)));
+    /// client.list_surfaces()?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_surface_opacity(&mut self, id: u32, opacity: f32, auto_commit: bool) -> Result<()> {
-        let value = json!({ "id": id, "opacity": opacity, "auto_commit": auto_commit });
-
-        self.send_request("set_surface_opacity", value).map(|_| ())
+    pub fn set_request_timeout(&mut self, timeout: Option<Duration>) {
+        self.request_timeout = timeout;
     }
 
-    /// Sets the z-order (stacking order) of a surface.
-    ///
-    /// # Arguments
-    ///
-    /// * `id` - The surface ID to modify
-    /// * `z_order` - The z-order value (higher values appear on top)
+    /// Sets a callback that observes every request sent and response received.
     ///
-    /// # Errors
-    ///
-    /// Returns an error if:
-    /// - The surface ID does not exist
-    /// - Communication with the controller fails
+    /// The callback receives the raw JSON of each frame. Pass `None` to remove it.
     ///
     /// # Example
     ///
     /// ```no_run
-    /// use ivi_client::IviClient;
+    /// use ivi_client::{IviClient, WireDirection};
+    /// use std::sync::Arc;
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_surface_z_order(1000, 10, false)?;
+    /// client.set_wire_logger(Some(Arc::new(|direction, bytes: &[u8]| {
+    ///     let prefix = match direction {
+    ///         WireDirection::Outgoing => ">>",
+    ///         WireDirection::Incoming => "<<",
+    ///     };
+    ///     eprintln!("{} {}", prefix, String::from_utf8_lossy(bytes));
+    /// })));
+    /// client.list_surfaces()?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_surface_z_order(&mut self, id: u32, z_order: i32, auto_commit: bool) -> Result<()> {
-        let value = json!({ "id": id, "z_order": z_order , "auto_commit": auto_commit });
-        self.send_request("set_surface_z_order", value).map(|_| ())
+    pub fn set_wire_logger(&mut self, logger: Option<WireLogger>) {
+        self.wire_logger = logger;
     }
 
-    /// Sets the input focus to a specific surface.
-    ///
-    /// # Arguments
-    ///
-    /// * `id` - The surface ID to receive focus
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if:
-    /// - The surface ID does not exist
-    /// - Communication with the controller fails
+    fn log_wire(&self, direction: WireDirection, bytes: &[u8]) {
+        if let Some(logger) = &self.wire_logger {
+            logger(direction, bytes);
+        }
+    }
+
+    /// Sends a JSON-RPC request to the IVI controller and receives the response.
     ///
-    /// # Example
+    /// This is an internal helper method that handles the low-level communication:
+    /// 1. Generates a unique request ID
+    /// 2. Serializes the request to JSON
+    /// 3. Sends the request over the socket with newline termination
+    /// 4. Receives the response from the socket
+    /// 5. Deserializes and validates the response
     ///
-    /// ```no_run
-    /// use ivi_client::IviClient;
+    /// # Arguments
     ///
-    /// # fn main() -> ivi_client::Result<()> {
-    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_surface_focus(1000, false)?;
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn set_surface_focus(&mut self, id: u32, auto_commit: bool) -> Result<()> {
-        let value = json!({ "id": id , "auto_commit": auto_commit });
-        self.send_request("set_surface_focus", value).map(|_| ())
-    }
-
-    /// Lists all available layers in the IVI compositor.
+    /// * `method` - The JSON-RPC method name to invoke
+    /// * `params` - The parameters to pass to the method as a JSON value
     ///
     /// # Returns
     ///
-    /// Returns a vector of `Layer` objects representing all layers currently
-    /// managed by the IVI controller.
+    /// Returns the result value from a successful response, or an error if:
+    /// - Serialization fails
+    /// - Network communication fails
+    /// - Deserialization fails
+    /// - The server returns an error response
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - Communication with the controller fails
-    /// - The response cannot be parsed
-    ///
-    /// # Example
+    /// - `IviError::SerializationError` - Failed to serialize the request
+    /// - `IviError::IoError` - Network communication error
+    /// - `IviError::Timeout` - No response within the request timeout
+    /// - `IviError::DeserializationError` - Failed to deserialize the response
+    /// - `IviError::RequestFailed` - The server returned an error response
     ///
-    /// ```no_run
-    /// use ivi_client::IviClient;
+    /// All errors have the method name appended to their message.
+    pub(crate) fn send_request(&mut self, method: &str, params: Value) -> Result<Value> {
+        let result = self
+            .send_request_inner(method, params)
+            .map_err(|e| e.with_method(method))?;
+        self.track_pending_changes(method, &result);
+        Ok(result)
+    }
+
+    /// Updates the local pending change count from a successful response.
     ///
-    /// # fn main() -> ivi_client::Result<()> {
-    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// let layers = client.list_layers()?;
-    /// for layer in layers {
-    ///     println!("Layer ID: {}", layer.id);
-    /// }
-    /// # Ok(())
-    /// # }
-    /// ```
-    pub fn list_layers(&mut self) -> Result<Vec<IviLayer>> {
-        let result = self.send_request("list_layers", json!({}))?;
+    /// Every mutating RPC reports whether it committed in its `committed` field.
+    fn track_pending_changes(&mut self, method: &str, result: &Value) {
+        // A dry run neither stages nor commits anything
+        if result.get("dry_run").and_then(|v| v.as_bool()) == Some(true) {
+            return;
+        }
 
-        // Extract the "layers" array from the result object
-        let layers: Vec<IviLayer> = serde_json::from_value(
-            result
-                .get("layers")
-                .ok_or_else(|| {
-                    IviError::DeserializationError("Missing 'layers' field in response".to_string())
-                })?
-                .clone(),
-        )
-        .map_err(|e| IviError::DeserializationError(format!("Failed to parse layers: {}", e)))?;
+        if method == "commit" {
+            // A single-surface commit leaves the changes of other surfaces pending
+            self.pending_changes = match result.get("surface_id") {
+                Some(_) => {
+                    let applied = result.get("applied").and_then(|v| v.as_u64()).unwrap_or(0);
+                    self.pending_changes.saturating_sub(applied as usize)
+                }
+                None => 0,
+            };
+            return;
+        }
 
-        Ok(layers)
+        match result.get("committed").and_then(|v| v.as_bool()) {
+            Some(true) => self.pending_changes = 0,
+            Some(false) => self.pending_changes += 1,
+            None => {}
+        }
     }
 
-    /// Gets detailed properties of a specific layer.
-    ///
-    /// # Arguments
+    fn send_request_inner(&mut self, method: &str, mut params: Value) -> Result<Value> {
+        // Generate unique request ID
+        let request_id = self.next_request_id();
+
+        if self.dry_run {
+            if let Value::Object(params) = &mut params {
+                params.insert("dry_run".to_string(), Value::Bool(true));
+            }
+        }
+
+        // Lets the controller recognize the resend after a reconnect
+        if self.auto_reconnect {
+            if let Value::Object(params) = &mut params {
+                let key = format!("{}-{}", self.idempotency_prefix, self.next_idempotency_key);
+                self.next_idempotency_key += 1;
+                params.insert("idempotency_key".to_string(), Value::String(key));
+            }
+        }
+
+        // Create JSON-RPC request
+        let request = JsonRpcRequest::new(request_id, method, params);
+
+        jtrace!(
+            event = "ivi_client_send_request",
+            request_id = request_id,
+            method = method,
+            request = format!("{:?}", request.params)
+        );
+
+        // Serialize request to JSON (as bytes for length-prefix protocol)
+        let request_json = serde_json::to_vec(&request)
+            .map_err(|e| IviError::SerializationError(e.to_string()))?;
+
+        let response = match self.exchange(&request_json, request_id) {
+            Err(e) if self.auto_reconnect && e.is_connection_failure() => {
+                jwarn!(
+                    "Connection lost while calling {}, reconnecting: {}",
+                    method,
+                    e
+                );
+                self.reconnect()?;
+                self.exchange(&request_json, request_id)?
+            }
+            result => result?,
+        };
+
+        jtrace!(
+            event = "ivi_client_receive_response",
+            request_id = request_id,
+            result = response
+                .result
+                .as_ref()
+                .map(|r| format!("{:?}", r))
+                .unwrap_or("None".to_string()),
+            error = response
+                .error
+                .as_ref()
+                .map(|e| format!("{:?}", e))
+                .unwrap_or("None".to_string()),
+        );
+
+        // Verify the response is for the method we called, if the controller says
+        if !response.matches_method(method) {
+            return Err(IviError::DeserializationError(format!(
+                "Response method mismatch: expected {}, got {}",
+                method,
+                response.method.as_deref().unwrap_or_default()
+            )));
+        }
+
+        // Check for error response
+        if let Some(error) = response.error {
+            return Err(IviError::RequestFailed {
+                code: error.code,
+                message: error.message,
+            });
+        }
+
+        // Return the result value
+        response.result.ok_or_else(|| {
+            IviError::DeserializationError("Response missing both result and error".to_string())
+        })
+    }
+
+    /// Sends a serialized request and waits for its response.
+    fn exchange(&mut self, request_json: &[u8], request_id: u64) -> Result<JsonRpcResponse> {
+        self.log_wire(WireDirection::Outgoing, request_json);
+
+        let timeout = self.request_timeout;
+        let transport = self.transport.as_mut().ok_or_else(|| {
+            IviError::ConnectionFailed("No active connection to send request.".to_string())
+        })?;
+
+        transport.set_write_timeout(timeout)?;
+        if let Err(e) = transport.send_request(request_json) {
+            return Err(match timeout {
+                Some(timeout) if e.is_timeout() => {
+                    // Part of the request may have been written, which leaves
+                    // the connection out of step with the controller
+                    self.transport = None;
+                    request_timeout(timeout)
+                }
+                _ => e,
+            });
+        }
+        self.receive_response_for(request_id)
+    }
+
+    /// Reads the next frame from the controller, waiting until `deadline`.
     ///
-    /// * `id` - The layer ID to query
+    /// Returns `Ok(None)` if no frame arrives before `deadline`; without a
+    /// deadline, blocks until one does. The read timeout is cleared again
+    /// before returning, and every frame read is passed to the wire logger.
+    fn read_frame_until(&mut self, deadline: Option<Instant>) -> Result<Option<Vec<u8>>> {
+        let transport = self.transport.as_mut().ok_or_else(|| {
+            IviError::ConnectionFailed("No active connection to read from.".to_string())
+        })?;
+
+        let frame = match deadline {
+            None => transport.receive_response()?,
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Ok(None);
+                }
+                transport.set_read_timeout(Some(remaining))?;
+                let result = transport.receive_response();
+                transport.set_read_timeout(None)?;
+                match result {
+                    Ok(frame) => frame,
+                    Err(e) if e.is_timeout() => return Ok(None),
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+
+        self.log_wire(WireDirection::Incoming, &frame);
+        Ok(Some(frame))
+    }
+
+    /// Reads frames until the response to `request_id` arrives.
+    ///
+    /// A subscribed connection can receive notifications before the
+    /// response; these are told apart by [`is_notification_frame`] and
+    /// queued for `next_notification`. Responses to
+    /// other requests mean the connection is out of step with the
+    /// controller and are skipped with a warning.
+    fn receive_response_for(&mut self, request_id: u64) -> Result<JsonRpcResponse> {
+        let timeout = match self.request_timeout {
+            Some(timeout) => timeout,
+            None => return self.read_response(request_id, None),
+        };
+
+        match self.read_response(request_id, Some(Instant::now() + timeout)) {
+            Err(e) if e.is_timeout() => Err(request_timeout(timeout)),
+            result => result,
+        }
+    }
+
+    fn read_response(
+        &mut self,
+        request_id: u64,
+        deadline: Option<Instant>,
+    ) -> Result<JsonRpcResponse> {
+        loop {
+            let frame = match self.read_frame_until(deadline)? {
+                Some(frame) => frame,
+                None => return Err(std::io::Error::from(ErrorKind::TimedOut).into()),
+            };
+
+            let value: Value = serde_json::from_slice(&frame)
+                .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+
+            if is_notification_frame(&value) {
+                if let Some(notif) = notification_from_frame(&frame) {
+                    self.queued_notifications.push_back(notif);
+                }
+                continue;
+            }
+
+            let response: JsonRpcResponse = serde_json::from_value(value)
+                .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+
+            if response.id != request_id {
+                jwarn!(
+                    "Protocol desync: skipping response {} while waiting for {}",
+                    response.id,
+                    request_id
+                );
+                continue;
+            }
+
+            return Ok(response);
+        }
+    }
+
+    /// Lists all available surfaces in the IVI compositor.
     ///
     /// # Returns
     ///
-    /// Returns a `Layer` object containing all properties of the specified layer.
+    /// Returns a vector of `Surface` objects representing all surfaces currently
+    /// managed by the IVI controller.
     ///
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The layer ID does not exist
     /// - Communication with the controller fails
     /// - The response cannot be parsed
     ///
@@ -585,119 +1126,137 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// let layer = client.get_layer(2000)?;
-    /// println!("Layer visibility: {}", layer.visibility);
+    /// let surfaces = client.list_surfaces()?;
+    /// for surface in surfaces {
+    ///     println!("Surface ID: {}", surface.id);
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn get_layer(&mut self, id: u32) -> Result<IviLayer> {
-        let result = self.send_request("get_layer", json!({ "id": id }))?;
+    pub fn list_surfaces(&mut self) -> Result<Vec<IviSurface>> {
+        let result = self.send_request("list_surfaces", json!({}))?;
+        jdebug!("list_surfaces result: {}", result);
+        check_schema_version(&result);
 
-        // Parse the result as a layer
-        let layer: IviLayer = serde_json::from_value(result)
-            .map_err(|e| IviError::DeserializationError(format!("Failed to parse layer: {}", e)))?;
+        // Extract the "surfaces" array from the result object
+        let surfaces: Vec<IviSurface> = serde_json::from_value(
+            result
+                .get("surfaces")
+                .ok_or_else(|| {
+                    IviError::DeserializationError(
+                        "Missing 'surfaces' field in response".to_string(),
+                    )
+                })?
+                .clone(),
+        )
+        .map_err(|e| IviError::DeserializationError(format!("Failed to parse surfaces: {}", e)))?;
 
-        Ok(layer)
+        Ok(surfaces)
     }
 
-    /// Creates a new layer in the IVI compositor.
+    /// Lists one page of surfaces, ordered by surface ID.
     ///
     /// # Arguments
     ///
-    /// * `id` - The desired layer ID
-    /// * `width` - The width of the layer in pixels
-    /// * `height` - The height of the layer in pixels
+    /// * `offset` - Number of surfaces to skip
+    /// * `limit` - Maximum number of surfaces to return
+    ///
+    /// # Returns
+    ///
+    /// Returns a `SurfacePage` with the requested surfaces and the total number
+    /// of surfaces, so callers can tell when the last page has been reached.
     ///
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The layer ID already exists
     /// - Communication with the controller fails
-    /// - Invalid parameter
+    /// - The response cannot be parsed
     ///
     /// # Example
     ///
     /// ```no_run
     /// use ivi_client::IviClient;
+    ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.create_layer(2000, 1920, 1080, true)?;
+    /// let page = client.list_surfaces_page(0, 10)?;
+    /// println!("Showing {} of {} surfaces", page.surfaces.len(), page.total);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn create_layer(
-        &mut self,
-        id: u32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<IviRequestResult> {
-        let value =
-            json!({ "id": id, "width": width, "height": height , "auto_commit": auto_commit });
-        let result = self.send_request("create_layer", value)?;
+    pub fn list_surfaces_page(&mut self, offset: usize, limit: usize) -> Result<SurfacePage> {
+        let result =
+            self.send_request("list_surfaces", json!({ "offset": offset, "limit": limit }))?;
+        check_schema_version(&result);
 
-        let id = result
-            .get("id")
-            .ok_or_else(|| {
-                IviError::DeserializationError("Missing 'id' field in response".to_string())
-            })
-            .and_then(|value| {
-                value.as_u64().map(|v| v as u32).ok_or_else(|| {
+        let surfaces: Vec<IviSurface> = serde_json::from_value(
+            result
+                .get("surfaces")
+                .ok_or_else(|| {
                     IviError::DeserializationError(
-                        "Invalid 'id' field type in response".to_string(),
+                        "Missing 'surfaces' field in response".to_string(),
                     )
-                })
-            })?;
+                })?
+                .clone(),
+        )
+        .map_err(|e| IviError::DeserializationError(format!("Failed to parse surfaces: {}", e)))?;
 
-        Ok(IviRequestResult::CreateLayer(id))
+        let total = result
+            .get("total")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| {
+                IviError::DeserializationError("Missing 'total' field in response".to_string())
+            })? as usize;
+
+        Ok(SurfacePage { surfaces, total })
     }
 
-    /// Destroys an existing layer in the IVI compositor.
-    ///
-    /// # Arguments
-    ///
-    /// * `id` - The layer ID to destroy
-    /// * `auto_commit` - If true, automatically commits the changes
+    /// Returns an iterator over all surfaces that fetches them page by page.
     ///
-    /// # Errors
+    /// Unlike [`list_surfaces`](Self::list_surfaces), only one page of surfaces
+    /// is held in memory at a time, which keeps response size and latency
+    /// bounded on systems with many surfaces. Surfaces are yielded in ID order.
     ///
-    /// Returns an error if:
-    /// - The layer ID does not exist
-    /// - Communication with the controller fails
+    /// If a request fails, the error is yielded and iteration ends.
     ///
     /// # Example
     ///
     /// ```no_run
     /// use ivi_client::IviClient;
+    ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.destroy_layer(2000, true)?;
+    /// for surface in client.surfaces_iter() {
+    ///     println!("Surface ID: {}", surface?.id);
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn destroy_layer(&mut self, id: u32, auto_commit: bool) -> Result<()> {
-        self.send_request(
-            "destroy_layer",
-            json!({ "id": id, "auto_commit": auto_commit }),
-        )?;
-        Ok(())
+    pub fn surfaces_iter(&mut self) -> SurfacesIter<'_> {
+        SurfacesIter {
+            client: self,
+            offset: 0,
+            page: Vec::new().into_iter(),
+            done: false,
+        }
     }
 
-    /// Sets the source rectangle of a layer.
+    /// Gets detailed properties of a specific surface.
     ///
     /// # Arguments
     ///
-    /// * `id` - The layer ID to modify
-    /// * `x` - The X coordinate in the source
-    /// * `y` - The Y coordinate in the source
-    /// * `width` - The width of the source rectangle in pixels
-    /// * `height` - The height of the source rectangle in pixels
+    /// * `id` - The surface ID to query
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Surface` object containing all properties of the specified surface.
     ///
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The layer ID does not exist
+    /// - The surface ID does not exist
     /// - Communication with the controller fails
+    /// - The response cannot be parsed
     ///
     /// # Example
     ///
@@ -706,40 +1265,41 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_layer_source_rectangle(2000, 0, 0, 1920, 1080, false)?;
+    /// let surface = client.get_surface(1000)?;
+    /// println!("Surface destination rectangle: {}", surface.dest_rect);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_layer_source_rectangle(
-        &mut self,
-        id: u32,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<()> {
-        let value = json!({ "id": id, "x": x, "y": y, "width": width, "height": height, "auto_commit": auto_commit });
+    pub fn get_surface(&mut self, id: u32) -> Result<IviSurface> {
+        let result = self.send_request("get_surface", json!({ "id": id }))?;
+        check_schema_version(&result);
 
-        self.send_request("set_layer_source_rectangle", value)
-            .map(|_| ())
+        // Parse the result as a surface
+        let surface: IviSurface = serde_json::from_value(result).map_err(|e| {
+            IviError::DeserializationError(format!("Failed to parse surface: {}", e))
+        })?;
+
+        Ok(surface)
     }
 
-    /// Sets the destination rectangle of a layer.
+    /// Gets a surface as the IVI layout currently reports it.
+    ///
+    /// Unlike [`get_surface`](Self::get_surface), which answers from the state the
+    /// controller tracks, this reads every property from the compositor. A difference
+    /// between the two means the controller missed an event, which is what this is
+    /// meant to diagnose. It costs a full property read per call, so prefer
+    /// `get_surface` for everything else.
     ///
     /// # Arguments
     ///
-    /// * `id` - The layer ID to modify
-    /// * `x` - The X coordinate on screen
-    /// * `y` - The Y coordinate on screen
-    /// * `width` - The width on screen in pixels
-    /// * `height` - The height on screen in pixels
+    /// * `id` - The surface ID
     ///
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The layer ID does not exist
+    /// - The IVI layout has no surface with this ID
     /// - Communication with the controller fails
+    /// - The response cannot be parsed
     ///
     /// # Example
     ///
@@ -748,37 +1308,43 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_layer_destination_rectangle(2000, 0, 0, 1920, 1080, false)?;
+    /// let cached = client.get_surface(1000)?;
+    /// let live = client.get_surface_live(1000)?;
+    /// if cached != live {
+    ///     println!("State drift on surface 1000: {:?} vs {:?}", cached, live);
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_layer_destination_rectangle(
-        &mut self,
-        id: u32,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<()> {
-        let value = json!({ "id": id, "x": x, "y": y, "width": width, "height": height, "auto_commit": auto_commit });
-
-        self.send_request("set_layer_destination_rectangle", value)?;
-        Ok(())
-    }
+    pub fn get_surface_live(&mut self, id: u32) -> Result<IviSurface> {
+        let result = self.send_request("get_surface", json!({ "id": id, "live": true }))?;
+        check_schema_version(&result);
 
-    /// Sets the visibility of a layer.
+        let surface: IviSurface = serde_json::from_value(result).map_err(|e| {
+            IviError::DeserializationError(format!("Failed to parse surface: {}", e))
+        })?;
+
+        Ok(surface)
+    }
+
+    /// Checks whether a surface exists.
+    ///
+    /// Unlike [`get_surface`](Self::get_surface), a missing surface is not an error,
+    /// which makes this suitable for polling, e.g. waiting for a surface to be torn down.
     ///
     /// # Arguments
     ///
-    /// * `id` - The layer ID to modify
-    /// * `visible` - Whether the layer should be visible (true) or hidden (false)
+    /// * `id` - The surface ID to check
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the surface exists, `false` otherwise.
     ///
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The layer ID does not exist
     /// - Communication with the controller fails
+    /// - The response cannot be parsed
     ///
     /// # Example
     ///
@@ -787,33 +1353,31 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_layer_visibility(2000, true, false)?;
+    /// while client.surface_exists(1000)? {
+    ///     std::thread::sleep(std::time::Duration::from_millis(100));
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_layer_visibility(
-        &mut self,
-        id: u32,
-        visible: bool,
-        auto_commit: bool,
-    ) -> Result<()> {
-        let value = json!({ "id": id, "visible": visible , "auto_commit": auto_commit });
-
-        self.send_request("set_layer_visibility", value).map(|_| ())
+    pub fn surface_exists(&mut self, id: u32) -> Result<bool> {
+        let result = self.send_request("surface_exists", json!({ "id": id }))?;
+        parse_exists(&result)
     }
 
-    /// Sets the opacity of a layer.
+    /// Sets the source rectangle of a surface (which part of the application buffer to display).
     ///
     /// # Arguments
     ///
-    /// * `id` - The layer ID to modify
-    /// * `opacity` - The opacity value (0.0 = fully transparent, 1.0 = fully opaque)
+    /// * `id` - The surface ID to modify
+    /// * `x` - The X coordinate in the source buffer
+    /// * `y` - The Y coordinate in the source buffer
+    /// * `width` - The width of the source rectangle in pixels
+    /// * `height` - The height of the source rectangle in pixels
     ///
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The layer ID does not exist
-    /// - The opacity value is outside the range 0.0 to 1.0
+    /// - The surface ID does not exist
     /// - Communication with the controller fails
     ///
     /// # Example
@@ -823,301 +1387,495 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
-    /// client.set_layer_opacity(2000, 0.75, false)?;
+    /// client.set_surface_source_rectangle(1000, 0, 0, 1920, 1080, false)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn set_layer_opacity(&mut self, id: u32, opacity: f32, auto_commit: bool) -> Result<()> {
-        let value = json!({ "id": id, "opacity": opacity, "auto_commit": auto_commit });
+    pub fn set_surface_source_rectangle(
+        &mut self,
+        id: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<()> {
+        let rect = Rectangle {
+            x,
+            y,
+            width,
+            height,
+        };
+        let value = self.rectangle_params(id, rect, auto_commit);
 
-        self.send_request("set_layer_opacity", value).map(|_| ())
+        self.send_request("set_surface_source_rectangle", value)?;
+        Ok(())
     }
 
-    /// Lists all available screens (outputs) in the IVI compositor.
+    /// Sets the destination rectangle of a surface (where and at what size to display on screen).
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// Returns a vector of `IviScreen` structures containing information about each screen.
+    /// * `id` - The surface ID to modify
+    /// * `x` - The X coordinate on screen
+    /// * `y` - The Y coordinate on screen
+    /// * `width` - The width on screen in pixels
+    /// * `height` - The height on screen in pixels
     ///
     /// # Errors
     ///
-    /// Returns an error if communication with the controller fails.
-    pub fn list_screens(&mut self) -> Result<Vec<IviScreen>> {
-        let response = self.send_request("list_screens", json!({}))?;
-        let screens: Vec<IviScreen> = serde_json::from_value(response["screens"].clone())
-            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
-        Ok(screens)
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_surface_destination_rectangle(1000, 100, 200, 1280, 720, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_surface_destination_rectangle(
+        &mut self,
+        id: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<()> {
+        let rect = Rectangle {
+            x,
+            y,
+            width,
+            height,
+        };
+        let value = self.rectangle_params(id, rect, auto_commit);
+
+        self.send_request("set_surface_destination_rectangle", value)
+            .map(|_| ())
     }
 
-    /// Gets information about a specific screen by name.
+    /// Sets the source and destination rectangles of a surface together.
+    ///
+    /// Setting the crop and the placement in separate requests can show a
+    /// frame with the new crop at the old scale; here the controller applies
+    /// both before any commit. Both rectangles are validated before either
+    /// is changed.
     ///
     /// # Arguments
     ///
-    /// * `name` - The screen name (e.g., "HDMI-A-1")
+    /// * `id` - The surface ID to modify
+    /// * `src` - The region of the surface buffer to show
+    /// * `dest` - Where and at what size to show it on screen
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// Returns an `IviScreen` structure with screen information.
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - Either rectangle is out of range
+    /// - Communication with the controller fails
     ///
-    /// # Errors
+    /// # Example
     ///
-    /// Returns an error if the screen is not found or communication fails.
-    pub fn get_screen(&mut self, name: &str) -> Result<IviScreen> {
-        let response = self.send_request("get_screen", json!({ "name": name }))?;
-        let screen: IviScreen = serde_json::from_value(response)
-            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
-        Ok(screen)
+    /// ```no_run
+    /// use ivi_client::{IviClient, Rectangle};
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// // Show the centre of a 1280x720 camera feed in the bottom-right corner
+    /// let src = Rectangle { x: 320, y: 180, width: 640, height: 360 };
+    /// let dest = Rectangle { x: 1280, y: 720, width: 640, height: 360 };
+    /// client.set_surface_rectangles(1000, src, dest, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_surface_rectangles(
+        &mut self,
+        id: u32,
+        src: Rectangle,
+        dest: Rectangle,
+        auto_commit: bool,
+    ) -> Result<()> {
+        let value = json!({
+            "id": id,
+            "src": self.rectangle_value(src),
+            "dest": self.rectangle_value(dest),
+            "auto_commit": auto_commit
+        });
+
+        self.send_request("set_surface_rectangles", value)
+            .map(|_| ())
     }
 
-    /// Gets the list of layer IDs assigned to a screen.
+    /// Moves a surface by an offset relative to its current position.
+    ///
+    /// The controller adds the offset to the current destination position
+    /// (including a staged but uncommitted position) and keeps the size, so
+    /// consecutive nudges accumulate. The move is committed immediately.
     ///
     /// # Arguments
     ///
-    /// * `screen_name` - The screen name
+    /// * `id` - The surface ID to move
+    /// * `dx` - The horizontal offset in pixels
+    /// * `dy` - The vertical offset in pixels
     ///
     /// # Returns
     ///
-    /// Returns a vector of layer IDs in render order (first = topmost).
+    /// Returns the new `(x, y)` position of the surface.
     ///
     /// # Errors
     ///
-    /// Returns an error if the screen is not found or communication fails.
-    pub fn get_screen_layers(&mut self, screen_name: &str) -> Result<Vec<u32>> {
-        let response =
-            self.send_request("get_screen_layers", json!({ "screen_name": screen_name }))?;
-        let layer_ids: Vec<u32> = serde_json::from_value(response["layer_ids"].clone())
-            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
-        Ok(layer_ids)
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - The resulting position is out of range
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// let (x, y) = client.nudge_surface(1000, 10, -10)?;
+    /// println!("Surface 1000 is now at ({}, {})", x, y);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn nudge_surface(&mut self, id: u32, dx: i32, dy: i32) -> Result<(i32, i32)> {
+        self.nudge("set_surface_position", id, dx, dy)
     }
 
-    /// Gets the list of screen names that a layer is assigned to.
+    /// Moves a layer by an offset relative to its current position.
+    ///
+    /// The controller adds the offset to the current destination position
+    /// of the layer and keeps its size. The move is committed immediately.
     ///
     /// # Arguments
     ///
-    /// * `layer_id` - The layer ID
+    /// * `id` - The layer ID to move
+    /// * `dx` - The horizontal offset in pixels
+    /// * `dy` - The vertical offset in pixels
     ///
     /// # Returns
     ///
-    /// Returns a vector of screen names.
+    /// Returns the new `(x, y)` position of the layer.
     ///
     /// # Errors
     ///
-    /// Returns an error if the layer is not found or communication fails.
-    pub fn get_layer_screens(&mut self, layer_id: u32) -> Result<Vec<String>> {
-        let response = self.send_request("get_layer_screens", json!({ "layer_id": layer_id }))?;
-        let screen_names: Vec<String> = serde_json::from_value(response["screen_names"].clone())
-            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
-        Ok(screen_names)
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - The resulting position is out of range
+    /// - Communication with the controller fails
+    pub fn nudge_layer(&mut self, id: u32, dx: i32, dy: i32) -> Result<(i32, i32)> {
+        self.nudge("set_layer_position", id, dx, dy)
     }
 
-    /// Adds layers to a screen, setting the render order.
+    fn nudge(&mut self, method: &str, id: u32, dx: i32, dy: i32) -> Result<(i32, i32)> {
+        let value = json!({ "id": id, "dx": dx, "dy": dy, "relative": true, "auto_commit": true });
+        let result = self.send_request(method, value)?;
+        serde_json::from_value(json!([result["x"], result["y"]]))
+            .map_err(|e| IviError::DeserializationError(e.to_string()))
+    }
+
+    /// Sets the visibility of a surface.
     ///
-    /// This replaces all existing layers on the screen with the specified layers.
-    /// The order in the array determines the z-order (first element = topmost layer).
+    /// Visibility does not affect render order: a surface that is hidden and
+    /// shown again keeps its place among the other surfaces of its layer.
     ///
     /// # Arguments
     ///
-    /// * `screen_name` - The screen name
-    /// * `layer_ids` - Vector of layer IDs in desired render order
-    /// * `auto_commit` - If true, automatically commits the changes
+    /// * `id` - The surface ID to modify
+    /// * `visible` - Whether the surface should be visible (true) or hidden (false)
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// Returns `Ok(())` on success.
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - Communication with the controller fails
     ///
-    /// # Errors
+    /// # Example
     ///
-    /// Returns an error if the screen or any layer is not found, or communication fails.
-    pub fn add_layers_to_screen(
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_surface_visibility(1000, true, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_surface_visibility(
         &mut self,
-        screen_name: &str,
-        layer_ids: &[u32],
+        id: u32,
+        visible: bool,
         auto_commit: bool,
     ) -> Result<()> {
-        self.send_request(
-            "add_layers_to_screen",
-            json!({
-                "screen_name": screen_name,
-                "layer_ids": layer_ids,
-                "auto_commit": auto_commit
-            }),
-        )
-        .map(|_| ())
+        let value = json!({ "id": id, "visible": visible , "auto_commit": auto_commit});
+
+        self.send_request("set_surface_visibility", value)
+            .map(|_| ())
     }
 
-    /// Removes a layer from a screen.
+    /// Sets the visibility of several surfaces in one request.
+    ///
+    /// Either all surfaces are changed or, if one of them does not exist,
+    /// none is. As with [`set_surface_visibility`](Self::set_surface_visibility),
+    /// the render order of the surfaces within their layers is preserved.
     ///
     /// # Arguments
     ///
-    /// * `screen_name` - The screen name
-    /// * `layer_id` - The layer ID to remove
+    /// * `ids` - The surface IDs to modify
+    /// * `visible` - Whether the surfaces should be visible (true) or hidden (false)
     /// * `auto_commit` - If true, automatically commits the changes
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// Returns `Ok(())` on success.
+    /// Returns an error if:
+    /// - One of the surface IDs does not exist
+    /// - Communication with the controller fails
     ///
-    /// # Errors
+    /// # Example
     ///
-    /// Returns an error if the screen or layer is not found, or communication fails.
-    pub fn remove_layer_from_screen(
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_surfaces_visibility(&[1000, 1001, 1002], false, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_surfaces_visibility(
         &mut self,
-        screen_name: &str,
-        layer_id: u32,
+        ids: &[u32],
+        visible: bool,
         auto_commit: bool,
     ) -> Result<()> {
-        self.send_request(
-            "remove_layer_from_screen",
-            json!({
-                "screen_name": screen_name,
-                "layer_id": layer_id,
-                "auto_commit": auto_commit
-            }),
-        )
-        .map(|_| ())
+        let value = json!({ "ids": ids, "visible": visible, "auto_commit": auto_commit });
+
+        self.send_request("set_surfaces_visibility", value)
+            .map(|_| ())
     }
 
-    /// Sets the complete list of surfaces on a layer, replacing any existing surfaces.
+    /// Sets the opacity of several surfaces in one request.
     ///
-    /// The z-order is determined by the position in the array:
-    /// - First surface ID = bottommost (rendered first, behind others)
-    /// - Last surface ID = topmost (rendered last, in front of others)
+    /// Either all surfaces are changed or, if one of them does not exist,
+    /// none is.
     ///
     /// # Arguments
     ///
-    /// * `layer_id` - The ID of the layer
-    /// * `surface_ids` - Array of surface IDs in z-order (first=bottom, last=top)
+    /// * `ids` - The surface IDs to modify
+    /// * `opacity` - The opacity value (0.0 = fully transparent, 1.0 = fully opaque)
     /// * `auto_commit` - If true, automatically commits the changes
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// Returns `Ok(())` on success.
+    /// Returns an error if:
+    /// - One of the surface IDs does not exist
+    /// - The opacity value is out of range
+    /// - Communication with the controller fails
     ///
-    /// # Errors
+    /// # Example
     ///
-    /// Returns an error if the layer or any surface is not found, or communication fails.
-    pub fn set_surfaces_on_layer(
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_surfaces_opacity(&[1000, 1001, 1002], 0.5, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_surfaces_opacity(
         &mut self,
-        layer_id: u32,
-        surface_ids: &[u32],
+        ids: &[u32],
+        opacity: f32,
         auto_commit: bool,
     ) -> Result<()> {
-        self.send_request(
-            "set_layer_surfaces",
-            json!({
-                "layer_id": layer_id,
-                "surface_ids": surface_ids,
-                "auto_commit": auto_commit
-            }),
-        )
-        .map(|_| ())
+        let value = json!({ "ids": ids, "opacity": opacity, "auto_commit": auto_commit });
+
+        self.send_request("set_surfaces_opacity", value).map(|_| ())
     }
 
-    /// Adds a single surface to a layer as the topmost surface.
-    ///
-    /// The surface will be rendered on top of all other surfaces currently on the layer.
+    /// Sets the opacity of a surface.
     ///
     /// # Arguments
     ///
-    /// * `layer_id` - The ID of the layer
-    /// * `surface_id` - The ID of the surface to add
-    /// * `auto_commit` - If true, automatically commits the changes
+    /// * `id` - The surface ID to modify
+    /// * `opacity` - The opacity value (0.0 = fully transparent, 1.0 = fully opaque)
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// Returns `Ok(())` on success.
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - The opacity value is outside the range 0.0 to 1.0
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_surface_opacity(1000, 0.75, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_surface_opacity(&mut self, id: u32, opacity: f32, auto_commit: bool) -> Result<()> {
+        let value = json!({ "id": id, "opacity": opacity, "auto_commit": auto_commit });
+
+        self.send_request("set_surface_opacity", value).map(|_| ())
+    }
+
+    /// Rotates a surface clockwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The surface ID to modify
+    /// * `degrees` - The rotation, a multiple of 90 (negative values rotate
+    ///   counter-clockwise)
     ///
     /// # Errors
     ///
-    /// Returns an error if the layer or surface is not found, or communication fails.
-    pub fn add_surface_to_layer(
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - `degrees` is not a multiple of 90
+    /// - The compositor's IVI layout API cannot set orientations
+    /// - Communication with the controller fails
+    pub fn set_surface_orientation(
         &mut self,
-        layer_id: u32,
-        surface_id: u32,
+        id: u32,
+        degrees: i32,
         auto_commit: bool,
     ) -> Result<()> {
-        self.send_request(
-            "add_surface_to_layer",
-            json!({
-                "layer_id": layer_id,
-                "surface_id": surface_id,
-                "auto_commit": auto_commit
-            }),
-        )
-        .map(|_| ())
+        let value = json!({ "id": id, "orientation": degrees, "auto_commit": auto_commit });
+
+        self.send_request("set_surface_orientation", value)
+            .map(|_| ())
     }
 
-    /// Removes a surface from a layer.
+    /// Sets the z-order (stacking order) of a surface.
     ///
     /// # Arguments
     ///
-    /// * `layer_id` - The ID of the layer
-    /// * `surface_id` - The ID of the surface to remove
-    /// * `auto_commit` - If true, automatically commits the changes
+    /// * `id` - The surface ID to modify
+    /// * `z_order` - The z-order value (higher values appear on top)
     ///
     /// # Returns
     ///
-    /// Returns `Ok(())` on success.
+    /// Returns the z-order the controller actually applied. A z-order beyond the
+    /// number of surfaces on the layer is clamped to the topmost position. Older
+    /// controllers that do not report it yield the requested z-order.
     ///
     /// # Errors
     ///
-    /// Returns an error if the layer or surface is not found, or communication fails.
-    pub fn remove_surface_from_layer(
-        &mut self,
-        layer_id: u32,
-        surface_id: u32,
-        auto_commit: bool,
-    ) -> Result<()> {
-        self.send_request(
-            "remove_surface_from_layer",
-            json!({
-                "layer_id": layer_id,
-                "surface_id": surface_id,
-                "auto_commit": auto_commit
-            }),
-        )
-        .map(|_| ())
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// let z_order = client.set_surface_z_order(1000, 10, false)?;
+    /// println!("Surface 1000 is at z-order {}", z_order);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_surface_z_order(&mut self, id: u32, z_order: i32, auto_commit: bool) -> Result<i32> {
+        let value = json!({ "id": id, "z_order": z_order , "auto_commit": auto_commit });
+        let result = self.send_request("set_surface_z_order", value)?;
+
+        Ok(result
+            .get("z_order")
+            .and_then(|v| v.as_i64())
+            .map(|v| v as i32)
+            .unwrap_or(z_order))
     }
 
-    /// Gets the list of surface IDs currently assigned to a layer.
+    /// Moves a surface to the top of the render order of every layer it is on.
     ///
-    /// Returns surfaces in z-order:
-    /// - First ID = bottommost (rendered first, behind others)
-    /// - Last ID = topmost (rendered last, in front of others)
+    /// The other surfaces on those layers keep their relative order.
     ///
     /// # Arguments
     ///
-    /// * `layer_id` - The ID of the layer
+    /// * `id` - The surface ID to raise
+    /// * `auto_commit` - If true, commits the change immediately
     ///
     /// # Returns
     ///
-    /// Returns a vector of surface IDs in z-order.
+    /// Returns the IDs of the layers that were restacked.
     ///
     /// # Errors
     ///
-    /// Returns an error if the layer is not found or communication fails.
-    pub fn get_layer_surfaces(&mut self, layer_id: u32) -> Result<Vec<u32>> {
-        let response = self.send_request("get_layer_surfaces", json!({ "layer_id": layer_id }))?;
-        let surface_ids: Vec<u32> = serde_json::from_value(response["surface_ids"].clone())
-            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
-        Ok(surface_ids)
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.bring_surface_to_front(1000, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bring_surface_to_front(&mut self, id: u32, auto_commit: bool) -> Result<Vec<u32>> {
+        self.restack_surface("bring_surface_to_front", id, auto_commit)
     }
 
-    /// Commits all pending changes to the IVI compositor atomically.
+    /// Moves a surface to the bottom of the render order of every layer it is on.
     ///
-    /// This method applies all pending surface and layer modifications in a single
-    /// atomic operation, ensuring that changes are applied simultaneously without
-    /// visual artifacts or tearing.
+    /// The other surfaces on those layers keep their relative order.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The surface ID to lower
+    /// * `auto_commit` - If true, commits the change immediately
     ///
     /// # Returns
     ///
-    /// Returns `Ok(())` on successful commit, or an error if the commit operation fails.
+    /// Returns the IDs of the layers that were restacked.
     ///
     /// # Errors
     ///
     /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - Communication with the controller fails
+    pub fn send_surface_to_back(&mut self, id: u32, auto_commit: bool) -> Result<Vec<u32>> {
+        self.restack_surface("send_surface_to_back", id, auto_commit)
+    }
+
+    fn restack_surface(&mut self, method: &str, id: u32, auto_commit: bool) -> Result<Vec<u32>> {
+        let result = self.send_request(method, json!({ "id": id, "auto_commit": auto_commit }))?;
+        serde_json::from_value(result["layers"].clone())
+            .map_err(|e| IviError::DeserializationError(e.to_string()))
+    }
+
+    /// Sets the input focus to a specific surface.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The surface ID to receive focus
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The surface ID does not exist
     /// - Communication with the controller fails
-    /// - The commit operation fails on the server side
     ///
     /// # Example
     ///
@@ -1126,47 +1884,2010 @@ impl IviClient {
     ///
     /// # fn main() -> ivi_client::Result<()> {
     /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_surface_focus(1000, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_surface_focus(&mut self, id: u32, auto_commit: bool) -> Result<()> {
+        let value = json!({ "id": id , "auto_commit": auto_commit });
+        self.send_request("set_surface_focus", value).map(|_| ())
+    }
+
+    /// Gets the currently focused surface.
     ///
-    /// // Make multiple changes
-    /// client.set_surface_destination_rectangle(1000, 100, 200, 1920, 1080, false)?;
-    /// client.set_surface_visibility(1000, true, false)?;
+    /// # Returns
     ///
-    /// // Commit all changes atomically
-    /// client.commit()?;
+    /// Returns the ID of the focused surface, or `None` if no surface has focus.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// if let Some(id) = client.get_focus()? {
+    ///     println!("Surface {} has focus", id);
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn commit(&mut self) -> Result<()> {
-        self.send_request("commit", json!({})).map(|_| ())
+    pub fn get_focus(&mut self) -> Result<Option<u32>> {
+        let response = self.send_request("get_focus", json!({}))?;
+        let surface_id: Option<u32> = serde_json::from_value(response["surface_id"].clone())
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        Ok(surface_id)
     }
-}
-
-// ============================================================================
-// NotificationListener
-// ============================================================================
 
-/// Callback type for notification events.
-pub type NotificationCallback = Arc<dyn Fn(&Notification) + Send + Sync + 'static>;
+    /// Clears the input focus so that no surface is reported as focused.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.clear_focus()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clear_focus(&mut self) -> Result<()> {
+        self.send_request("clear_focus", json!({})).map(|_| ())
+    }
 
-/// Listens for event notifications from the IVI controller.
-///
-/// Opens its own dedicated socket connection so that notifications are not
-/// mixed with RPC responses on the shared `IviClient` connection.
-///
-/// # Example
-///
-/// ```no_run
-/// use ivi_client::{NotificationListener, EventType};
-///
-/// # fn main() -> ivi_client::Result<()> {
-/// let mut listener = NotificationListener::new(None)?;
-///
-/// listener.on(EventType::SurfaceCreated, |notif| {
-///     println!("Surface created: {}", notif.params["surface_id"]);
-/// });
-///
-/// listener.on_all(|notif| {
-///     println!("Event: {:?}", notif.event_type);
+    /// Lists all available layers in the IVI compositor.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of `Layer` objects representing all layers currently
+    /// managed by the IVI controller.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Communication with the controller fails
+    /// - The response cannot be parsed
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// let layers = client.list_layers()?;
+    /// for layer in layers {
+    ///     println!("Layer ID: {}", layer.id);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_layers(&mut self) -> Result<Vec<IviLayer>> {
+        let result = self.send_request("list_layers", json!({}))?;
+        check_schema_version(&result);
+
+        // Extract the "layers" array from the result object
+        let layers: Vec<IviLayer> = serde_json::from_value(
+            result
+                .get("layers")
+                .ok_or_else(|| {
+                    IviError::DeserializationError("Missing 'layers' field in response".to_string())
+                })?
+                .clone(),
+        )
+        .map_err(|e| IviError::DeserializationError(format!("Failed to parse layers: {}", e)))?;
+
+        Ok(layers)
+    }
+
+    /// Gets detailed properties of a specific layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to query
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Layer` object containing all properties of the specified layer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    /// - The response cannot be parsed
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// let layer = client.get_layer(2000)?;
+    /// println!("Layer visibility: {}", layer.visibility);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_layer(&mut self, id: u32) -> Result<IviLayer> {
+        let result = self.send_request("get_layer", json!({ "id": id }))?;
+        check_schema_version(&result);
+
+        // Parse the result as a layer
+        let layer: IviLayer = serde_json::from_value(result)
+            .map_err(|e| IviError::DeserializationError(format!("Failed to parse layer: {}", e)))?;
+
+        Ok(layer)
+    }
+
+    /// Checks whether a layer exists.
+    ///
+    /// Unlike [`get_layer`](Self::get_layer), a missing layer is not an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to check
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the layer exists, `false` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Communication with the controller fails
+    /// - The response cannot be parsed
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// if !client.layer_exists(2000)? {
+    ///     client.create_layer(2000, 1920, 1080, true)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layer_exists(&mut self, id: u32) -> Result<bool> {
+        let result = self.send_request("layer_exists", json!({ "id": id }))?;
+        parse_exists(&result)
+    }
+
+    /// Creates a new layer in the IVI compositor.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The desired layer ID
+    /// * `width` - The width of the layer in pixels
+    /// * `height` - The height of the layer in pixels
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID already exists
+    /// - Communication with the controller fails
+    /// - Invalid parameter
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.create_layer(2000, 1920, 1080, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_layer(
+        &mut self,
+        id: u32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<IviRequestResult> {
+        let value =
+            json!({ "id": id, "width": width, "height": height , "auto_commit": auto_commit });
+        let result = self.send_request("create_layer", value)?;
+
+        let id = result
+            .get("id")
+            .ok_or_else(|| {
+                IviError::DeserializationError("Missing 'id' field in response".to_string())
+            })
+            .and_then(|value| {
+                value.as_u64().map(|v| v as u32).ok_or_else(|| {
+                    IviError::DeserializationError(
+                        "Invalid 'id' field type in response".to_string(),
+                    )
+                })
+            })?;
+
+        Ok(IviRequestResult::CreateLayer(id))
+    }
+
+    /// Destroys an existing layer in the IVI compositor.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to destroy
+    /// * `auto_commit` - If true, automatically commits the changes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.destroy_layer(2000, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn destroy_layer(&mut self, id: u32, auto_commit: bool) -> Result<()> {
+        self.send_request(
+            "destroy_layer",
+            json!({ "id": id, "auto_commit": auto_commit }),
+        )?;
+        Ok(())
+    }
+
+    /// Sets the source rectangle of a layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to modify
+    /// * `x` - The X coordinate in the source
+    /// * `y` - The Y coordinate in the source
+    /// * `width` - The width of the source rectangle in pixels
+    /// * `height` - The height of the source rectangle in pixels
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_layer_source_rectangle(2000, 0, 0, 1920, 1080, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_layer_source_rectangle(
+        &mut self,
+        id: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<()> {
+        let rect = Rectangle {
+            x,
+            y,
+            width,
+            height,
+        };
+        let value = self.rectangle_params(id, rect, auto_commit);
+
+        self.send_request("set_layer_source_rectangle", value)
+            .map(|_| ())
+    }
+
+    /// Sets the destination rectangle of a layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to modify
+    /// * `x` - The X coordinate on screen
+    /// * `y` - The Y coordinate on screen
+    /// * `width` - The width on screen in pixels
+    /// * `height` - The height on screen in pixels
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_layer_destination_rectangle(2000, 0, 0, 1920, 1080, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_layer_destination_rectangle(
+        &mut self,
+        id: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<()> {
+        let rect = Rectangle {
+            x,
+            y,
+            width,
+            height,
+        };
+        let value = self.rectangle_params(id, rect, auto_commit);
+
+        self.send_request("set_layer_destination_rectangle", value)?;
+        Ok(())
+    }
+
+    /// Sets the visibility of a layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to modify
+    /// * `visible` - Whether the layer should be visible (true) or hidden (false)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_layer_visibility(2000, true, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_layer_visibility(
+        &mut self,
+        id: u32,
+        visible: bool,
+        auto_commit: bool,
+    ) -> Result<()> {
+        let value = json!({ "id": id, "visible": visible , "auto_commit": auto_commit });
+
+        self.send_request("set_layer_visibility", value).map(|_| ())
+    }
+
+    /// Sets the opacity of a layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to modify
+    /// * `opacity` - The opacity value (0.0 = fully transparent, 1.0 = fully opaque)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - The opacity value is outside the range 0.0 to 1.0
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_layer_opacity(2000, 0.75, false)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_layer_opacity(&mut self, id: u32, opacity: f32, auto_commit: bool) -> Result<()> {
+        let value = json!({ "id": id, "opacity": opacity, "auto_commit": auto_commit });
+
+        self.send_request("set_layer_opacity", value).map(|_| ())
+    }
+
+    /// Adjusts the opacity of a layer relative to its current value.
+    ///
+    /// The resulting opacity is clamped to the range 0.0 to 1.0.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to modify
+    /// * `delta` - The amount to add to the current opacity (may be negative)
+    /// * `auto_commit` - If true, automatically commits the changes
+    ///
+    /// # Returns
+    ///
+    /// Returns the new opacity of the layer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// let opacity = client.adjust_layer_opacity(2000, -0.25, true)?;
+    /// println!("Layer opacity is now {}", opacity);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn adjust_layer_opacity(&mut self, id: u32, delta: f32, auto_commit: bool) -> Result<f32> {
+        let value = json!({
+            "id": id,
+            "opacity": delta,
+            "relative": true,
+            "auto_commit": auto_commit
+        });
+
+        let result = self.send_request("set_layer_opacity", value)?;
+        let opacity = result
+            .get("opacity")
+            .and_then(|v| v.as_f64())
+            .ok_or_else(|| {
+                IviError::DeserializationError("Missing 'opacity' field in response".to_string())
+            })?;
+
+        Ok(opacity as f32)
+    }
+
+    /// Sets the transition animation used for the next changes of a layer.
+    ///
+    /// The animation runs when the changes are committed, e.g. a `Move`
+    /// transition animates a change of the destination rectangle.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to modify
+    /// * `transition` - The animation to use
+    /// * `duration_ms` - Duration of the animation in milliseconds
+    /// * `auto_commit` - If true, automatically commits the changes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::{IviClient, IviLayerTransition};
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_layer_transition(2000, IviLayerTransition::Move, 300, false)?;
+    /// client.set_layer_destination_rectangle(2000, 0, 0, 1920, 1080, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_layer_transition(
+        &mut self,
+        id: u32,
+        transition: IviLayerTransition,
+        duration_ms: u32,
+        auto_commit: bool,
+    ) -> Result<()> {
+        let value = json!({
+            "id": id,
+            "type": transition,
+            "duration": duration_ms,
+            "auto_commit": auto_commit
+        });
+
+        self.send_request("set_layer_transition", value).map(|_| ())
+    }
+
+    /// Cancels an in-progress move animation of a layer.
+    ///
+    /// The layer stays where the animation left it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The layer ID to modify
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.cancel_layer_transition(2000)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cancel_layer_transition(&mut self, id: u32) -> Result<()> {
+        self.send_request("cancel_layer_transition", json!({ "id": id }))
+            .map(|_| ())
+    }
+
+    /// Lists all available screens (outputs) in the IVI compositor.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of `IviScreen` structures containing information about each screen.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    pub fn list_screens(&mut self) -> Result<Vec<IviScreen>> {
+        let response = self.send_request("list_screens", json!({}))?;
+        let screens: Vec<IviScreen> = serde_json::from_value(response["screens"].clone())
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        Ok(screens)
+    }
+
+    /// Gets information about a specific screen by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The screen name (e.g., "HDMI-A-1")
+    ///
+    /// # Returns
+    ///
+    /// Returns an `IviScreen` structure with screen information.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the screen is not found or communication fails.
+    pub fn get_screen(&mut self, name: &str) -> Result<IviScreen> {
+        let response = self.send_request("get_screen", json!({ "name": name }))?;
+        let screen: IviScreen = serde_json::from_value(response)
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        Ok(screen)
+    }
+
+    /// Lists the geometry of all compositor outputs.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `IviOutput` for each output, in the compositor's output order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// for output in client.list_outputs()? {
+    ///     println!("{}: {}x{} at ({}, {})", output.name, output.width, output.height, output.x, output.y);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_outputs(&mut self) -> Result<Vec<IviOutput>> {
+        let response = self.send_request("list_outputs", json!({}))?;
+        serde_json::from_value(response["outputs"].clone())
+            .map_err(|e| IviError::DeserializationError(e.to_string()))
+    }
+
+    /// Gets the geometry of a compositor output.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the output in the compositor's output list
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is out of range or communication fails.
+    pub fn get_output(&mut self, index: u32) -> Result<IviOutput> {
+        let response = self.send_request("get_output", json!({ "index": index }))?;
+        serde_json::from_value(response).map_err(|e| IviError::DeserializationError(e.to_string()))
+    }
+
+    /// Gets the list of layer IDs assigned to a screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `screen_name` - The screen name
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of layer IDs in render order (first = topmost).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the screen is not found or communication fails.
+    pub fn get_screen_layers(&mut self, screen_name: &str) -> Result<Vec<u32>> {
+        let response =
+            self.send_request("get_screen_layers", json!({ "screen_name": screen_name }))?;
+        let layer_ids: Vec<u32> = serde_json::from_value(response["layer_ids"].clone())
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        Ok(layer_ids)
+    }
+
+    /// Lists the surfaces shown on a compositor output.
+    ///
+    /// Joins [`get_output`](Self::get_output),
+    /// [`get_screen_layers`](Self::get_screen_layers) and
+    /// [`get_layer_surfaces`](Self::get_layer_surfaces): a surface is listed
+    /// if it is visible and on a visible layer of the output's screen.
+    /// Surfaces are returned topmost first, each once even if it is on
+    /// several of the layers.
+    ///
+    /// The result is read with several requests, so a change made by another
+    /// client in the meantime may be seen only in part.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The position of the output in the compositor's output list
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is out of range or communication fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// for surface in client.surfaces_on_output(1)? {
+    ///     println!("Surface {} at {}", surface.id, surface.dest_rect);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn surfaces_on_output(&mut self, index: u32) -> Result<Vec<IviSurface>> {
+        let output = self.get_output(index)?;
+        let layer_ids = self.get_screen_layers(&output.name)?;
+        let visible_layers: Vec<u32> = self
+            .list_layers()?
+            .into_iter()
+            .filter(|layer| layer.visibility)
+            .map(|layer| layer.id)
+            .collect();
+        let mut surfaces: HashMap<u32, IviSurface> = self
+            .list_surfaces()?
+            .into_iter()
+            .map(|surface| (surface.id, surface))
+            .collect();
+
+        let mut on_output = Vec::new();
+        for layer_id in layer_ids {
+            if !visible_layers.contains(&layer_id) {
+                continue;
+            }
+
+            // Layers are listed topmost first, their surfaces bottommost first
+            for surface_id in self.get_layer_surfaces(layer_id)?.into_iter().rev() {
+                if let Some(surface) = surfaces.remove(&surface_id) {
+                    if surface.visibility {
+                        on_output.push(surface);
+                    }
+                }
+            }
+        }
+
+        Ok(on_output)
+    }
+
+    /// Gets the list of screen names that a layer is assigned to.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer_id` - The layer ID
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of screen names.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer is not found or communication fails.
+    pub fn get_layer_screens(&mut self, layer_id: u32) -> Result<Vec<String>> {
+        let response = self.send_request("get_layer_screens", json!({ "layer_id": layer_id }))?;
+        let screen_names: Vec<String> = serde_json::from_value(response["screen_names"].clone())
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        Ok(screen_names)
+    }
+
+    /// Adds layers to a screen, setting the render order.
+    ///
+    /// This replaces all existing layers on the screen with the specified layers.
+    /// The order in the array determines the z-order (first element = topmost layer).
+    ///
+    /// # Arguments
+    ///
+    /// * `screen_name` - The screen name
+    /// * `layer_ids` - Vector of layer IDs in desired render order
+    /// * `auto_commit` - If true, automatically commits the changes
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the screen or any layer is not found, or communication fails.
+    pub fn add_layers_to_screen(
+        &mut self,
+        screen_name: &str,
+        layer_ids: &[u32],
+        auto_commit: bool,
+    ) -> Result<()> {
+        self.send_request(
+            "add_layers_to_screen",
+            json!({
+                "screen_name": screen_name,
+                "layer_ids": layer_ids,
+                "auto_commit": auto_commit
+            }),
+        )
+        .map(|_| ())
+    }
+
+    /// Removes a layer from a screen.
+    ///
+    /// # Arguments
+    ///
+    /// * `screen_name` - The screen name
+    /// * `layer_id` - The layer ID to remove
+    /// * `auto_commit` - If true, automatically commits the changes
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the screen or layer is not found, or communication fails.
+    pub fn remove_layer_from_screen(
+        &mut self,
+        screen_name: &str,
+        layer_id: u32,
+        auto_commit: bool,
+    ) -> Result<()> {
+        self.send_request(
+            "remove_layer_from_screen",
+            json!({
+                "screen_name": screen_name,
+                "layer_id": layer_id,
+                "auto_commit": auto_commit
+            }),
+        )
+        .map(|_| ())
+    }
+
+    /// Sets the complete list of surfaces on a layer, replacing any existing surfaces.
+    ///
+    /// The z-order is determined by the position in the array:
+    /// - First surface ID = bottommost (rendered first, behind others)
+    /// - Last surface ID = topmost (rendered last, in front of others)
+    ///
+    /// # Arguments
+    ///
+    /// * `layer_id` - The ID of the layer
+    /// * `surface_ids` - Array of surface IDs in z-order (first=bottom, last=top)
+    /// * `auto_commit` - If true, automatically commits the changes
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer or any surface is not found, or communication fails.
+    pub fn set_surfaces_on_layer(
+        &mut self,
+        layer_id: u32,
+        surface_ids: &[u32],
+        auto_commit: bool,
+    ) -> Result<()> {
+        self.send_request(
+            "set_layer_surfaces",
+            json!({
+                "layer_id": layer_id,
+                "surface_ids": surface_ids,
+                "auto_commit": auto_commit
+            }),
+        )
+        .map(|_| ())
+    }
+
+    /// Adds a single surface to a layer as the topmost surface.
+    ///
+    /// The surface will be rendered on top of all other surfaces currently on the layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer_id` - The ID of the layer
+    /// * `surface_id` - The ID of the surface to add
+    /// * `auto_commit` - If true, automatically commits the changes
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer or surface is not found, or communication fails.
+    pub fn add_surface_to_layer(
+        &mut self,
+        layer_id: u32,
+        surface_id: u32,
+        auto_commit: bool,
+    ) -> Result<()> {
+        self.send_request(
+            "add_surface_to_layer",
+            json!({
+                "layer_id": layer_id,
+                "surface_id": surface_id,
+                "auto_commit": auto_commit
+            }),
+        )
+        .map(|_| ())
+    }
+
+    /// Removes a surface from a layer.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer_id` - The ID of the layer
+    /// * `surface_id` - The ID of the surface to remove
+    /// * `auto_commit` - If true, automatically commits the changes
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer or surface is not found, or communication fails.
+    pub fn remove_surface_from_layer(
+        &mut self,
+        layer_id: u32,
+        surface_id: u32,
+        auto_commit: bool,
+    ) -> Result<()> {
+        self.send_request(
+            "remove_surface_from_layer",
+            json!({
+                "layer_id": layer_id,
+                "surface_id": surface_id,
+                "auto_commit": auto_commit
+            }),
+        )
+        .map(|_| ())
+    }
+
+    /// Gets the list of surface IDs currently assigned to a layer.
+    ///
+    /// Returns surfaces in z-order:
+    /// - First ID = bottommost (rendered first, behind others)
+    /// - Last ID = topmost (rendered last, in front of others)
+    ///
+    /// # Arguments
+    ///
+    /// * `layer_id` - The ID of the layer
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of surface IDs in z-order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer is not found or communication fails.
+    pub fn get_layer_surfaces(&mut self, layer_id: u32) -> Result<Vec<u32>> {
+        let response = self.send_request("get_layer_surfaces", json!({ "layer_id": layer_id }))?;
+        let surface_ids: Vec<u32> = serde_json::from_value(response["surface_ids"].clone())
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        Ok(surface_ids)
+    }
+
+    /// Gets the render order of the surfaces on a layer.
+    ///
+    /// The same list as [`get_layer_surfaces`](Self::get_layer_surfaces):
+    /// the first ID is the bottommost surface and the last is the topmost.
+    /// It has the form accepted by
+    /// [`set_surfaces_on_layer`](Self::set_surfaces_on_layer), for
+    /// read-modify-write restacking.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the layer
+    ///
+    /// # Returns
+    ///
+    /// Returns the surface IDs in render order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The layer ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    ///
+    /// // Bring surface 1000 to the front of layer 2000
+    /// let mut order = client.get_layer_render_order(2000)?;
+    /// order.retain(|&id| id != 1000);
+    /// order.push(1000);
+    /// client.set_surfaces_on_layer(2000, &order, true)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_layer_render_order(&mut self, id: u32) -> Result<Vec<u32>> {
+        self.get_layer_surfaces(id)
+    }
+
+    /// Gets the position of a surface in the render order of each layer it
+    /// is on.
+    ///
+    /// Cheaper than fetching the render order of every layer when only the
+    /// stacking of one surface is needed. The result is empty if the surface
+    /// is not on any layer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    ///
+    /// for z in client.get_surface_z_index(1000)? {
+    ///     println!("layer {}: {} of {}", z.layer_id, z.index + 1, z.count);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_surface_z_index(&mut self, id: u32) -> Result<Vec<SurfaceZIndex>> {
+        let response = self.send_request("get_surface_z_index", json!({ "id": id }))?;
+        serde_json::from_value(response["layers"].clone())
+            .map_err(|e| IviError::DeserializationError(e.to_string()))
+    }
+
+    /// Gets the surfaces currently assigned to a layer, with their properties.
+    ///
+    /// Combines [`get_layer_surfaces`](Self::get_layer_surfaces) and
+    /// [`list_surfaces`](Self::list_surfaces), so it costs two requests. Use
+    /// `get_layer_surfaces` when only the IDs are needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `layer_id` - The ID of the layer
+    ///
+    /// # Returns
+    ///
+    /// Returns the surfaces in z-order (first = bottommost). A surface destroyed
+    /// between the two requests is left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layer is not found, communication fails or the
+    /// response cannot be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// for surface in client.get_layer_surface_objects(2000)? {
+    ///     println!("Surface {}: {:?}", surface.id, surface.dest_rect);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_layer_surface_objects(&mut self, layer_id: u32) -> Result<Vec<IviSurface>> {
+        let surface_ids = self.get_layer_surfaces(layer_id)?;
+        let mut surfaces: HashMap<u32, IviSurface> = self
+            .list_surfaces()?
+            .into_iter()
+            .map(|surface| (surface.id, surface))
+            .collect();
+
+        Ok(surface_ids
+            .iter()
+            .filter_map(|id| surfaces.remove(id))
+            .collect())
+    }
+
+    /// Gets a snapshot of the complete scene.
+    ///
+    /// The snapshot contains all surfaces and layers with their properties,
+    /// the surface render order of each layer, and the layer render order of
+    /// each screen, fetched in a single request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails or the
+    /// response cannot be parsed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// let scene = client.get_scene()?;
+    /// for screen in &scene.screens {
+    ///     println!("{}: layers {:?}", screen.name, screen.layer_ids);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_scene(&mut self) -> Result<SceneSnapshot> {
+        let response = self.send_request("get_scene", json!({}))?;
+        let scene: SceneSnapshot = serde_json::from_value(response)
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        Ok(scene)
+    }
+
+    /// Commits all pending changes to the IVI compositor atomically.
+    ///
+    /// This method applies all pending surface and layer modifications in a single
+    /// atomic operation, ensuring that changes are applied simultaneously without
+    /// visual artifacts or tearing.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` on successful commit, or an error if the commit operation fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Communication with the controller fails
+    /// - The commit operation fails on the server side
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    ///
+    /// // Make multiple changes
+    /// client.set_surface_destination_rectangle(1000, 100, 200, 1920, 1080, false)?;
+    /// client.set_surface_visibility(1000, true, false)?;
+    ///
+    /// // Commit all changes atomically
+    /// client.commit()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn commit(&mut self) -> Result<()> {
+        self.send_request("commit", json!({})).map(|_| ())
+    }
+
+    /// Commits only the pending changes of one surface.
+    ///
+    /// The controller stages surface source and destination rectangle,
+    /// visibility and opacity changes that are not auto-committed, and this
+    /// applies the staged changes of `id` while the changes of other surfaces
+    /// stay queued.
+    ///
+    /// # Limitations
+    ///
+    /// The IVI layout only supports a global commit, so changes that are not
+    /// staged by the controller (layer properties, z-order, focus and changes
+    /// made by other IVI controllers) are applied by this call as well.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of staged changes that were applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The surface ID does not exist
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.set_surface_visibility(1000, true, false)?;
+    /// client.set_surface_visibility(1001, true, false)?;
+    ///
+    /// // Show surface 1000 now, surface 1001 later
+    /// client.commit_surface(1000)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn commit_surface(&mut self, id: u32) -> Result<usize> {
+        let result = self.send_request("commit", json!({ "id": id }))?;
+        Ok(result.get("applied").and_then(|v| v.as_u64()).unwrap_or(0) as usize)
+    }
+
+    /// Returns the number of changes this client has made since its last commit.
+    ///
+    /// The count is tracked locally: each successful mutation sent without
+    /// auto-commit increments it, and a commit (explicit or auto) resets it.
+    ///
+    /// # Limitations
+    ///
+    /// Commits are compositor-wide, so the count only reflects this client's
+    /// view. Another client may commit these changes first, in which case the
+    /// count stays non-zero until this client commits. Changes made by other
+    /// clients are never counted.
+    pub fn pending_changes(&self) -> usize {
+        self.pending_changes
+    }
+
+    /// Commits pending changes only if this client has made any since its last commit.
+    ///
+    /// This avoids a needless commit RPC, and the repaint it triggers, for tools
+    /// that commit defensively. See [`pending_changes`](Self::pending_changes) for
+    /// how the count is tracked and its limitations when several clients are connected.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if a commit was sent, `false` if there was nothing to commit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Communication with the controller fails
+    /// - The commit operation fails on the server side
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    ///
+    /// client.set_surface_visibility(1000, true, false)?;
+    /// assert!(client.commit_if_pending()?);
+    ///
+    /// // Nothing changed since the last commit, so no request is sent
+    /// assert!(!client.commit_if_pending()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn commit_if_pending(&mut self) -> Result<bool> {
+        if self.pending_changes == 0 {
+            return Ok(false);
+        }
+
+        self.commit()?;
+        Ok(true)
+    }
+
+    /// Starts queueing several changes to one surface.
+    ///
+    /// Nothing is sent until [`SurfaceEdit::apply`] or
+    /// [`SurfaceEdit::apply_and_commit`] is called.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client
+    ///     .edit_surface(1000)
+    ///     .position(0, 0)
+    ///     .size(1920, 1080)
+    ///     .opacity(0.8)
+    ///     .visibility(true)
+    ///     .apply_and_commit()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn edit_surface(&mut self, id: u32) -> SurfaceEdit<'_> {
+        SurfaceEdit {
+            client: self,
+            id,
+            position: None,
+            size: None,
+            opacity: None,
+            visible: None,
+        }
+    }
+
+    /// Subscribes this connection to the given event types.
+    ///
+    /// Notifications are then delivered on this connection and read with
+    /// [`next_notification`](Self::next_notification) or
+    /// [`raw_notification_stream`](Self::raw_notification_stream). They are
+    /// interleaved with RPC responses, so a subscribed client should only be
+    /// used for reading notifications; use [`try_clone`](Self::try_clone) for
+    /// a second connection to send requests on, or [`NotificationListener`]
+    /// for callbacks on a background thread.
+    ///
+    /// # Returns
+    ///
+    /// Returns the requested event types that are disabled in the controller
+    /// configuration. The subscription succeeds, but these events are never
+    /// delivered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    pub fn subscribe(&mut self, event_types: &[EventType]) -> Result<Vec<EventType>> {
+        let result = self.send_request("subscribe", json!({ "event_types": event_types }))?;
+        Ok(ignored_event_types(&result))
+    }
+
+    /// Subscribes this connection to every event type the controller knows.
+    ///
+    /// The controller expands the list, so event types added in newer
+    /// controller versions are included. This includes `RepaintComplete`,
+    /// which fires on every frame. See [`subscribe`](Self::subscribe) for how
+    /// notifications are read and what is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails, or if the
+    /// controller limits subscriptions per client to fewer than its event
+    /// types.
+    pub fn subscribe_all(&mut self) -> Result<Vec<EventType>> {
+        let result = self.send_request("subscribe", json!({ "event_types": "all" }))?;
+        Ok(ignored_event_types(&result))
+    }
+
+    /// Unsubscribes this connection from the given event types.
+    ///
+    /// Notifications sent before the controller handled the request may still
+    /// be queued on the connection, so this must not be called while
+    /// notifications are arriving.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    pub fn unsubscribe(&mut self, event_types: &[EventType]) -> Result<()> {
+        self.send_request("unsubscribe", json!({ "event_types": event_types }))
+            .map(|_| ())
+    }
+
+    /// Removes every subscription of this connection.
+    ///
+    /// The same caveat about queued notifications as for
+    /// [`unsubscribe`](Self::unsubscribe) applies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    pub fn unsubscribe_all(&mut self) -> Result<()> {
+        self.send_request("unsubscribe", json!({ "event_types": "all" }))
+            .map(|_| ())
+    }
+
+    /// Removes the subscriptions of another client, or of this connection.
+    ///
+    /// `client_id` is a key of the controller's `get_all_subscriptions`
+    /// result; with `None`, only the subscriptions of this connection are
+    /// removed. Clearing another client is an admin method meant for
+    /// recovering from leaked subscriptions: the controller only accepts it
+    /// from processes running as one of its admin uids. Buffered
+    /// notifications of the cleared client are dropped.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of clients whose subscriptions were removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - `client_id` names another client and this process is not allowed
+    ///   to call admin methods
+    /// - Communication with the controller fails
+    pub fn clear_subscriptions(&mut self, client_id: Option<u64>) -> Result<usize> {
+        let params = match client_id {
+            Some(client_id) => json!({ "client_id": client_id }),
+            None => json!({}),
+        };
+        let result = self.send_request("clear_subscriptions", params)?;
+        Ok(result
+            .get("cleared_clients")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as usize)
+    }
+
+    /// Removes the subscriptions of every client, this one included.
+    ///
+    /// An admin method like [`clear_subscriptions`](Self::clear_subscriptions)
+    /// for another client.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of clients whose subscriptions were removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - This process is not allowed to call admin methods
+    /// - Communication with the controller fails
+    pub fn clear_all_subscriptions(&mut self) -> Result<usize> {
+        let result = self.send_request("clear_subscriptions", json!({ "all": true }))?;
+        Ok(result
+            .get("cleared_clients")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as usize)
+    }
+
+    /// Blocks until the next notification arrives on this connection.
+    ///
+    /// Notifications that arrived while a request was waiting for its
+    /// response are returned first. Frames that are not notifications, such
+    /// as stray RPC responses, are skipped. See [`subscribe`](Self::subscribe).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The connection is closed (`IviError::IoError` with `UnexpectedEof`)
+    /// - Communication with the controller fails
+    /// - A notification cannot be parsed
+    pub fn next_notification(&mut self) -> Result<Notification> {
+        if let Some(notif) = self.queued_notifications.pop_front() {
+            return Ok(notif);
+        }
+
+        loop {
+            let transport = self.transport.as_mut().ok_or_else(|| {
+                IviError::ConnectionFailed("No active connection to read from.".to_string())
+            })?;
+            let frame = transport.receive_response()?;
+
+            self.log_wire(WireDirection::Incoming, &frame);
+
+            if let Some(notif) = Notification::try_from_frame(&frame)? {
+                return Ok(notif);
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for the next notification on this connection.
+    ///
+    /// Like [`next_notification`](Self::next_notification), but returns
+    /// `Ok(None)` if no notification arrives in time, so an event loop can
+    /// do other work between polls. With `None`, waits as long as it takes;
+    /// with a zero timeout, only notifications that arrived while a request
+    /// was waiting for its response are returned. With a timeout, malformed
+    /// notifications are logged and skipped, as while waiting for a response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The transport does not support read timeouts (IPCON)
+    /// - Communication with the controller fails
+    /// - A notification cannot be parsed (without a timeout only)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::{EventType, IviClient};
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.subscribe(&[EventType::SurfaceCreated])?;
+    /// while let Some(notif) = client.poll_notification(Some(Duration::from_millis(100)))? {
+    ///     println!("{:?}", notif.event_type);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn poll_notification(&mut self, timeout: Option<Duration>) -> Result<Option<Notification>> {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return self.next_notification().map(Some),
+        };
+        if let Some(notif) = self.queued_notifications.pop_front() {
+            return Ok(Some(notif));
+        }
+
+        let deadline = Instant::now() + timeout;
+        while let Some(frame) = self.read_frame_until(Some(deadline))? {
+            if let Some(notif) = notification_from_frame(&frame) {
+                return Ok(Some(notif));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Discards the notifications that are already waiting on this connection.
+    ///
+    /// Queued notifications are dropped, then frames are read and discarded
+    /// until none arrives within a short timeout. Use this before a request
+    /// when earlier events are no longer of interest. The subscription itself
+    /// is not changed.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of discarded notifications.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The transport does not support read timeouts (IPCON)
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::{EventType, IviClient};
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.subscribe(&[EventType::SurfaceCreated])?;
+    /// // ... later, ignore whatever arrived in the meantime
+    /// client.flush_notifications()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn flush_notifications(&mut self) -> Result<usize> {
+        self.discard_notifications(|_| true)
+    }
+
+    /// Drop the queued and still arriving notifications selected by `discard`
+    ///
+    /// Frames are read until none arrives within a short timeout. The
+    /// notifications that are not discarded stay queued for
+    /// [`next_notification`](Self::next_notification).
+    fn discard_notifications<F>(&mut self, mut discard: F) -> Result<usize>
+    where
+        F: FnMut(&Notification) -> bool,
+    {
+        let queued = self.queued_notifications.len();
+        self.queued_notifications.retain(|n| !discard(n));
+        let mut discarded = queued - self.queued_notifications.len();
+
+        while let Some(frame) = self.read_frame_until(Some(Instant::now() + FLUSH_READ_TIMEOUT))? {
+            if let Some(notif) = notification_from_frame(&frame) {
+                if discard(&notif) {
+                    discarded += 1;
+                } else {
+                    self.queued_notifications.push_back(notif);
+                }
+            }
+        }
+        Ok(discarded)
+    }
+
+    /// Waits for a single notification, subscribing only for as long as it takes.
+    ///
+    /// Subscribes to `event_type`, waits for the first notification of that
+    /// type for which `predicate` returns true, then unsubscribes again. This
+    /// covers the common "do X, wait for the event it causes" pattern:
+    ///
+    /// - Notifications of `event_type` queued before the call are dropped, so
+    ///   a stale event cannot satisfy the wait.
+    /// - Notifications of `event_type` rejected by `predicate` are dropped.
+    /// - After unsubscribing, notifications of `event_type` that the controller
+    ///   sent before it handled the unsubscribe are read and dropped.
+    ///
+    /// Notifications of other event types are kept for
+    /// [`next_notification`](Self::next_notification). If the connection was
+    /// already subscribed to `event_type`, it stays subscribed, and later
+    /// notifications of that type are kept as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `event_type` - The event type to wait for
+    /// * `predicate` - Selects the notification to return, e.g. by surface ID
+    /// * `timeout` - How long to wait for a matching notification
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - No matching notification arrives within `timeout` ([`IviError::is_timeout`])
+    /// - `event_type` is disabled in the controller configuration
+    /// - The transport does not support read timeouts (IPCON)
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::{EventType, IviClient};
+    /// use std::time::Duration;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// // ... start the application that owns surface 1000, then
+    /// let notification = client.subscribe_once(
+    ///     EventType::SurfaceCreated,
+    ///     |n| n.params["surface_id"] == 1000,
+    ///     Duration::from_secs(5),
+    /// )?;
+    /// println!("Surface 1000 appeared: {}", notification.params);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn subscribe_once<F>(
+        &mut self,
+        event_type: EventType,
+        mut predicate: F,
+        timeout: Duration,
+    ) -> Result<Notification>
+    where
+        F: FnMut(&Notification) -> bool,
+    {
+        self.queued_notifications
+            .retain(|n| n.event_type != event_type);
+
+        let response = self.send_request("subscribe", json!({ "event_types": [event_type] }))?;
+        // Controllers that do not report what was added are taken to have added it
+        let added = match response.get("subscribed") {
+            Some(subscribed) => serde_json::from_value::<Vec<EventType>>(subscribed.clone())
+                .map(|subscribed| subscribed.contains(&event_type))
+                .unwrap_or(true),
+            None => true,
+        };
+        let result = if ignored_event_types(&response).contains(&event_type) {
+            Err(IviError::IoError(std::io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("{:?} is disabled in the controller", event_type),
+            )))
+        } else {
+            self.wait_for_notification(&event_type, &mut predicate, timeout)
+        };
+
+        // Undo a subscription made here in any case, but report a failed wait first
+        let cleanup = if added {
+            self.unsubscribe(std::slice::from_ref(&event_type))
+                .and_then(|()| {
+                    self.discard_notifications(|n| n.event_type == event_type)
+                        .map(|_| ())
+                })
+        } else {
+            Ok(())
+        };
+        let notification = result?;
+        cleanup?;
+        Ok(notification)
+    }
+
+    /// Read notifications until one of `event_type` matches `predicate`
+    fn wait_for_notification<F>(
+        &mut self,
+        event_type: &EventType,
+        predicate: &mut F,
+        timeout: Duration,
+    ) -> Result<Notification>
+    where
+        F: FnMut(&Notification) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+
+        // Notifications queued while subscribing come first
+        let mut found = None;
+        for notif in std::mem::take(&mut self.queued_notifications) {
+            if notif.event_type != *event_type {
+                self.queued_notifications.push_back(notif);
+            } else if found.is_none() && predicate(&notif) {
+                found = Some(notif);
+            }
+        }
+        if let Some(notif) = found {
+            return Ok(notif);
+        }
+
+        loop {
+            let frame = match self.read_frame_until(Some(deadline))? {
+                Some(frame) => frame,
+                None => return Err(notification_timeout(event_type)),
+            };
+            match notification_from_frame(&frame) {
+                Some(notif) if notif.event_type != *event_type => {
+                    self.queued_notifications.push_back(notif)
+                }
+                Some(notif) if predicate(&notif) => return Ok(notif),
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads the limits of the controller from `get_capabilities`.
+    ///
+    /// Use them to stay within what the controller accepts, e.g. to split a
+    /// large scene load or to subscribe to fewer event types, instead of
+    /// finding out from errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// if let Some(max) = client.limits()?.max_subscriptions {
+    ///     println!("At most {} subscriptions", max);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn limits(&mut self) -> Result<Limits> {
+        let result = self.send_request("get_capabilities", json!({}))?;
+        serde_json::from_value(result).map_err(|e| IviError::DeserializationError(e.to_string()))
+    }
+
+    /// Reads a summary of the controller state with one cheap `stats` request.
+    ///
+    /// Meant for frequent polling, e.g. by a status bar; use `get_metrics`
+    /// for request counters and rates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// let stats = client.stats()?;
+    /// println!("{} surfaces on {} layers", stats.surfaces, stats.layers);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&mut self) -> Result<Stats> {
+        let result = self.send_request("stats", json!({}))?;
+        serde_json::from_value(result).map_err(|e| IviError::DeserializationError(e.to_string()))
+    }
+
+    /// Measures the round-trip latency of the connection.
+    ///
+    /// Sends `samples` `ping` requests one after the other and times each
+    /// request until its response arrives. `ping` does no compositor work, so
+    /// the result is the cost of the transport and the plugin's request
+    /// handling alone.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - `samples` is zero or does not fit in a `u32`
+    /// - Communication with the controller fails
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// let stats = client.round_trip_latency(100)?;
+    /// println!("min {:?} avg {:?} max {:?}", stats.min, stats.avg, stats.max);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn round_trip_latency(&mut self, samples: usize) -> Result<LatencyStats> {
+        // The average divides a Duration, which takes a u32
+        let divisor = match u32::try_from(samples) {
+            Ok(divisor) if divisor >= 1 => divisor,
+            _ => {
+                return Err(IviError::IoError(std::io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Between 1 and {} samples are required", u32::MAX),
+                )))
+            }
+        };
+
+        let mut min = Duration::MAX;
+        let mut max = Duration::ZERO;
+        let mut total = Duration::ZERO;
+        for _ in 0..samples {
+            let start = Instant::now();
+            self.send_request("ping", json!({}))?;
+            let elapsed = start.elapsed();
+            min = min.min(elapsed);
+            max = max.max(elapsed);
+            total += elapsed;
+        }
+
+        Ok(LatencyStats {
+            samples,
+            min,
+            avg: total / divisor,
+            max,
+        })
+    }
+
+    /// Returns an iterator over the notifications received on this connection.
+    ///
+    /// The iterator calls [`next_notification`](Self::next_notification)
+    /// repeatedly and ends when the controller closes the connection. Any
+    /// other error is yielded; an unparsable notification does not end the
+    /// iteration, a transport error does. Dropping the iterator does not
+    /// unsubscribe.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::{EventType, IviClient};
+    ///
+    /// # fn main() -> ivi_client::Result<()> {
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// client.subscribe(&[EventType::SurfaceCreated])?;
+    ///
+    /// for notif in client.raw_notification_stream().take(3) {
+    ///     println!("Surface created: {}", notif?.params["surface_id"]);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn raw_notification_stream(&mut self) -> NotificationStream<'_> {
+        NotificationStream {
+            client: self,
+            done: false,
+        }
+    }
+}
+
+/// Parses a frame read while waiting for notifications
+///
+/// Responses and malformed notifications are logged and skipped.
+fn notification_from_frame(frame: &[u8]) -> Option<Notification> {
+    match Notification::try_from_frame(frame) {
+        Ok(Some(notif)) => Some(notif),
+        Ok(None) => {
+            jwarn!("Discarding unexpected response while waiting for notifications");
+            None
+        }
+        Err(e) => {
+            jwarn!("Discarding malformed notification: {}", e);
+            None
+        }
+    }
+}
+
+/// Event types listed as `ignored` in a `subscribe` response
+///
+/// Controllers that predate the field never report any.
+fn ignored_event_types(result: &Value) -> Vec<EventType> {
+    result
+        .get("ignored")
+        .and_then(|ignored| serde_json::from_value(ignored.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Iterator over received notifications, returned by
+/// [`IviClient::raw_notification_stream`].
+pub struct NotificationStream<'a> {
+    client: &'a mut IviClient,
+    done: bool,
+}
+
+impl Iterator for NotificationStream<'_> {
+    type Item = Result<Notification>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.client.next_notification() {
+            Ok(notif) => Some(Ok(notif)),
+            // A malformed frame does not affect the frames after it
+            Err(e @ IviError::SerializationError(_))
+            | Err(e @ IviError::DeserializationError(_)) => Some(Err(e)),
+            Err(IviError::IoError(ref e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterator over all surfaces, returned by [`IviClient::surfaces_iter`].
+pub struct SurfacesIter<'a> {
+    client: &'a mut IviClient,
+    offset: usize,
+    page: std::vec::IntoIter<IviSurface>,
+    done: bool,
+}
+
+impl Iterator for SurfacesIter<'_> {
+    type Item = Result<IviSurface>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(surface) = self.page.next() {
+                return Some(Ok(surface));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            match self
+                .client
+                .list_surfaces_page(self.offset, SURFACES_PAGE_SIZE)
+            {
+                Ok(page) => {
+                    self.offset += page.surfaces.len();
+                    self.done = page.surfaces.is_empty() || self.offset >= page.total;
+                    self.page = page.surfaces.into_iter();
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Changes to one surface queued by [`IviClient::edit_surface`].
+///
+/// Setting a property twice keeps the last value.
+#[must_use = "a SurfaceEdit does nothing until it is applied"]
+pub struct SurfaceEdit<'a> {
+    client: &'a mut IviClient,
+    id: u32,
+    position: Option<(i32, i32)>,
+    size: Option<(i32, i32)>,
+    opacity: Option<f32>,
+    visible: Option<bool>,
+}
+
+impl SurfaceEdit<'_> {
+    /// Moves the surface to `(x, y)` on screen.
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Sets the size of the surface on screen.
+    pub fn size(mut self, width: i32, height: i32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Sets the opacity of the surface.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = Some(opacity);
+        self
+    }
+
+    /// Shows or hides the surface.
+    pub fn visibility(mut self, visible: bool) -> Self {
+        self.visible = Some(visible);
+        self
+    }
+
+    /// Sends the queued changes without committing them.
+    ///
+    /// The changes are sent one request at a time: the destination
+    /// rectangle, position or size, then visibility, then opacity. A size
+    /// without a position keeps the position the surface will have once
+    /// committed, including a staged one. If a request fails, the changes
+    /// sent before it stay pending.
+    pub fn apply(mut self) -> Result<()> {
+        self.send()
+    }
+
+    /// Sends the queued changes and commits them together.
+    ///
+    /// The commit applies every pending change, including those made before
+    /// this edit.
+    pub fn apply_and_commit(mut self) -> Result<()> {
+        self.send()?;
+        self.client.commit()
+    }
+
+    fn send(&mut self) -> Result<()> {
+        let (client, id) = (&mut *self.client, self.id);
+        match (self.position, self.size) {
+            (Some((x, y)), Some((width, height))) => {
+                client.set_surface_destination_rectangle(id, x, y, width, height, false)?
+            }
+            (Some((x, y)), None) => {
+                client.send_request(
+                    "set_surface_position",
+                    json!({ "id": id, "x": x, "y": y, "auto_commit": false }),
+                )?;
+            }
+            (None, Some((width, height))) => {
+                client.send_request(
+                    "set_surface_size",
+                    json!({ "id": id, "width": width, "height": height, "auto_commit": false }),
+                )?;
+            }
+            (None, None) => {}
+        }
+        if let Some(visible) = self.visible {
+            client.set_surface_visibility(id, visible, false)?;
+        }
+        if let Some(opacity) = self.opacity {
+            client.set_surface_opacity(id, opacity, false)?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// NotificationListener
+// ============================================================================
+
+/// Callback type for notification events.
+pub type NotificationCallback = Arc<dyn Fn(&Notification) + Send + Sync + 'static>;
+
+/// Listens for event notifications from the IVI controller.
+///
+/// Opens its own dedicated socket connection so that notifications are not
+/// mixed with RPC responses on the shared `IviClient` connection.
+///
+/// # Example
+///
+/// ```no_run
+/// use ivi_client::{NotificationListener, EventType};
+///
+/// # fn main() -> ivi_client::Result<()> {
+/// let mut listener = NotificationListener::new(None)?;
+///
+/// listener.on(EventType::SurfaceCreated, |notif| {
+///     println!("Surface created: {}", notif.params["surface_id"]);
+/// });
+///
+/// listener.on_all(|notif| {
+///     println!("Event: {:?}", notif.event_type);
 /// });
 ///
 /// listener.start(&[EventType::SurfaceCreated, EventType::VisibilityChanged])?;
@@ -1182,159 +3903,1676 @@ pub struct NotificationListener {
     catch_all_callbacks: Arc<Mutex<Vec<NotificationCallback>>>,
     stop_flag: Arc<AtomicBool>,
     thread_handle: Option<JoinHandle<()>>,
+    wire_logger: Option<WireLogger>,
+}
+
+impl std::fmt::Debug for NotificationListener {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let transport = self.transport.lock().unwrap();
+        let mut event_types: Vec<EventType> =
+            self.callbacks.lock().unwrap().keys().cloned().collect();
+        event_types.sort_by_key(|event_type| format!("{:?}", event_type));
+
+        f.debug_struct("NotificationListener")
+            .field("peer_addr", &transport.peer_addr())
+            .field("local_addr", &transport.local_addr())
+            .field("next_request_id", &self.request_id.load(Ordering::SeqCst))
+            .field("running", &self.thread_handle.is_some())
+            .field("callback_event_types", &event_types)
+            .field(
+                "catch_all_callbacks",
+                &self.catch_all_callbacks.lock().unwrap().len(),
+            )
+            .finish()
+    }
+}
+
+impl NotificationListener {
+    /// Create a new listener connected to the IVI controller.
+    pub fn new(remote: Option<&str>) -> Result<Self> {
+        #[cfg(not(feature = "enable-ipcon"))]
+        let transport: Box<dyn IviClientTransport> =
+            Box::new(UnixDomainIviClient::connect(remote)?);
+
+        #[cfg(feature = "enable-ipcon")]
+        let transport: Box<dyn IviClientTransport> =
+            Box::new(IpconIviClient::ipcon_connect(None, remote)?);
+
+        Ok(Self {
+            transport: Arc::new(Mutex::new(transport)),
+            request_id: AtomicU64::new(1),
+            callbacks: Arc::new(Mutex::new(HashMap::new())),
+            catch_all_callbacks: Arc::new(Mutex::new(Vec::new())),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            thread_handle: None,
+            wire_logger: None,
+        })
+    }
+
+    fn next_request_id(&self) -> u64 {
+        self.request_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Sets a callback that observes the raw frames exchanged with the controller,
+    /// including every notification read by the background thread.
+    ///
+    /// Must be called before [`start`](Self::start) for notifications to be observed.
+    /// See [`IviClient::set_wire_logger`].
+    pub fn set_wire_logger(&mut self, logger: Option<WireLogger>) {
+        self.wire_logger = logger;
+    }
+
+    fn log_wire(&self, direction: WireDirection, bytes: &[u8]) {
+        if let Some(logger) = &self.wire_logger {
+            logger(direction, bytes);
+        }
+    }
+
+    fn send_rpc(&self, method: &str, params: Value) -> Result<Value> {
+        self.send_rpc_inner(method, params)
+            .map_err(|e| e.with_method(method))
+    }
+
+    fn send_rpc_inner(&self, method: &str, params: Value) -> Result<Value> {
+        let request_id = self.next_request_id();
+        let request = JsonRpcRequest::new(request_id, method, params);
+        let request_json = serde_json::to_vec(&request)
+            .map_err(|e| IviError::SerializationError(e.to_string()))?;
+
+        self.log_wire(WireDirection::Outgoing, &request_json);
+
+        let response_buf = {
+            let mut transport = self.transport.lock().unwrap();
+            transport.send_request(&request_json)?;
+            transport.receive_response()?
+        };
+
+        self.log_wire(WireDirection::Incoming, &response_buf);
+
+        let response: JsonRpcResponse = serde_json::from_slice(&response_buf)
+            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+
+        if let Some(error) = response.error {
+            return Err(IviError::RequestFailed {
+                code: error.code,
+                message: error.message,
+            });
+        }
+
+        response.result.ok_or_else(|| {
+            IviError::DeserializationError("Response missing both result and error".to_string())
+        })
+    }
+
+    /// Register a callback for a specific event type.
+    /// Multiple callbacks per event type are allowed.
+    pub fn on<F>(&mut self, event_type: EventType, callback: F)
+    where
+        F: Fn(&Notification) + Send + Sync + 'static,
+    {
+        self.callbacks
+            .lock()
+            .unwrap()
+            .entry(event_type)
+            .or_default()
+            .push(Arc::new(callback));
+    }
+
+    /// Register a catch-all callback invoked for every received event.
+    pub fn on_all<F>(&mut self, callback: F)
+    where
+        F: Fn(&Notification) + Send + Sync + 'static,
+    {
+        self.catch_all_callbacks
+            .lock()
+            .unwrap()
+            .push(Arc::new(callback));
+    }
+
+    /// Subscribe to the given event types on the server and start the
+    /// background reader thread. Callbacks registered with `on`/`on_all`
+    /// will fire from this thread.
+    ///
+    /// Returns the requested event types that are disabled in the controller
+    /// configuration and will never be delivered, see [`IviClient::subscribe`].
+    pub fn start(&mut self, event_types: &[EventType]) -> Result<Vec<EventType>> {
+        let result = self.send_rpc("subscribe", json!({ "event_types": event_types }))?;
+        let ignored = ignored_event_types(&result);
+
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        let transport = Arc::clone(&self.transport);
+        let callbacks = Arc::clone(&self.callbacks);
+        let catch_all_callbacks = Arc::clone(&self.catch_all_callbacks);
+        let stop_flag = Arc::clone(&self.stop_flag);
+        let wire_logger = self.wire_logger.clone();
+
+        self.thread_handle = Some(std::thread::spawn(move || {
+            loop {
+                if stop_flag.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let frame = {
+                    let mut t = transport.lock().unwrap();
+                    let _ = t.set_read_timeout(Some(Duration::from_millis(100)));
+                    let r = t.receive_response();
+                    let _ = t.set_read_timeout(None);
+                    r
+                };
+
+                // The transport lock is released before the logger runs
+                if let (Ok(bytes), Some(logger)) = (&frame, &wire_logger) {
+                    logger(WireDirection::Incoming, bytes);
+                }
+
+                match frame {
+                    Ok(bytes) => match Notification::try_from_frame(&bytes) {
+                        Ok(Some(notif)) => {
+                            // Clone Arc refs before dispatching to avoid holding the lock
+                            // while callbacks run.
+                            let per_type: Vec<NotificationCallback> = callbacks
+                                .lock()
+                                .unwrap()
+                                .get(&notif.event_type)
+                                .cloned()
+                                .unwrap_or_default();
+                            for cb in &per_type {
+                                cb(&notif);
+                            }
+                            let all: Vec<NotificationCallback> =
+                                catch_all_callbacks.lock().unwrap().clone();
+                            for cb in &all {
+                                cb(&notif);
+                            }
+                        }
+                        Ok(None) => {} // stray RPC response, skip
+                        Err(_) => {}   // parse error, skip
+                    },
+                    Err(IviError::IoError(ref e))
+                        if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut =>
+                    {
+                        continue; // timeout — check stop flag and retry
+                    }
+                    Err(_) => break, // real error — exit thread
+                }
+            }
+        }));
+
+        Ok(ignored)
+    }
+
+    /// Whether the background thread is still reading notifications.
+    ///
+    /// Turns false once the controller closes the connection.
+    pub fn is_running(&self) -> bool {
+        self.thread_handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Signal the background thread to stop and wait for it to finish.
+    pub fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.thread_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for NotificationListener {
+    fn drop(&mut self) {
+        self.stop();
+        let mut t = self.transport.lock().unwrap();
+        let _ = t.disconnect();
+    }
 }
 
-impl NotificationListener {
-    /// Create a new listener connected to the IVI controller.
-    pub fn new(remote: Option<&str>) -> Result<Self> {
-        #[cfg(not(feature = "enable-ipcon"))]
-        let transport: Box<dyn IviClientTransport> =
-            Box::new(UnixDomainIviClient::connect(remote)?);
+#[cfg(all(test, not(feature = "enable-ipcon")))]
+mod tests {
+    use super::*;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use weston_ivi_controller::rpc::framing::{write_frame, FrameReadResult, FrameReader};
+
+    #[test]
+    fn test_wire_logger_observes_request_and_response() {
+        let socket_path =
+            std::env::temp_dir().join(format!("ivi-client-wire-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let response = br#"{"id":1,"result":{"v":1,"surfaces":[],"total":0}}"#.to_vec();
+        let server_response = response.clone();
+
+        // Mock controller: answer a single request and hand back what it received
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = FrameReader::new();
+            let request = loop {
+                match reader.read_frame(&mut stream).unwrap() {
+                    FrameReadResult::Complete(msg) => break msg,
+                    FrameReadResult::NeedMore => continue,
+                    FrameReadResult::Eof => panic!("client closed the connection"),
+                }
+            };
+            write_frame(&mut stream, &server_response).unwrap();
+            request
+        });
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let frames: Arc<Mutex<Vec<(WireDirection, Vec<u8>)>>> = Arc::new(Mutex::new(Vec::new()));
+        let logged = Arc::clone(&frames);
+        client.set_wire_logger(Some(Arc::new(move |direction, bytes: &[u8]| {
+            logged.lock().unwrap().push((direction, bytes.to_vec()));
+        })));
+
+        let surfaces = client.list_surfaces().unwrap();
+        assert!(surfaces.is_empty());
+
+        let request = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert_eq!(
+            *frames.lock().unwrap(),
+            vec![
+                (WireDirection::Outgoing, request),
+                (WireDirection::Incoming, response),
+            ]
+        );
+    }
+
+    /// Spawn a mock controller that answers requests with `responses` in order
+    /// and hands back the requests it received
+    fn spawn_mock_controller(
+        name: &str,
+        responses: Vec<Vec<u8>>,
+    ) -> (std::path::PathBuf, std::thread::JoinHandle<Vec<Vec<u8>>>) {
+        let socket_path =
+            std::env::temp_dir().join(format!("ivi-client-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = FrameReader::new();
+            let mut requests = Vec::new();
+            for response in responses {
+                let request = loop {
+                    match reader.read_frame(&mut stream).unwrap() {
+                        FrameReadResult::Complete(msg) => break msg,
+                        FrameReadResult::NeedMore => continue,
+                        FrameReadResult::Eof => panic!("client closed the connection"),
+                    }
+                };
+                write_frame(&mut stream, &response).unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+
+        (socket_path, server)
+    }
+
+    #[test]
+    fn test_connect_abstract() {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::SocketAddr;
+
+        let name = format!("ivi-client-abstract-{}", std::process::id());
+        let addr = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+        let listener = UnixListener::bind_addr(&addr).unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = FrameReader::new();
+            loop {
+                match reader.read_frame(&mut stream).unwrap() {
+                    FrameReadResult::Complete(_) => break,
+                    FrameReadResult::NeedMore => continue,
+                    FrameReadResult::Eof => panic!("client closed the connection"),
+                }
+            }
+            write_frame(&mut stream, br#"{"id":1,"result":{"pong":true}}"#).unwrap();
+        });
+
+        let mut client = IviClient::connect_abstract(&name).unwrap();
+        assert!(format!("{:?}", client).contains(&format!("@{}", name)));
+        client.round_trip_latency(1).unwrap();
+        server.join().unwrap();
+
+        // Nothing listens on the name any more
+        assert!(matches!(
+            IviClient::connect_abstract(&name),
+            Err(IviError::ConnectionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_latency() {
+        let responses = (1..=3)
+            .map(|id| serde_json::to_vec(&json!({"id": id, "result": {"pong": true}})).unwrap())
+            .collect();
+        let (socket_path, server) = spawn_mock_controller("latency", responses);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let stats = client.round_trip_latency(3).unwrap();
+        assert!(client.round_trip_latency(0).is_err());
+        // Rejected before any ping is sent
+        if let Some(too_many) = (u32::MAX as usize).checked_add(1) {
+            assert!(client.round_trip_latency(too_many).is_err());
+        }
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert_eq!(stats.samples, 3);
+        assert!(stats.min <= stats.avg && stats.avg <= stats.max);
+        assert!(requests
+            .iter()
+            .all(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().method == "ping"));
+    }
+
+    #[test]
+    fn test_get_layer_surface_objects_keeps_layer_order() {
+        let surface = |id: u32| {
+            json!({
+                "v": 1, "id": id, "orig_size": {"width": 100, "height": 100},
+                "src_rect": {"x": 0, "y": 0, "width": 100, "height": 100},
+                "dest_rect": {"x": 0, "y": 0, "width": 100, "height": 100},
+                "visibility": true, "opacity": 1.0, "orientation": "Normal", "z_order": 0
+            })
+        };
+        let responses = vec![
+            json!({"id": 1, "result": {"layer_id": 2000, "surface_ids": [1002, 1000]}}),
+            json!({"id": 2, "result": {
+                "v": 1, "surfaces": [surface(1000), surface(1001), surface(1002)], "total": 3
+            }}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "layer-surfaces",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let surfaces = client.get_layer_surface_objects(2000).unwrap();
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let ids: Vec<u32> = surfaces.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![1002, 1000]);
+
+        let methods: Vec<String> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().method)
+            .collect();
+        assert_eq!(methods, vec!["get_layer_surfaces", "list_surfaces"]);
+    }
+
+    #[test]
+    fn test_list_outputs() {
+        let responses = vec![
+            json!({"id": 1, "result": {"outputs": [
+                {"index": 0, "name": "HDMI-A-1", "width": 1920, "height": 1080, "x": 0, "y": 0},
+                {"index": 1, "name": "DP-1", "width": 1280, "height": 720, "x": 1920, "y": 0}
+            ]}}),
+            json!({"id": 2, "result":
+                {"index": 1, "name": "DP-1", "width": 1280, "height": 720, "x": 1920, "y": 0}
+            }),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "outputs",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let outputs = client.list_outputs().unwrap();
+        let output = client.get_output(1).unwrap();
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[1], output);
+        assert_eq!((output.x, output.width), (1920, 1280));
+
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[1]).unwrap();
+        assert_eq!(request.method, "get_output");
+        assert_eq!(request.params, json!({ "index": 1 }));
+    }
+
+    #[test]
+    fn test_get_surface_live_requests_live_read() {
+        let surface = |visibility: bool| {
+            json!({
+                "v": 1, "id": 1000, "orig_size": {"width": 100, "height": 100},
+                "src_rect": {"x": 0, "y": 0, "width": 100, "height": 100},
+                "dest_rect": {"x": 0, "y": 0, "width": 100, "height": 100},
+                "visibility": visibility, "opacity": 1.0, "orientation": "Normal", "z_order": 0
+            })
+        };
+        let responses = vec![
+            json!({"id": 1, "result": surface(false)}),
+            json!({"id": 2, "result": surface(true)}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "surface-live",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let cached = client.get_surface(1000).unwrap();
+        let live = client.get_surface_live(1000).unwrap();
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert!(!cached.visibility);
+        assert!(live.visibility);
+
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.params, json!({ "id": 1000 }));
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[1]).unwrap();
+        assert_eq!(request.method, "get_surface");
+        assert_eq!(request.params, json!({ "id": 1000, "live": true }));
+    }
+
+    #[test]
+    fn test_edit_surface_sends_queued_changes_then_commits() {
+        let responses = (1..=4)
+            .map(|id| serde_json::to_vec(&json!({"id": id, "result": {"success": true}})).unwrap())
+            .collect();
+        let (socket_path, server) = spawn_mock_controller("edit-surface", responses);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client
+            .edit_surface(1000)
+            .opacity(0.25)
+            .position(10, 20)
+            .visibility(true)
+            .size(640, 480)
+            .opacity(0.5)
+            .apply_and_commit()
+            .unwrap();
+
+        let requests: Vec<(String, Value)> = server
+            .join()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                let request: JsonRpcRequest = serde_json::from_slice(r).unwrap();
+                (request.method, request.params)
+            })
+            .collect();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert_eq!(
+            requests,
+            vec![
+                (
+                    "set_surface_destination_rectangle".to_string(),
+                    json!({ "id": 1000, "x": 10, "y": 20, "width": 640, "height": 480, "auto_commit": false })
+                ),
+                (
+                    "set_surface_visibility".to_string(),
+                    json!({ "id": 1000, "visible": true, "auto_commit": false })
+                ),
+                (
+                    "set_surface_opacity".to_string(),
+                    json!({ "id": 1000, "opacity": 0.5, "auto_commit": false })
+                ),
+                ("commit".to_string(), json!({})),
+            ]
+        );
+        assert_eq!(client.pending_changes(), 0);
+    }
+
+    #[test]
+    fn test_edit_surface_position_or_size_alone() {
+        let responses = vec![
+            json!({"id": 1, "result": {"success": true, "x": 5, "y": 6, "committed": false}}),
+            json!({"id": 2, "result": {"success": true, "x": 5, "y": 6, "committed": false}}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "edit-surface-partial",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.edit_surface(1000).position(5, 6).apply().unwrap();
+        // A size alone keeps the position, which the controller resolves
+        client.edit_surface(1000).size(200, 150).apply().unwrap();
+        // An empty edit sends nothing
+        client.edit_surface(1000).apply().unwrap();
+
+        let requests: Vec<(String, Value)> = server
+            .join()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                let request: JsonRpcRequest = serde_json::from_slice(r).unwrap();
+                (request.method, request.params)
+            })
+            .collect();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert_eq!(
+            requests,
+            vec![
+                (
+                    "set_surface_position".to_string(),
+                    json!({ "id": 1000, "x": 5, "y": 6, "auto_commit": false })
+                ),
+                (
+                    "set_surface_size".to_string(),
+                    json!({ "id": 1000, "width": 200, "height": 150, "auto_commit": false })
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_request_timeout() {
+        // The controller reads the requests but answers only the second one
+        let answers = vec![vec![], vec![br#"{"id":2,"result":{"pong":true}}"#.to_vec()]];
+        let (socket_path, server) = spawn_scripted_controller("request-timeout", answers);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.set_request_timeout(Some(Duration::from_millis(50)));
+
+        let started = Instant::now();
+        let err = client.send_request("ping", json!({})).unwrap_err();
+        assert!(err.is_timeout());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(err.to_string().contains("Timed out talking to controller"));
+
+        // The connection is still usable afterwards
+        assert_eq!(
+            client.send_request("ping", json!({})).unwrap()["pong"],
+            true
+        );
+
+        drop(client);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn test_timeout_on_silent_controller() {
+        // The other end of the pair never reads nor answers
+        let (stream, _controller) = UnixStream::pair().unwrap();
+        let mut client = IviClient::unconnected(None);
+        client.transport = Some(Box::new(UnixDomainIviClient::from_stream(stream)));
+        client.set_request_timeout(Some(Duration::from_millis(50)));
+
+        let started = Instant::now();
+        let err = client.send_request("ping", json!({})).unwrap_err();
+        assert!(matches!(err, IviError::Timeout(_)), "{:?}", err);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // A request larger than the socket buffer cannot be sent either
+        let padding = "x".repeat(4 * 1024 * 1024);
+        let started = Instant::now();
+        let err = client
+            .send_request("ping", json!({ "padding": padding }))
+            .unwrap_err();
+        assert!(matches!(err, IviError::Timeout(_)), "{:?}", err);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // The half-sent request leaves the connection unusable, so it is dropped
+        assert!(client.transport.is_none());
+        let err = client.send_request("ping", json!({})).unwrap_err();
+        assert!(err.is_connection_failure(), "{:?}", err);
+    }
+
+    #[test]
+    fn test_connect_with_timeout() {
+        let (socket_path, server) = spawn_scripted_controller("connect-timeout", vec![vec![]]);
+
+        let mut client =
+            IviClient::connect_with_timeout(socket_path.to_str(), Duration::from_millis(50))
+                .unwrap();
+        let err = client.list_surfaces().unwrap_err();
+        assert!(matches!(err, IviError::Timeout(_)), "{:?}", err);
+
+        drop(client);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn test_set_surface_rectangles_request() {
+        let responses = vec![br#"{"id":1,"result":{"success":true,"committed":false}}"#.to_vec()];
+        let (socket_path, server) = spawn_mock_controller("surface-rectangles", responses);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let src = Rectangle {
+            x: 10,
+            y: 20,
+            width: 320,
+            height: 240,
+        };
+        let dest = Rectangle {
+            x: 100,
+            y: 200,
+            width: 640,
+            height: 480,
+        };
+        client
+            .set_surface_rectangles(1000, src, dest, false)
+            .unwrap();
+        assert_eq!(client.pending_changes(), 1);
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.method, "set_surface_rectangles");
+        assert_eq!(
+            request.params,
+            json!({
+                "id": 1000,
+                "src": { "x": 10, "y": 20, "width": 320, "height": 240 },
+                "dest": { "x": 100, "y": 200, "width": 640, "height": 480 },
+                "auto_commit": false
+            })
+        );
+    }
+
+    #[test]
+    fn test_compact_rectangles_request() {
+        let responses = vec![
+            br#"{"id":1,"result":{"success":true,"committed":true}}"#.to_vec(),
+            br#"{"id":2,"result":{"success":true,"committed":true}}"#.to_vec(),
+            br#"{"id":3,"result":{"success":true,"committed":true}}"#.to_vec(),
+        ];
+        let (socket_path, server) = spawn_mock_controller("compact-rectangles", responses);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client
+            .set_surface_destination_rectangle(1000, 1, 2, 30, 40, true)
+            .unwrap();
+        client.set_compact_rectangles(true);
+        client
+            .set_surface_destination_rectangle(1000, 1, 2, 30, 40, true)
+            .unwrap();
+        let rect = Rectangle {
+            x: 10,
+            y: 20,
+            width: 320,
+            height: 240,
+        };
+        client
+            .set_surface_rectangles(1000, rect, rect, true)
+            .unwrap();
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let params: Vec<Value> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().params)
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                json!({ "id": 1000, "x": 1, "y": 2, "width": 30, "height": 40, "auto_commit": true }),
+                json!({ "id": 1000, "rect": [1, 2, 30, 40], "auto_commit": true }),
+                json!({
+                    "id": 1000,
+                    "src": [10, 20, 320, 240],
+                    "dest": [10, 20, 320, 240],
+                    "auto_commit": true
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dry_run_request() {
+        let responses = vec![
+            br#"{"id":1,"result":{"success":true,"committed":false}}"#.to_vec(),
+            br#"{"id":2,"result":{"id":2000,"width":1920,"height":1080,"auto_commit":true,"dry_run":true,"valid":true}}"#.to_vec(),
+            br#"{"id":3,"result":{"dry_run":true,"valid":true}}"#.to_vec(),
+        ];
+        let (socket_path, server) = spawn_mock_controller("dry-run", responses);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.set_surface_visibility(1000, true, false).unwrap();
+        client.set_dry_run(true);
+        assert!(matches!(
+            client.create_layer(2000, 1920, 1080, true).unwrap(),
+            IviRequestResult::CreateLayer(2000)
+        ));
+        // The dry run commit leaves the real change pending
+        client.commit().unwrap();
+        assert_eq!(client.pending_changes(), 1);
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let params: Vec<Value> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().params)
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                json!({ "id": 1000, "visible": true, "auto_commit": false }),
+                json!({ "id": 2000, "width": 1920, "height": 1080, "auto_commit": true, "dry_run": true }),
+                json!({ "dry_run": true }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_surfaces_on_output() {
+        let surface = |id: u32, visible: bool| {
+            json!({"id": id, "orig_size": {"width": 800, "height": 600},
+                "src_rect": {"x": 0, "y": 0, "width": 800, "height": 600},
+                "dest_rect": {"x": 0, "y": 0, "width": 800, "height": 600},
+                "visibility": visible, "opacity": 1.0, "orientation": "Normal", "z_order": 0})
+        };
+        let layer = |id: u32, visible: bool| {
+            json!({"id": id,
+                "src_rect": {"x": 0, "y": 0, "width": 1920, "height": 1080},
+                "dest_rect": {"x": 0, "y": 0, "width": 1920, "height": 1080},
+                "visibility": visible, "opacity": 1.0, "orientation": "Normal"})
+        };
+        let responses = vec![
+            json!({"id": 1, "result": {"index": 1, "name": "HDMI-A-2",
+                "width": 1920, "height": 1080, "x": 1920, "y": 0}}),
+            json!({"id": 2, "result": {"screen_name": "HDMI-A-2", "layer_ids": [3000, 2000, 4000]}}),
+            json!({"id": 3, "result": {"layers": [layer(2000, true), layer(3000, true), layer(4000, false)]}}),
+            json!({"id": 4, "result": {"surfaces": [
+                surface(1000, true), surface(1001, false), surface(1002, true), surface(1003, true)
+            ], "total": 4}}),
+            json!({"id": 5, "result": {"layer_id": 3000, "surface_ids": [1002]}}),
+            json!({"id": 6, "result": {"layer_id": 2000, "surface_ids": [1000, 1001, 1002]}}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "surfaces-on-output",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let ids: Vec<u32> = client
+            .surfaces_on_output(1)
+            .unwrap()
+            .iter()
+            .map(|surface| surface.id)
+            .collect();
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        // 1001 is hidden, 1002 is listed once, and hidden layer 4000 is not read
+        assert_eq!(ids, vec![1002, 1000]);
+        let methods: Vec<String> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().method)
+            .collect();
+        assert_eq!(
+            methods,
+            vec![
+                "get_output",
+                "get_screen_layers",
+                "list_layers",
+                "list_surfaces",
+                "get_layer_surfaces",
+                "get_layer_surfaces"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_limits_from_capabilities() {
+        let responses = vec![
+            json!({"id": 1, "result": {
+                "protocol": 2, "methods": ["ping"],
+                "max_frame_bytes": 67108864, "max_subscriptions_per_client": 8,
+                "notify_queue_depth": 100, "max_surface_size": 4096
+            }}),
+            // An older controller reports no limits
+            json!({"id": 2, "result": {"v": 1, "methods": ["ping"]}}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "limits",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        assert_eq!(
+            client.limits().unwrap(),
+            Limits {
+                max_frame_bytes: Some(64 * 1024 * 1024),
+                max_subscriptions: Some(8),
+                notify_queue_depth: Some(100),
+                max_surface_size: Some(4096),
+            }
+        );
+        assert_eq!(client.limits().unwrap(), Limits::default());
 
-        #[cfg(feature = "enable-ipcon")]
-        let transport: Box<dyn IviClientTransport> =
-            Box::new(IpconIviClient::ipcon_connect(None, remote)?);
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
 
-        Ok(Self {
-            transport: Arc::new(Mutex::new(transport)),
-            request_id: AtomicU64::new(1),
-            callbacks: Arc::new(Mutex::new(HashMap::new())),
-            catch_all_callbacks: Arc::new(Mutex::new(Vec::new())),
-            stop_flag: Arc::new(AtomicBool::new(false)),
-            thread_handle: None,
-        })
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.method, "get_capabilities");
     }
 
-    fn next_request_id(&self) -> u64 {
-        self.request_id.fetch_add(1, Ordering::SeqCst)
+    #[test]
+    fn test_connect_with_handshake() {
+        let responses = vec![
+            json!({"id": 1, "result": {
+                "v": 1, "protocol": 2, "version": "0.1.0", "transport": "unix",
+                "methods": ["ping", "stats"], "max_surface_size": 4096
+            }}),
+            json!({"id": 2, "result": {"pong": true}}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "handshake",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::connect_with_handshake(socket_path.to_str()).unwrap();
+        assert_eq!(client.protocol_version(), Some(2));
+        let capabilities = client.capabilities().unwrap();
+        assert_eq!(capabilities.version.as_deref(), Some("0.1.0"));
+        assert!(capabilities.supports("stats"));
+        assert!(!capabilities.supports("clear_subscriptions"));
+        assert_eq!(capabilities.limits.max_surface_size, Some(4096));
+        assert_eq!(capabilities.limits.max_frame_bytes, None);
+
+        // Kept on the client, so later calls send no request
+        assert_eq!(client.protocol_version(), Some(2));
+        client.send_request("ping", json!({})).unwrap();
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let methods: Vec<String> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().method)
+            .collect();
+        assert_eq!(methods, ["get_capabilities", "ping"]);
     }
 
-    fn send_rpc(&self, method: &str, params: Value) -> Result<Value> {
-        let request_id = self.next_request_id();
-        let request = JsonRpcRequest::new(request_id, method, params);
-        let request_json = serde_json::to_vec(&request)
-            .map_err(|e| IviError::SerializationError(e.to_string()))?;
+    #[test]
+    fn test_set_surface_orientation() {
+        let responses = vec![json!({"id": 1, "result": {"success": true, "committed": true}})];
+        let (socket_path, server) = spawn_mock_controller(
+            "set_surface_orientation",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
 
-        let mut transport = self.transport.lock().unwrap();
-        transport.send_request(&request_json)?;
-        let response_buf = transport.receive_response()?;
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.set_surface_orientation(1000, 270, true).unwrap();
 
-        let response: JsonRpcResponse = serde_json::from_slice(&response_buf)
-            .map_err(|e| IviError::DeserializationError(e.to_string()))?;
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
 
-        if let Some(error) = response.error {
-            return Err(IviError::RequestFailed {
-                code: error.code,
-                message: error.message,
-            });
-        }
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.method, "set_surface_orientation");
+        assert_eq!(
+            request.params,
+            json!({ "id": 1000, "orientation": 270, "auto_commit": true })
+        );
+    }
 
-        response.result.ok_or_else(|| {
-            IviError::DeserializationError("Response missing both result and error".to_string())
-        })
+    #[test]
+    fn test_clear_subscriptions() {
+        let responses = vec![
+            json!({"id": 1, "result": {"success": true, "cleared_clients": 1}}),
+            json!({"id": 2, "result": {"success": true, "cleared_clients": 1}}),
+            json!({"id": 3, "result": {"success": true, "cleared_clients": 4}}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "clear_subscriptions",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        assert_eq!(client.clear_subscriptions(Some(7)).unwrap(), 1);
+        assert_eq!(client.clear_subscriptions(None).unwrap(), 1);
+        assert_eq!(client.clear_all_subscriptions().unwrap(), 4);
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.method, "clear_subscriptions");
+        assert_eq!(request.params, json!({ "client_id": 7 }));
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[1]).unwrap();
+        assert_eq!(request.params, json!({}));
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[2]).unwrap();
+        assert_eq!(request.params, json!({ "all": true }));
     }
 
-    /// Register a callback for a specific event type.
-    /// Multiple callbacks per event type are allowed.
-    pub fn on<F>(&mut self, event_type: EventType, callback: F)
-    where
-        F: Fn(&Notification) + Send + Sync + 'static,
-    {
-        self.callbacks
-            .lock()
-            .unwrap()
-            .entry(event_type)
-            .or_default()
-            .push(Arc::new(callback));
+    #[test]
+    fn test_stats() {
+        let responses = vec![json!({"id": 1, "result": {
+            "surfaces": 5, "layers": 3, "connected_clients": 2,
+            "subscriptions": 7, "pending_changes": 1
+        }})];
+        let (socket_path, server) = spawn_mock_controller(
+            "stats",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        assert_eq!(
+            client.stats().unwrap(),
+            Stats {
+                surfaces: 5,
+                layers: 3,
+                connected_clients: 2,
+                subscriptions: 7,
+                pending_changes: 1,
+            }
+        );
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.method, "stats");
     }
 
-    /// Register a catch-all callback invoked for every received event.
-    pub fn on_all<F>(&mut self, callback: F)
-    where
-        F: Fn(&Notification) + Send + Sync + 'static,
-    {
-        self.catch_all_callbacks
-            .lock()
+    #[test]
+    fn test_get_surface_z_index() {
+        let responses = vec![json!({"id": 1, "result": {
+            "id": 1000,
+            "layers": [{"layer_id": 2000, "index": 2, "count": 7}]
+        }})];
+        let (socket_path, server) = spawn_mock_controller(
+            "surface-z-index",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        assert_eq!(
+            client.get_surface_z_index(1000).unwrap(),
+            vec![SurfaceZIndex {
+                layer_id: 2000,
+                index: 2,
+                count: 7,
+            }]
+        );
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.method, "get_surface_z_index");
+        assert_eq!(request.params, json!({ "id": 1000 }));
+    }
+
+    #[test]
+    fn test_nudge_surface_accumulates() {
+        let responses = vec![
+            json!({"id": 1, "result": {"success": true, "x": 110, "y": 190, "committed": true}}),
+            json!({"id": 2, "result": {"success": true, "x": 120, "y": 180, "committed": true}}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "nudge",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        assert_eq!(client.nudge_surface(1000, 10, -10).unwrap(), (110, 190));
+        assert_eq!(client.nudge_surface(1000, 10, -10).unwrap(), (120, 180));
+
+        let requests: Vec<(String, Value)> = server
+            .join()
             .unwrap()
-            .push(Arc::new(callback));
+            .iter()
+            .map(|r| {
+                let request: JsonRpcRequest = serde_json::from_slice(r).unwrap();
+                (request.method, request.params)
+            })
+            .collect();
+        let _ = std::fs::remove_file(&socket_path);
+
+        // Only offsets are sent; the controller adds them to the position
+        let nudge = (
+            "set_surface_position".to_string(),
+            json!({ "id": 1000, "dx": 10, "dy": -10, "relative": true, "auto_commit": true }),
+        );
+        assert_eq!(requests, vec![nudge.clone(), nudge]);
     }
 
-    /// Subscribe to the given event types on the server and start the
-    /// background reader thread. Callbacks registered with `on`/`on_all`
-    /// will fire from this thread.
-    pub fn start(&mut self, event_types: &[EventType]) -> Result<()> {
-        self.send_rpc("subscribe", json!({ "event_types": event_types }))?;
+    #[test]
+    fn test_try_clone_opens_independent_connection() {
+        let socket_path =
+            std::env::temp_dir().join(format!("ivi-client-clone-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
 
-        self.stop_flag.store(false, Ordering::Relaxed);
+        // Mock controller: answer one request on each of two connections
+        let server = std::thread::spawn(move || {
+            let mut ids = Vec::new();
+            for _ in 0..2 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = FrameReader::new();
+                let request = loop {
+                    match reader.read_frame(&mut stream).unwrap() {
+                        FrameReadResult::Complete(msg) => break msg,
+                        FrameReadResult::NeedMore => continue,
+                        FrameReadResult::Eof => panic!("client closed the connection"),
+                    }
+                };
+                let request: JsonRpcRequest = serde_json::from_slice(&request).unwrap();
+                let response = json!({"id": request.id, "result": {"pong": true}});
+                write_frame(&mut stream, &serde_json::to_vec(&response).unwrap()).unwrap();
+                ids.push(request.id);
+            }
+            ids
+        });
 
-        let transport = Arc::clone(&self.transport);
-        let callbacks = Arc::clone(&self.callbacks);
-        let catch_all_callbacks = Arc::clone(&self.catch_all_callbacks);
-        let stop_flag = Arc::clone(&self.stop_flag);
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.next_request_id();
+        let mut clone = client.try_clone().unwrap();
 
-        self.thread_handle = Some(std::thread::spawn(move || {
-            loop {
-                if stop_flag.load(Ordering::Relaxed) {
-                    break;
+        client.send_request("ping", json!({})).unwrap();
+        clone.send_request("ping", json!({})).unwrap();
+
+        let ids = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        // The clone starts its own request-id counter
+        assert_eq!(ids, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_set_surfaces_visibility_sends_single_request() {
+        let (socket_path, server) = spawn_mock_controller(
+            "surfaces-visibility",
+            vec![br#"{"id":1,"result":{"success":true,"count":2,"committed":false}}"#.to_vec()],
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client
+            .set_surfaces_visibility(&[1000, 1001], false, false)
+            .unwrap();
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.method, "set_surfaces_visibility");
+        assert_eq!(
+            request.params,
+            json!({ "ids": [1000, 1001], "visible": false, "auto_commit": false })
+        );
+        assert_eq!(client.pending_changes(), 1);
+    }
+
+    #[test]
+    fn test_commit_surface_keeps_other_changes_pending() {
+        let responses = vec![
+            json!({"id": 1, "result": {"success": true, "committed": false}}),
+            json!({"id": 2, "result": {"success": true, "committed": false}}),
+            json!({"id": 3, "result": {"success": true, "surface_id": 1000, "applied": 1}}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "commit-surface",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.set_surface_visibility(1000, true, false).unwrap();
+        client.set_surface_visibility(1001, true, false).unwrap();
+        assert_eq!(client.commit_surface(1000).unwrap(), 1);
+        assert_eq!(client.pending_changes(), 1);
+
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let commit: JsonRpcRequest = serde_json::from_slice(&requests[2]).unwrap();
+        assert_eq!(commit.method, "commit");
+        assert_eq!(commit.params, json!({ "id": 1000 }));
+    }
+
+    #[test]
+    fn test_subscribe_returns_ignored_event_types() {
+        let responses = vec![
+            json!({"id": 1, "result": {
+                "success": true,
+                "subscribed": ["SurfaceCreated", "LayerCreated"],
+                "ignored": ["SurfaceCreated"]
+            }}),
+            json!({"id": 2, "result": {"success": true, "subscribed": ["LayerCreated"]}}),
+        ];
+        let (socket_path, server) = spawn_mock_controller(
+            "subscribe-ignored",
+            responses
+                .iter()
+                .map(|r| serde_json::to_vec(r).unwrap())
+                .collect(),
+        );
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let ignored = client
+            .subscribe(&[EventType::SurfaceCreated, EventType::LayerCreated])
+            .unwrap();
+        assert_eq!(ignored, vec![EventType::SurfaceCreated]);
+
+        // Older controllers do not report ignored event types
+        let ignored = client.subscribe(&[EventType::LayerCreated]).unwrap();
+        assert!(ignored.is_empty());
+
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn test_raw_notification_stream_ends_on_disconnect() {
+        let socket_path =
+            std::env::temp_dir().join(format!("ivi-client-stream-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        // Mock controller: answer the subscribe request, push frames and disconnect
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = FrameReader::new();
+            let request = loop {
+                match reader.read_frame(&mut stream).unwrap() {
+                    FrameReadResult::Complete(msg) => break msg,
+                    FrameReadResult::NeedMore => continue,
+                    FrameReadResult::Eof => panic!("client closed the connection"),
                 }
+            };
+            let notification = |surface_id: u32| {
+                serde_json::to_vec(&json!({
+                    "method": "notification",
+                    "params": {"event_type": "SurfaceCreated", "surface_id": surface_id}
+                }))
+                .unwrap()
+            };
+            for frame in [
+                br#"{"id":1,"result":{"success":true}}"#.to_vec(),
+                notification(1000),
+                b"{invalid json}".to_vec(),
+                br#"{"id":7,"result":{}}"#.to_vec(),
+                notification(1001),
+            ] {
+                write_frame(&mut stream, &frame).unwrap();
+            }
+            serde_json::from_slice::<JsonRpcRequest>(&request).unwrap()
+        });
 
-                let frame = {
-                    let mut t = transport.lock().unwrap();
-                    let _ = t.set_read_timeout(Some(Duration::from_millis(100)));
-                    let r = t.receive_response();
-                    let _ = t.set_read_timeout(None);
-                    r
-                };
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.subscribe(&[EventType::SurfaceCreated]).unwrap();
+        let received: Vec<Result<Notification>> = client.raw_notification_stream().collect();
 
-                match frame {
-                    Ok(bytes) => match Notification::try_from_frame(&bytes) {
-                        Ok(Some(notif)) => {
-                            // Clone Arc refs before dispatching to avoid holding the lock
-                            // while callbacks run.
-                            let per_type: Vec<NotificationCallback> = callbacks
-                                .lock()
-                                .unwrap()
-                                .get(&notif.event_type)
-                                .cloned()
-                                .unwrap_or_default();
-                            for cb in &per_type {
-                                cb(&notif);
-                            }
-                            let all: Vec<NotificationCallback> =
-                                catch_all_callbacks.lock().unwrap().clone();
-                            for cb in &all {
-                                cb(&notif);
-                            }
+        let request = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        assert_eq!(request.method, "subscribe");
+        assert_eq!(received.len(), 3);
+        assert_eq!(received[0].as_ref().unwrap().params["surface_id"], 1000);
+        assert!(received[1].is_err());
+        assert_eq!(received[2].as_ref().unwrap().params["surface_id"], 1001);
+
+        // Dropping the stream leaves the client usable
+        assert!(client.raw_notification_stream().next().is_none());
+    }
+
+    #[test]
+    fn test_send_request_queues_interleaved_notifications() {
+        let notification = serde_json::to_vec(&json!({
+            "method": "notification",
+            "params": {"event_type": "SurfaceCreated", "surface_id": 1000}
+        }))
+        .unwrap();
+        let answers = vec![
+            vec![br#"{"id":1,"result":{"success":true}}"#.to_vec()],
+            vec![
+                // Notification and a stale response ahead of the response to request 2
+                notification,
+                br#"{"id":1,"result":{"success":true}}"#.to_vec(),
+                br#"{"id":2,"result":{"surface_id":null}}"#.to_vec(),
+            ],
+        ];
+        let (socket_path, server) = spawn_scripted_controller("interleaved", answers);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.subscribe(&[EventType::SurfaceCreated]).unwrap();
+        assert_eq!(client.get_focus().unwrap(), None);
+
+        let notif = client.next_notification().unwrap();
+        assert_eq!(notif.event_type, EventType::SurfaceCreated);
+        assert_eq!(notif.params["surface_id"], 1000);
+
+        drop(client);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    /// Mock controller that answers each request with a batch of frames and
+    /// keeps the connection open until the client closes it
+    fn spawn_scripted_controller(
+        name: &str,
+        answers: Vec<Vec<Vec<u8>>>,
+    ) -> (std::path::PathBuf, std::thread::JoinHandle<Vec<Vec<u8>>>) {
+        let socket_path =
+            std::env::temp_dir().join(format!("ivi-client-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = FrameReader::new();
+            let mut requests = Vec::new();
+            let mut answers = answers.into_iter();
+            loop {
+                match reader.read_frame(&mut stream).unwrap() {
+                    FrameReadResult::Complete(request) => {
+                        requests.push(request);
+                        for frame in answers.next().unwrap_or_default() {
+                            write_frame(&mut stream, &frame).unwrap();
                         }
-                        Ok(None) => {} // stray RPC response, skip
-                        Err(_) => {}   // parse error, skip
-                    },
-                    Err(IviError::IoError(ref e))
-                        if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut =>
-                    {
-                        continue; // timeout — check stop flag and retry
                     }
-                    Err(_) => break, // real error — exit thread
+                    FrameReadResult::NeedMore => continue,
+                    FrameReadResult::Eof => break,
                 }
             }
-        }));
+            requests
+        });
 
-        Ok(())
+        (socket_path, server)
     }
 
-    /// Signal the background thread to stop and wait for it to finish.
-    pub fn stop(&mut self) {
-        self.stop_flag.store(true, Ordering::Relaxed);
-        if let Some(handle) = self.thread_handle.take() {
-            let _ = handle.join();
-        }
+    #[test]
+    fn test_auto_reconnect_resends_with_same_idempotency_key() {
+        let socket_path = std::env::temp_dir().join(format!(
+            "ivi-client-auto-reconnect-{}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let read_request = |stream: &mut std::os::unix::net::UnixStream| {
+                let mut reader = FrameReader::new();
+                loop {
+                    match reader.read_frame(stream).unwrap() {
+                        FrameReadResult::Complete(request) => break request,
+                        FrameReadResult::NeedMore => continue,
+                        FrameReadResult::Eof => panic!("client closed the connection"),
+                    }
+                }
+            };
+
+            // The first connection breaks before the response is sent
+            let (mut stream, _) = listener.accept().unwrap();
+            let first = read_request(&mut stream);
+            drop(stream);
+
+            let (mut stream, _) = listener.accept().unwrap();
+            let retry = read_request(&mut stream);
+            let response = json!({"id": 1, "result": {"success": true, "x": 10, "y": 0}});
+            write_frame(&mut stream, &serde_json::to_vec(&response).unwrap()).unwrap();
+            (first, retry)
+        });
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.set_auto_reconnect(true);
+        assert_eq!(client.nudge_surface(1000, 10, 0).unwrap(), (10, 0));
+
+        let (first, retry) = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let first: JsonRpcRequest = serde_json::from_slice(&first).unwrap();
+        let retry: JsonRpcRequest = serde_json::from_slice(&retry).unwrap();
+        assert!(first.params["idempotency_key"].is_string());
+        assert_eq!(first.params, retry.params);
     }
-}
 
-impl Drop for NotificationListener {
-    fn drop(&mut self) {
-        self.stop();
-        let mut t = self.transport.lock().unwrap();
-        let _ = t.disconnect();
+    fn surface_created(surface_id: u32) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "type": "notification",
+            "method": "notification",
+            "params": {"event_type": "SurfaceCreated", "surface_id": surface_id}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_subscribe_once_returns_first_match_and_cleans_up() {
+        let layer_created = serde_json::to_vec(&json!({
+            "type": "notification",
+            "method": "notification",
+            "params": {"event_type": "LayerCreated", "layer_id": 2000}
+        }))
+        .unwrap();
+        let answers = vec![
+            vec![
+                br#"{"id":1,"result":{"success":true}}"#.to_vec(),
+                surface_created(999),
+                layer_created,
+                surface_created(1000),
+            ],
+            vec![
+                // Sent before the controller handled the unsubscribe
+                surface_created(1001),
+                br#"{"id":2,"result":{"success":true}}"#.to_vec(),
+                surface_created(1002),
+            ],
+        ];
+        let (socket_path, server) = spawn_scripted_controller("subscribe-once", answers);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let notif = client
+            .subscribe_once(
+                EventType::SurfaceCreated,
+                |n| n.params["surface_id"] == 1000,
+                Duration::from_secs(5),
+            )
+            .unwrap();
+        assert_eq!(notif.params["surface_id"], 1000);
+
+        // Only the notification of another event type is left
+        assert_eq!(client.queued_notifications.len(), 1);
+        assert_eq!(
+            client.next_notification().unwrap().event_type,
+            EventType::LayerCreated
+        );
+
+        drop(client);
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let requests: Vec<JsonRpcRequest> = requests
+            .iter()
+            .map(|r| serde_json::from_slice(r).unwrap())
+            .collect();
+        assert_eq!(requests[0].method, "subscribe");
+        assert_eq!(requests[1].method, "unsubscribe");
+        assert_eq!(
+            requests[1].params,
+            json!({ "event_types": ["SurfaceCreated"] })
+        );
+    }
+
+    #[test]
+    fn test_subscribe_once_keeps_existing_subscription() {
+        let answers = vec![
+            vec![
+                br#"{"id":1,"result":{"success":true,"subscribed":[],"ignored":[]}}"#.to_vec(),
+                surface_created(1000),
+            ],
+            vec![br#"{"id":2,"result":{"pong":true}}"#.to_vec()],
+        ];
+        let (socket_path, server) = spawn_scripted_controller("subscribe-once-existing", answers);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let notif = client
+            .subscribe_once(EventType::SurfaceCreated, |_| true, Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(notif.params["surface_id"], 1000);
+        client.send_request("ping", json!({})).unwrap();
+
+        drop(client);
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        // The subscription predates the call, so it is not removed
+        let methods: Vec<String> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().method)
+            .collect();
+        assert_eq!(methods, vec!["subscribe", "ping"]);
+    }
+
+    #[test]
+    fn test_poll_notification() {
+        let answers = vec![
+            vec![
+                surface_created(999),
+                br#"{"id":1,"result":{"success":true}}"#.to_vec(),
+                b"{invalid json}".to_vec(),
+                surface_created(1000),
+            ],
+            vec![br#"{"id":2,"result":{"pong":true}}"#.to_vec()],
+        ];
+        let (socket_path, server) = spawn_scripted_controller("poll-notification", answers);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.subscribe(&[EventType::SurfaceCreated]).unwrap();
+
+        // The notification that arrived before the response was queued
+        let notif = client.poll_notification(Some(Duration::ZERO)).unwrap();
+        assert_eq!(notif.unwrap().params["surface_id"], 999);
+        assert!(client
+            .poll_notification(Some(Duration::ZERO))
+            .unwrap()
+            .is_none());
+
+        // The malformed frame is skipped
+        let notif = client
+            .poll_notification(Some(Duration::from_secs(5)))
+            .unwrap();
+        assert_eq!(notif.unwrap().params["surface_id"], 1000);
+
+        let started = Instant::now();
+        assert!(client
+            .poll_notification(Some(Duration::from_millis(50)))
+            .unwrap()
+            .is_none());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        // The connection is still usable for requests afterwards
+        assert_eq!(
+            client.send_request("ping", json!({})).unwrap()["pong"],
+            true
+        );
+
+        drop(client);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn test_subscribe_once_times_out() {
+        let answers = vec![
+            vec![
+                br#"{"id":1,"result":{"success":true}}"#.to_vec(),
+                surface_created(999),
+            ],
+            vec![br#"{"id":2,"result":{"success":true}}"#.to_vec()],
+        ];
+        let (socket_path, server) = spawn_scripted_controller("subscribe-once-timeout", answers);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let started = Instant::now();
+        let err = client
+            .subscribe_once(
+                EventType::SurfaceCreated,
+                |n| n.params["surface_id"] == 1000,
+                Duration::from_millis(50),
+            )
+            .unwrap_err();
+        assert!(err.is_timeout());
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(client.queued_notifications.is_empty());
+
+        drop(client);
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        // The subscription is removed after a timeout too
+        let methods: Vec<String> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().method)
+            .collect();
+        assert_eq!(methods, vec!["subscribe", "unsubscribe"]);
+    }
+
+    #[test]
+    fn test_flush_notifications_discards_pending() {
+        let notification = |surface_id: u32| {
+            serde_json::to_vec(&json!({
+                "type": "notification",
+                "method": "notification",
+                "params": {"event_type": "SurfaceCreated", "surface_id": surface_id}
+            }))
+            .unwrap()
+        };
+        let answers = vec![
+            // One notification queued by the request, two left on the socket
+            vec![
+                notification(1000),
+                br#"{"id":1,"result":{"success":true}}"#.to_vec(),
+                notification(1001),
+                notification(1002),
+            ],
+            vec![br#"{"id":2,"result":{"surface_id":1000}}"#.to_vec()],
+        ];
+        let (socket_path, server) = spawn_scripted_controller("flush", answers);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.subscribe(&[EventType::SurfaceCreated]).unwrap();
+        assert_eq!(client.flush_notifications().unwrap(), 3);
+        assert_eq!(client.get_focus().unwrap(), Some(1000));
+
+        drop(client);
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn test_set_socket_path_redirects_reconnect() {
+        let response = |id: u64| {
+            serde_json::to_vec(&json!({"id": id, "result": {"surface_id": 1000}})).unwrap()
+        };
+        let (first_path, first) = spawn_mock_controller("redirect-first", vec![response(1)]);
+        // Request ids continue across the reconnect
+        let (second_path, second) = spawn_mock_controller("redirect-second", vec![response(2)]);
+
+        let mut client = IviClient::new(first_path.to_str()).unwrap();
+        assert_eq!(client.get_focus().unwrap(), Some(1000));
+        assert_eq!(first.join().unwrap().len(), 1);
+
+        assert!(client.set_socket_path("").is_err());
+        client
+            .set_socket_path(second_path.to_str().unwrap())
+            .unwrap();
+        client.reconnect().unwrap();
+        assert_eq!(client.get_focus().unwrap(), Some(1000));
+
+        let requests = second.join().unwrap();
+        let _ = std::fs::remove_file(&first_path);
+        let _ = std::fs::remove_file(&second_path);
+
+        let request: JsonRpcRequest = serde_json::from_slice(&requests[0]).unwrap();
+        assert_eq!(request.method, "get_focus");
+    }
+
+    #[test]
+    fn test_id_generator_sets_request_ids() {
+        let answers = vec![
+            // A response to another id is skipped, not taken for this one
+            vec![
+                br#"{"id":7,"result":{"pong":true}}"#.to_vec(),
+                br#"{"id":42,"result":{"pong":true}}"#.to_vec(),
+            ],
+            vec![br#"{"id":42,"result":{"pong":true}}"#.to_vec()],
+            vec![br#"{"id":1,"result":{"pong":true}}"#.to_vec()],
+        ];
+        let (socket_path, server) = spawn_scripted_controller("id-generator", answers);
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        client.set_id_generator(Some(Box::new(|| 42)));
+        assert_eq!(
+            client.send_request("ping", json!({})).unwrap()["pong"],
+            true
+        );
+        assert_eq!(
+            client.send_request("ping", json!({})).unwrap()["pong"],
+            true
+        );
+
+        // Removing the generator goes back to the counter
+        client.set_id_generator(None);
+        assert_eq!(
+            client.send_request("ping", json!({})).unwrap()["pong"],
+            true
+        );
+
+        drop(client);
+        let requests = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let ids: Vec<u64> = requests
+            .iter()
+            .map(|r| serde_json::from_slice::<JsonRpcRequest>(r).unwrap().id)
+            .collect();
+        assert_eq!(ids, vec![42, 42, 1]);
+    }
+
+    #[test]
+    fn test_debug_shows_connection_state() {
+        let socket_path =
+            std::env::temp_dir().join(format!("ivi-client-debug-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let mut client = IviClient::new(socket_path.to_str()).unwrap();
+        let (_stream, _) = listener.accept().unwrap();
+
+        let debug = format!("{:?}", client);
+        assert!(debug.starts_with("IviClient {"));
+        assert!(debug.contains("connected: true"));
+        assert!(debug.contains(&format!("{:?}", socket_path.display().to_string())));
+        assert!(debug.contains("next_request_id: 1"));
+        assert!(debug.contains("pending_changes: 0"));
+
+        client.disconnect().unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+
+        let debug = format!("{:?}", client);
+        assert!(debug.contains("connected: false"));
+        assert!(debug.contains("peer_addr: None"));
     }
 }
@@ -1,11 +1,15 @@
 use super::IviClientTransport;
 use crate::error::{IviError, Result};
-use std::os::unix::net::UnixStream;
+use std::io;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixStream};
+use std::path::Path;
 use std::time::Duration;
 use weston_ivi_controller::rpc::framing::{write_frame, FrameReadResult, FrameReader};
+use weston_ivi_controller::transport::abstract_socket_name;
 
-/// Default socket path for the IVI controller
-pub const DEFAULT_SOCKET_PATH: &str = "/tmp/weston-ivi-controller.sock";
+/// Default socket path for the IVI controller, shared with the plugin
+pub use weston_ivi_controller::transport::DEFAULT_SOCKET_PATH;
 
 /// Client for communicating with the Weston IVI controller.
 ///
@@ -37,7 +41,8 @@ impl UnixDomainIviClient {
     ///
     /// # Arguments
     ///
-    /// * `socket_path` - Path to the UNIX domain socket (e.g., "/tmp/weston-ivi-controller.sock")
+    /// * `socket_path` - Path to the UNIX domain socket (e.g., "/tmp/weston-ivi-controller.sock"),
+    ///   or `@` followed by the name of an abstract socket (e.g., "@weston-ivi-controller")
     ///
     /// # Returns
     ///
@@ -62,13 +67,38 @@ impl UnixDomainIviClient {
     /// ```
     pub fn connect(socket_path: Option<&str>) -> Result<Self> {
         let socket_path = socket_path.unwrap_or(DEFAULT_SOCKET_PATH);
-        let socket = UnixStream::connect(socket_path)
+        let socket = connect_stream(socket_path)
             .map_err(|e| IviError::ConnectionFailed(format!("{}: {}", socket_path, e)))?;
 
-        Ok(Self {
+        Ok(Self::from_stream(socket))
+    }
+
+    /// Wraps an already connected socket
+    pub(crate) fn from_stream(socket: UnixStream) -> Self {
+        Self {
             socket: Some(socket),
             frame_reader: FrameReader::new(),
-        })
+        }
+    }
+}
+
+/// Connect to a socket path, or to an abstract socket if the path starts with `@`
+fn connect_stream(socket_path: &str) -> io::Result<UnixStream> {
+    match abstract_socket_name(Path::new(socket_path)) {
+        Some(name) => UnixStream::connect_addr(&SocketAddr::from_abstract_name(name)?),
+        None => UnixStream::connect(socket_path),
+    }
+}
+
+/// Format a socket address as its path, `@name` for abstract sockets, or
+/// `(unnamed)` for unbound client ends
+fn socket_addr_to_string(addr: &SocketAddr) -> String {
+    if let Some(path) = addr.as_pathname() {
+        return path.display().to_string();
+    }
+    match addr.as_abstract_name() {
+        Some(name) => format!("@{}", String::from_utf8_lossy(name)),
+        None => "(unnamed)".to_string(),
     }
 }
 
@@ -94,9 +124,9 @@ impl IviClientTransport for UnixDomainIviClient {
             match self.frame_reader.read_frame(socket)? {
                 FrameReadResult::Complete(msg) => break msg,
                 FrameReadResult::NeedMore => {
-                    // Partial read, continue reading
-                    std::thread::yield_now();
-                    continue;
+                    // The socket blocks, so no data means the read timeout
+                    // expired. A partial frame stays buffered for the next call
+                    return Err(IviError::IoError(io::Error::from(io::ErrorKind::TimedOut)));
                 }
                 FrameReadResult::Eof => {
                     return Err(IviError::IoError(std::io::Error::new(
@@ -118,6 +148,16 @@ impl IviClientTransport for UnixDomainIviClient {
         Ok(())
     }
 
+    fn peer_addr(&self) -> Option<String> {
+        let addr = self.socket.as_ref()?.peer_addr().ok()?;
+        Some(socket_addr_to_string(&addr))
+    }
+
+    fn local_addr(&self) -> Option<String> {
+        let addr = self.socket.as_ref()?.local_addr().ok()?;
+        Some(socket_addr_to_string(&addr))
+    }
+
     fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
         let socket = self.socket.as_mut().ok_or_else(|| {
             IviError::IoError(std::io::Error::new(
@@ -127,4 +167,14 @@ impl IviClientTransport for UnixDomainIviClient {
         })?;
         socket.set_read_timeout(timeout).map_err(IviError::IoError)
     }
+
+    fn set_write_timeout(&mut self, timeout: Option<Duration>) -> Result<()> {
+        let socket = self.socket.as_mut().ok_or_else(|| {
+            IviError::IoError(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "Socket is not connected",
+            ))
+        })?;
+        socket.set_write_timeout(timeout).map_err(IviError::IoError)
+    }
 }
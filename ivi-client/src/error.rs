@@ -1,5 +1,6 @@
 //! Error types for the IVI client library
 
+use crate::protocol::JsonRpcError;
 use thiserror::Error;
 
 /// Result type alias for IVI client operations
@@ -27,6 +28,99 @@ pub enum IviError {
     /// I/O error occurred during communication
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
+
+    /// The controller did not answer a request, or take it, in time
+    #[error("{0}")]
+    Timeout(String),
+}
+
+impl IviError {
+    /// Returns true if the request failed because the surface or layer does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use ivi_client::IviClient;
+    ///
+    /// let mut client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+    /// match client.get_layer(2000) {
+    ///     Ok(layer) => println!("{:?}", layer),
+    ///     Err(e) if e.is_not_found() => println!("Layer 2000 does not exist"),
+    ///     Err(e) => return Err(e),
+    /// }
+    /// # Ok::<(), ivi_client::IviError>(())
+    /// ```
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            IviError::RequestFailed {
+                code: JsonRpcError::NOT_FOUND,
+                ..
+            }
+        )
+    }
+
+    /// Returns true if the controller rejected the request parameters.
+    pub fn is_invalid_params(&self) -> bool {
+        matches!(
+            self,
+            IviError::RequestFailed {
+                code: JsonRpcError::INVALID_PARAMS,
+                ..
+            }
+        )
+    }
+
+    /// Returns true if waiting for the controller timed out.
+    ///
+    /// True for [`IviError::Timeout`], and for I/O errors of a socket whose
+    /// read timeout expired.
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            IviError::Timeout(_) => true,
+            IviError::IoError(e) => {
+                e.kind() == std::io::ErrorKind::TimedOut
+                    || e.kind() == std::io::ErrorKind::WouldBlock
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the controller could not be reached or the connection was lost.
+    pub fn is_connection_failure(&self) -> bool {
+        match self {
+            IviError::ConnectionFailed(_) => true,
+            IviError::IoError(_) => !self.is_timeout(),
+            _ => false,
+        }
+    }
+
+    /// Appends the name of the RPC method that was being called to the error message.
+    ///
+    /// The variant (and the error code for `RequestFailed`) is preserved so callers
+    /// can still match on it.
+    pub(crate) fn with_method(self, method: &str) -> Self {
+        let context = format!("while calling {}", method);
+        match self {
+            IviError::ConnectionFailed(msg) => {
+                IviError::ConnectionFailed(format!("{} {}", msg, context))
+            }
+            IviError::RequestFailed { code, message } => IviError::RequestFailed {
+                code,
+                message: format!("{} {}", message, context),
+            },
+            IviError::SerializationError(msg) => {
+                IviError::SerializationError(format!("{} {}", msg, context))
+            }
+            IviError::DeserializationError(msg) => {
+                IviError::DeserializationError(format!("{} {}", msg, context))
+            }
+            IviError::IoError(e) => {
+                IviError::IoError(std::io::Error::new(e.kind(), format!("{} {}", e, context)))
+            }
+            IviError::Timeout(msg) => IviError::Timeout(format!("{} {}", msg, context)),
+        }
+    }
 }
 
 impl From<serde_json::Error> for IviError {
@@ -58,6 +152,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_is_not_found() {
+        let surface = IviError::RequestFailed {
+            code: -32000,
+            message: "Surface not found: 1000".to_string(),
+        }
+        .with_method("get_surface");
+        let layer = IviError::RequestFailed {
+            code: -32000,
+            message: "Layer not found: 2000".to_string(),
+        }
+        .with_method("get_layer");
+        assert!(surface.is_not_found());
+        assert!(layer.is_not_found());
+
+        let invalid = IviError::RequestFailed {
+            code: -32602,
+            message: "Invalid params".to_string(),
+        };
+        assert!(!invalid.is_not_found());
+        assert!(!IviError::ConnectionFailed("refused".to_string()).is_not_found());
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let invalid = IviError::RequestFailed {
+            code: -32602,
+            message: "Invalid params".to_string(),
+        };
+        assert!(invalid.is_invalid_params());
+        assert!(!invalid.is_connection_failure());
+
+        let timeout = IviError::from(io::Error::new(io::ErrorKind::WouldBlock, "timed out"));
+        assert!(timeout.is_timeout());
+        assert!(!timeout.is_connection_failure());
+
+        let broken = IviError::from(io::Error::new(io::ErrorKind::BrokenPipe, "Broken pipe"));
+        assert!(broken.is_connection_failure());
+        assert!(!broken.is_timeout());
+
+        let timeout = IviError::Timeout("Timed out talking to controller after 50 ms".to_string())
+            .with_method("ping");
+        assert!(timeout.is_timeout());
+        assert!(!timeout.is_connection_failure());
+        assert_eq!(
+            timeout.to_string(),
+            "Timed out talking to controller after 50 ms while calling ping"
+        );
+
+        assert!(IviError::ConnectionFailed("refused".to_string()).is_connection_failure());
+        assert!(!IviError::DeserializationError("bad".to_string()).is_invalid_params());
+    }
+
     #[test]
     fn test_serialization_error() {
         let error = IviError::SerializationError("Invalid JSON".to_string());
@@ -77,6 +224,36 @@ mod tests {
         assert!(matches!(ivi_error, IviError::IoError(_)));
     }
 
+    #[test]
+    fn test_with_method_request_failed() {
+        let error = IviError::RequestFailed {
+            code: -32000,
+            message: "Surface not found".to_string(),
+        }
+        .with_method("get_surface");
+        assert!(matches!(
+            error,
+            IviError::RequestFailed { code: -32000, .. }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Request failed (code -32000): Surface not found while calling get_surface"
+        );
+    }
+
+    #[test]
+    fn test_with_method_io_error() {
+        let io_error = io::Error::new(io::ErrorKind::BrokenPipe, "Broken pipe");
+        let error = IviError::from(io_error).with_method("list_surfaces");
+        match error {
+            IviError::IoError(e) => {
+                assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+                assert_eq!(e.to_string(), "Broken pipe while calling list_surfaces");
+            }
+            _ => panic!("Expected IoError"),
+        }
+    }
+
     #[test]
     fn test_serde_json_error_conversion() {
         let json_str = "{invalid json}";
@@ -63,6 +63,8 @@ pub enum IviErrorCode {
     Io = -5,
     /// Invalid parameter (null pointer, etc.)
     InvalidParam = -6,
+    /// The controller did not answer within the request timeout
+    Timeout = -7,
 }
 
 impl From<IviError> for IviErrorCode {
@@ -73,6 +75,7 @@ impl From<IviError> for IviErrorCode {
             IviError::SerializationError(_) => IviErrorCode::Serialization,
             IviError::DeserializationError(_) => IviErrorCode::Deserialization,
             IviError::IoError(_) => IviErrorCode::Io,
+            IviError::Timeout(_) => IviErrorCode::Timeout,
         }
     }
 }
@@ -98,6 +101,7 @@ impl From<IviErrorCode> for IviError {
                 std::io::ErrorKind::InvalidInput,
                 "Invalid parameter",
             )),
+            IviErrorCode::Timeout => IviError::Timeout("Timed out".to_string()),
             IviErrorCode::Ok => panic!("Cannot convert Ok to IviError"),
         }
     }
@@ -132,6 +136,45 @@ impl Display for IviOrientation {
     }
 }
 
+/// C-compatible shell surface kind enum
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IviSurfaceType {
+    /// Created through the ivi_application protocol
+    #[default]
+    Ivi = 0,
+    /// Desktop (xdg-shell) surface adopted by ivi-shell
+    Desktop = 1,
+    /// Input panel surface, such as a virtual keyboard
+    InputPanel = 2,
+}
+
+impl Display for IviSurfaceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IviSurfaceType::Ivi => write!(f, "ivi"),
+            IviSurfaceType::Desktop => write!(f, "desktop"),
+            IviSurfaceType::InputPanel => write!(f, "input_panel"),
+        }
+    }
+}
+
+/// C-compatible layer transition animation enum
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IviLayerTransition {
+    /// No animation
+    None = 0,
+    /// Fade the layer in or out
+    Fade = 1,
+    /// Animate changes of the destination rectangle
+    Move = 2,
+    /// Animate changes of the surface render order
+    ViewOrder = 3,
+}
+
 /// C-compatible surface structure
 #[repr(C)]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -144,6 +187,12 @@ pub struct IviSurface {
     pub opacity: f32,
     pub orientation: IviOrientation,
     pub z_order: i32,
+    /// True if the surface is visible, on a visible layer, and that layer is on a screen
+    #[serde(default)]
+    pub on_screen: bool,
+    /// Shell surface kind, `Ivi` until the surface has been configured
+    #[serde(default)]
+    pub surface_type: IviSurfaceType,
 }
 
 /// C-compatible layer structure
@@ -172,6 +221,20 @@ pub struct IviScreen {
     pub scale: i32,
 }
 
+/// Geometry of a compositor output
+///
+/// `index` is the position of the output in the compositor's output list and
+/// is what `get_output` takes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IviOutput {
+    pub index: u32,
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub x: i32,
+    pub y: i32,
+}
+
 /// Helper function to write error message to C buffer
 fn write_error_to_buffer(error: &IviError, error_buf: *mut c_char, error_buf_len: usize) {
     if error_buf.is_null() || error_buf_len == 0 {
@@ -212,6 +275,9 @@ fn write_error_to_buffer(error: &IviError, error_buf: *mut c_char, error_buf_len
 ///
 /// Returns a pointer to an IviClient on success, or NULL on failure.
 /// If NULL is returned, the error message is written to `error_buf`.
+///
+/// A `remote` starting with `@`, such as `"@weston-ivi-controller"`, connects
+/// to an abstract socket of that name. NULL connects to the default path.
 #[no_mangle]
 pub unsafe extern "C" fn ivi_client_connect(
     remote: *const c_char,
@@ -798,13 +864,14 @@ pub unsafe extern "C" fn ivi_free_layers(layers: *mut IviLayer, count: usize) {
 // Notification types
 // ============================================================================
 
-/// Indicates whether a notification refers to a surface or a layer.
+/// Indicates whether a notification refers to a surface, a layer or an output.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum IviObjectType {
     #[default]
     Surface = 0,
     Layer = 1,
+    Output = 2,
 }
 
 /// Event type enum for C consumers.
@@ -827,6 +894,9 @@ pub enum IviEventType {
     LayerOpacityChanged = 12,
     SurfaceContentReady = 13,
     SurfaceContentSizeChanged = 14,
+    RepaintComplete = 15,
+    InputPanelShown = 16,
+    InputPanelHidden = 17,
 }
 
 impl From<&EventType> for IviEventType {
@@ -847,6 +917,9 @@ impl From<&EventType> for IviEventType {
             EventType::LayerOpacityChanged => IviEventType::LayerOpacityChanged,
             EventType::SurfaceContentReady => IviEventType::SurfaceContentReady,
             EventType::SurfaceContentSizeChanged => IviEventType::SurfaceContentSizeChanged,
+            EventType::RepaintComplete => IviEventType::RepaintComplete,
+            EventType::InputPanelShown => IviEventType::InputPanelShown,
+            EventType::InputPanelHidden => IviEventType::InputPanelHidden,
         }
     }
 }
@@ -869,6 +942,9 @@ impl From<IviEventType> for EventType {
             IviEventType::LayerOpacityChanged => EventType::LayerOpacityChanged,
             IviEventType::SurfaceContentReady => EventType::SurfaceContentReady,
             IviEventType::SurfaceContentSizeChanged => EventType::SurfaceContentSizeChanged,
+            IviEventType::RepaintComplete => EventType::RepaintComplete,
+            IviEventType::InputPanelShown => EventType::InputPanelShown,
+            IviEventType::InputPanelHidden => EventType::InputPanelHidden,
         }
     }
 }
@@ -944,7 +1020,7 @@ pub struct IviContentSizeChange {
 ///
 /// Only the fields relevant to `event_type` are populated; all others are
 /// zero-initialised. Check `object_type` to determine whether `object_id`
-/// refers to a surface or a layer.
+/// refers to a surface, a layer or an output.
 ///
 /// For `FocusChanged` events:
 ///   - `object_type`   = `SURFACE`
@@ -979,6 +1055,14 @@ fn parse_rect(params: &serde_json::Value, key: &str) -> Rectangle {
     }
 }
 
+/// Read an object id, using 0 for ids that are missing or do not fit a `u32`
+fn parse_id(params: &serde_json::Value, key: &str) -> u32 {
+    params[key]
+        .as_u64()
+        .and_then(|id| u32::try_from(id).ok())
+        .unwrap_or(0)
+}
+
 fn parse_orientation(params: &serde_json::Value, key: &str) -> IviOrientation {
     match params[key].as_str().unwrap_or("Normal") {
         "Rotate90" => IviOrientation::Rotate90,
@@ -1002,11 +1086,11 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
     match &notif.event_type {
         EventType::SurfaceCreated | EventType::SurfaceDestroyed => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
         }
         EventType::SourceGeometryChanged => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
             result.src_geometry = IviGeometryChange {
                 old_rect: parse_rect(p, "old_rect"),
                 new_rect: parse_rect(p, "new_rect"),
@@ -1014,7 +1098,7 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::DestinationGeometryChanged => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
             result.dest_geometry = IviGeometryChange {
                 old_rect: parse_rect(p, "old_rect"),
                 new_rect: parse_rect(p, "new_rect"),
@@ -1022,7 +1106,7 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::VisibilityChanged => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
             result.visibility = IviVisibilityChange {
                 old_visibility: p["old_visibility"].as_bool().unwrap_or(false),
                 new_visibility: p["new_visibility"].as_bool().unwrap_or(false),
@@ -1030,7 +1114,7 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::OpacityChanged => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
             result.opacity = IviOpacityChange {
                 old_opacity: p["old_opacity"].as_f64().unwrap_or(0.0) as f32,
                 new_opacity: p["new_opacity"].as_f64().unwrap_or(0.0) as f32,
@@ -1038,7 +1122,7 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::OrientationChanged => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
             result.orientation = IviOrientationChange {
                 old_orientation: parse_orientation(p, "old_orientation"),
                 new_orientation: parse_orientation(p, "new_orientation"),
@@ -1046,7 +1130,7 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::ZOrderChanged => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
             result.z_order = IviZOrderChange {
                 old_z_order: p["old_z_order"].as_i64().unwrap_or(0) as i32,
                 new_z_order: p["new_z_order"].as_i64().unwrap_or(0) as i32,
@@ -1054,16 +1138,16 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::FocusChanged => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["new_focused_surface"].as_u64().unwrap_or(0) as u32;
-            result.object_old_id = p["old_focused_surface"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "new_focused_surface");
+            result.object_old_id = parse_id(p, "old_focused_surface");
         }
         EventType::LayerCreated | EventType::LayerDestroyed => {
             result.object_type = IviObjectType::Layer;
-            result.object_id = p["layer_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "layer_id");
         }
         EventType::LayerVisibilityChanged => {
             result.object_type = IviObjectType::Layer;
-            result.object_id = p["layer_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "layer_id");
             result.visibility = IviVisibilityChange {
                 old_visibility: p["old_visibility"].as_bool().unwrap_or(false),
                 new_visibility: p["new_visibility"].as_bool().unwrap_or(false),
@@ -1071,7 +1155,7 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::LayerOpacityChanged => {
             result.object_type = IviObjectType::Layer;
-            result.object_id = p["layer_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "layer_id");
             result.opacity = IviOpacityChange {
                 old_opacity: p["old_opacity"].as_f64().unwrap_or(0.0) as f32,
                 new_opacity: p["new_opacity"].as_f64().unwrap_or(0.0) as f32,
@@ -1079,7 +1163,7 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::SurfaceContentReady => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
             result.content_ready = IviContentReadyInfo {
                 width: p["width"].as_i64().unwrap_or(0) as i32,
                 height: p["height"].as_i64().unwrap_or(0) as i32,
@@ -1087,7 +1171,7 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
         }
         EventType::SurfaceContentSizeChanged => {
             result.object_type = IviObjectType::Surface;
-            result.object_id = p["surface_id"].as_u64().unwrap_or(0) as u32;
+            result.object_id = parse_id(p, "surface_id");
             result.content_size = IviContentSizeChange {
                 old_width: p["old_width"].as_i64().unwrap_or(0) as i32,
                 old_height: p["old_height"].as_i64().unwrap_or(0) as i32,
@@ -1095,6 +1179,14 @@ fn notification_to_ffi(notif: &Notification) -> IviNotification {
                 new_height: p["new_height"].as_i64().unwrap_or(0) as i32,
             };
         }
+        EventType::RepaintComplete => {
+            result.object_type = IviObjectType::Output;
+            result.object_id = parse_id(p, "output_id");
+        }
+        EventType::InputPanelShown | EventType::InputPanelHidden => {
+            result.object_type = IviObjectType::Surface;
+            result.object_id = parse_id(p, "surface_id");
+        }
     }
 
     result
@@ -1253,7 +1345,7 @@ pub unsafe extern "C" fn ivi_notification_listener_start(
     };
 
     match listener.start(&types) {
-        Ok(()) => IviErrorCode::Ok,
+        Ok(_) => IviErrorCode::Ok,
         Err(err) => {
             write_error_to_buffer(&err, error_buf, error_buf_len);
             err.into()
@@ -91,6 +91,8 @@
 //! - [`types`] - Data structures for surfaces, layers, and properties
 //! - [`error`] - Error types and result aliases
 //! - [`protocol`] - JSON-RPC protocol structures
+//! - [`scene`] - Scene snapshot types for capturing and restoring a layout
+//! - [`live_scene`] - [`IviScene`], an in-memory mirror kept current by notifications
 //! - [`ffi`] - C FFI bindings for C language integration
 //!
 //! # Examples
@@ -103,10 +105,21 @@
 pub mod client;
 pub mod error;
 pub mod ffi;
+pub mod live_scene;
 pub mod protocol;
+pub mod scene;
 
 // Re-export main types for convenience
-pub use client::{IviClient, NotificationCallback, NotificationListener};
+pub use client::{
+    Capabilities, IviClient, LatencyStats, Limits, NotificationCallback, NotificationListener,
+    NotificationStream, RequestIdGenerator, Stats, SurfaceEdit, SurfacePage, SurfaceZIndex,
+    SurfacesIter, WireDirection, WireLogger,
+};
 pub use error::{IviError, Result};
 pub use ffi::*;
-pub use protocol::{EventType, JsonRpcError, JsonRpcRequest, JsonRpcResponse, Notification};
+pub use live_scene::IviScene;
+pub use protocol::{
+    EventType, JsonRpcError, JsonRpcRequest, JsonRpcResponse, Notification, PROTOCOL_VERSION,
+    SCHEMA_VERSION,
+};
+pub use scene::{SceneLayer, SceneScreen, SceneSnapshot, SCENE_SNAPSHOT_VERSION};
@@ -0,0 +1,419 @@
+//! In-memory mirror of the compositor scene.
+//!
+//! [`IviScene`] fetches the scene once and then keeps it current by applying
+//! event notifications, so surfaces, layers and focus can be queried without
+//! a round trip to the controller.
+
+use crate::client::{IviClient, NotificationListener};
+use crate::error::Result;
+use crate::ffi::{IviLayer, IviOrientation, IviSize, IviSurface, Rectangle};
+use crate::protocol::{EventType, Notification};
+#[allow(unused)]
+use jlogger_tracing::{jdebug, jerror, jinfo, jtrace, jwarn};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Event types that change the mirrored scene
+///
+/// `RepaintComplete` fires on every frame and the input panel events carry
+/// no scene state, so they are left out.
+fn scene_event_types() -> Vec<EventType> {
+    EventType::ALL
+        .iter()
+        .filter(|event_type| {
+            !matches!(
+                event_type,
+                EventType::RepaintComplete
+                    | EventType::InputPanelShown
+                    | EventType::InputPanelHidden
+            )
+        })
+        .cloned()
+        .collect()
+}
+
+/// An object announced by a notification that has to be fetched with an RPC
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Missing {
+    Surface(u32),
+    Layer(u32),
+}
+
+/// Mirrored scene contents
+#[derive(Debug, Default)]
+struct SceneState {
+    surfaces: HashMap<u32, IviSurface>,
+    layers: HashMap<u32, IviLayer>,
+    focused: Option<u32>,
+}
+
+/// Read a typed field from notification params
+fn param<T: DeserializeOwned>(notif: &Notification, name: &str) -> Option<T> {
+    notif
+        .params
+        .get(name)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+}
+
+impl SceneState {
+    /// Apply a notification to the mirror
+    ///
+    /// Returns the object to fetch when the notification announces a new
+    /// object without carrying its properties.
+    fn apply(&mut self, notif: &Notification) -> Option<Missing> {
+        let surface_id: Option<u32> = param(notif, "surface_id");
+        let layer_id: Option<u32> = param(notif, "layer_id");
+        let surface = surface_id.and_then(|id| self.surfaces.get_mut(&id));
+
+        match notif.event_type {
+            EventType::SurfaceCreated => match notif.surface() {
+                Some(surface) => {
+                    self.surfaces.insert(surface.id, surface);
+                }
+                None => return surface_id.map(Missing::Surface),
+            },
+            EventType::SurfaceDestroyed => {
+                if let Some(id) = surface_id {
+                    self.surfaces.remove(&id);
+                }
+            }
+            EventType::SurfaceContentReady => {
+                if let (Some(surface), Some(width), Some(height)) =
+                    (surface, param(notif, "width"), param(notif, "height"))
+                {
+                    surface.orig_size = IviSize { width, height };
+                }
+            }
+            EventType::SurfaceContentSizeChanged => {
+                if let (Some(surface), Some(width), Some(height)) = (
+                    surface,
+                    param(notif, "new_width"),
+                    param(notif, "new_height"),
+                ) {
+                    surface.orig_size = IviSize { width, height };
+                }
+            }
+            EventType::SourceGeometryChanged => {
+                if let (Some(surface), Some(rect)) =
+                    (surface, param::<Rectangle>(notif, "new_rect"))
+                {
+                    surface.src_rect = rect;
+                }
+            }
+            EventType::DestinationGeometryChanged => {
+                if let (Some(surface), Some(rect)) =
+                    (surface, param::<Rectangle>(notif, "new_rect"))
+                {
+                    surface.dest_rect = rect;
+                }
+            }
+            EventType::VisibilityChanged => {
+                if let (Some(surface), Some(visible)) = (surface, param(notif, "new_visibility")) {
+                    surface.visibility = visible;
+                }
+            }
+            EventType::OpacityChanged => {
+                if let (Some(surface), Some(opacity)) = (surface, param(notif, "new_opacity")) {
+                    surface.opacity = opacity;
+                }
+            }
+            EventType::OrientationChanged => {
+                if let (Some(surface), Some(orientation)) =
+                    (surface, param::<IviOrientation>(notif, "new_orientation"))
+                {
+                    surface.orientation = orientation;
+                }
+            }
+            EventType::ZOrderChanged => {
+                if let (Some(surface), Some(z_order)) = (surface, param(notif, "new_z_order")) {
+                    surface.z_order = z_order;
+                }
+            }
+            EventType::FocusChanged => {
+                self.focused = param::<Option<u32>>(notif, "new_focused_surface").flatten();
+            }
+            EventType::LayerCreated => return layer_id.map(Missing::Layer),
+            EventType::LayerDestroyed => {
+                if let Some(id) = layer_id {
+                    self.layers.remove(&id);
+                }
+            }
+            EventType::LayerVisibilityChanged => {
+                if let (Some(layer), Some(visible)) = (
+                    layer_id.and_then(|id| self.layers.get_mut(&id)),
+                    param(notif, "new_visibility"),
+                ) {
+                    layer.visibility = visible;
+                }
+            }
+            EventType::LayerOpacityChanged => {
+                if let (Some(layer), Some(opacity)) = (
+                    layer_id.and_then(|id| self.layers.get_mut(&id)),
+                    param(notif, "new_opacity"),
+                ) {
+                    layer.opacity = opacity;
+                }
+            }
+            // Frame timing and input panel requests carry no scene state
+            EventType::RepaintComplete
+            | EventType::InputPanelShown
+            | EventType::InputPanelHidden => {}
+        }
+
+        None
+    }
+}
+
+/// A live, in-memory mirror of the compositor's surfaces, layers and focus.
+///
+/// On creation the scene is fetched with `get_scene` and `get_focus`, and a
+/// [`NotificationListener`] subscribed to every event type keeps it current
+/// from a background thread. Queries return copies and never talk to the
+/// controller.
+///
+/// The mirror opens two connections of its own, one for notifications and
+/// one to fetch layers (and, from older controllers, surfaces) announced by
+/// creation events. No event carries the surface `on_screen` flag or a
+/// layer's `src_rect`, `dest_rect` and orientation, so these are only as
+/// fresh as the last [`refresh`](Self::refresh). A change that lands while
+/// `refresh` is fetching the scene can also be overwritten by the older
+/// snapshot until the next refresh or event for that object.
+///
+/// # Example
+///
+/// ```no_run
+/// use ivi_client::{IviClient, IviScene};
+///
+/// # fn main() -> ivi_client::Result<()> {
+/// let client = IviClient::new(Some("/tmp/weston-ivi-controller.sock"))?;
+/// let scene = IviScene::new(&client)?;
+///
+/// for surface in scene.surfaces() {
+///     println!("Surface {} visible: {}", surface.id, surface.visibility);
+/// }
+/// println!("Focused surface: {:?}", scene.focused());
+/// # Ok(())
+/// # }
+/// ```
+pub struct IviScene {
+    state: Arc<Mutex<SceneState>>,
+    client: Arc<Mutex<IviClient>>,
+    listener: NotificationListener,
+}
+
+impl std::fmt::Debug for IviScene {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("IviScene")
+            .field("surfaces", &state.surfaces.len())
+            .field("layers", &state.layers.len())
+            .field("focused", &state.focused)
+            .field("listener", &self.listener)
+            .finish()
+    }
+}
+
+impl IviScene {
+    /// Creates a mirror of the scene of the controller `client` is connected to.
+    ///
+    /// `client` itself is not used after this call; the mirror opens its own
+    /// connections to the same controller.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connections cannot be opened, the subscription
+    /// fails, or the initial scene cannot be fetched.
+    pub fn new(client: &IviClient) -> Result<Self> {
+        let client = Arc::new(Mutex::new(client.try_clone()?));
+        let state = Arc::new(Mutex::new(SceneState::default()));
+
+        // Subscribe before fetching so no change between the two is missed
+        let mut listener = NotificationListener::new(client.lock().unwrap().remote())?;
+        let callback_state = Arc::clone(&state);
+        let callback_client = Arc::clone(&client);
+        listener.on_all(move |notif| {
+            let missing = callback_state.lock().unwrap().apply(notif);
+            match missing {
+                Some(Missing::Surface(id)) => {
+                    match callback_client.lock().unwrap().get_surface(id) {
+                        Ok(surface) => {
+                            callback_state.lock().unwrap().surfaces.insert(id, surface);
+                        }
+                        Err(e) => jwarn!("Failed to fetch new surface {}: {}", id, e),
+                    }
+                }
+                Some(Missing::Layer(id)) => match callback_client.lock().unwrap().get_layer(id) {
+                    Ok(layer) => {
+                        callback_state.lock().unwrap().layers.insert(id, layer);
+                    }
+                    Err(e) => jwarn!("Failed to fetch new layer {}: {}", id, e),
+                },
+                None => {}
+            }
+        });
+        listener.start(&scene_event_types())?;
+
+        let scene = Self {
+            state,
+            client,
+            listener,
+        };
+        scene.refresh()?;
+
+        Ok(scene)
+    }
+
+    /// Re-fetches the whole scene from the controller, replacing the mirror.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if communication with the controller fails; the
+    /// mirror is left unchanged in that case.
+    pub fn refresh(&self) -> Result<()> {
+        let (snapshot, focused) = {
+            let mut client = self.client.lock().unwrap();
+            (client.get_scene()?, client.get_focus()?)
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.surfaces = snapshot.surfaces.into_iter().map(|s| (s.id, s)).collect();
+        state.layers = snapshot
+            .layers
+            .into_iter()
+            .map(|l| (l.layer.id, l.layer))
+            .collect();
+        state.focused = focused;
+
+        Ok(())
+    }
+
+    /// Returns all surfaces, ordered by ID.
+    pub fn surfaces(&self) -> Vec<IviSurface> {
+        let mut surfaces: Vec<IviSurface> = self
+            .state
+            .lock()
+            .unwrap()
+            .surfaces
+            .values()
+            .cloned()
+            .collect();
+        surfaces.sort_by_key(|s| s.id);
+        surfaces
+    }
+
+    /// Returns the surface with the given ID, if it exists.
+    pub fn surface(&self, id: u32) -> Option<IviSurface> {
+        self.state.lock().unwrap().surfaces.get(&id).cloned()
+    }
+
+    /// Returns all layers, ordered by ID.
+    pub fn layers(&self) -> Vec<IviLayer> {
+        let mut layers: Vec<IviLayer> = self
+            .state
+            .lock()
+            .unwrap()
+            .layers
+            .values()
+            .cloned()
+            .collect();
+        layers.sort_by_key(|l| l.id);
+        layers
+    }
+
+    /// Returns the layer with the given ID, if it exists.
+    pub fn layer(&self, id: u32) -> Option<IviLayer> {
+        self.state.lock().unwrap().layers.get(&id).cloned()
+    }
+
+    /// Returns the ID of the focused surface, if any.
+    pub fn focused(&self) -> Option<u32> {
+        self.state.lock().unwrap().focused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(params: &str) -> Notification {
+        let frame = format!(r#"{{"method":"notification","params":{}}}"#, params);
+        Notification::try_from_frame(frame.as_bytes())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn state_with_surface() -> SceneState {
+        let created = notification(
+            r#"{"event_type":"SurfaceCreated","surface_id":1000,
+            "surface":{"v":1,"id":1000,"orig_size":{"width":1920,"height":1080},
+            "src_rect":{"x":0,"y":0,"width":1920,"height":1080},
+            "dest_rect":{"x":0,"y":0,"width":1920,"height":1080},
+            "visibility":false,"opacity":1.0,"orientation":"Normal","z_order":0}}"#,
+        );
+        let mut state = SceneState::default();
+        assert_eq!(state.apply(&created), None);
+        state
+    }
+
+    #[test]
+    fn test_apply_surface_changes() {
+        let mut state = state_with_surface();
+
+        for params in [
+            r#"{"event_type":"VisibilityChanged","surface_id":1000,"old_visibility":false,"new_visibility":true}"#,
+            r#"{"event_type":"OpacityChanged","surface_id":1000,"old_opacity":1.0,"new_opacity":0.5}"#,
+            r#"{"event_type":"DestinationGeometryChanged","surface_id":1000,
+                "old_rect":{"x":0,"y":0,"width":1920,"height":1080},
+                "new_rect":{"x":10,"y":20,"width":800,"height":600}}"#,
+            r#"{"event_type":"OrientationChanged","surface_id":1000,"old_orientation":"Normal","new_orientation":"Rotate90"}"#,
+            r#"{"event_type":"ZOrderChanged","surface_id":1000,"old_z_order":0,"new_z_order":3}"#,
+            r#"{"event_type":"FocusChanged","old_focused_surface":null,"new_focused_surface":1000}"#,
+        ] {
+            assert_eq!(state.apply(&notification(params)), None);
+        }
+
+        let surface = &state.surfaces[&1000];
+        assert!(surface.visibility);
+        assert_eq!(surface.opacity, 0.5);
+        assert_eq!(
+            surface.dest_rect,
+            Rectangle {
+                x: 10,
+                y: 20,
+                width: 800,
+                height: 600
+            }
+        );
+        assert_eq!(surface.orientation, IviOrientation::Rotate90);
+        assert_eq!(surface.z_order, 3);
+        assert_eq!(state.focused, Some(1000));
+
+        let destroyed = notification(r#"{"event_type":"SurfaceDestroyed","surface_id":1000}"#);
+        state.apply(&destroyed);
+        assert!(state.surfaces.is_empty());
+    }
+
+    #[test]
+    fn test_apply_reports_objects_to_fetch() {
+        let mut state = SceneState::default();
+
+        let created = notification(r#"{"event_type":"SurfaceCreated","surface_id":1000}"#);
+        assert_eq!(state.apply(&created), Some(Missing::Surface(1000)));
+
+        let created = notification(r#"{"event_type":"LayerCreated","layer_id":2000}"#);
+        assert_eq!(state.apply(&created), Some(Missing::Layer(2000)));
+    }
+
+    #[test]
+    fn test_apply_ignores_unknown_objects() {
+        let mut state = state_with_surface();
+
+        let changed = notification(
+            r#"{"event_type":"OpacityChanged","surface_id":1001,"old_opacity":1.0,"new_opacity":0.5}"#,
+        );
+        assert_eq!(state.apply(&changed), None);
+        assert_eq!(state.surfaces.len(), 1);
+        assert_eq!(state.surfaces[&1000].opacity, 1.0);
+    }
+}
@@ -4,9 +4,70 @@
 //! used to communicate with the Weston IVI controller over UNIX domain sockets.
 
 use crate::error::{IviError, Result};
+use crate::ffi::IviSurface;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Schema version of the surface and layer objects understood by this client.
+///
+/// The controller tags surface and layer objects, and the responses listing
+/// them, with a `"v"` field. Unknown fields are ignored when parsing, so
+/// objects from a newer controller still parse; `v` tells callers whether
+/// fields they do not know about may be present.
+///
+/// - 1: first versioned schema
+/// - 2: surfaces carry `surface_type`
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Newest wire protocol version understood by this client.
+///
+/// Compared with the `protocol` reported by the controller's
+/// `get_capabilities`, see [`IviClient::handshake`](crate::IviClient::handshake).
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Returns the `"v"` schema version of a surface or layer object or list
+/// response, or 0 if the controller predates schema versioning.
+///
+/// # Example
+///
+/// ```
+/// use ivi_client::protocol::schema_version;
+/// use serde_json::json;
+///
+/// assert_eq!(schema_version(&json!({ "v": 1, "surfaces": [] })), 1);
+/// assert_eq!(schema_version(&json!({ "surfaces": [] })), 0);
+/// ```
+pub fn schema_version(value: &Value) -> u32 {
+    value
+        .get("v")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// Whether a frame is a notification rather than an RPC response.
+///
+/// Controllers with protocol version 2 or later tag notifications with
+/// `"type": "notification"`. Older controllers send them without an `"id"`,
+/// which is used as a fallback.
+///
+/// # Example
+///
+/// ```
+/// use ivi_client::protocol::is_notification_frame;
+/// use serde_json::json;
+///
+/// assert!(is_notification_frame(&json!({ "type": "notification", "method": "notification" })));
+/// assert!(is_notification_frame(&json!({ "method": "notification" })));
+/// assert!(!is_notification_frame(&json!({ "id": 1, "result": {} })));
+/// ```
+pub fn is_notification_frame(value: &Value) -> bool {
+    match value.get("type").and_then(|v| v.as_str()) {
+        Some(frame_type) => frame_type == "notification",
+        None => value.get("id").is_none(),
+    }
+}
+
 /// JSON-RPC 2.0 request structure.
 ///
 /// Represents a request to be sent to the IVI controller.
@@ -74,6 +135,7 @@ impl JsonRpcRequest {
 ///
 /// let response = JsonRpcResponse {
 ///     id: 1,
+///     method: Some("list_surfaces".to_string()),
 ///     result: Some(json!({"surfaces": [1000, 1001]})),
 ///     error: None,
 /// };
@@ -83,6 +145,12 @@ pub struct JsonRpcResponse {
     /// Request identifier that this response corresponds to.
     pub id: u64,
 
+    /// Method of the request this response corresponds to, echoed by the
+    /// controller. Absent from older controllers and from responses to
+    /// requests the controller could not parse.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+
     /// The result of a successful method invocation.
     /// Present only if the request succeeded.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -113,6 +181,7 @@ impl JsonRpcResponse {
     pub fn success(id: u64, result: Value) -> Self {
         Self {
             id,
+            method: None,
             result: Some(result),
             error: None,
         }
@@ -136,6 +205,7 @@ impl JsonRpcResponse {
     pub fn error(id: u64, error: JsonRpcError) -> Self {
         Self {
             id,
+            method: None,
             result: None,
             error: Some(error),
         }
@@ -150,6 +220,29 @@ impl JsonRpcResponse {
     pub fn is_error(&self) -> bool {
         self.error.is_some()
     }
+
+    /// Checks whether this response may belong to a request for `method`.
+    ///
+    /// Responses without a `method` echo match any method.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ivi_client::protocol::JsonRpcResponse;
+    /// use serde_json::json;
+    ///
+    /// let mut response = JsonRpcResponse::success(1, json!({}));
+    /// assert!(response.matches_method("commit"));
+    ///
+    /// response.method = Some("get_focus".to_string());
+    /// assert!(!response.matches_method("commit"));
+    /// ```
+    pub fn matches_method(&self, method: &str) -> bool {
+        match &self.method {
+            Some(m) => m == method,
+            None => true,
+        }
+    }
 }
 
 /// JSON-RPC 2.0 error structure.
@@ -177,6 +270,15 @@ pub struct JsonRpcError {
 }
 
 impl JsonRpcError {
+    /// Error code returned when a surface or layer does not exist.
+    ///
+    /// The error `data` carries the missing `id` and its `kind`
+    /// (`"surface"` or `"layer"`).
+    pub const NOT_FOUND: i32 = -32000;
+
+    /// Error code returned when request parameters are missing or invalid.
+    pub const INVALID_PARAMS: i32 = -32602;
+
     /// Creates a new JSON-RPC error.
     ///
     /// # Arguments
@@ -233,6 +335,33 @@ pub enum EventType {
     LayerDestroyed,
     LayerVisibilityChanged,
     LayerOpacityChanged,
+    RepaintComplete,
+    InputPanelShown,
+    InputPanelHidden,
+}
+
+impl EventType {
+    /// Every event type, as subscribed to by [`IviClient::subscribe_all`](crate::IviClient::subscribe_all)
+    pub const ALL: [EventType; 18] = [
+        EventType::SurfaceCreated,
+        EventType::SurfaceContentReady,
+        EventType::SurfaceContentSizeChanged,
+        EventType::SurfaceDestroyed,
+        EventType::SourceGeometryChanged,
+        EventType::DestinationGeometryChanged,
+        EventType::VisibilityChanged,
+        EventType::OpacityChanged,
+        EventType::OrientationChanged,
+        EventType::ZOrderChanged,
+        EventType::FocusChanged,
+        EventType::LayerCreated,
+        EventType::LayerDestroyed,
+        EventType::LayerVisibilityChanged,
+        EventType::LayerOpacityChanged,
+        EventType::RepaintComplete,
+        EventType::InputPanelShown,
+        EventType::InputPanelHidden,
+    ];
 }
 
 /// A notification received from the IVI controller.
@@ -244,13 +373,12 @@ pub struct Notification {
 impl Notification {
     /// Parse a raw frame into a Notification.
     ///
-    /// Returns `Ok(None)` if the frame is an RPC response (has an `"id"` field),
-    /// so callers can silently skip it.
+    /// Returns `Ok(None)` if the frame is an RPC response, so callers can
+    /// silently skip it. See [`is_notification_frame`].
     pub fn try_from_frame(frame: &[u8]) -> Result<Option<Self>> {
         let value: Value = serde_json::from_slice(frame)?;
 
-        // Frames with "id" are RPC responses, not notifications.
-        if value.get("id").is_some() {
+        if !is_notification_frame(&value) {
             return Ok(None);
         }
 
@@ -277,6 +405,30 @@ impl Notification {
 
         Ok(Some(Notification { event_type, params }))
     }
+
+    /// Returns the initial surface state carried by a `SurfaceCreated` notification.
+    ///
+    /// Returns `None` for other event types, or if the controller predates
+    /// including the surface state in the notification.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use ivi_client::protocol::Notification;
+    ///
+    /// let frame = br#"{"method":"notification","params":{"event_type":"SurfaceCreated","surface_id":1000}}"#;
+    /// let notif = Notification::try_from_frame(frame).unwrap().unwrap();
+    /// assert!(notif.surface().is_none());
+    /// ```
+    pub fn surface(&self) -> Option<IviSurface> {
+        if self.event_type != EventType::SurfaceCreated {
+            return None;
+        }
+
+        self.params
+            .get("surface")
+            .and_then(|surface| serde_json::from_value(surface.clone()).ok())
+    }
 }
 
 #[cfg(test)]
@@ -316,6 +468,58 @@ mod tests {
         assert_eq!(notif.params["surface_id"].as_u64().unwrap(), 1000);
     }
 
+    #[test]
+    fn test_surface_created_carries_surface_state() {
+        let frame = br#"{"method":"notification","params":{"event_type":"SurfaceCreated","surface_id":1000,
+            "surface":{"v":1,"id":1000,"orig_size":{"width":1920,"height":1080},
+            "src_rect":{"x":0,"y":0,"width":1920,"height":1080},
+            "dest_rect":{"x":100,"y":50,"width":800,"height":600},
+            "visibility":true,"opacity":0.5,"orientation":"Normal","z_order":0,"on_screen":false}}}"#;
+        let notif = Notification::try_from_frame(frame).unwrap().unwrap();
+
+        let surface = notif.surface().unwrap();
+        assert_eq!(surface.id, 1000);
+        assert_eq!(surface.dest_rect.x, 100);
+        assert_eq!(surface.dest_rect.width, 800);
+        assert!(surface.visibility);
+        assert_eq!(surface.opacity, 0.5);
+    }
+
+    #[test]
+    fn test_surface_ids_at_u32_bounds() {
+        let surface = |id: u64| {
+            json!({"v":1,"id":id,"orig_size":{"width":1920,"height":1080},
+                "src_rect":{"x":0,"y":0,"width":1920,"height":1080},
+                "dest_rect":{"x":0,"y":0,"width":1920,"height":1080},
+                "visibility":true,"opacity":1.0,"orientation":"Normal","z_order":0})
+        };
+
+        for id in [0, u32::MAX] {
+            let parsed: IviSurface = serde_json::from_value(surface(id as u64)).unwrap();
+            assert_eq!(parsed.id, id);
+            let round_trip: IviSurface =
+                serde_json::from_value(serde_json::to_value(&parsed).unwrap()).unwrap();
+            assert_eq!(round_trip.id, id);
+        }
+
+        // Out-of-range ids are rejected instead of wrapping around
+        assert!(serde_json::from_value::<IviSurface>(surface(u32::MAX as u64 + 1)).is_err());
+    }
+
+    #[test]
+    fn test_response_method_echo() {
+        let response: JsonRpcResponse =
+            serde_json::from_str(r#"{"id":1,"method":"get_focus","result":{}}"#).unwrap();
+        assert_eq!(response.method.as_deref(), Some("get_focus"));
+        assert!(response.matches_method("get_focus"));
+        assert!(!response.matches_method("commit"));
+
+        // Older controllers do not echo the method
+        let response: JsonRpcResponse = serde_json::from_str(r#"{"id":1,"result":{}}"#).unwrap();
+        assert_eq!(response.method, None);
+        assert!(response.matches_method("commit"));
+    }
+
     #[test]
     fn test_try_from_frame_skips_rpc_response() {
         let frame = br#"{"id":1,"result":{"surfaces":[]}}"#;
@@ -323,6 +527,27 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_schema_version() {
+        assert_eq!(
+            schema_version(&json!({ "v": SCHEMA_VERSION, "id": 1000 })),
+            SCHEMA_VERSION
+        );
+        assert_eq!(schema_version(&json!({ "v": 1, "id": 1000 })), 1);
+        assert_eq!(schema_version(&json!({ "id": 1000 })), 0);
+        assert_eq!(schema_version(&json!({ "v": "1" })), 0);
+    }
+
+    #[test]
+    fn test_try_from_frame_tagged_notification() {
+        let frame = br#"{"type":"notification","method":"notification","params":{"event_type":"FocusChanged","surface_id":1000}}"#;
+        let notif = Notification::try_from_frame(frame).unwrap().unwrap();
+        assert_eq!(notif.event_type, EventType::FocusChanged);
+
+        let frame = br#"{"type":"response","id":1,"result":{}}"#;
+        assert!(Notification::try_from_frame(frame).unwrap().is_none());
+    }
+
     #[test]
     fn test_try_from_frame_malformed() {
         let frame = b"{invalid json}";
@@ -0,0 +1,99 @@
+//! Scene snapshot types.
+//!
+//! A [`SceneSnapshot`] captures everything needed to restore a layout: every
+//! surface and layer with their properties, the surface render order of each
+//! layer, and the layer render order of each screen. It is returned by
+//! [`IviClient::get_scene`](crate::IviClient::get_scene) and is the on-disk
+//! format used by `ivi_cli scene dump`/`scene load`.
+
+use crate::ffi::{IviLayer, IviSurface};
+use serde::{Deserialize, Serialize};
+
+/// Current version of the scene snapshot schema.
+///
+/// Bumped whenever a change to the schema cannot be read by older versions.
+/// Fields added in a compatible way must use `#[serde(default)]` instead.
+pub const SCENE_SNAPSHOT_VERSION: u32 = 1;
+
+fn default_version() -> u32 {
+    SCENE_SNAPSHOT_VERSION
+}
+
+/// A layer together with the surfaces assigned to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneLayer {
+    #[serde(flatten)]
+    pub layer: IviLayer,
+    /// Surface IDs in render order (first = bottommost, last = topmost)
+    #[serde(default)]
+    pub surface_ids: Vec<u32>,
+}
+
+/// A screen together with the layers assigned to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneScreen {
+    pub name: String,
+    /// Layer IDs in render order
+    #[serde(default)]
+    pub layer_ids: Vec<u32>,
+}
+
+/// Snapshot of the complete compositor scene.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SceneSnapshot {
+    /// Schema version, see [`SCENE_SNAPSHOT_VERSION`]
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub surfaces: Vec<IviSurface>,
+    #[serde(default)]
+    pub layers: Vec<SceneLayer>,
+    #[serde(default)]
+    pub screens: Vec<SceneScreen>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scene_snapshot_from_rpc_result() {
+        let value = json!({
+            "surfaces": [],
+            "layers": [{
+                "id": 2000,
+                "src_rect": { "x": 0, "y": 0, "width": 1920, "height": 1080 },
+                "dest_rect": { "x": 0, "y": 0, "width": 1920, "height": 1080 },
+                "visibility": true,
+                "opacity": 1.0,
+                "orientation": "Normal",
+                "surface_ids": [1000, 1001]
+            }],
+            "screens": [{ "name": "HDMI-A-1", "layer_ids": [2000] }]
+        });
+
+        let scene: SceneSnapshot = serde_json::from_value(value).unwrap();
+        assert_eq!(scene.version, SCENE_SNAPSHOT_VERSION);
+        assert_eq!(scene.layers[0].layer.id, 2000);
+        assert_eq!(scene.layers[0].surface_ids, vec![1000, 1001]);
+        assert_eq!(scene.screens[0].layer_ids, vec![2000]);
+    }
+
+    #[test]
+    fn test_scene_snapshot_round_trip() {
+        let scene = SceneSnapshot {
+            version: SCENE_SNAPSHOT_VERSION,
+            surfaces: vec![],
+            layers: vec![],
+            screens: vec![SceneScreen {
+                name: "HDMI-A-1".to_string(),
+                layer_ids: vec![2000, 2001],
+            }],
+        };
+
+        let json = serde_json::to_string(&scene).unwrap();
+        let parsed: SceneSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, scene);
+    }
+}
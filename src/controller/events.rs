@@ -1,7 +1,7 @@
 // Event handling for IVI surface lifecycle
 
 use super::id_assignment::IdAssignmentManager;
-use super::state::StateManager;
+use super::state::{StateManager, SurfaceType};
 use crate::ffi::bindings::ivi_layer::IviLayer;
 use crate::ffi::bindings::ivi_layout_api::IviLayoutApi;
 use crate::ffi::bindings::ivi_surface::IviSurface;
@@ -19,6 +19,20 @@ pub struct EventContext {
     id_assignment_manager: Arc<IdAssignmentManager>,
     surface_prop_listeners: Mutex<HashMap<u32, *mut wl_listener>>, // per-surface property listeners
     layer_prop_listeners: Mutex<HashMap<u32, *mut wl_listener>>,   // per-layer property listeners
+    repaint_listeners: Mutex<HashMap<usize, RepaintOutput>>,       // per-output frame listeners
+    coarse_events: bool, // skip per-surface and per-layer property listeners
+}
+
+/// Output a repaint listener is attached to
+#[derive(Debug, Clone)]
+struct RepaintOutput {
+    output_id: u32,
+    output_name: String,
+    /// Listener on the output's destroy signal, which unlinks both listeners
+    destroy_listener: *mut wl_listener,
+    /// Commits made before the previous frame of the output, all of which the
+    /// next frame shows
+    shown_commits: u64,
 }
 
 // Safety: We ensure thread-safety through the Mutex on StateManager
@@ -38,9 +52,26 @@ impl EventContext {
             id_assignment_manager,
             surface_prop_listeners: Mutex::new(HashMap::new()),
             layer_prop_listeners: Mutex::new(HashMap::new()),
+            repaint_listeners: Mutex::new(HashMap::new()),
+            coarse_events: false,
         }
     }
 
+    /// Rely on the global configure listeners only
+    ///
+    /// Surfaces and layers created afterwards get no property listener of their
+    /// own, so changes made outside this controller are noticed later, or not
+    /// at all for layers.
+    pub fn with_coarse_events(mut self, coarse_events: bool) -> Self {
+        self.coarse_events = coarse_events;
+        self
+    }
+
+    /// Whether per-surface and per-layer property listeners are skipped
+    pub fn coarse_events(&self) -> bool {
+        self.coarse_events
+    }
+
     /// Helper function to cleanup allocated listeners
     unsafe fn cleanup_listeners(listeners: &[*mut wl_listener]) {
         for &listener in listeners {
@@ -50,6 +81,21 @@ impl EventContext {
         }
     }
 
+    /// Undo a partial registration
+    ///
+    /// Unlinks every listener that made it into a Weston signal, drops its
+    /// `LISTENER_CONTEXTS` entry and frees it.
+    unsafe fn rollback_listeners(listeners: &[*mut wl_listener]) {
+        {
+            let mut contexts = LISTENER_CONTEXTS.lock().unwrap();
+            for &listener in listeners {
+                unlink_listener(listener);
+                contexts.remove(&(listener as usize));
+            }
+        }
+        Self::cleanup_listeners(listeners);
+    }
+
     /// Register all surface lifecycle event listeners
     ///
     /// # Safety
@@ -111,15 +157,50 @@ impl EventContext {
         (*layer_create_listener).notify = Some(layer_created_callback);
         (*layer_remove_listener).notify = Some(layer_removed_callback);
 
-        // Register listeners with the IVI API
-        self.ivi_api.add_listener_create_surface(create_listener)?;
-        self.ivi_api.add_listener_remove_surface(remove_listener)?;
-        self.ivi_api
-            .add_listener_configure_surface(configure_listener)?;
-        self.ivi_api
-            .add_listener_create_layer(layer_create_listener)?;
-        self.ivi_api
-            .add_listener_remove_layer(layer_remove_listener)?;
+        // Register listeners with the IVI API, undoing everything on failure
+        let registered = self
+            .ivi_api
+            .add_listener_create_surface(create_listener)
+            .and_then(|_| self.ivi_api.add_listener_remove_surface(remove_listener))
+            .and_then(|_| {
+                self.ivi_api
+                    .add_listener_configure_surface(configure_listener)
+            })
+            .and_then(|_| {
+                self.ivi_api
+                    .add_listener_create_layer(layer_create_listener)
+            })
+            .and_then(|_| {
+                self.ivi_api
+                    .add_listener_remove_layer(layer_remove_listener)
+            });
+        if let Err(e) = registered {
+            Self::rollback_listeners(&all_listeners);
+            return Err(e);
+        }
+
+        // Repaint notifications are best effort; the plugin works without them
+        if let Err(e) = self.register_repaint_listeners() {
+            jwarn!("Failed to register repaint listeners: {}", e);
+        }
+
+        // Surface type tracking, unavailable in some Weston versions
+        let desktop_configure_listener = self.register_optional_listener(
+            desktop_surface_configured_callback,
+            IviLayoutApi::add_listener_configure_desktop_surface,
+        );
+        let input_panel_configure_listener = self.register_optional_listener(
+            input_panel_surface_configured_callback,
+            IviLayoutApi::add_listener_configure_input_panel_surface,
+        );
+        let input_panel_show_listener = self.register_optional_listener(
+            input_panel_shown_callback,
+            IviLayoutApi::add_listener_show_input_panel,
+        );
+        let input_panel_hide_listener = self.register_optional_listener(
+            input_panel_hidden_callback,
+            IviLayoutApi::add_listener_hide_input_panel,
+        );
 
         Ok(EventListeners {
             ctx: Arc::clone(&self),
@@ -128,14 +209,52 @@ impl EventContext {
             configure_listener,
             layer_create_listener,
             layer_remove_listener,
+            desktop_configure_listener,
+            input_panel_configure_listener,
+            input_panel_show_listener,
+            input_panel_hide_listener,
         })
     }
 
+    /// Register a listener that the plugin can run without
+    ///
+    /// Returns a null pointer if the listener could not be registered.
+    ///
+    /// # Safety
+    /// This function is unsafe because it registers C callbacks with raw pointers.
+    unsafe fn register_optional_listener(
+        self: &Arc<Self>,
+        notify: unsafe extern "C" fn(*mut wl_listener, *mut c_void),
+        add: unsafe fn(&IviLayoutApi, *mut wl_listener) -> Result<(), &'static str>,
+    ) -> *mut wl_listener {
+        let listener = libc::malloc(std::mem::size_of::<wl_listener>()) as *mut wl_listener;
+        if listener.is_null() {
+            jwarn!("Failed to allocate optional listener");
+            return listener;
+        }
+        // Zero-initialize to clear wl_list link field
+        std::ptr::write_bytes(listener, 0, 1);
+        (*listener).notify = Some(notify);
+
+        LISTENER_CONTEXTS
+            .lock()
+            .unwrap()
+            .insert(listener as usize, Arc::clone(self));
+
+        if let Err(e) = add(&self.ivi_api, listener) {
+            jwarn!("Optional listener not registered: {}", e);
+            Self::rollback_listeners(&[listener]);
+            return std::ptr::null_mut();
+        }
+
+        listener
+    }
+
     /// Register a per-surface property change listener by surface id
     /// # Safety
     /// This function is unsafe because it deals with raw pointers.
     pub unsafe fn register_surface_property_listener_by_id(
-        &self,
+        self: &Arc<Self>,
         surface_id: u32,
     ) -> Result<(), &'static str> {
         let surface = self
@@ -154,16 +273,10 @@ impl EventContext {
         (*listener).notify = Some(surface_property_changed_callback);
 
         // Store context for callback
-        LISTENER_CONTEXTS.lock().unwrap().insert(
-            listener as usize,
-            Arc::new(Self {
-                state_manager: Arc::clone(&self.state_manager),
-                ivi_api: Arc::clone(&self.ivi_api),
-                id_assignment_manager: Arc::clone(&self.id_assignment_manager),
-                surface_prop_listeners: Mutex::new(HashMap::new()),
-                layer_prop_listeners: Mutex::new(HashMap::new()),
-            }),
-        );
+        LISTENER_CONTEXTS
+            .lock()
+            .unwrap()
+            .insert(listener as usize, Arc::clone(self));
 
         // Add to per-surface map for cleanup
         self.surface_prop_listeners
@@ -199,6 +312,7 @@ impl EventContext {
     pub unsafe fn clear_property_listeners(&self) {
         let mut map = self.surface_prop_listeners.lock().unwrap();
         for (_, listener) in map.drain() {
+            unlink_listener(listener);
             LISTENER_CONTEXTS
                 .lock()
                 .unwrap()
@@ -211,7 +325,7 @@ impl EventContext {
     /// # Safety
     /// This function is unsafe because it deals with raw pointers.
     pub unsafe fn register_layer_property_listener_by_id(
-        &self,
+        self: &Arc<Self>,
         layer_id: u32,
     ) -> Result<(), &'static str> {
         let layer = self
@@ -229,16 +343,10 @@ impl EventContext {
         (*listener).notify = Some(layer_property_changed_callback);
 
         // Store context for callback
-        LISTENER_CONTEXTS.lock().unwrap().insert(
-            listener as usize,
-            Arc::new(Self {
-                state_manager: Arc::clone(&self.state_manager),
-                ivi_api: Arc::clone(&self.ivi_api),
-                id_assignment_manager: Arc::clone(&self.id_assignment_manager),
-                surface_prop_listeners: Mutex::new(HashMap::new()),
-                layer_prop_listeners: Mutex::new(HashMap::new()),
-            }),
-        );
+        LISTENER_CONTEXTS
+            .lock()
+            .unwrap()
+            .insert(listener as usize, Arc::clone(self));
 
         // Add to per-layer map for cleanup
         self.layer_prop_listeners
@@ -268,6 +376,7 @@ impl EventContext {
     pub unsafe fn clear_layer_property_listeners(&self) {
         let mut map = self.layer_prop_listeners.lock().unwrap();
         for (_, listener) in map.drain() {
+            unlink_listener(listener);
             LISTENER_CONTEXTS
                 .lock()
                 .unwrap()
@@ -276,6 +385,148 @@ impl EventContext {
         }
     }
 
+    /// Register a frame listener on every output present at startup
+    ///
+    /// Each listener fires after weston has repainted its output and emits a
+    /// `RepaintComplete` notification. A second listener on the output's
+    /// destroy signal removes both when the output goes away. Outputs
+    /// hotplugged later are not covered.
+    ///
+    /// # Safety
+    /// This function is unsafe because it links raw listeners into weston signals.
+    pub unsafe fn register_repaint_listeners(self: &Arc<Self>) -> Result<(), &'static str> {
+        for output in self.ivi_api.get_screens() {
+            let listener = alloc_listener(output_frame_callback)
+                .map_err(|_| "Failed to allocate repaint listener")?;
+            let destroy_listener = match alloc_listener(output_destroy_callback) {
+                Ok(destroy_listener) => destroy_listener,
+                Err(_) => {
+                    libc::free(listener as *mut c_void);
+                    return Err("Failed to allocate repaint listener");
+                }
+            };
+
+            {
+                let mut contexts = LISTENER_CONTEXTS.lock().unwrap();
+                contexts.insert(listener as usize, Arc::clone(self));
+                contexts.insert(destroy_listener as usize, Arc::clone(self));
+            }
+            self.repaint_listeners.lock().unwrap().insert(
+                listener as usize,
+                RepaintOutput {
+                    output_id: output.id(),
+                    output_name: output.name().unwrap_or_default(),
+                    destroy_listener,
+                    shown_commits: self.ivi_api.commit_count(),
+                },
+            );
+
+            signal_add(output.frame_signal(), listener);
+            signal_add(output.destroy_signal(), destroy_listener);
+        }
+
+        Ok(())
+    }
+
+    /// Unlink and free all repaint listeners
+    ///
+    /// Listeners of destroyed outputs were already removed by their destroy
+    /// listener, so every listener left is still linked into a live signal.
+    ///
+    /// # Safety
+    /// This function is unsafe because it frees raw pointers.
+    pub unsafe fn clear_repaint_listeners(&self) {
+        let mut map = self.repaint_listeners.lock().unwrap();
+        for (key, output) in map.drain() {
+            free_repaint_listeners(key as *mut wl_listener, &output);
+        }
+    }
+
+    /// Start tracking a new surface
+    ///
+    /// Replaces an invalid surface ID through the ID assignment manager,
+    /// records the surface in the StateManager and registers its property
+    /// listener.
+    ///
+    /// # Safety
+    /// This function is unsafe because it registers C callbacks with raw pointers.
+    unsafe fn track_new_surface(self: &Arc<Self>, surface: &IviSurface, surface_type: SurfaceType) {
+        // Get the surface ID using the stored API pointer
+        let surface_id = surface.id();
+
+        // Handle ID assignment and replacement if needed
+        match self
+            .id_assignment_manager
+            .handle_surface_created(surface_id)
+        {
+            Ok(assignment_info) => {
+                if let Some(info) = assignment_info {
+                    // ID was assigned and replaced - use the new ID for state management
+                    jinfo!(
+                        "Surface {} had invalid ID, assigned and replaced with new ID: {}",
+                        surface_id,
+                        info.assigned_id
+                    );
+
+                    // Update state manager with the assigned surface ID and assignment info
+                    if let Ok(mut state_manager) = self.state_manager.lock() {
+                        state_manager.handle_surface_created_with_type(
+                            info.assigned_id,
+                            true,             // is_auto_assigned
+                            Some(surface_id), // original_id
+                            surface_type,
+                        );
+                    }
+
+                    // Register per-surface property listener for the assigned surface ID
+                    if !self.coarse_events {
+                        self.register_surface_property_listener_by_id(info.assigned_id)
+                            .ok();
+                    }
+                } else {
+                    // Valid ID - use as-is, mark as manually assigned
+                    // Update state manager with the original surface ID
+                    if let Ok(mut state_manager) = self.state_manager.lock() {
+                        state_manager.handle_surface_created_with_type(
+                            surface_id,
+                            false, // is_auto_assigned (valid ID means manual)
+                            None,  // original_id
+                            surface_type,
+                        );
+                    }
+
+                    // Register per-surface property listener for the original surface ID
+                    if !self.coarse_events {
+                        self.register_surface_property_listener_by_id(surface_id)
+                            .ok();
+                    }
+                }
+            }
+            Err(e) => {
+                jerror!(
+                    "Failed to handle surface creation with ID assignment for surface {}: {}",
+                    surface_id,
+                    e
+                );
+
+                // Fall back to normal surface creation handling
+                if let Ok(mut state_manager) = self.state_manager.lock() {
+                    state_manager.handle_surface_created_with_type(
+                        surface_id,
+                        false, // is_auto_assigned
+                        None,  // original_id
+                        surface_type,
+                    );
+                }
+
+                if !self.coarse_events {
+                    self.register_surface_property_listener_by_id(surface_id)
+                        .ok();
+                }
+            }
+        }
+    }
+
     /// Get a reference to the ID assignment manager
     pub fn id_assignment_manager(&self) -> &Arc<IdAssignmentManager> {
         &self.id_assignment_manager
@@ -291,13 +542,22 @@ pub struct EventListeners {
     // Layer listeners (optional based on API availability)
     layer_create_listener: *mut wl_listener,
     layer_remove_listener: *mut wl_listener,
+    // Surface type listeners, null if the API lacks them
+    desktop_configure_listener: *mut wl_listener,
+    input_panel_configure_listener: *mut wl_listener,
+    // Input panel visibility listeners, null if the API lacks them
+    input_panel_show_listener: *mut wl_listener,
+    input_panel_hide_listener: *mut wl_listener,
 }
 
 impl Drop for EventListeners {
     fn drop(&mut self) {
         unsafe {
-            // Clear all per-surface listeners first
+            // Clear all per-surface and per-layer listeners first, their
+            // LISTENER_CONTEXTS entries keep the shared context alive
             self.ctx.clear_property_listeners();
+            self.ctx.clear_layer_property_listeners();
+            self.ctx.clear_repaint_listeners();
             // Clean up listener contexts from global map
             LISTENER_CONTEXTS
                 .lock()
@@ -323,6 +583,30 @@ impl Drop for EventListeners {
                     .unwrap()
                     .remove(&(self.layer_remove_listener as usize));
             }
+            if !self.desktop_configure_listener.is_null() {
+                LISTENER_CONTEXTS
+                    .lock()
+                    .unwrap()
+                    .remove(&(self.desktop_configure_listener as usize));
+            }
+            if !self.input_panel_configure_listener.is_null() {
+                LISTENER_CONTEXTS
+                    .lock()
+                    .unwrap()
+                    .remove(&(self.input_panel_configure_listener as usize));
+            }
+            if !self.input_panel_show_listener.is_null() {
+                LISTENER_CONTEXTS
+                    .lock()
+                    .unwrap()
+                    .remove(&(self.input_panel_show_listener as usize));
+            }
+            if !self.input_panel_hide_listener.is_null() {
+                LISTENER_CONTEXTS
+                    .lock()
+                    .unwrap()
+                    .remove(&(self.input_panel_hide_listener as usize));
+            }
 
             // Free the listener structures
             if !self.create_listener.is_null() {
@@ -340,10 +624,80 @@ impl Drop for EventListeners {
             if !self.layer_remove_listener.is_null() {
                 libc::free(self.layer_remove_listener as *mut c_void);
             }
+            if !self.desktop_configure_listener.is_null() {
+                libc::free(self.desktop_configure_listener as *mut c_void);
+            }
+            if !self.input_panel_configure_listener.is_null() {
+                libc::free(self.input_panel_configure_listener as *mut c_void);
+            }
+            if !self.input_panel_show_listener.is_null() {
+                libc::free(self.input_panel_show_listener as *mut c_void);
+            }
+            if !self.input_panel_hide_listener.is_null() {
+                libc::free(self.input_panel_hide_listener as *mut c_void);
+            }
         }
     }
 }
 
+/// Allocate a zeroed listener that calls `notify`
+unsafe fn alloc_listener(
+    notify: unsafe extern "C" fn(*mut wl_listener, *mut c_void),
+) -> Result<*mut wl_listener, &'static str> {
+    let listener = libc::malloc(std::mem::size_of::<wl_listener>()) as *mut wl_listener;
+    if listener.is_null() {
+        return Err("Failed to allocate listener");
+    }
+    // Zero-initialize to clear wl_list link field
+    std::ptr::write_bytes(listener, 0, 1);
+    (*listener).notify = Some(notify);
+    Ok(listener)
+}
+
+/// Append a listener to a signal
+///
+/// wl_signal_add() is static inline, so append to the listener list by hand.
+unsafe fn signal_add(signal: *mut wl_signal, listener: *mut wl_listener) {
+    let list = std::ptr::addr_of_mut!((*signal).listener_list);
+    let link = std::ptr::addr_of_mut!((*listener).link);
+    let tail = (*list).prev;
+    (*link).prev = tail;
+    (*link).next = list;
+    (*tail).next = link;
+    (*list).prev = link;
+}
+
+/// Unlink and free the frame and destroy listeners of one output
+unsafe fn free_repaint_listeners(listener: *mut wl_listener, output: &RepaintOutput) {
+    for listener in [listener, output.destroy_listener] {
+        unlink_listener(listener);
+        LISTENER_CONTEXTS
+            .lock()
+            .unwrap()
+            .remove(&(listener as usize));
+        libc::free(listener as *mut c_void);
+    }
+}
+
+/// Remove a listener from the signal it was added to
+///
+/// `wl_list_remove()` is not part of the generated bindings, so unlink by
+/// hand. Listeners that were never added still have a zeroed link and are
+/// left alone.
+unsafe fn unlink_listener(listener: *mut wl_listener) {
+    if listener.is_null() {
+        return;
+    }
+    let link = std::ptr::addr_of_mut!((*listener).link);
+    if (*link).prev.is_null() || (*link).next.is_null() {
+        return;
+    }
+    (*(*link).prev).next = (*link).next;
+    (*(*link).next).prev = (*link).prev;
+    (*link).prev = std::ptr::null_mut();
+    (*link).next = std::ptr::null_mut();
+}
+
 // Global map to store listener contexts
 // This is needed because C callbacks don't have a way to pass user data
 lazy_static::lazy_static! {
@@ -372,72 +726,7 @@ pub unsafe extern "C" fn surface_created_callback(listener: *mut wl_listener, da
             data as *mut ivi_layout_surface,
             Arc::clone(&context.ivi_api),
         ) {
-            // Get the surface ID using the stored API pointer
-            let surface_id = surface.id();
-
-            // Handle ID assignment and replacement if needed
-            match context
-                .id_assignment_manager
-                .handle_surface_created(surface_id)
-            {
-                Ok(assignment_info) => {
-                    if let Some(info) = assignment_info {
-                        // ID was assigned and replaced - use the new ID for state management
-                        jinfo!(
-                            "Surface {} had invalid ID, assigned and replaced with new ID: {}",
-                            surface_id,
-                            info.assigned_id
-                        );
-
-                        // Update state manager with the assigned surface ID and assignment info
-                        if let Ok(mut state_manager) = context.state_manager.lock() {
-                            state_manager.handle_surface_created_with_assignment_info(
-                                info.assigned_id,
-                                true,             // is_auto_assigned
-                                Some(surface_id), // original_id
-                            );
-                        }
-
-                        // Register per-surface property listener for the assigned surface ID
-                        context
-                            .register_surface_property_listener_by_id(info.assigned_id)
-                            .ok();
-                    } else {
-                        // Valid ID - use as-is, mark as manually assigned
-                        // Update state manager with the original surface ID
-                        if let Ok(mut state_manager) = context.state_manager.lock() {
-                            state_manager.handle_surface_created_with_assignment_info(
-                                surface_id, false, // is_auto_assigned (valid ID means manual)
-                                None,  // original_id
-                            );
-                        }
-
-                        // Register per-surface property listener for the original surface ID
-                        context
-                            .register_surface_property_listener_by_id(surface_id)
-                            .ok();
-                    }
-                }
-                Err(e) => {
-                    jerror!(
-                        "Failed to handle surface creation with ID assignment for surface {}: {}",
-                        surface_id,
-                        e
-                    );
-
-                    // Fall back to normal surface creation handling
-                    if let Ok(mut state_manager) = context.state_manager.lock() {
-                        state_manager.handle_surface_created_with_assignment_info(
-                            surface_id, false, // is_auto_assigned
-                            None,  // original_id
-                        );
-                    }
-
-                    context
-                        .register_surface_property_listener_by_id(surface_id)
-                        .ok();
-                }
-            }
+            context.track_new_surface(&surface, SurfaceType::Ivi);
         }
     }
 }
@@ -532,6 +821,151 @@ pub unsafe extern "C" fn surface_configured_callback(
     }
 }
 
+/// C callback for input panel surface configure events
+/// # Safety
+/// This function is unsafe because it is called from C code with raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn input_panel_surface_configured_callback(
+    listener: *mut wl_listener,
+    data: *mut c_void,
+) {
+    if listener.is_null() || data.is_null() {
+        return;
+    }
+
+    let context = {
+        let contexts = LISTENER_CONTEXTS.lock().unwrap();
+        contexts.get(&(listener as usize)).cloned()
+    };
+
+    if let Some(context) = context {
+        if let Some(surface) = IviSurface::new(
+            data as *mut ivi_layout_surface,
+            Arc::clone(&context.ivi_api),
+        ) {
+            let surface_id = surface.id();
+            if let Ok(mut state_manager) = context.state_manager.lock() {
+                state_manager.set_surface_type(surface_id, SurfaceType::InputPanel);
+            }
+        }
+    }
+}
+
+/// C callback for desktop surface configure events
+///
+/// Desktop (xdg-shell) surfaces may only report desktop configure events, so
+/// a surface seen here for the first time is tracked as if it had just been
+/// created.
+/// # Safety
+/// This function is unsafe because it is called from C code with raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn desktop_surface_configured_callback(
+    listener: *mut wl_listener,
+    data: *mut c_void,
+) {
+    if listener.is_null() || data.is_null() {
+        return;
+    }
+
+    let context = {
+        let contexts = LISTENER_CONTEXTS.lock().unwrap();
+        contexts.get(&(listener as usize)).cloned()
+    };
+
+    if let Some(context) = context {
+        if let Some(surface) = IviSurface::new(
+            data as *mut ivi_layout_surface,
+            Arc::clone(&context.ivi_api),
+        ) {
+            let surface_id = surface.id();
+            let tracked = match context.state_manager.lock() {
+                Ok(mut state_manager) => {
+                    let tracked = state_manager.get_surface(surface_id).is_some();
+                    if tracked {
+                        state_manager.set_surface_type(surface_id, SurfaceType::Desktop);
+                        state_manager.handle_surface_configured(surface_id);
+                    }
+                    tracked
+                }
+                Err(_) => return,
+            };
+
+            if !tracked {
+                context.track_new_surface(&surface, SurfaceType::Desktop);
+            }
+        }
+    }
+}
+
+/// Surface ID of an ivi_layout_surface, if it is known
+unsafe fn layout_surface_id(
+    surface: *mut ivi_layout_surface,
+    ivi_api: &Arc<IviLayoutApi>,
+) -> Option<u32> {
+    IviSurface::new(surface, Arc::clone(ivi_api)).map(|s| s.id())
+}
+
+/// C callback for input panel show requests
+/// # Safety
+/// This function is unsafe because it is called from C code with raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn input_panel_shown_callback(listener: *mut wl_listener, data: *mut c_void) {
+    if listener.is_null() || data.is_null() {
+        return;
+    }
+
+    let context = {
+        let contexts = LISTENER_CONTEXTS.lock().unwrap();
+        contexts.get(&(listener as usize)).cloned()
+    };
+
+    if let Some(context) = context {
+        // data is a pointer to ivi_layout_text_input_state
+        let state = &*(data as *const ivi_layout_text_input_state);
+        let surface_id = layout_surface_id(state.input_panel, &context.ivi_api);
+        let target_surface_id = layout_surface_id(state.surface, &context.ivi_api);
+
+        let notification_manager = match context.state_manager.lock() {
+            Ok(state_manager) => state_manager.notification_manager(),
+            Err(_) => return,
+        };
+        if let Ok(nm) = notification_manager.lock() {
+            nm.emit_input_panel_shown(surface_id, target_surface_id, state.overlay_panel);
+        }
+    }
+}
+
+/// C callback for input panel hide requests
+/// # Safety
+/// This function is unsafe because it is called from C code with raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn input_panel_hidden_callback(
+    listener: *mut wl_listener,
+    data: *mut c_void,
+) {
+    if listener.is_null() {
+        return;
+    }
+
+    let context = {
+        let contexts = LISTENER_CONTEXTS.lock().unwrap();
+        contexts.get(&(listener as usize)).cloned()
+    };
+
+    if let Some(context) = context {
+        // data is a pointer to the input panel ivi_layout_surface
+        let surface_id = layout_surface_id(data as *mut ivi_layout_surface, &context.ivi_api);
+
+        let notification_manager = match context.state_manager.lock() {
+            Ok(state_manager) => state_manager.notification_manager(),
+            Err(_) => return,
+        };
+        if let Ok(nm) = notification_manager.lock() {
+            nm.emit_input_panel_hidden(surface_id);
+        }
+    }
+}
+
 /// C callback for per-surface property change events
 /// # Safety
 /// This function is unsafe because it is called from C code with raw pointers.
@@ -587,9 +1021,11 @@ pub unsafe extern "C" fn layer_created_callback(listener: *mut wl_listener, data
                 state_manager.handle_layer_created(layer_id);
             }
             // Register per-layer property listener for this layer
-            context
-                .register_layer_property_listener_by_id(layer_id)
-                .ok();
+            if !context.coarse_events {
+                context
+                    .register_layer_property_listener_by_id(layer_id)
+                    .ok();
+            }
         }
     }
 }
@@ -651,3 +1087,194 @@ pub unsafe extern "C" fn layer_property_changed_callback(
         }
     }
 }
+
+/// C callback for output frame events, fired after an output is repainted
+/// # Safety
+/// This function is unsafe because it is called from C code with raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn output_frame_callback(listener: *mut wl_listener, _data: *mut c_void) {
+    if listener.is_null() {
+        return;
+    }
+
+    let context = {
+        let contexts = LISTENER_CONTEXTS.lock().unwrap();
+        contexts.get(&(listener as usize)).cloned()
+    };
+
+    if let Some(context) = context {
+        // The signal payload differs between weston versions, so identify the
+        // output from the listener instead
+        // Output repaints run one after another, so a commit made before the
+        // previous frame was signalled is shown by this one. A commit made
+        // since may have landed after this frame was drawn and is only
+        // counted by the next one.
+        let output = {
+            let mut listeners = context.repaint_listeners.lock().unwrap();
+            listeners.get_mut(&(listener as usize)).map(|output| {
+                let shown = output.clone();
+                output.shown_commits = context.ivi_api.commit_count();
+                shown
+            })
+        };
+        if let Some(output) = output {
+            let notification_manager = match context.state_manager.lock() {
+                Ok(state_manager) => state_manager.notification_manager(),
+                Err(_) => return,
+            };
+            if let Ok(nm) = notification_manager.lock() {
+                nm.emit_repaint_complete(
+                    output.output_id,
+                    &output.output_name,
+                    output.shown_commits,
+                );
+            }
+        }
+    }
+}
+
+/// C callback for output destroy events, removes the output's repaint listeners
+/// # Safety
+/// This function is unsafe because it is called from C code with raw pointers.
+#[no_mangle]
+pub unsafe extern "C" fn output_destroy_callback(listener: *mut wl_listener, _data: *mut c_void) {
+    if listener.is_null() {
+        return;
+    }
+
+    let context = {
+        let contexts = LISTENER_CONTEXTS.lock().unwrap();
+        contexts.get(&(listener as usize)).cloned()
+    };
+
+    if let Some(context) = context {
+        let mut listeners = context.repaint_listeners.lock().unwrap();
+        let key = listeners
+            .iter()
+            .find(|(_, output)| output.destroy_listener == listener)
+            .map(|(&key, _)| key);
+        if let Some(key) = key {
+            if let Some(output) = listeners.remove(&key) {
+                jinfo!(
+                    "Output {} destroyed, removing its repaint listener",
+                    output.output_name
+                );
+                free_repaint_listeners(key as *mut wl_listener, &output);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::IdAssignmentConfig;
+    use std::sync::atomic::{AtomicPtr, Ordering};
+
+    // Signal list the mock IVI API links listeners into
+    static MOCK_SIGNAL: AtomicPtr<wl_list> = AtomicPtr::new(std::ptr::null_mut());
+
+    unsafe extern "C" fn mock_add_listener(listener: *mut wl_listener) {
+        let list = MOCK_SIGNAL.load(Ordering::SeqCst);
+        let link = std::ptr::addr_of_mut!((*listener).link);
+        let tail = (*list).prev;
+        (*link).prev = tail;
+        (*link).next = list;
+        (*tail).next = link;
+        (*list).prev = link;
+    }
+
+    #[test]
+    fn test_register_listeners_rolls_back_on_failure() {
+        let list: *mut wl_list = Box::into_raw(Box::new(unsafe { std::mem::zeroed() }));
+        unsafe {
+            (*list).prev = list;
+            (*list).next = list;
+        }
+        MOCK_SIGNAL.store(list, Ordering::SeqCst);
+
+        // Surface listeners register fine, the layer ones are unavailable
+        let mut interface: ivi_layout_interface = unsafe { std::mem::zeroed() };
+        interface.add_listener_create_surface = Some(mock_add_listener);
+        interface.add_listener_remove_surface = Some(mock_add_listener);
+        interface.add_listener_configure_surface = Some(mock_add_listener);
+        let interface: &'static ivi_layout_interface = Box::leak(Box::new(interface));
+
+        let ivi_api = Arc::new(IviLayoutApi::from_raw(interface).unwrap());
+        let id_assignment_manager =
+            IdAssignmentManager::new(IdAssignmentConfig::default(), Arc::clone(&ivi_api)).unwrap();
+        let context = Arc::new(EventContext::new(
+            Arc::new(Mutex::new(StateManager::new(Arc::clone(&ivi_api)))),
+            ivi_api,
+            Arc::new(id_assignment_manager),
+        ));
+
+        let result = unsafe { Arc::clone(&context).register_listeners() };
+
+        assert!(result.is_err());
+        assert!(!LISTENER_CONTEXTS
+            .lock()
+            .unwrap()
+            .values()
+            .any(|ctx| Arc::ptr_eq(ctx, &context)));
+        assert_eq!(Arc::strong_count(&context), 1);
+        // Every listener the mock accepted was unlinked again
+        unsafe {
+            assert_eq!((*list).next, list);
+            assert_eq!((*list).prev, list);
+        }
+    }
+
+    unsafe extern "C" fn mock_get_surface_from_id(_id: u32) -> *mut ivi_layout_surface {
+        std::ptr::NonNull::dangling().as_ptr()
+    }
+
+    unsafe extern "C" fn mock_surface_add_listener(
+        _surface: *mut ivi_layout_surface,
+        _listener: *mut wl_listener,
+    ) -> i32 {
+        0
+    }
+
+    #[test]
+    fn test_property_listener_uses_shared_context() {
+        let mut interface: ivi_layout_interface = unsafe { std::mem::zeroed() };
+        interface.get_surface_from_id = Some(mock_get_surface_from_id);
+        interface.surface_add_listener = Some(mock_surface_add_listener);
+        let interface: &'static ivi_layout_interface = Box::leak(Box::new(interface));
+
+        let ivi_api = Arc::new(IviLayoutApi::from_raw(interface).unwrap());
+        let id_assignment_manager =
+            IdAssignmentManager::new(IdAssignmentConfig::default(), Arc::clone(&ivi_api)).unwrap();
+        let context = Arc::new(EventContext::new(
+            Arc::new(Mutex::new(StateManager::new(Arc::clone(&ivi_api)))),
+            ivi_api,
+            Arc::new(id_assignment_manager),
+        ));
+
+        unsafe { context.register_surface_property_listener_by_id(1000) }.unwrap();
+
+        // The callback looks up the very context holding the listener map
+        let listener = context.surface_prop_listeners.lock().unwrap()[&1000];
+        let callback_context = LISTENER_CONTEXTS
+            .lock()
+            .unwrap()
+            .get(&(listener as usize))
+            .cloned()
+            .unwrap();
+        assert!(Arc::ptr_eq(&callback_context, &context));
+        assert!(callback_context
+            .surface_prop_listeners
+            .lock()
+            .unwrap()
+            .contains_key(&1000));
+        drop(callback_context);
+
+        unsafe { context.remove_surface_property_listener(1000) };
+        assert!(!LISTENER_CONTEXTS
+            .lock()
+            .unwrap()
+            .contains_key(&(listener as usize)));
+        assert_eq!(Arc::strong_count(&context), 1);
+    }
+}
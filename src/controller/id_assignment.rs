@@ -18,6 +18,7 @@
 //! - `IdAssignmentError`: Error types specific to ID assignment operations
 //! - Validation functions for configuration parameters
 
+use serde::Deserialize;
 use std::collections::HashSet;
 use std::sync::{
     atomic::{AtomicBool, AtomicU64, Ordering},
@@ -32,7 +33,8 @@ use thiserror::Error;
 /// This struct defines the parameters that control how surface IDs are automatically
 /// assigned when invalid IDs are detected. The default configuration uses a dedicated
 /// range that avoids conflicts with manually specified IDs.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
 pub struct IdAssignmentConfig {
     /// Starting ID for automatic assignment range (default: 0x10000000)
     pub start_id: u32,
@@ -3,6 +3,7 @@
 pub mod events;
 pub mod id_assignment;
 pub mod notifications;
+pub mod staging;
 pub mod state;
 pub mod subscriptions;
 pub mod validation;
@@ -13,9 +14,10 @@ pub use id_assignment::{
     IdAssignmentResult, IdAssignmentStats,
 };
 pub use notifications::{Notification, NotificationData, NotificationManager, NotificationType};
+pub use staging::{StagedChanges, SurfaceChange};
 pub use state::StateManager;
 pub use subscriptions::SubscriptionManager;
 pub use validation::{
-    validate_opacity, validate_orientation, validate_position, validate_size, validate_z_order,
-    ValidationError,
+    validate_opacity, validate_orientation, validate_position, validate_size,
+    validate_size_with_max, validate_z_order, ValidationError,
 };
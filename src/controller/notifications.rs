@@ -1,9 +1,11 @@
 // Notification system for surface and focus changes
 
+use super::state::SurfaceState;
 use crate::ffi::bindings::*;
 #[allow(unused)]
 use jlogger_tracing::{jdebug, jerror, jinfo, jwarn, JloggerBuilder, LevelFilter};
 use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
 
 /// Type of notification
@@ -42,6 +44,16 @@ pub enum NotificationType {
     SurfaceContentReady,
     /// Surface buffer dimensions changed
     SurfaceContentSizeChanged,
+
+    // Output events
+    /// Output finished repainting a frame
+    RepaintComplete,
+
+    // Input panel events
+    /// Input panel (on-screen keyboard) requested to be shown
+    InputPanelShown,
+    /// Input panel (on-screen keyboard) requested to be hidden
+    InputPanelHidden,
 }
 
 /// Notification data for geometry changes
@@ -119,8 +131,9 @@ pub enum NotificationData {
     SourceGeometryChange(GeometryChangeNotification),
     DestinationGeometryChange(GeometryChangeNotification),
     FocusChange(FocusChangeNotification),
+    /// Carries the initial state so subscribers need no follow-up query
     SurfaceCreated {
-        surface_id: u32,
+        state: SurfaceState,
     },
     SurfaceDestroyed {
         surface_id: u32,
@@ -153,6 +166,25 @@ pub enum NotificationData {
         new_width: i32,
         new_height: i32,
     },
+
+    // Output notifications
+    RepaintComplete {
+        output_id: u32,
+        output_name: String,
+        /// Sequence number of the latest commit the frame is known to show
+        commit_seq: u64,
+    },
+
+    // Input panel notifications
+    /// IDs are `None` when Weston does not report the surface
+    InputPanelShown {
+        surface_id: Option<u32>,
+        target_surface_id: Option<u32>,
+        overlay: bool,
+    },
+    InputPanelHidden {
+        surface_id: Option<u32>,
+    },
 }
 
 /// A notification event
@@ -192,12 +224,35 @@ impl NotificationManager {
     }
 
     /// Emit a notification to all registered callbacks
+    ///
+    /// A panicking callback is logged and skipped. Callers usually hold the
+    /// state manager and notification manager locks, which a panic would
+    /// otherwise poison for every later emission.
     pub fn emit(&self, notification: Notification) {
-        let callbacks = self.callbacks.lock().unwrap();
+        // Callbacks run without the callbacks lock held
+        let callback_list = match self
+            .callbacks
+            .lock()
+            .unwrap()
+            .get(&notification.notification_type)
+        {
+            Some(callback_list) => callback_list.clone(),
+            None => return,
+        };
 
-        if let Some(callback_list) = callbacks.get(&notification.notification_type) {
-            for callback in callback_list {
-                callback(&notification);
+        for callback in callback_list {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| callback(&notification)))
+            {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                jerror!(
+                    "Notification callback for {:?} panicked: {}",
+                    notification.notification_type,
+                    message
+                );
             }
         }
     }
@@ -251,15 +306,15 @@ impl NotificationManager {
         self.emit(notification);
     }
 
-    /// Emit a surface created notification
-    pub fn emit_surface_created(&self, surface_id: u32) {
+    /// Emit a surface created notification with the initial surface state
+    pub fn emit_surface_created(&self, state: SurfaceState) {
+        jinfo!("Surface created notification for surface {}", state.id);
+
         let notification = Notification {
             notification_type: NotificationType::SurfaceCreated,
-            data: NotificationData::SurfaceCreated { surface_id },
+            data: NotificationData::SurfaceCreated { state },
         };
 
-        jinfo!("Surface created notification for surface {}", surface_id);
-
         self.emit(notification);
     }
 
@@ -468,6 +523,55 @@ impl NotificationManager {
         self.emit(notification);
     }
 
+    /// Emit a repaint complete notification for an output
+    pub fn emit_repaint_complete(&self, output_id: u32, output_name: &str, commit_seq: u64) {
+        let notification = Notification {
+            notification_type: NotificationType::RepaintComplete,
+            data: NotificationData::RepaintComplete {
+                output_id,
+                output_name: output_name.to_string(),
+                commit_seq,
+            },
+        };
+        // Fires on every frame, so keep it out of the info log
+        jdebug!("Repaint complete: output {} ({})", output_id, output_name);
+        self.emit(notification);
+    }
+
+    /// Emit an input panel shown notification
+    pub fn emit_input_panel_shown(
+        &self,
+        surface_id: Option<u32>,
+        target_surface_id: Option<u32>,
+        overlay: bool,
+    ) {
+        let notification = Notification {
+            notification_type: NotificationType::InputPanelShown,
+            data: NotificationData::InputPanelShown {
+                surface_id,
+                target_surface_id,
+                overlay,
+            },
+        };
+        jinfo!(
+            "Input panel shown: surface {:?} for surface {:?} (overlay: {})",
+            surface_id,
+            target_surface_id,
+            overlay
+        );
+        self.emit(notification);
+    }
+
+    /// Emit an input panel hidden notification
+    pub fn emit_input_panel_hidden(&self, surface_id: Option<u32>) {
+        let notification = Notification {
+            notification_type: NotificationType::InputPanelHidden,
+            data: NotificationData::InputPanelHidden { surface_id },
+        };
+        jinfo!("Input panel hidden: surface {:?}", surface_id);
+        self.emit(notification);
+    }
+
     /// Emit a layer opacity change notification
     pub fn emit_layer_opacity_change(&self, layer_id: u32, old_opacity: f32, new_opacity: f32) {
         let notification = Notification {
@@ -495,3 +599,36 @@ impl Default for NotificationManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panicking_callback_does_not_stop_delivery() {
+        let mut manager = NotificationManager::new();
+        let seen: Arc<Mutex<Vec<u32>>> = Arc::new(Mutex::new(Vec::new()));
+
+        manager.register_callback(
+            NotificationType::ZOrderChanged,
+            Arc::new(|_: &Notification| panic!("callback failure")),
+        );
+        let seen_clone = Arc::clone(&seen);
+        manager.register_callback(
+            NotificationType::ZOrderChanged,
+            Arc::new(move |n: &Notification| {
+                if let NotificationData::ZOrderChange(change) = &n.data {
+                    seen_clone.lock().unwrap().push(change.surface_id);
+                }
+            }),
+        );
+
+        // Emitting through a shared manager, as the state manager does
+        let shared = Arc::new(Mutex::new(manager));
+        shared.lock().unwrap().emit_z_order_change(1000, 0, 1);
+        shared.lock().unwrap().emit_z_order_change(1001, 0, 1);
+
+        assert!(!shared.is_poisoned());
+        assert_eq!(*seen.lock().unwrap(), vec![1000, 1001]);
+    }
+}
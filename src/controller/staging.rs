@@ -0,0 +1,154 @@
+// Server-side staging of uncommitted surface changes
+//
+// The IVI layout API only offers a global commit, so changes that are not
+// auto-committed are held here and written to the IVI layout when they are
+// committed. This lets `commit` apply the changes of a single surface while
+// the changes of other surfaces stay queued.
+
+use crate::ffi::bindings::{Orientation, Rectangle};
+use std::collections::BTreeMap;
+use std::mem::discriminant;
+
+/// A staged change to a surface property
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurfaceChange {
+    SourceRectangle(Rectangle),
+    DestinationRectangle(Rectangle),
+    Visibility(bool),
+    Opacity(f32),
+    Orientation(Orientation),
+}
+
+impl SurfaceChange {
+    /// Whether both changes set the same property
+    fn same_property(&self, other: &SurfaceChange) -> bool {
+        discriminant(self) == discriminant(other)
+    }
+}
+
+/// Uncommitted surface changes, keyed by surface ID
+#[derive(Debug, Default)]
+pub struct StagedChanges {
+    surfaces: BTreeMap<u32, Vec<SurfaceChange>>,
+}
+
+impl StagedChanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stage a change, replacing an earlier staged change of the same property
+    pub fn stage(&mut self, surface_id: u32, change: SurfaceChange) {
+        let changes = self.surfaces.entry(surface_id).or_default();
+        changes.retain(|c| !c.same_property(&change));
+        changes.push(change);
+    }
+
+    /// Drop a staged change of the same property, e.g. because it was
+    /// overridden by an auto-committed change
+    pub fn discard(&mut self, surface_id: u32, change: &SurfaceChange) {
+        if let Some(changes) = self.surfaces.get_mut(&surface_id) {
+            changes.retain(|c| !c.same_property(change));
+            if changes.is_empty() {
+                self.surfaces.remove(&surface_id);
+            }
+        }
+    }
+
+    /// The staged destination rectangle of a surface, if any
+    pub fn destination_rectangle(&self, surface_id: u32) -> Option<Rectangle> {
+        self.surfaces
+            .get(&surface_id)?
+            .iter()
+            .find_map(|change| match change {
+                SurfaceChange::DestinationRectangle(rect) => Some(*rect),
+                _ => None,
+            })
+    }
+
+    /// Remove and return the staged changes of one surface, in staging order
+    pub fn take_surface(&mut self, surface_id: u32) -> Vec<SurfaceChange> {
+        self.surfaces.remove(&surface_id).unwrap_or_default()
+    }
+
+    /// Remove and return all staged changes, ordered by surface ID
+    pub fn take_all(&mut self) -> Vec<(u32, Vec<SurfaceChange>)> {
+        std::mem::take(&mut self.surfaces).into_iter().collect()
+    }
+
+    /// Number of staged changes across all surfaces
+    pub fn len(&self) -> usize {
+        self.surfaces.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.surfaces.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_replaces_same_property() {
+        let mut staged = StagedChanges::new();
+        staged.stage(1000, SurfaceChange::Opacity(0.5));
+        staged.stage(1000, SurfaceChange::Visibility(true));
+        staged.stage(1000, SurfaceChange::Opacity(0.8));
+
+        assert_eq!(staged.len(), 2);
+        assert_eq!(
+            staged.take_surface(1000),
+            vec![SurfaceChange::Visibility(true), SurfaceChange::Opacity(0.8)]
+        );
+        assert!(staged.is_empty());
+    }
+
+    #[test]
+    fn test_take_surface_leaves_others_staged() {
+        let mut staged = StagedChanges::new();
+        staged.stage(1001, SurfaceChange::Visibility(false));
+        staged.stage(1000, SurfaceChange::Visibility(true));
+
+        assert_eq!(
+            staged.take_surface(1000),
+            vec![SurfaceChange::Visibility(true)]
+        );
+        assert!(staged.take_surface(1000).is_empty());
+        assert_eq!(
+            staged.take_all(),
+            vec![(1001, vec![SurfaceChange::Visibility(false)])]
+        );
+    }
+
+    #[test]
+    fn test_destination_rectangle() {
+        let mut staged = StagedChanges::new();
+        assert_eq!(staged.destination_rectangle(1000), None);
+
+        let rect = Rectangle {
+            x: 10,
+            y: 20,
+            width: 100,
+            height: 100,
+        };
+        staged.stage(1000, SurfaceChange::Opacity(0.5));
+        staged.stage(1000, SurfaceChange::DestinationRectangle(rect));
+        assert_eq!(staged.destination_rectangle(1000), Some(rect));
+        assert_eq!(staged.destination_rectangle(1001), None);
+    }
+
+    #[test]
+    fn test_discard_removes_only_same_property() {
+        let mut staged = StagedChanges::new();
+        staged.stage(1000, SurfaceChange::Opacity(0.5));
+        staged.stage(1000, SurfaceChange::Visibility(true));
+
+        staged.discard(1000, &SurfaceChange::Opacity(1.0));
+        assert_eq!(staged.len(), 1);
+
+        staged.discard(1000, &SurfaceChange::Visibility(false));
+        assert!(staged.is_empty());
+    }
+}
@@ -2,12 +2,36 @@
 
 use super::notifications::GeometryType;
 use crate::ffi::bindings::ivi_layout_api::IviLayoutApi;
+use crate::ffi::bindings::ivi_surface::IviSurface;
 use crate::ffi::bindings::*;
 #[allow(unused)]
 use jlogger_tracing::{jdebug, jerror, jinfo, jwarn, JloggerBuilder, LevelFilter};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+/// Kind of shell surface behind an IVI surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SurfaceType {
+    /// Created through the ivi_application protocol
+    #[default]
+    Ivi,
+    /// Desktop (xdg-shell) surface adopted by ivi-shell
+    Desktop,
+    /// Input panel surface, such as a virtual keyboard
+    InputPanel,
+}
+
+impl SurfaceType {
+    /// Name used for the `surface_type` field in the JSON-RPC API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SurfaceType::Ivi => "ivi",
+            SurfaceType::Desktop => "desktop",
+            SurfaceType::InputPanel => "input_panel",
+        }
+    }
+}
+
 /// Represents the state of an IVI surface
 #[derive(Debug, Clone)]
 pub struct SurfaceState {
@@ -23,6 +47,62 @@ pub struct SurfaceState {
     pub is_auto_assigned: bool,
     /// Original invalid ID if this surface was auto-assigned
     pub original_id: Option<u32>,
+    /// Whether the IVI API has reported both rectangles. Until then the
+    /// rectangles are zero-sized placeholders
+    pub initialized: bool,
+    /// Shell surface kind, known once the surface has been configured
+    pub surface_type: SurfaceType,
+}
+
+/// Resolve the rectangles of a surface as reported by the IVI API
+///
+/// A surface without a committed buffer may not report its rectangles yet.
+/// Rather than dropping it, missing rectangles fall back to the previously
+/// tracked value or a zero-sized placeholder. The returned flag tells whether
+/// real geometry is known.
+fn resolve_geometry(
+    src_rect: Option<Rectangle>,
+    dest_rect: Option<Rectangle>,
+    previous: Option<&SurfaceState>,
+) -> (Rectangle, Rectangle, bool) {
+    let initialized =
+        (src_rect.is_some() && dest_rect.is_some()) || previous.is_some_and(|s| s.initialized);
+    let src_rect = src_rect
+        .or(previous.map(|s| s.src_rect))
+        .unwrap_or_default();
+    let dest_rect = dest_rect
+        .or(previous.map(|s| s.dest_rect))
+        .unwrap_or_default();
+    (src_rect, dest_rect, initialized)
+}
+
+impl SurfaceState {
+    /// Read the current properties of a surface from the IVI API
+    ///
+    /// The auto-assignment fields and the surface type are not known to the
+    /// IVI API and are left at their defaults.
+    pub fn from_ivi(surface: &IviSurface) -> Self {
+        let (src_rect, dest_rect, initialized) = resolve_geometry(
+            surface.source_rectangle(),
+            surface.destination_rectangle(),
+            None,
+        );
+
+        Self {
+            id: surface.id(),
+            orig_size: surface.orig_size(),
+            src_rect,
+            dest_rect,
+            visibility: surface.visibility(),
+            opacity: surface.opacity(),
+            orientation: surface.orientation(),
+            z_order: 0, // Z-order is managed at layer level
+            is_auto_assigned: false,
+            original_id: None,
+            initialized,
+            surface_type: SurfaceType::default(),
+        }
+    }
 }
 
 /// Represents the state of an IVI layer
@@ -115,10 +195,12 @@ impl StateManager {
         surfaces.get(&id).cloned()
     }
 
-    /// Get all surfaces
+    /// Get all surfaces, ordered by surface ID
     pub fn get_all_surfaces(&self) -> Vec<SurfaceState> {
         let surfaces = self.surfaces.lock().unwrap();
-        surfaces.values().cloned().collect()
+        let mut all: Vec<SurfaceState> = surfaces.values().cloned().collect();
+        all.sort_by_key(|s| s.id);
+        all
     }
 
     /// Get the number of tracked surfaces
@@ -193,51 +275,26 @@ impl StateManager {
             .iter()
             .map(|(&id, state)| (id, (state.is_auto_assigned, state.original_id)))
             .collect();
+        // The surface type is only reported by configure events
+        let existing_types: std::collections::HashMap<u32, SurfaceType> = surfaces
+            .iter()
+            .map(|(&id, state)| (id, state.surface_type))
+            .collect();
 
         // Clear existing state
         surfaces.clear();
 
         // Populate with current IVI surfaces
         for surface in ivi_surfaces {
-            let id = surface.id();
-            let (orig_width, orig_height) = surface.orig_size();
-            let src_rect;
-            let dest_rect;
-
-            if let Some(s) = surface.source_rectangle() {
-                src_rect = s;
-            } else {
-                continue; // Skip surfaces without source rectangle
-            }
-
-            if let Some(d) = surface.destination_rectangle() {
-                dest_rect = d;
-            } else {
-                continue; // Skip surfaces without destination rectangle
-            }
-
-            let visibility = surface.visibility();
-            let opacity = surface.opacity();
-            let orientation = surface.orientation();
+            let mut state = SurfaceState::from_ivi(&surface);
+            let id = state.id;
 
             // Restore auto-assignment information if available
-            let (is_auto_assigned, original_id) = existing_auto_info
-                .get(&id)
-                .copied()
-                .unwrap_or((false, None));
-
-            let state = SurfaceState {
-                id,
-                orig_size: (orig_width, orig_height),
-                src_rect,
-                dest_rect,
-                visibility,
-                opacity,
-                orientation,
-                z_order: 0, // Z-order is managed at layer level
-                is_auto_assigned,
-                original_id,
-            };
+            if let Some(&(is_auto_assigned, original_id)) = existing_auto_info.get(&id) {
+                state.is_auto_assigned = is_auto_assigned;
+                state.original_id = original_id;
+            }
+            state.surface_type = existing_types.get(&id).copied().unwrap_or_default();
 
             surfaces.insert(id, state);
         }
@@ -274,24 +331,31 @@ impl StateManager {
         surface_id: u32,
         is_auto_assigned: bool,
         original_id: Option<u32>,
+    ) {
+        self.handle_surface_created_with_type(
+            surface_id,
+            is_auto_assigned,
+            original_id,
+            SurfaceType::Ivi,
+        );
+    }
+
+    /// Surface creation handler for surfaces whose shell surface kind is known
+    pub fn handle_surface_created_with_type(
+        &mut self,
+        surface_id: u32,
+        is_auto_assigned: bool,
+        original_id: Option<u32>,
+        surface_type: SurfaceType,
     ) {
         // Query the IVI API for the new surface
         if let Some(surface) = self.ivi_api.get_surface_from_id(surface_id) {
             let (orig_width, orig_height) = surface.orig_size();
-            let src_rect;
-            let dest_rect;
-
-            if let Some(s) = surface.source_rectangle() {
-                src_rect = s;
-            } else {
-                return; // Cannot create state without source rectangle
-            }
-
-            if let Some(d) = surface.destination_rectangle() {
-                dest_rect = d;
-            } else {
-                return; // Cannot create state without destination rectangle
-            }
+            let (src_rect, dest_rect, initialized) = resolve_geometry(
+                surface.source_rectangle(),
+                surface.destination_rectangle(),
+                None,
+            );
 
             let visibility = surface.visibility();
             let opacity = surface.opacity();
@@ -308,13 +372,15 @@ impl StateManager {
                 z_order: 0,
                 is_auto_assigned,
                 original_id,
+                initialized,
+                surface_type,
             };
 
-            self.add_surface(surface_id, state);
+            self.add_surface(surface_id, state.clone());
 
             // Emit surface created notification with the final surface ID
             let notification_manager = self.notification_manager.lock().unwrap();
-            notification_manager.emit_surface_created(surface_id);
+            notification_manager.emit_surface_created(state);
 
             // Log additional information for auto-assigned surfaces
             if is_auto_assigned {
@@ -331,6 +397,40 @@ impl StateManager {
         }
     }
 
+    /// Record the shell surface kind of a surface
+    /// This is called by the desktop and input panel configure listeners
+    pub fn set_surface_type(&mut self, surface_id: u32, surface_type: SurfaceType) {
+        let mut surfaces = self.surfaces.lock().unwrap();
+        if let Some(state) = surfaces.get_mut(&surface_id) {
+            if state.surface_type != surface_type {
+                jinfo!(
+                    "Surface {} is a {} surface",
+                    surface_id,
+                    surface_type.as_str()
+                );
+                state.surface_type = surface_type;
+            }
+        }
+    }
+
+    /// Record the z-order of a surface after it was committed
+    /// The IVI API does not report z-order, so the RPC handler records it and a
+    /// ZOrderChanged notification is emitted only if the value changed
+    pub fn set_surface_z_order(&mut self, surface_id: u32, z_order: i32) {
+        let old_z_order = {
+            let mut surfaces = self.surfaces.lock().unwrap();
+            match surfaces.get_mut(&surface_id) {
+                Some(state) => std::mem::replace(&mut state.z_order, z_order),
+                None => return,
+            }
+        };
+
+        if old_z_order != z_order {
+            let notification_manager = self.notification_manager.lock().unwrap();
+            notification_manager.emit_z_order_change(surface_id, old_z_order, z_order);
+        }
+    }
+
     /// Handle surface destruction event
     /// This is called by the event listener when a surface is destroyed
     pub fn handle_surface_destroyed(&mut self, surface_id: u32) {
@@ -362,31 +462,28 @@ impl StateManager {
         // Query the IVI API for updated surface properties
         if let Some(surface) = self.ivi_api.get_surface_from_id(surface_id) {
             let (orig_width, orig_height) = surface.orig_size();
-            let src_rect;
-            let dest_rect;
-
-            if let Some(s) = surface.source_rectangle() {
-                src_rect = s;
-            } else {
-                return; // Cannot update state without source rectangle
-            }
-
-            if let Some(d) = surface.destination_rectangle() {
-                dest_rect = d;
-            } else {
-                return; // Cannot update state without destination rectangle
-            }
+            let (src_rect, dest_rect, initialized) = resolve_geometry(
+                surface.source_rectangle(),
+                surface.destination_rectangle(),
+                old_state.as_ref(),
+            );
 
             let visibility = surface.visibility();
             let opacity = surface.opacity();
             let orientation = surface.orientation();
 
-            // Preserve existing z_order, auto-assignment info, and original ID
-            let (z_order, is_auto_assigned, original_id) = if let Some(ref old) = old_state {
-                (old.z_order, old.is_auto_assigned, old.original_id)
-            } else {
-                (0, false, None)
-            };
+            // Preserve existing z_order, auto-assignment info, original ID and type
+            let (z_order, is_auto_assigned, original_id, surface_type) =
+                if let Some(ref old) = old_state {
+                    (
+                        old.z_order,
+                        old.is_auto_assigned,
+                        old.original_id,
+                        old.surface_type,
+                    )
+                } else {
+                    (0, false, None, SurfaceType::Ivi)
+                };
 
             let new_state = SurfaceState {
                 id: surface_id,
@@ -399,6 +496,8 @@ impl StateManager {
                 z_order,
                 is_auto_assigned,
                 original_id,
+                initialized,
+                surface_type,
             };
 
             // Check property changes and emit notifications
@@ -489,13 +588,13 @@ impl StateManager {
         new: &SurfaceState,
         event_mask: u32,
     ) {
-        let has = |bit: u32| (event_mask & bit) != 0;
+        let has = |mask: NotificationMask| mask.is_set(event_mask);
         if let Ok(notification_manager) = self.notification_manager.lock() {
             // Geometry
-            if has(NotificationMask::Position.into())
-                || has(NotificationMask::SourceRect.into())
-                || has(NotificationMask::DestRect.into())
-                || has(NotificationMask::Dimension.into())
+            if has(NotificationMask::Position)
+                || has(NotificationMask::SourceRect)
+                || has(NotificationMask::DestRect)
+                || has(NotificationMask::Dimension)
             {
                 // Geometry (any position or size change)
                 if old.src_rect != new.src_rect {
@@ -518,7 +617,7 @@ impl StateManager {
             }
 
             // Visibility
-            if has(NotificationMask::Visibility.into()) && old.visibility != new.visibility {
+            if has(NotificationMask::Visibility) && old.visibility != new.visibility {
                 notification_manager.emit_visibility_change(
                     surface_id,
                     old.visibility,
@@ -527,14 +626,12 @@ impl StateManager {
             }
 
             // Opacity
-            if has(NotificationMask::Opacity.into())
-                && (old.opacity - new.opacity).abs() > f32::EPSILON
-            {
+            if has(NotificationMask::Opacity) && (old.opacity - new.opacity).abs() > f32::EPSILON {
                 notification_manager.emit_opacity_change(surface_id, old.opacity, new.opacity);
             }
 
             // Orientation
-            if has(NotificationMask::Orientation.into()) && old.orientation != new.orientation {
+            if has(NotificationMask::Orientation) && old.orientation != new.orientation {
                 notification_manager.emit_orientation_change(
                     surface_id,
                     old.orientation,
@@ -573,10 +670,12 @@ impl StateManager {
         layers.get(&id).cloned()
     }
 
-    /// Get all layers
+    /// Get all layers, ordered by layer ID
     pub fn get_all_layers(&self) -> Vec<LayerState> {
         let layers = self.layers.lock().unwrap();
-        layers.values().cloned().collect()
+        let mut all: Vec<LayerState> = layers.values().cloned().collect();
+        all.sort_by_key(|l| l.id);
+        all
     }
 
     /// Get the number of tracked layers
@@ -655,17 +754,18 @@ impl StateManager {
 
             // Check if visibility changed and emit notification
             if let Some(ref old) = old_state {
-                // Bit definitions from ivi_layout_notification_mask
-                const NOTIF_OPACITY: u32 = 1 << 1;
-                const NOTIF_VISIBILITY: u32 = 1 << 7;
-                let has = |bit: u32| (event_mask & bit) != 0;
+                // As for surfaces, an empty event_mask means IVI did not say
+                // what changed, so every property is compared
+                let has = |mask: NotificationMask| event_mask == 0 || mask.is_set(event_mask);
 
-                if has(NOTIF_VISIBILITY) && old.visibility != new_state.visibility {
+                if has(NotificationMask::Visibility) && old.visibility != new_state.visibility {
                     let nm = self.notification_manager.lock().unwrap();
                     nm.emit_layer_visibility_change(layer_id, old.visibility, new_state.visibility);
                 }
 
-                if has(NOTIF_OPACITY) && (old.opacity - new_state.opacity).abs() > f32::EPSILON {
+                if has(NotificationMask::Opacity)
+                    && (old.opacity - new_state.opacity).abs() > f32::EPSILON
+                {
                     let nm = self.notification_manager.lock().unwrap();
                     nm.emit_layer_opacity_change(layer_id, old.opacity, new_state.opacity);
                 }
@@ -679,7 +779,7 @@ impl StateManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::controller::notifications::{Notification, NotificationType};
+    use crate::controller::notifications::{Notification, NotificationData, NotificationType};
     use crate::ffi::bindings::ivi_layout_api::IviLayoutApi;
     use std::sync::{Arc, Mutex};
 
@@ -734,6 +834,8 @@ mod tests {
             z_order: 0,
             is_auto_assigned: false,
             original_id: None,
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
         };
         let new_state = SurfaceState {
             id: 42,
@@ -756,6 +858,8 @@ mod tests {
             z_order: 0,
             is_auto_assigned: false,
             original_id: None,
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
         };
 
         sm.emit_surface_property_changes(42, &old, &new_state);
@@ -844,6 +948,8 @@ mod tests {
             z_order: 0,
             is_auto_assigned: false,
             original_id: None,
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
         };
         let new_state = SurfaceState {
             orientation: Orientation::Rotate180,
@@ -857,6 +963,112 @@ mod tests {
         assert_eq!(got[0], NotificationType::OrientationChanged);
     }
 
+    #[test]
+    fn filtered_changes_follow_event_mask_bits() {
+        let sm = make_state_manager();
+
+        let seen: Arc<Mutex<Vec<NotificationType>>> = Arc::new(Mutex::new(Vec::new()));
+        let nm_arc = sm.notification_manager();
+        {
+            let mut nm = nm_arc.lock().unwrap();
+            for nt in [
+                NotificationType::GeometryChanged,
+                NotificationType::VisibilityChanged,
+                NotificationType::OpacityChanged,
+                NotificationType::OrientationChanged,
+            ] {
+                let seen_clone = Arc::clone(&seen);
+                nm.register_callback(
+                    nt,
+                    Arc::new(move |n: &Notification| {
+                        seen_clone.lock().unwrap().push(n.notification_type);
+                    }),
+                );
+            }
+        }
+
+        let rect = Rectangle {
+            x: 0,
+            y: 0,
+            width: 200,
+            height: 150,
+        };
+        let old = SurfaceState {
+            id: 1,
+            orig_size: (200, 150),
+            src_rect: rect,
+            dest_rect: rect,
+            visibility: true,
+            opacity: 0.75,
+            orientation: Orientation::Normal,
+            z_order: 0,
+            is_auto_assigned: false,
+            original_id: None,
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
+        };
+        // Every property differs, so the mask alone decides what is emitted
+        let new_state = SurfaceState {
+            src_rect: Rectangle { x: 10, ..rect },
+            dest_rect: Rectangle { x: 10, ..rect },
+            visibility: false,
+            opacity: 0.5,
+            orientation: Orientation::Rotate90,
+            ..old.clone()
+        };
+
+        let cases = [
+            (
+                NotificationMask::Opacity,
+                vec![NotificationType::OpacityChanged],
+            ),
+            (
+                NotificationMask::Visibility,
+                vec![NotificationType::VisibilityChanged],
+            ),
+            (
+                NotificationMask::Orientation,
+                vec![NotificationType::OrientationChanged],
+            ),
+            (
+                NotificationMask::SourceRect,
+                vec![
+                    NotificationType::GeometryChanged,
+                    NotificationType::GeometryChanged,
+                ],
+            ),
+            (
+                NotificationMask::DestRect,
+                vec![
+                    NotificationType::GeometryChanged,
+                    NotificationType::GeometryChanged,
+                ],
+            ),
+            (
+                NotificationMask::Position,
+                vec![
+                    NotificationType::GeometryChanged,
+                    NotificationType::GeometryChanged,
+                ],
+            ),
+            (
+                NotificationMask::Dimension,
+                vec![
+                    NotificationType::GeometryChanged,
+                    NotificationType::GeometryChanged,
+                ],
+            ),
+            (NotificationMask::PixelFormat, vec![]),
+            (NotificationMask::Configure, vec![]),
+        ];
+
+        for (mask, expected) in cases {
+            seen.lock().unwrap().clear();
+            sm.emit_surface_property_changes_filtered(1, &old, &new_state, mask.into());
+            assert_eq!(*seen.lock().unwrap(), expected, "{:?}", mask);
+        }
+    }
+
     #[test]
     fn test_auto_assigned_surface_tracking() {
         let sm = make_state_manager();
@@ -883,6 +1095,8 @@ mod tests {
             z_order: 0,
             is_auto_assigned: true,
             original_id: Some(0xFFFFFFFF),
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
         };
 
         let manual_assigned_state = SurfaceState {
@@ -906,6 +1120,8 @@ mod tests {
             z_order: 0,
             is_auto_assigned: false,
             original_id: None,
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
         };
 
         // Add surfaces to state manager
@@ -973,6 +1189,8 @@ mod tests {
             z_order: 0,
             is_auto_assigned: true,
             original_id: Some(0xFFFFFFFF),
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
         };
 
         let manual_assigned_state = SurfaceState {
@@ -996,6 +1214,8 @@ mod tests {
             z_order: 0,
             is_auto_assigned: false,
             original_id: None,
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
         };
 
         // Add surfaces to state manager
@@ -1029,4 +1249,164 @@ mod tests {
         );
         assert_eq!(existing_auto_info.get(&42), Some(&(false, None)));
     }
+
+    #[test]
+    fn test_surface_without_rectangles_is_tracked() {
+        let mut sm = make_state_manager();
+        let rect = Rectangle {
+            x: 10,
+            y: 20,
+            width: 640,
+            height: 480,
+        };
+
+        // No buffer committed yet: neither rectangle is reported
+        let (src_rect, dest_rect, initialized) = resolve_geometry(None, None, None);
+        assert_eq!(src_rect, Rectangle::default());
+        assert_eq!(dest_rect, Rectangle::default());
+        assert!(!initialized);
+
+        sm.add_surface(
+            7,
+            SurfaceState {
+                id: 7,
+                orig_size: (0, 0),
+                src_rect,
+                dest_rect,
+                visibility: false,
+                opacity: 1.0,
+                orientation: Orientation::Normal,
+                z_order: 0,
+                is_auto_assigned: false,
+                original_id: None,
+                initialized,
+                surface_type: SurfaceType::Ivi,
+            },
+        );
+        let ids: Vec<u32> = sm.get_all_surfaces().iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![7]);
+
+        // Geometry arrives with a later configure
+        let previous = sm.get_surface(7).unwrap();
+        let (src_rect, dest_rect, initialized) =
+            resolve_geometry(Some(rect), Some(rect), Some(&previous));
+        assert_eq!((src_rect, dest_rect, initialized), (rect, rect, true));
+
+        // A partial report keeps the known rectangle instead of dropping the surface
+        let previous = SurfaceState {
+            src_rect,
+            dest_rect,
+            initialized,
+            ..previous
+        };
+        let (src_rect, dest_rect, initialized) =
+            resolve_geometry(None, Some(rect), Some(&previous));
+        assert_eq!((src_rect, dest_rect, initialized), (rect, rect, true));
+    }
+
+    #[test]
+    fn test_set_surface_type() {
+        let mut sm = make_state_manager();
+        sm.add_surface(
+            9,
+            SurfaceState {
+                id: 9,
+                orig_size: (0, 0),
+                src_rect: Rectangle::default(),
+                dest_rect: Rectangle::default(),
+                visibility: false,
+                opacity: 1.0,
+                orientation: Orientation::Normal,
+                z_order: 0,
+                is_auto_assigned: false,
+                original_id: None,
+                initialized: false,
+                surface_type: SurfaceType::Ivi,
+            },
+        );
+
+        sm.set_surface_type(9, SurfaceType::InputPanel);
+        let surface = sm.get_surface(9).unwrap();
+        assert_eq!(surface.surface_type, SurfaceType::InputPanel);
+        assert_eq!(surface.surface_type.as_str(), "input_panel");
+
+        // Unknown surfaces are ignored
+        sm.set_surface_type(10, SurfaceType::Desktop);
+        assert!(sm.get_surface(10).is_none());
+    }
+    #[test]
+    fn test_set_surface_z_order_emits_only_on_change() {
+        let mut sm = make_state_manager();
+        sm.add_surface(
+            9,
+            SurfaceState {
+                id: 9,
+                orig_size: (0, 0),
+                src_rect: Rectangle::default(),
+                dest_rect: Rectangle::default(),
+                visibility: true,
+                opacity: 1.0,
+                orientation: Orientation::Normal,
+                z_order: 0,
+                is_auto_assigned: false,
+                original_id: None,
+                initialized: false,
+                surface_type: SurfaceType::Ivi,
+            },
+        );
+
+        let seen: Arc<Mutex<Vec<NotificationType>>> = Arc::new(Mutex::new(Vec::new()));
+        {
+            let seen = Arc::clone(&seen);
+            sm.notification_manager().lock().unwrap().register_callback(
+                NotificationType::ZOrderChanged,
+                Arc::new(move |n: &Notification| {
+                    seen.lock().unwrap().push(n.notification_type);
+                }),
+            );
+        }
+
+        sm.set_surface_z_order(9, 3);
+        sm.set_surface_z_order(9, 3);
+        sm.set_surface_z_order(10, 3);
+
+        assert_eq!(sm.get_surface(9).unwrap().z_order, 3);
+        assert_eq!(*seen.lock().unwrap(), vec![NotificationType::ZOrderChanged]);
+    }
+
+    #[test]
+    fn test_focus_change_carries_old_and_new_surface() {
+        let mut sm = make_state_manager();
+
+        let seen: Arc<Mutex<Vec<(Option<u32>, Option<u32>)>>> = Arc::new(Mutex::new(Vec::new()));
+        {
+            let seen = Arc::clone(&seen);
+            sm.notification_manager().lock().unwrap().register_callback(
+                NotificationType::FocusChanged,
+                Arc::new(move |n: &Notification| {
+                    if let NotificationData::FocusChange(change) = &n.data {
+                        seen.lock()
+                            .unwrap()
+                            .push((change.old_focused_surface, change.new_focused_surface));
+                    }
+                }),
+            );
+        }
+
+        sm.set_focused_surface(Some(1000));
+        sm.set_focused_surface(Some(1001));
+        sm.set_focused_surface(Some(1000));
+        sm.set_focused_surface(Some(1000));
+        sm.set_focused_surface(None);
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                (None, Some(1000)),
+                (Some(1000), Some(1001)),
+                (Some(1001), Some(1000)),
+                (Some(1000), None),
+            ]
+        );
+    }
 }
@@ -10,6 +10,13 @@ use std::sync::{Arc, Mutex};
 /// Default notification buffer size per client
 pub const DEFAULT_BUFFER_SIZE: usize = 100;
 
+/// Default maximum number of distinct subscriptions per client
+///
+/// A client holds at most one subscription per event type, so this lets
+/// every client subscribe to all of them; a lower limit restricts clients
+/// to a subset.
+pub const DEFAULT_MAX_SUBSCRIPTIONS: usize = EventType::ALL.len();
+
 /// Per-client subscription state
 struct ClientSubscription {
     event_types: HashSet<EventType>,
@@ -26,10 +33,12 @@ impl ClientSubscription {
         }
     }
 
-    fn subscribe(&mut self, event_types: Vec<EventType>) {
-        for event_type in event_types {
-            self.event_types.insert(event_type);
-        }
+    /// Subscribe to `event_types`, returning those not subscribed before
+    fn subscribe(&mut self, event_types: Vec<EventType>) -> Vec<EventType> {
+        event_types
+            .into_iter()
+            .filter(|event_type| self.event_types.insert(*event_type))
+            .collect()
     }
 
     fn unsubscribe(&mut self, event_types: Vec<EventType>) {
@@ -38,6 +47,15 @@ impl ClientSubscription {
         }
     }
 
+    /// Number of distinct subscriptions after subscribing to `event_types`
+    fn count_after_subscribe(&self, event_types: &[EventType]) -> usize {
+        let added: HashSet<&EventType> = event_types
+            .iter()
+            .filter(|event_type| !self.event_types.contains(event_type))
+            .collect();
+        self.event_types.len() + added.len()
+    }
+
     fn is_subscribed(&self, event_type: &EventType) -> bool {
         self.event_types.contains(event_type)
     }
@@ -57,6 +75,18 @@ impl ClientSubscription {
         self.event_buffer.drain(..).collect()
     }
 
+    fn requeue_notifications(&mut self, notifications: Vec<RpcNotification>) {
+        for notification in notifications.into_iter().rev() {
+            self.event_buffer.push_front(notification);
+        }
+
+        // Requeued notifications are the oldest, so they are dropped first
+        while self.event_buffer.len() > self.buffer_size {
+            self.event_buffer.pop_front();
+            jdebug!("Dropped oldest notification due to buffer overflow");
+        }
+    }
+
     fn get_subscriptions(&self) -> Vec<EventType> {
         self.event_types.iter().copied().collect()
     }
@@ -66,6 +96,7 @@ impl ClientSubscription {
 pub struct SubscriptionManager {
     subscriptions: Arc<Mutex<HashMap<ClientId, ClientSubscription>>>,
     buffer_size: usize,
+    max_subscriptions: usize,
 }
 
 impl SubscriptionManager {
@@ -74,6 +105,7 @@ impl SubscriptionManager {
         Self {
             subscriptions: Arc::new(Mutex::new(HashMap::new())),
             buffer_size: DEFAULT_BUFFER_SIZE,
+            max_subscriptions: DEFAULT_MAX_SUBSCRIPTIONS,
         }
     }
 
@@ -82,25 +114,68 @@ impl SubscriptionManager {
         Self {
             subscriptions: Arc::new(Mutex::new(HashMap::new())),
             buffer_size,
+            max_subscriptions: DEFAULT_MAX_SUBSCRIPTIONS,
         }
     }
 
+    /// Maximum number of distinct subscriptions per client
+    pub fn max_subscriptions(&self) -> usize {
+        self.max_subscriptions
+    }
+
+    /// Number of notifications buffered per client before the oldest is dropped
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Set the maximum number of distinct subscriptions per client
+    ///
+    /// Subscriptions made before the limit was lowered are kept.
+    pub fn set_max_subscriptions(&mut self, max_subscriptions: usize) {
+        self.max_subscriptions = max_subscriptions;
+    }
+
     /// Subscribe a client to event types
+    ///
+    /// Subscribing is idempotent: event types the client is already subscribed
+    /// to are left as they are and still delivered once. Returns the event
+    /// types that were newly subscribed.
+    ///
+    /// Fails without subscribing to any of `event_types` if the client would
+    /// end up with more than the maximum number of subscriptions.
     pub fn subscribe(
         &self,
         client_id: &ClientId,
         event_types: Vec<EventType>,
     ) -> Result<Vec<EventType>, String> {
         let mut subs = self.subscriptions.lock().unwrap();
+
+        let current = subs.get(client_id);
+        let count = current
+            .map(|client_sub| client_sub.count_after_subscribe(&event_types))
+            .unwrap_or_else(|| event_types.iter().collect::<HashSet<_>>().len());
+        if count > self.max_subscriptions {
+            jwarn!(
+                "Client {} exceeds the subscription limit ({} > {})",
+                client_id,
+                count,
+                self.max_subscriptions
+            );
+            return Err(format!(
+                "Subscription limit exceeded: {} subscriptions requested, at most {} allowed per client",
+                count, self.max_subscriptions
+            ));
+        }
+
         let client_sub = subs
             .entry(client_id.clone())
             .or_insert_with(|| ClientSubscription::new(self.buffer_size));
 
-        client_sub.subscribe(event_types.clone());
+        let added = client_sub.subscribe(event_types);
 
-        jinfo!("Client {} subscribed to {:?}", client_id, event_types);
+        jinfo!("Client {} subscribed to {:?}", client_id, added);
 
-        Ok(event_types)
+        Ok(added)
     }
 
     /// Unsubscribe a client from event types
@@ -151,6 +226,14 @@ impl SubscriptionManager {
         );
     }
 
+    /// Get the current subscriptions of every client
+    pub fn all_subscriptions(&self) -> HashMap<ClientId, Vec<EventType>> {
+        let subs = self.subscriptions.lock().unwrap();
+        subs.iter()
+            .map(|(client_id, client_sub)| (client_id.clone(), client_sub.get_subscriptions()))
+            .collect()
+    }
+
     /// Drain all pending notifications for a client
     pub fn drain_notifications(&self, client_id: &ClientId) -> Vec<RpcNotification> {
         let mut subs = self.subscriptions.lock().unwrap();
@@ -159,6 +242,15 @@ impl SubscriptionManager {
             .unwrap_or_default()
     }
 
+    /// Put notifications that could not be sent back at the front of a
+    /// client's buffer so they are delivered first on the next attempt
+    pub fn requeue_notifications(&self, client_id: &ClientId, notifications: Vec<RpcNotification>) {
+        let mut subs = self.subscriptions.lock().unwrap();
+        if let Some(client_sub) = subs.get_mut(client_id) {
+            client_sub.requeue_notifications(notifications);
+        }
+    }
+
     /// Remove a client (called on disconnect)
     pub fn remove_client(&self, client_id: &ClientId) {
         let mut subs = self.subscriptions.lock().unwrap();
@@ -170,11 +262,30 @@ impl SubscriptionManager {
         }
     }
 
+    /// Remove the subscriptions of every client, returning how many had any
+    pub fn clear_all(&self) -> usize {
+        let mut subs = self.subscriptions.lock().unwrap();
+        let cleared = subs.len();
+        subs.clear();
+        if cleared > 0 {
+            jinfo!("Removed subscriptions for all {} clients", cleared);
+        }
+        cleared
+    }
+
     /// Get the number of active subscribers
     pub fn subscriber_count(&self) -> usize {
         let subs = self.subscriptions.lock().unwrap();
         subs.len()
     }
+
+    /// Get the number of subscribed event types, summed over all clients
+    pub fn subscription_count(&self) -> usize {
+        let subs = self.subscriptions.lock().unwrap();
+        subs.values()
+            .map(|client_sub| client_sub.get_subscriptions().len())
+            .sum()
+    }
 }
 
 impl Default for SubscriptionManager {
@@ -202,6 +313,86 @@ mod tests {
         assert_eq!(subs.len(), 2);
     }
 
+    #[test]
+    fn test_subscribe_twice_delivers_once() {
+        let manager = SubscriptionManager::new();
+        let client_id = ClientId::from_u64(1);
+
+        assert_eq!(
+            manager
+                .subscribe(
+                    &client_id,
+                    vec![EventType::SurfaceCreated, EventType::SurfaceCreated]
+                )
+                .unwrap(),
+            vec![EventType::SurfaceCreated]
+        );
+        assert_eq!(
+            manager
+                .subscribe(
+                    &client_id,
+                    vec![EventType::SurfaceCreated, EventType::SurfaceDestroyed]
+                )
+                .unwrap(),
+            vec![EventType::SurfaceDestroyed]
+        );
+        assert!(manager
+            .subscribe(&client_id, vec![EventType::SurfaceCreated])
+            .unwrap()
+            .is_empty());
+
+        let notification = RpcNotification::new(
+            "notification".to_string(),
+            json!({"event_type": "SurfaceCreated", "surface_id": 1000}),
+        );
+        manager.queue_notification(EventType::SurfaceCreated, notification);
+
+        assert_eq!(manager.drain_notifications(&client_id).len(), 1);
+    }
+
+    #[test]
+    fn test_subscription_limit() {
+        let mut manager = SubscriptionManager::new();
+        manager.set_max_subscriptions(2);
+        let client_id = ClientId::from_u64(1);
+
+        // Duplicates and repeated subscriptions count once
+        manager
+            .subscribe(
+                &client_id,
+                vec![EventType::SurfaceCreated, EventType::SurfaceCreated],
+            )
+            .unwrap();
+        manager
+            .subscribe(
+                &client_id,
+                vec![EventType::SurfaceCreated, EventType::SurfaceDestroyed],
+            )
+            .unwrap();
+
+        let err = manager
+            .subscribe(&client_id, vec![EventType::FocusChanged])
+            .unwrap_err();
+        assert!(err.contains("limit"));
+        assert_eq!(manager.get_subscriptions(&client_id).len(), 2);
+
+        // The limit is per client
+        let other = ClientId::from_u64(2);
+        assert!(manager
+            .subscribe(&other, vec![EventType::FocusChanged])
+            .is_ok());
+        assert!(manager
+            .subscribe(
+                &other,
+                vec![
+                    EventType::LayerCreated,
+                    EventType::LayerDestroyed,
+                    EventType::LayerOpacityChanged
+                ]
+            )
+            .is_err());
+    }
+
     #[test]
     fn test_unsubscribe() {
         let manager = SubscriptionManager::new();
@@ -287,6 +478,66 @@ mod tests {
         assert_eq!(manager.get_subscriptions(&client_id).len(), 0);
     }
 
+    #[test]
+    fn test_clear_all() {
+        let manager = SubscriptionManager::new();
+
+        for id in 1..=2 {
+            manager
+                .subscribe(&ClientId::from_u64(id), vec![EventType::SurfaceCreated])
+                .unwrap();
+        }
+
+        assert_eq!(manager.clear_all(), 2);
+        assert_eq!(manager.subscriber_count(), 0);
+        assert_eq!(manager.clear_all(), 0);
+    }
+
+    #[test]
+    fn test_all_subscriptions() {
+        let manager = SubscriptionManager::new();
+        let client1 = ClientId::from_u64(1);
+        let client2 = ClientId::from_u64(2);
+
+        manager
+            .subscribe(&client1, vec![EventType::SurfaceCreated])
+            .unwrap();
+        manager
+            .subscribe(&client2, vec![EventType::LayerCreated])
+            .unwrap();
+
+        let all = manager.all_subscriptions();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[&client1], vec![EventType::SurfaceCreated]);
+        assert_eq!(all[&client2], vec![EventType::LayerCreated]);
+
+        manager.remove_client(&client1);
+        assert!(!manager.all_subscriptions().contains_key(&client1));
+    }
+
+    #[test]
+    fn test_subscription_count() {
+        let manager = SubscriptionManager::new();
+        let client1 = ClientId::from_u64(1);
+        let client2 = ClientId::from_u64(2);
+
+        assert_eq!(manager.subscription_count(), 0);
+
+        manager
+            .subscribe(&client1, vec![EventType::SurfaceCreated])
+            .unwrap();
+        manager
+            .subscribe(
+                &client2,
+                vec![EventType::LayerCreated, EventType::SurfaceCreated],
+            )
+            .unwrap();
+        assert_eq!(manager.subscription_count(), 3);
+
+        manager.remove_client(&client2);
+        assert_eq!(manager.subscription_count(), 1);
+    }
+
     #[test]
     fn test_only_subscribed_clients_receive_notifications() {
         let manager = SubscriptionManager::new();
@@ -315,4 +566,33 @@ mod tests {
         let drained2 = manager.drain_notifications(&client2);
         assert_eq!(drained2.len(), 0);
     }
+
+    #[test]
+    fn test_requeue_notifications_preserves_order() {
+        let manager = SubscriptionManager::with_buffer_size(3);
+        let client_id = ClientId::from_u64(1);
+
+        manager
+            .subscribe(&client_id, vec![EventType::SurfaceCreated])
+            .unwrap();
+
+        let make = |id: u32| {
+            RpcNotification::new(
+                "notification".to_string(),
+                json!({"event_type": "SurfaceCreated", "surface_id": id}),
+            )
+        };
+
+        manager.queue_notification(EventType::SurfaceCreated, make(1));
+        manager.queue_notification(EventType::SurfaceCreated, make(2));
+        let failed = manager.drain_notifications(&client_id);
+
+        manager.queue_notification(EventType::SurfaceCreated, make(3));
+        manager.queue_notification(EventType::SurfaceCreated, make(4));
+        manager.requeue_notifications(&client_id, failed);
+
+        // Buffer holds 3, so the oldest requeued notification is dropped
+        let drained = manager.drain_notifications(&client_id);
+        assert_eq!(drained, vec![make(2), make(3), make(4)]);
+    }
 }
@@ -1,7 +1,31 @@
 // Input validation for IVI controller operations
 
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicI32, Ordering};
 use thiserror::Error;
 
+/// Default maximum surface width and height, in pixels
+pub const DEFAULT_MAX_SIZE: i32 = 16384;
+
+/// Largest accepted distance of a coordinate from the origin, in pixels
+pub const MAX_COORDINATE: i32 = 1 << 20;
+
+/// Z-orders accepted by `set_surface_z_order`
+pub const Z_ORDER_RANGE: RangeInclusive<i32> = 0..=1000;
+
+/// Maximum width and height accepted by `validate_size`
+static MAX_SIZE: AtomicI32 = AtomicI32::new(DEFAULT_MAX_SIZE);
+
+/// Set the maximum width and height accepted by `validate_size`
+pub fn set_max_size(max: i32) {
+    MAX_SIZE.store(max, Ordering::Relaxed);
+}
+
+/// Maximum width and height accepted by `validate_size`
+pub fn max_size() -> i32 {
+    MAX_SIZE.load(Ordering::Relaxed)
+}
+
 /// Validation errors for IVI controller operations
 #[derive(Debug, Error, Clone, PartialEq)]
 pub enum ValidationError {
@@ -12,8 +36,12 @@ pub enum ValidationError {
         reason: String,
     },
 
-    #[error("Invalid size: {param} = {value}, must be positive non-zero")]
-    InvalidSize { param: String, value: i32 },
+    #[error("Invalid size: {param} = {value}, {reason}")]
+    InvalidSize {
+        param: String,
+        value: i32,
+        reason: String,
+    },
 
     #[error("Invalid opacity: {value}, must be in range [0.0, 1.0]")]
     InvalidOpacity { value: f32 },
@@ -28,27 +56,17 @@ pub enum ValidationError {
 /// Validate position coordinates
 ///
 /// Position coordinates can be negative (for off-screen positioning),
-/// but should be within reasonable bounds to prevent overflow issues.
+/// but must be within [-MAX_COORDINATE, MAX_COORDINATE] so that adding a
+/// size to them can never overflow.
 pub fn validate_position(x: i32, y: i32) -> Result<(), ValidationError> {
-    // Check for reasonable bounds to prevent overflow
-    // Using i32::MIN/2 and i32::MAX/2 as reasonable limits
-    const MIN_COORD: i32 = i32::MIN / 2;
-    const MAX_COORD: i32 = i32::MAX / 2;
-
-    if !(MIN_COORD..=MAX_COORD).contains(&x) {
-        return Err(ValidationError::InvalidPosition {
-            param: "x".to_string(),
-            value: x.to_string(),
-            reason: format!("must be in range [{}, {}]", MIN_COORD, MAX_COORD),
-        });
-    }
-
-    if !(MIN_COORD..=MAX_COORD).contains(&y) {
-        return Err(ValidationError::InvalidPosition {
-            param: "y".to_string(),
-            value: y.to_string(),
-            reason: format!("must be in range [{}, {}]", MIN_COORD, MAX_COORD),
-        });
+    for (param, value) in [("x", x), ("y", y)] {
+        if !(-MAX_COORDINATE..=MAX_COORDINATE).contains(&value) {
+            return Err(ValidationError::InvalidPosition {
+                param: param.to_string(),
+                value: value.to_string(),
+                reason: format!("must be in range [{}, {}]", -MAX_COORDINATE, MAX_COORDINATE),
+            });
+        }
     }
 
     Ok(())
@@ -56,20 +74,30 @@ pub fn validate_position(x: i32, y: i32) -> Result<(), ValidationError> {
 
 /// Validate size dimensions
 ///
-/// Size dimensions must be positive non-zero values.
+/// Size dimensions must be positive non-zero values no larger than the
+/// configured maximum (see `set_max_size`).
 pub fn validate_size(width: i32, height: i32) -> Result<(), ValidationError> {
-    if width <= 0 {
-        return Err(ValidationError::InvalidSize {
-            param: "width".to_string(),
-            value: width,
-        });
-    }
+    validate_size_with_max(width, height, max_size())
+}
 
-    if height <= 0 {
-        return Err(ValidationError::InvalidSize {
-            param: "height".to_string(),
-            value: height,
-        });
+/// Validate size dimensions against an explicit maximum
+pub fn validate_size_with_max(width: i32, height: i32, max: i32) -> Result<(), ValidationError> {
+    for (param, value) in [("width", width), ("height", height)] {
+        if value <= 0 {
+            return Err(ValidationError::InvalidSize {
+                param: param.to_string(),
+                value,
+                reason: "must be positive non-zero".to_string(),
+            });
+        }
+
+        if value > max {
+            return Err(ValidationError::InvalidSize {
+                param: param.to_string(),
+                value,
+                reason: format!("must not exceed {}", max),
+            });
+        }
     }
 
     Ok(())
@@ -133,6 +161,23 @@ mod tests {
         assert!(validate_position(0, i32::MAX).is_err());
         assert!(validate_position(i32::MIN, 0).is_err());
         assert!(validate_position(0, i32::MIN).is_err());
+        assert!(validate_position(MAX_COORDINATE + 1, 0).is_err());
+        assert!(validate_position(0, -MAX_COORDINATE - 1).is_err());
+        assert!(validate_position(MAX_COORDINATE, -MAX_COORDINATE).is_ok());
+    }
+
+    #[test]
+    fn test_validate_position_error_message() {
+        let err = validate_position(0, i32::MAX).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Invalid position: y = {}, must be in range [{}, {}]",
+                i32::MAX,
+                -MAX_COORDINATE,
+                MAX_COORDINATE
+            )
+        );
     }
 
     #[test]
@@ -149,6 +194,26 @@ mod tests {
         assert!(validate_size(-1, 100).is_err());
         assert!(validate_size(100, -1).is_err());
         assert!(validate_size(0, 0).is_err());
+        assert!(validate_size(1_000_000, 100).is_err());
+        assert!(validate_size(100, DEFAULT_MAX_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn test_validate_size_with_max() {
+        assert!(validate_size_with_max(4096, 4096, 4096).is_ok());
+        assert!(validate_size_with_max(4097, 100, 4096).is_err());
+
+        let err = validate_size_with_max(100, 5000, 4096).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid size: height = 5000, must not exceed 4096"
+        );
+
+        let err = validate_size_with_max(-5, 100, 4096).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid size: width = -5, must be positive non-zero"
+        );
     }
 
     #[test]
@@ -21,6 +21,9 @@ pub mod ivi_surface;
 pub mod weston_output_m;
 pub mod weston_surface_m;
 
+/// Bits of `ivi_layout_notification_mask`, reported in the `event_mask` of
+/// surface and layer properties
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NotificationMask {
     NoneMask,
     Opacity,
@@ -37,6 +40,13 @@ pub enum NotificationMask {
     All,
 }
 
+impl NotificationMask {
+    /// Whether this bit is set in an `event_mask`
+    pub fn is_set(self, event_mask: u32) -> bool {
+        event_mask & ivi_layout_notification_mask::from(self) != 0
+    }
+}
+
 impl From<ivi_layout_notification_mask> for NotificationMask {
     fn from(value: ivi_layout_notification_mask) -> Self {
         match value {
@@ -369,3 +379,70 @@ fn ivi_layout_get_api(
         api_ptr as *const ivi_layout_interface
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_MASKS: [NotificationMask; 13] = [
+        NotificationMask::NoneMask,
+        NotificationMask::Opacity,
+        NotificationMask::SourceRect,
+        NotificationMask::DestRect,
+        NotificationMask::Dimension,
+        NotificationMask::Position,
+        NotificationMask::Orientation,
+        NotificationMask::Visibility,
+        NotificationMask::PixelFormat,
+        NotificationMask::Add,
+        NotificationMask::Remove,
+        NotificationMask::Configure,
+        NotificationMask::All,
+    ];
+
+    #[test]
+    fn test_notification_mask_values_match_weston() {
+        // Values of enum ivi_layout_notification_mask in ivi-layout-export.h
+        let expected: [u32; 13] = [
+            0,
+            1 << 1,
+            1 << 2,
+            1 << 3,
+            1 << 4,
+            1 << 5,
+            1 << 6,
+            1 << 7,
+            1 << 8,
+            1 << 9,
+            1 << 10,
+            1 << 11,
+            0xFFFF,
+        ];
+        for (mask, value) in ALL_MASKS.iter().zip(expected) {
+            assert_eq!(
+                ivi_layout_notification_mask::from(*mask),
+                value,
+                "{:?}",
+                mask
+            );
+        }
+    }
+
+    #[test]
+    fn test_notification_mask_round_trip() {
+        for mask in ALL_MASKS {
+            let value = ivi_layout_notification_mask::from(mask);
+            assert_eq!(NotificationMask::from(value), mask);
+        }
+    }
+
+    #[test]
+    fn test_notification_mask_is_set() {
+        let event_mask = (1 << 1) | (1 << 7);
+        assert!(NotificationMask::Opacity.is_set(event_mask));
+        assert!(NotificationMask::Visibility.is_set(event_mask));
+        assert!(!NotificationMask::Orientation.is_set(event_mask));
+        assert!(!NotificationMask::NoneMask.is_set(event_mask));
+        assert!(NotificationMask::Opacity.is_set(NotificationMask::All.into()));
+    }
+}
@@ -132,6 +132,24 @@ impl IviLayer {
             .map_err(|e| e.to_string())
     }
 
+    /// Set the transition animation used for the next changes of this layer
+    pub fn set_transition(
+        &mut self,
+        transition_type: IviLayoutTransitionType,
+        duration: u32,
+    ) -> Result<(), String> {
+        self.api
+            .layer_set_transition(self, transition_type, duration)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Cancel an in-progress move animation of this layer
+    pub fn cancel_move_transition(&mut self) -> Result<(), String> {
+        self.api
+            .transition_move_layer_cancel(self)
+            .map_err(|e| e.to_string())
+    }
+
     /// Destroy this layer
     pub fn destroy(self) -> Result<(), String> {
         self.api.layer_destroy(&self).map_err(|e| e.to_string())
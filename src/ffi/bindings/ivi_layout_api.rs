@@ -7,6 +7,7 @@ use super::weston_output_m::WestonOutput;
 use super::weston_surface_m::WestonSurface;
 use super::IviLayoutTransitionType;
 use super::*;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 pub struct SurfaceSize {
     pub width: i32,
@@ -17,6 +18,8 @@ pub struct SurfaceSize {
 pub struct IviLayoutApi {
     api: *const ivi_layout_interface,
     compositor: *mut crate::ffi::weston::weston_compositor,
+    /// Successful commits made through this API
+    commits: AtomicU64,
 }
 
 // Safety: The IVI layout API is thread-safe as per Weston's design
@@ -31,6 +34,7 @@ impl IviLayoutApi {
             Some(IviLayoutApi {
                 api,
                 compositor: std::ptr::null_mut(),
+                commits: AtomicU64::new(0),
             })
         }
     }
@@ -44,10 +48,21 @@ impl IviLayoutApi {
         if api.is_null() {
             None
         } else {
-            Some(IviLayoutApi { api, compositor })
+            Some(IviLayoutApi {
+                api,
+                compositor,
+                commits: AtomicU64::new(0),
+            })
         }
     }
 
+    /// Number of successful commits made through this API
+    ///
+    /// Doubles as the sequence number of the latest commit.
+    pub fn commit_count(&self) -> u64 {
+        self.commits.load(Ordering::SeqCst)
+    }
+
     /// Commit all changes and execute all enqueued commands
     pub fn commit_changes(&self) -> Result<(), &'static str> {
         unsafe {
@@ -61,6 +76,7 @@ impl IviLayoutApi {
             let result = commit_fn();
 
             if result == IVI_SUCCEEDED {
+                self.commits.fetch_add(1, Ordering::SeqCst);
                 Ok(())
             } else {
                 Err("Failed to commit changes")
@@ -307,6 +323,34 @@ impl IviLayoutApi {
         Ok(())
     }
 
+    /// Set the orientation of a ivi surface
+    ///
+    /// Fails when the IVI layout interface of the Weston headers built
+    /// against has no `surface_set_orientation`.
+    pub fn surface_set_orientation(
+        &self,
+        surface: &IviSurface,
+        orientation: Orientation,
+    ) -> Result<(), &'static str> {
+        #[cfg(ivi_surface_set_orientation)]
+        unsafe {
+            let set_orientation_fn = (*self.api)
+                .surface_set_orientation
+                .ok_or("surface_set_orientation function is null")?;
+            let ret = set_orientation_fn(surface.handle(), orientation.into());
+            if ret != IVI_SUCCEEDED {
+                return Err("Failed to set surface orientation");
+            }
+            Ok(())
+        }
+
+        #[cfg(not(ivi_surface_set_orientation))]
+        {
+            let _ = (surface, orientation);
+            Err("surface_set_orientation is not provided by this IVI layout API")
+        }
+    }
+
     /// Set the area of a ivi surface used for rendering
     pub fn surface_set_source_rectangle(
         &self,
@@ -59,7 +59,7 @@ impl IviSurface {
             .map(|props| props.source_rectangle())
     }
 
-    /// Set surface size (destination rectangle)
+    /// Set surface source rectangle (the region of the buffer that is shown)
     pub fn set_source_rectangle(&mut self, rect: Rectangle) -> Result<(), String> {
         // Validate size
         crate::controller::validation::validate_position(rect.x, rect.y)
@@ -133,11 +133,13 @@ impl IviSurface {
             .unwrap_or(Orientation::Normal)
     }
 
-    /// Set surface orientation from degrees (not supported in current IVI API)
-    pub fn set_orientation(&mut self, degrees: i32) -> Result<(), String> {
-        // Validate to provide consistent error messages, then report unsupported
-        let _ = Orientation::from_degrees(degrees)?;
-        Err("Orientation control not supported by current IVI API".to_string())
+    /// Set surface orientation
+    ///
+    /// Fails if the IVI layout API has no `surface_set_orientation`.
+    pub fn set_orientation(&mut self, orientation: Orientation) -> Result<(), String> {
+        self.api
+            .surface_set_orientation(self, orientation)
+            .map_err(|e| e.to_string())
     }
 
     /// Set keyboard focus to this surface
@@ -184,9 +186,13 @@ impl IviSurface {
 
     /// Set surface z-order within its layer
     /// Note: Z-order is managed through layer render order in IVI shell.
-    /// This method validates the z-order value but actual implementation
-    /// requires layer context.
-    pub fn set_z_order(&mut self, z_order: i32, min: i32, max: i32) -> Result<(), String> {
+    /// The surface is moved to index `z_order` of the render order of each
+    /// layer it is on, keeping the order of the other surfaces. A z-order
+    /// beyond the layer's surface count is clamped to topmost.
+    ///
+    /// Returns the resolved index in the first layer holding the surface, or
+    /// the requested z-order if the surface is not on any layer.
+    pub fn set_z_order(&mut self, z_order: i32, min: i32, max: i32) -> Result<i32, String> {
         // Validate z-order
         crate::controller::validation::validate_z_order(z_order, min, max)
             .map_err(|e| e.to_string())?;
@@ -196,16 +202,16 @@ impl IviSurface {
             .get_layers_under_surface(self)
             .map_err(|e| e.to_string())?;
 
+        let mut resolved = None;
         for layer in layers.iter_mut() {
+            // Keep the order of the other surfaces
             let mut surfaces = layer.get_surfaces();
-            surfaces.sort_by_key(|s| s.id());
-
-            // Remove the surface if it exists
             surfaces.retain(|s| s.id() != self.id());
 
             // Insert at the desired z-order position
             let insert_index = z_order.min(surfaces.len() as i32) as usize;
             surfaces.insert(insert_index, self.clone());
+            resolved.get_or_insert(insert_index as i32);
 
             // Update layer render order
             let surface_refs: Vec<&IviSurface> = surfaces.iter().collect();
@@ -213,6 +219,46 @@ impl IviSurface {
                 .set_render_order(&surface_refs)
                 .map_err(|e| format!("Failed to set render order: {}", e))?;
         }
-        Ok(())
+        Ok(resolved.unwrap_or(z_order))
+    }
+
+    /// Move the surface to the top of the render order of every layer it is on
+    ///
+    /// Returns the IDs of those layers.
+    pub fn bring_to_front(&self) -> Result<Vec<u32>, String> {
+        self.restack(true)
+    }
+
+    /// Move the surface to the bottom of the render order of every layer it is on
+    ///
+    /// Returns the IDs of those layers.
+    pub fn send_to_back(&self) -> Result<Vec<u32>, String> {
+        self.restack(false)
+    }
+
+    fn restack(&self, to_front: bool) -> Result<Vec<u32>, String> {
+        let mut layers = self
+            .api
+            .get_layers_under_surface(self)
+            .map_err(|e| e.to_string())?;
+
+        let mut layer_ids = Vec::new();
+        for layer in layers.iter_mut() {
+            // Keep the order of the other surfaces
+            let mut surfaces = layer.get_surfaces();
+            surfaces.retain(|s| s.id() != self.id());
+            if to_front {
+                surfaces.push(self.clone());
+            } else {
+                surfaces.insert(0, self.clone());
+            }
+
+            let surface_refs: Vec<&IviSurface> = surfaces.iter().collect();
+            layer
+                .set_render_order(&surface_refs)
+                .map_err(|e| format!("Failed to set render order: {}", e))?;
+            layer_ids.push(layer.id());
+        }
+        Ok(layer_ids)
     }
 }
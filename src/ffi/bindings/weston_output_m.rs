@@ -130,4 +130,13 @@ impl WestonOutput {
     pub fn id(&self) -> u32 {
         unsafe { (*self.handle).id }
     }
+
+    /// Signal emitted by weston after each repaint of this output
+    pub fn frame_signal(&self) -> *mut wl_signal {
+        unsafe { std::ptr::addr_of_mut!((*self.handle).frame_signal) }
+    }
+
+    pub fn destroy_signal(&self) -> *mut wl_signal {
+        unsafe { std::ptr::addr_of_mut!((*self.handle).destroy_signal) }
+    }
 }
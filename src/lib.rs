@@ -47,8 +47,35 @@
 //! The plugin accepts the following command-line arguments:
 //!
 //! ## Transport Configuration
-//! - `--socket-path=<path>`: Path to the UNIX domain socket (default: /tmp/weston-ivi-controller.sock)
+//! - `--socket-path=<path>`: Path to the UNIX domain socket (default: /tmp/weston-ivi-controller.sock,
+//!   or `WESTON_IVI_DEFAULT_SOCKET_PATH` at build time); `@name` listens on a Linux abstract socket
 //! - `--max-connections=<num>`: Maximum number of client connections (default: 10)
+//! - `--allow-uid=<uid>`: Only accept clients running as this uid; repeat for several uids (default: any uid)
+//! - `--admin-uid=<uid>`: Allow clients running as this uid to call admin methods such as
+//!   `get_all_subscriptions` and `clear_subscriptions`; repeat for several uids (default: none)
+//! - `--metrics-socket=<path>`: Also serve the read-only monitoring methods on this socket (default: disabled)
+//!
+//! ## Validation Configuration
+//! - `--max-surface-size=<px>`: Largest width or height accepted for surfaces and layers (default: 16384)
+//!
+//! ## Commit Configuration
+//! - `--auto-commit-debounce-ms=<ms>`: Window in which the commits of `auto_commit` requests
+//!   are coalesced, 0 commits each request immediately (default: 0)
+//!
+//! ## Notification Configuration
+//! - `--notify-interval-ms=<ms>`: Pause between notification delivery passes, 0 polls without sleeping (default: 10)
+//! - `--max-subscriptions=<num>`: Maximum number of distinct subscriptions per client (default: the number of event types)
+//! - `--coarse-events`: Skip per-surface and per-layer property listeners, relying on the
+//!   global configure listeners only
+//!
+//! ## Logging Configuration
+//! - `--log-stderr-only`: Do not write a log file
+//! - `--log-max-size=<bytes>`: Rotate the log file above this size, 0 disables rotation (default: 10485760)
+//! - `--log-keep=<num>`: Number of rotated log files to keep (default: 3)
+//!
+//! ## Configuration File
+//! - `--config=<path>`: TOML or JSON file with any of the settings below, plus
+//!   `log_level`, `log_file` and `disabled_events` (see `docs/configuration.md`)
 //!
 //! ## ID Assignment Configuration
 //! - `--id-start=<id>`: Starting ID for auto-assignment range (default: 0x10000000, supports hex with 0x prefix)
@@ -78,6 +105,7 @@
 pub mod controller;
 pub mod error;
 pub mod ffi;
+pub mod logging;
 pub mod rpc;
 pub mod transport;
 
@@ -88,17 +116,24 @@ use jlogger_tracing::{jdebug, jerror, jinfo, jwarn, JloggerBuilder, LevelFilter,
 
 use std::env;
 use std::ffi::CStr;
+use std::fs;
 use std::panic;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use libc::{c_char, c_int, c_void};
+use serde::Deserialize;
 
-use crate::controller::notifications::NotificationType;
+use crate::controller::notifications::{NotificationData, NotificationType};
 use crate::ffi::bindings::ivi_layout_api::IviLayoutApi;
+use controller::subscriptions::DEFAULT_MAX_SUBSCRIPTIONS;
 use controller::{
-    EventContext, EventListeners, IdAssignmentConfig, IdAssignmentManager, StateManager,
+    validation, EventContext, EventListeners, IdAssignmentConfig, IdAssignmentManager, StateManager,
 };
+use logging::LogRotator;
+use rpc::handler::{DEFAULT_AUTO_COMMIT_DEBOUNCE, DEFAULT_NOTIFY_INTERVAL};
+use rpc::protocol::EventType;
 use rpc::{NotificationBridge, RpcHandler};
 #[cfg(not(feature = "enable-ipcon"))]
 use transport::{unix_socket::UnixSocketConfig, UnixSocketTransport};
@@ -110,7 +145,10 @@ use transport::ipcon::IpconTransport;
 ///
 /// This struct holds all configuration parameters for the plugin,
 /// including socket configuration and ID assignment settings.
-#[derive(Debug, Clone)]
+/// It can be loaded from a TOML or JSON file with `--config=<path>`;
+/// fields missing from the file keep their defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
 pub struct PluginConfig {
     /// Path to the UNIX domain socket
     pub socket_path: PathBuf,
@@ -118,6 +156,45 @@ pub struct PluginConfig {
     /// Maximum number of client connections
     pub max_connections: usize,
 
+    /// Uids allowed to connect over the UNIX domain socket; empty allows every uid
+    pub allowed_uids: Vec<u32>,
+
+    /// Uids allowed to call admin methods such as `get_all_subscriptions`
+    pub admin_uids: Vec<u32>,
+
+    /// Path of the read-only monitoring socket, or `None` to disable it
+    pub metrics_socket: Option<PathBuf>,
+
+    /// Log level: off, error, warn, info, debug or trace
+    pub log_level: String,
+
+    /// Log file path, or `None` to log to the console only
+    pub log_file: Option<PathBuf>,
+
+    /// Size in bytes above which the log file is rotated, or 0 to never rotate
+    pub log_max_size: u64,
+
+    /// Number of rotated log files to keep
+    pub log_keep: usize,
+
+    /// Largest width or height, in pixels, accepted for surfaces and layers
+    pub max_surface_size: i32,
+
+    /// Event types that are never delivered to clients
+    pub disabled_events: Vec<EventType>,
+
+    /// Milliseconds in which `auto_commit` commits are coalesced, 0 to commit immediately
+    pub auto_commit_debounce_ms: u64,
+
+    /// Milliseconds between notification delivery passes, 0 to poll without sleeping
+    pub notify_interval_ms: u64,
+
+    /// Maximum number of distinct subscriptions per client
+    pub max_subscriptions: usize,
+
+    /// Skip per-surface and per-layer property listeners
+    pub coarse_events: bool,
+
     /// ID assignment configuration
     pub id_assignment: IdAssignmentConfig,
 }
@@ -125,22 +202,62 @@ pub struct PluginConfig {
 impl Default for PluginConfig {
     fn default() -> Self {
         Self {
-            socket_path: PathBuf::from("/tmp/weston-ivi-controller.sock"),
+            socket_path: PathBuf::from(transport::DEFAULT_SOCKET_PATH),
             max_connections: 10,
+            allowed_uids: Vec::new(),
+            admin_uids: Vec::new(),
+            metrics_socket: None,
+            log_level: "debug".to_string(),
+            log_file: Some(PathBuf::from("/tmp/weston-ivi-controller.log")),
+            log_max_size: 10 * 1024 * 1024,
+            log_keep: 3,
+            max_surface_size: validation::DEFAULT_MAX_SIZE,
+            disabled_events: Vec::new(),
+            auto_commit_debounce_ms: DEFAULT_AUTO_COMMIT_DEBOUNCE.as_millis() as u64,
+            notify_interval_ms: DEFAULT_NOTIFY_INTERVAL.as_millis() as u64,
+            max_subscriptions: DEFAULT_MAX_SUBSCRIPTIONS,
+            coarse_events: false,
             id_assignment: IdAssignmentConfig::default(),
         }
     }
 }
 
 impl PluginConfig {
+    /// Load the plugin configuration from a file
+    ///
+    /// Files ending in `.toml` are parsed as TOML, anything else as JSON.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the configuration file
+    ///
+    /// # Returns
+    /// * `Ok(PluginConfig)` - Configuration with file values applied over the defaults
+    /// * `Err(String)` - The file could not be read or parsed
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content)
+                .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))
+        } else {
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))
+        }
+    }
+
     /// Validate the plugin configuration
     ///
     /// # Returns
     /// * `Ok(())` - Configuration is valid
     /// * `Err(String)` - Configuration is invalid with error message
     pub fn validate(&self) -> Result<(), String> {
-        // Validate socket path
-        if let Some(parent) = self.socket_path.parent() {
+        // Validate socket path; abstract sockets have no directory
+        let socket_dir = match transport::abstract_socket_name(&self.socket_path) {
+            Some(_) => None,
+            None => self.socket_path.parent(),
+        };
+        if let Some(parent) = socket_dir {
             if !parent.exists() {
                 return Err(format!(
                     "Socket directory does not exist: {}",
@@ -158,6 +275,28 @@ impl PluginConfig {
             return Err("max_connections should not exceed 1000".to_string());
         }
 
+        // Validate maximum surface size
+        if self.max_surface_size <= 0 {
+            return Err("max_surface_size must be greater than 0".to_string());
+        }
+
+        if self.max_subscriptions == 0 {
+            return Err("max_subscriptions must be greater than 0".to_string());
+        }
+
+        // A client cannot hold more subscriptions than there are event types
+        if self.max_subscriptions > EventType::ALL.len() {
+            return Err(format!(
+                "max_subscriptions should not exceed {}, the number of event types",
+                EventType::ALL.len()
+            ));
+        }
+
+        // Validate log level
+        if !["off", "error", "warn", "info", "debug", "trace"].contains(&self.log_level.as_str()) {
+            return Err(format!("Invalid log_level: {}", self.log_level));
+        }
+
         // Validate ID assignment configuration
         self.id_assignment
             .validate()
@@ -182,6 +321,9 @@ struct PluginState {
     // Plugin configuration for cleanup reference
     #[allow(dead_code)]
     config: PluginConfig,
+    // Kept alive to keep rotating the log file
+    #[allow(dead_code)]
+    log_rotator: Option<LogRotator>,
 }
 
 // Safety: PluginState is used in a single-threaded Weston plugin context.
@@ -282,21 +424,30 @@ pub unsafe extern "C" fn wet_module_init(
     argc: c_int,
     argv: *const *const c_char,
 ) -> c_int {
+    // Parse command-line arguments, config file and environment variables
+    // first, as they decide where and how much to log
+    let config = parse_plugin_config(argc, argv);
+
     // Initialize logging
-    JloggerBuilder::new()
-        .log_console(true)
-        .log_file(Some(("/tmp/weston-ivi-controller.log", false)))
-        .log_time(LogTimeFormat::TimeStamp)
-        .max_level(LevelFilter::DEBUG)
-        .build();
+    let log_rotator = init_logging(&config.as_ref().cloned().unwrap_or_default());
+
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            jerror!("Invalid plugin configuration: {}", e);
+            return -1;
+        }
+    };
 
     // Catch panics to prevent unwinding across FFI boundary
-    let result = panic::catch_unwind(|| {
-        plugin_init_impl(compositor as *mut ffi::weston_compositor, argc, argv)
+    let result = panic::catch_unwind(move || {
+        plugin_init_impl(compositor as *mut ffi::weston_compositor, config)
     });
 
     match result {
-        Ok(Ok((state, compositor_ptr))) => {
+        Ok(Ok((mut state, compositor_ptr))) => {
+            state.log_rotator = log_rotator;
+
             // Store plugin state in global
             *PLUGIN_STATE.lock().unwrap() = Some(Box::new(state));
 
@@ -345,6 +496,34 @@ pub unsafe extern "C" fn wet_module_init(
     }
 }
 
+/// Initialize logging to the console and, if configured, the log file
+///
+/// The log left by a previous run is rotated rather than overwritten, and a
+/// `LogRotator` is returned to keep the file within `log_max_size` while the
+/// plugin runs.
+fn init_logging(config: &PluginConfig) -> Option<LogRotator> {
+    let log_file = config.log_file.as_deref().and_then(|p| p.to_str());
+    let rotate = log_file.is_some() && config.log_max_size > 0;
+
+    if let (Some(path), true) = (log_file, rotate) {
+        let _ = logging::rotate_log_file(Path::new(path), config.log_keep);
+    }
+
+    JloggerBuilder::new()
+        .log_console(true)
+        .log_file(log_file.map(|p| (p, false)))
+        .log_time(LogTimeFormat::TimeStamp)
+        .max_level(LevelFilter::from(config.log_level.clone()))
+        .build();
+
+    if !rotate {
+        return None;
+    }
+
+    log_file
+        .map(|path| LogRotator::start(PathBuf::from(path), config.log_max_size, config.log_keep))
+}
+
 /// Internal implementation of plugin initialization
 ///
 /// # Safety
@@ -357,14 +536,10 @@ pub unsafe extern "C" fn wet_module_init(
 /// Returns (PluginState, compositor pointer) on success
 unsafe fn plugin_init_impl(
     compositor: *mut ffi::weston_compositor,
-    argc: c_int,
-    argv: *const *const c_char,
+    config: PluginConfig,
 ) -> Result<(PluginState, *mut ffi::weston_compositor), String> {
     jinfo!("Weston IVI Controller plugin initializing...");
 
-    // Parse command-line arguments and environment variables
-    let config = parse_plugin_config(argc, argv);
-
     // Validate configuration
     if let Err(e) = config.validate() {
         jerror!("Invalid plugin configuration: {}", e);
@@ -375,7 +550,13 @@ unsafe fn plugin_init_impl(
     {
         jinfo!("Using socket path: {:?}", config.socket_path);
         jinfo!("Max connections: {}", config.max_connections);
+        if !config.allowed_uids.is_empty() {
+            jinfo!("Allowed uids: {:?}", config.allowed_uids);
+        }
     }
+    jinfo!("Max surface size: {}", config.max_surface_size);
+    validation::set_max_size(config.max_surface_size);
+
     jinfo!(
         "ID assignment range: {:#x} - {:#x}",
         config.id_assignment.start_id,
@@ -442,6 +623,7 @@ unsafe fn plugin_init_impl(
         let transport_config = UnixSocketConfig {
             socket_path: config.socket_path.clone(),
             max_connections: config.max_connections,
+            allowed_uids: config.allowed_uids.clone(),
         };
 
         let transport = Box::new(UnixSocketTransport::new(transport_config));
@@ -451,6 +633,29 @@ unsafe fn plugin_init_impl(
         })?;
 
         jinfo!("UnixDomainSocket Transport registered");
+
+        if let Some(metrics_socket) = &config.metrics_socket {
+            let metrics_config = UnixSocketConfig {
+                socket_path: metrics_socket.clone(),
+                max_connections: config.max_connections,
+                allowed_uids: Vec::new(),
+            };
+
+            let transport = Box::new(UnixSocketTransport::new(metrics_config));
+            rpc_handler
+                .register_metrics_transport(transport)
+                .map_err(|e| {
+                    jerror!("Failed to register monitoring transport: {:?}", e);
+                    format!("Failed to register monitoring transport: {:?}", e)
+                })?;
+
+            jinfo!("Monitoring socket registered at {:?}", metrics_socket);
+        }
+    }
+
+    #[cfg(feature = "enable-ipcon")]
+    if config.metrics_socket.is_some() {
+        jwarn!("--metrics-socket is not supported with the IPCon transport, ignoring");
     }
 
     // Create ID assignment manager with parsed configuration
@@ -462,11 +667,14 @@ unsafe fn plugin_init_impl(
     jinfo!("ID assignment manager created");
 
     // Register IVI event listeners
-    let event_context = Arc::new(EventContext::new(
-        Arc::clone(&state_manager),
-        Arc::clone(&ivi_api),
-        Arc::clone(&id_assignment_manager),
-    ));
+    let event_context = Arc::new(
+        EventContext::new(
+            Arc::clone(&state_manager),
+            Arc::clone(&ivi_api),
+            Arc::clone(&id_assignment_manager),
+        )
+        .with_coarse_events(config.coarse_events),
+    );
 
     let event_listeners = Arc::clone(&event_context)
         .register_listeners()
@@ -477,8 +685,12 @@ unsafe fn plugin_init_impl(
 
     jinfo!("Event listeners registered");
 
+    if config.coarse_events {
+        jinfo!("Coarse events: per-surface and per-layer property listeners are disabled");
+    }
+
     // Register per-surface property listeners for existing surfaces
-    unsafe {
+    if !config.coarse_events {
         let existing_ids: Vec<u32> = {
             state_manager
                 .lock()
@@ -489,24 +701,38 @@ unsafe fn plugin_init_impl(
                 .collect()
         };
         for id in existing_ids {
-            let _ = event_context.register_surface_property_listener_by_id(id);
+            let _ = unsafe { event_context.register_surface_property_listener_by_id(id) };
         }
     }
 
     // Register per-layer property listeners for existing layers
-    unsafe {
+    if !config.coarse_events {
         let existing_layer_ids: Vec<u32> = {
             let layers = ivi_api.get_layers()?;
             layers.iter().map(|l| l.id()).collect()
         };
         for id in existing_layer_ids {
-            let _ = event_context.register_layer_property_listener_by_id(id);
+            let _ = unsafe { event_context.register_layer_property_listener_by_id(id) };
         }
     }
 
     // Bridge notifications -> subscriptions, and register callbacks
     {
-        let bridge = Arc::new(NotificationBridge::new(rpc_handler.subscription_manager()));
+        if !config.disabled_events.is_empty() {
+            jinfo!("Disabled events: {:?}", config.disabled_events);
+        }
+        rpc_handler.set_disabled_events(&config.disabled_events);
+        rpc_handler.set_max_subscriptions(config.max_subscriptions);
+        rpc_handler.set_coarse_events(config.coarse_events);
+        if !config.admin_uids.is_empty() {
+            jinfo!("Admin uids: {:?}", config.admin_uids);
+        }
+        rpc_handler.set_admin_uids(&config.admin_uids);
+
+        let bridge = Arc::new(NotificationBridge::with_disabled_events(
+            rpc_handler.subscription_manager(),
+            config.disabled_events.clone(),
+        ));
 
         let notification_manager_arc = {
             // Get Arc<Mutex<NotificationManager>> from state manager
@@ -541,6 +767,25 @@ unsafe fn plugin_init_impl(
         register(NotificationType::LayerDestroyed);
         register(NotificationType::LayerVisibilityChanged);
         register(NotificationType::LayerOpacityChanged);
+        // Output notifications
+        register(NotificationType::RepaintComplete);
+        // Input panel notifications
+        register(NotificationType::InputPanelShown);
+        register(NotificationType::InputPanelHidden);
+
+        // Count repaints for get_metrics. The handler owns the notification
+        // manager, so the callback only holds a weak reference to it.
+        let handler = Arc::downgrade(&rpc_handler);
+        nm.register_callback(
+            NotificationType::RepaintComplete,
+            Arc::new(move |n| {
+                if let (Some(handler), NotificationData::RepaintComplete { output_name, .. }) =
+                    (handler.upgrade(), &n.data)
+                {
+                    handler.record_repaint(output_name);
+                }
+            }),
+        );
     }
 
     // Start the transport
@@ -549,8 +794,14 @@ unsafe fn plugin_init_impl(
         format!("Failed to start transport: {:?}", e)
     })?;
 
+    // Coalesce the commits of rapid auto_commit requests
+    rpc_handler.start_auto_commit_debounce(Duration::from_millis(config.auto_commit_debounce_ms));
+
     // Start background notification delivery to subscribed clients
-    rpc_handler.start_notification_delivery();
+    if config.notify_interval_ms == 0 {
+        jwarn!("Notification delivery polls without sleeping and keeps a CPU core busy");
+    }
+    rpc_handler.start_notification_delivery(Duration::from_millis(config.notify_interval_ms));
 
     jinfo!("Transport started");
     jinfo!("Weston IVI Controller plugin initialized successfully");
@@ -562,15 +813,18 @@ unsafe fn plugin_init_impl(
             id_assignment_manager,
             event_listeners: Some(event_listeners),
             config,
+            log_rotator: None,
         },
         compositor, // Return compositor pointer for destroy listener registration
     ))
 }
 
-/// Parse plugin configuration from command-line arguments and environment variables
+/// Parse plugin configuration from command-line arguments, a config file and
+/// environment variables
 ///
-/// This function parses both command-line arguments and environment variables
-/// to build the complete plugin configuration, including ID assignment settings.
+/// Values are applied in order of increasing precedence: built-in defaults,
+/// the file given with `--config`, environment variables, then the remaining
+/// command-line arguments.
 ///
 /// # Safety
 /// This function is unsafe because it dereferences raw pointers from C
@@ -580,9 +834,16 @@ unsafe fn plugin_init_impl(
 /// * `argv` - Array of command-line argument strings
 ///
 /// # Returns
-/// A `PluginConfig` struct with parsed configuration values
-unsafe fn parse_plugin_config(argc: c_int, argv: *const *const c_char) -> PluginConfig {
-    let mut config = PluginConfig::default();
+/// * `Ok(PluginConfig)` - Parsed configuration values
+/// * `Err(String)` - The config file could not be read or parsed
+unsafe fn parse_plugin_config(
+    argc: c_int,
+    argv: *const *const c_char,
+) -> Result<PluginConfig, String> {
+    let mut config = match find_config_path(argc, argv) {
+        Some(path) => PluginConfig::from_file(&path)?,
+        None => PluginConfig::default(),
+    };
 
     // Parse environment variables first (command-line args will override them)
     parse_environment_config(&mut config);
@@ -608,6 +869,44 @@ unsafe fn parse_plugin_config(argc: c_int, argv: *const *const c_char) -> Plugin
                 let path = arg.strip_prefix("--socket-path=").unwrap();
                 config.socket_path = PathBuf::from(path.to_string());
             }
+            // Log to stderr only
+            else if arg == "--log-stderr-only" {
+                config.log_file = None;
+            }
+            // Property listeners
+            else if arg == "--coarse-events" {
+                config.coarse_events = true;
+            }
+            // Log rotation size
+            else if arg == "--log-max-size" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let value = CStr::from_ptr(value_ptr).to_string_lossy();
+                    if let Ok(max_size) = value.parse::<u64>() {
+                        config.log_max_size = max_size;
+                    }
+                }
+            } else if arg.starts_with("--log-max-size=") {
+                let value = arg.strip_prefix("--log-max-size=").unwrap();
+                if let Ok(max_size) = value.parse::<u64>() {
+                    config.log_max_size = max_size;
+                }
+            }
+            // Number of rotated log files to keep
+            else if arg == "--log-keep" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let value = CStr::from_ptr(value_ptr).to_string_lossy();
+                    if let Ok(keep) = value.parse::<usize>() {
+                        config.log_keep = keep;
+                    }
+                }
+            } else if arg.starts_with("--log-keep=") {
+                let value = arg.strip_prefix("--log-keep=").unwrap();
+                if let Ok(keep) = value.parse::<usize>() {
+                    config.log_keep = keep;
+                }
+            }
             // Max connections configuration
             else if arg == "--max-connections" && i + 1 < argc as isize {
                 let value_ptr = *argv.offset(i + 1);
@@ -623,6 +922,107 @@ unsafe fn parse_plugin_config(argc: c_int, argv: *const *const c_char) -> Plugin
                     config.max_connections = max_conn;
                 }
             }
+            // Uid allowlist, may be given several times
+            else if arg == "--allow-uid" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let value = CStr::from_ptr(value_ptr).to_string_lossy();
+                    if let Ok(uid) = value.parse::<u32>() {
+                        config.allowed_uids.push(uid);
+                    }
+                }
+            } else if arg.starts_with("--allow-uid=") {
+                let value = arg.strip_prefix("--allow-uid=").unwrap();
+                if let Ok(uid) = value.parse::<u32>() {
+                    config.allowed_uids.push(uid);
+                }
+            }
+            // Admin uids, may be given several times
+            else if arg == "--admin-uid" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let value = CStr::from_ptr(value_ptr).to_string_lossy();
+                    if let Ok(uid) = value.parse::<u32>() {
+                        config.admin_uids.push(uid);
+                    }
+                }
+            } else if arg.starts_with("--admin-uid=") {
+                let value = arg.strip_prefix("--admin-uid=").unwrap();
+                if let Ok(uid) = value.parse::<u32>() {
+                    config.admin_uids.push(uid);
+                }
+            }
+            // Maximum surface size
+            else if arg == "--max-surface-size" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let value = CStr::from_ptr(value_ptr).to_string_lossy();
+                    if let Ok(max_size) = value.parse::<i32>() {
+                        config.max_surface_size = max_size;
+                    }
+                }
+            } else if arg.starts_with("--max-surface-size=") {
+                let value = arg.strip_prefix("--max-surface-size=").unwrap();
+                if let Ok(max_size) = value.parse::<i32>() {
+                    config.max_surface_size = max_size;
+                }
+            }
+            // Auto-commit debounce window
+            else if arg == "--auto-commit-debounce-ms" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let value = CStr::from_ptr(value_ptr).to_string_lossy();
+                    if let Ok(window) = value.parse::<u64>() {
+                        config.auto_commit_debounce_ms = window;
+                    }
+                }
+            } else if arg.starts_with("--auto-commit-debounce-ms=") {
+                let value = arg.strip_prefix("--auto-commit-debounce-ms=").unwrap();
+                if let Ok(window) = value.parse::<u64>() {
+                    config.auto_commit_debounce_ms = window;
+                }
+            }
+            // Notification delivery interval
+            else if arg == "--notify-interval-ms" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let value = CStr::from_ptr(value_ptr).to_string_lossy();
+                    if let Ok(interval) = value.parse::<u64>() {
+                        config.notify_interval_ms = interval;
+                    }
+                }
+            } else if arg.starts_with("--notify-interval-ms=") {
+                let value = arg.strip_prefix("--notify-interval-ms=").unwrap();
+                if let Ok(interval) = value.parse::<u64>() {
+                    config.notify_interval_ms = interval;
+                }
+            }
+            // Subscription limit
+            else if arg == "--max-subscriptions" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let value = CStr::from_ptr(value_ptr).to_string_lossy();
+                    if let Ok(max) = value.parse::<usize>() {
+                        config.max_subscriptions = max;
+                    }
+                }
+            } else if arg.starts_with("--max-subscriptions=") {
+                let value = arg.strip_prefix("--max-subscriptions=").unwrap();
+                if let Ok(max) = value.parse::<usize>() {
+                    config.max_subscriptions = max;
+                }
+            }
+            // Read-only monitoring socket
+            else if arg == "--metrics-socket" && i + 1 < argc as isize {
+                let value_ptr = *argv.offset(i + 1);
+                if !value_ptr.is_null() {
+                    let path = CStr::from_ptr(value_ptr).to_string_lossy();
+                    config.metrics_socket = Some(PathBuf::from(path.into_owned()));
+                }
+            } else if arg.starts_with("--metrics-socket=") {
+                let path = arg.strip_prefix("--metrics-socket=").unwrap();
+                config.metrics_socket = Some(PathBuf::from(path.to_string()));
+            }
             // ID assignment start ID
             else if arg == "--id-start" && i + 1 < argc as isize {
                 let value_ptr = *argv.offset(i + 1);
@@ -716,7 +1116,38 @@ unsafe fn parse_plugin_config(argc: c_int, argv: *const *const c_char) -> Plugin
         }
     }
 
-    config
+    Ok(config)
+}
+
+/// Find the config file path given with `--config` in the command-line arguments
+///
+/// # Safety
+/// This function is unsafe because it dereferences raw pointers from C
+unsafe fn find_config_path(argc: c_int, argv: *const *const c_char) -> Option<PathBuf> {
+    if argv.is_null() {
+        return None;
+    }
+
+    for i in 0..argc as isize {
+        let arg_ptr = *argv.offset(i);
+        if arg_ptr.is_null() {
+            continue;
+        }
+
+        let arg = CStr::from_ptr(arg_ptr).to_string_lossy();
+
+        if arg == "--config" && i + 1 < argc as isize {
+            let path_ptr = *argv.offset(i + 1);
+            if !path_ptr.is_null() {
+                let path = CStr::from_ptr(path_ptr).to_string_lossy();
+                return Some(PathBuf::from(path.into_owned()));
+            }
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path.to_string()));
+        }
+    }
+
+    None
 }
 
 /// Parse environment variables for plugin configuration
@@ -727,55 +1158,63 @@ unsafe fn parse_plugin_config(argc: c_int, argv: *const *const c_char) -> Plugin
 /// # Arguments
 /// * `config` - Mutable reference to the configuration to update
 fn parse_environment_config(config: &mut PluginConfig) {
+    apply_environment_config(config, |name| env::var(name).ok());
+}
+
+/// Apply the `WESTON_IVI_*` variables looked up by `var` to the configuration
+///
+/// Split from `parse_environment_config` so tests can supply the variables
+/// without touching the process environment.
+fn apply_environment_config<F: Fn(&str) -> Option<String>>(config: &mut PluginConfig, var: F) {
     // Socket path
-    if let Ok(socket_path) = env::var("WESTON_IVI_SOCKET_PATH") {
+    if let Some(socket_path) = var("WESTON_IVI_SOCKET_PATH") {
         config.socket_path = PathBuf::from(socket_path);
     }
 
     // Max connections
-    if let Ok(max_conn_str) = env::var("WESTON_IVI_MAX_CONNECTIONS") {
+    if let Some(max_conn_str) = var("WESTON_IVI_MAX_CONNECTIONS") {
         if let Ok(max_conn) = max_conn_str.parse::<usize>() {
             config.max_connections = max_conn;
         }
     }
 
     // ID assignment start ID
-    if let Ok(start_id_str) = env::var("WESTON_IVI_ID_START") {
+    if let Some(start_id_str) = var("WESTON_IVI_ID_START") {
         if let Ok(start_id) = parse_hex_or_decimal(&start_id_str) {
             config.id_assignment.start_id = start_id;
         }
     }
 
     // ID assignment max ID
-    if let Ok(max_id_str) = env::var("WESTON_IVI_ID_MAX") {
+    if let Some(max_id_str) = var("WESTON_IVI_ID_MAX") {
         if let Ok(max_id) = parse_hex_or_decimal(&max_id_str) {
             config.id_assignment.max_id = max_id;
         }
     }
 
     // ID assignment invalid ID
-    if let Ok(invalid_id_str) = env::var("WESTON_IVI_ID_INVALID") {
+    if let Some(invalid_id_str) = var("WESTON_IVI_ID_INVALID") {
         if let Ok(invalid_id) = parse_hex_or_decimal(&invalid_id_str) {
             config.id_assignment.invalid_id = invalid_id;
         }
     }
 
     // ID assignment lock timeout
-    if let Ok(timeout_str) = env::var("WESTON_IVI_ID_LOCK_TIMEOUT") {
+    if let Some(timeout_str) = var("WESTON_IVI_ID_LOCK_TIMEOUT") {
         if let Ok(timeout) = timeout_str.parse::<u64>() {
             config.id_assignment.lock_timeout_ms = timeout;
         }
     }
 
     // ID assignment max concurrent assignments
-    if let Ok(max_concurrent_str) = env::var("WESTON_IVI_ID_MAX_CONCURRENT") {
+    if let Some(max_concurrent_str) = var("WESTON_IVI_ID_MAX_CONCURRENT") {
         if let Ok(max_concurrent) = max_concurrent_str.parse::<usize>() {
             config.id_assignment.max_concurrent_assignments = max_concurrent;
         }
     }
 
     // ID assignment operation timeout
-    if let Ok(timeout_str) = env::var("WESTON_IVI_ID_ASSIGNMENT_TIMEOUT") {
+    if let Some(timeout_str) = var("WESTON_IVI_ID_ASSIGNMENT_TIMEOUT") {
         if let Ok(timeout) = timeout_str.parse::<u64>() {
             config.id_assignment.assignment_timeout_ms = timeout;
         }
@@ -837,7 +1276,7 @@ mod tests {
         let config = PluginConfig::default();
         assert_eq!(
             config.socket_path,
-            PathBuf::from("/tmp/weston-ivi-controller.sock")
+            PathBuf::from(transport::DEFAULT_SOCKET_PATH)
         );
         assert_eq!(config.max_connections, 10);
         assert_eq!(config.id_assignment.start_id, 0x10000000);
@@ -864,6 +1303,19 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_plugin_config_validation_socket_path() {
+        let mut config = PluginConfig {
+            socket_path: PathBuf::from("/nonexistent-dir/ivi.sock"),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        // Abstract sockets have no directory to check
+        config.socket_path = PathBuf::from("@weston-ivi-controller");
+        assert!(config.validate().is_ok());
+    }
+
     #[test]
     fn test_parse_hex_or_decimal() {
         assert_eq!(parse_hex_or_decimal("42").unwrap(), 42);
@@ -876,10 +1328,10 @@ mod tests {
     #[test]
     fn test_parse_plugin_config_defaults() {
         unsafe {
-            let config = parse_plugin_config(0, std::ptr::null());
+            let config = parse_plugin_config(0, std::ptr::null()).unwrap();
             assert_eq!(
                 config.socket_path,
-                PathBuf::from("/tmp/weston-ivi-controller.sock")
+                PathBuf::from(transport::DEFAULT_SOCKET_PATH)
             );
             assert_eq!(config.max_connections, 10);
             assert_eq!(config.id_assignment.start_id, 0x10000000);
@@ -902,7 +1354,7 @@ mod tests {
                 id_max_arg.as_ptr(),
             ];
 
-            let config = parse_plugin_config(args.len() as i32, args.as_ptr());
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
 
             assert_eq!(config.socket_path, PathBuf::from("/tmp/test.sock"));
             assert_eq!(config.max_connections, 5);
@@ -911,23 +1363,250 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_plugin_config_from_file() {
+        let path = env::temp_dir().join(format!("ivi-controller-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            r#"
+max_connections = 20
+log_level = "info"
+disabled_events = ["SurfaceCreated"]
+
+[id_assignment]
+start_id = 0x20000000
+"#,
+        )
+        .unwrap();
+
+        unsafe {
+            let config_arg = CString::new(format!("--config={}", path.display())).unwrap();
+            let max_conn_arg = CString::new("--max-connections=5").unwrap();
+
+            // File values override defaults
+            let args = [config_arg.as_ptr()];
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+            assert_eq!(config.max_connections, 20);
+            assert_eq!(config.log_level, "info");
+            assert_eq!(config.disabled_events, vec![EventType::SurfaceCreated]);
+            assert_eq!(config.id_assignment.start_id, 0x20000000);
+            assert_eq!(config.id_assignment.max_id, 0xFFFFFFFE);
+
+            // Explicit arguments override the file
+            let args = [config_arg.as_ptr(), max_conn_arg.as_ptr()];
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+            assert_eq!(config.max_connections, 5);
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_plugin_config_from_json_file() {
+        let path = env::temp_dir().join(format!("ivi-controller-{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{"socket_path": "/tmp/json.sock", "log_file": null}"#,
+        )
+        .unwrap();
+
+        let config = PluginConfig::from_file(&path).unwrap();
+        assert_eq!(config.socket_path, PathBuf::from("/tmp/json.sock"));
+        assert_eq!(config.log_file, None);
+        assert_eq!(config.max_connections, 10);
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(PluginConfig::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_plugin_config_validation_invalid_log_level() {
+        let config = PluginConfig {
+            log_level: "verbose".to_string(),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_plugin_config_logging_args() {
+        unsafe {
+            let stderr_only_arg = CString::new("--log-stderr-only").unwrap();
+            let max_size_arg = CString::new("--log-max-size=4096").unwrap();
+            let keep_arg = CString::new("--log-keep=5").unwrap();
+
+            let args = [
+                stderr_only_arg.as_ptr(),
+                max_size_arg.as_ptr(),
+                keep_arg.as_ptr(),
+            ];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+
+            assert_eq!(config.log_file, None);
+            assert_eq!(config.log_max_size, 4096);
+            assert_eq!(config.log_keep, 5);
+        }
+    }
+
+    #[test]
+    fn test_parse_plugin_config_allow_uid() {
+        unsafe {
+            let first_arg = CString::new("--allow-uid=1000").unwrap();
+            let flag_arg = CString::new("--allow-uid").unwrap();
+            let value_arg = CString::new("1001").unwrap();
+
+            let args = [first_arg.as_ptr(), flag_arg.as_ptr(), value_arg.as_ptr()];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+
+            assert_eq!(config.allowed_uids, vec![1000, 1001]);
+        }
+    }
+
+    #[test]
+    fn test_parse_plugin_config_admin_uid() {
+        unsafe {
+            let first_arg = CString::new("--admin-uid=0").unwrap();
+            let flag_arg = CString::new("--admin-uid").unwrap();
+            let value_arg = CString::new("1000").unwrap();
+
+            let args = [first_arg.as_ptr(), flag_arg.as_ptr(), value_arg.as_ptr()];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+
+            assert_eq!(config.admin_uids, vec![0, 1000]);
+            assert!(config.allowed_uids.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_parse_plugin_config_max_surface_size() {
+        unsafe {
+            let max_size_arg = CString::new("--max-surface-size=4096").unwrap();
+            let args = [max_size_arg.as_ptr()];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+
+            assert_eq!(config.max_surface_size, 4096);
+        }
+
+        let config = PluginConfig {
+            max_surface_size: 0,
+            ..PluginConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_plugin_config_notify_interval() {
+        unsafe {
+            let config = parse_plugin_config(0, std::ptr::null()).unwrap();
+            assert_eq!(config.notify_interval_ms, 10);
+
+            let interval_arg = CString::new("--notify-interval-ms=0").unwrap();
+            let args = [interval_arg.as_ptr()];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+
+            assert_eq!(config.notify_interval_ms, 0);
+        }
+    }
+
+    #[test]
+    fn test_parse_plugin_config_auto_commit_debounce() {
+        unsafe {
+            let config = parse_plugin_config(0, std::ptr::null()).unwrap();
+            assert_eq!(config.auto_commit_debounce_ms, 0);
+
+            let flag = CString::new("--auto-commit-debounce-ms").unwrap();
+            let value = CString::new("4").unwrap();
+            let args = [flag.as_ptr(), value.as_ptr()];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+            assert_eq!(config.auto_commit_debounce_ms, 4);
+        }
+    }
+
+    #[test]
+    fn test_parse_plugin_config_max_subscriptions() {
+        unsafe {
+            let config = parse_plugin_config(0, std::ptr::null()).unwrap();
+            assert_eq!(config.max_subscriptions, DEFAULT_MAX_SUBSCRIPTIONS);
+
+            let flag = CString::new("--max-subscriptions").unwrap();
+            let value = CString::new("4").unwrap();
+            let args = [flag.as_ptr(), value.as_ptr()];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+            assert_eq!(config.max_subscriptions, 4);
+        }
+
+        let config = PluginConfig {
+            max_subscriptions: 0,
+            ..PluginConfig::default()
+        };
+        assert!(config.validate().is_err());
+
+        // The default allows every event type, and more can never be used
+        assert_eq!(DEFAULT_MAX_SUBSCRIPTIONS, EventType::ALL.len());
+        let config = PluginConfig {
+            max_subscriptions: EventType::ALL.len() + 1,
+            ..PluginConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_plugin_config_metrics_socket() {
+        unsafe {
+            let config = parse_plugin_config(0, std::ptr::null()).unwrap();
+            assert_eq!(config.metrics_socket, None);
+
+            let metrics_arg = CString::new("--metrics-socket=/tmp/metrics.sock").unwrap();
+            let args = [metrics_arg.as_ptr()];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+
+            assert_eq!(
+                config.metrics_socket,
+                Some(PathBuf::from("/tmp/metrics.sock"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_plugin_config_coarse_events() {
+        unsafe {
+            let config = parse_plugin_config(0, std::ptr::null()).unwrap();
+            assert!(!config.coarse_events);
+
+            let flag = CString::new("--coarse-events").unwrap();
+            let args = [flag.as_ptr()];
+
+            let config = parse_plugin_config(args.len() as i32, args.as_ptr()).unwrap();
+            assert!(config.coarse_events);
+        }
+    }
+
     #[test]
     fn test_parse_environment_config() {
-        // Set test environment variables
-        env::set_var("WESTON_IVI_SOCKET_PATH", "/tmp/env-test.sock");
-        env::set_var("WESTON_IVI_MAX_CONNECTIONS", "15");
-        env::set_var("WESTON_IVI_ID_START", "0x40000000");
+        // The process environment is shared with the tests running in
+        // parallel, so the variables are supplied directly
+        let vars = std::collections::HashMap::from([
+            ("WESTON_IVI_SOCKET_PATH", "/tmp/env-test.sock"),
+            ("WESTON_IVI_MAX_CONNECTIONS", "15"),
+            ("WESTON_IVI_ID_START", "0x40000000"),
+        ]);
 
         let mut config = PluginConfig::default();
-        parse_environment_config(&mut config);
+        apply_environment_config(&mut config, |name| {
+            vars.get(name).map(|value| value.to_string())
+        });
 
         assert_eq!(config.socket_path, PathBuf::from("/tmp/env-test.sock"));
         assert_eq!(config.max_connections, 15);
         assert_eq!(config.id_assignment.start_id, 0x40000000);
-
-        // Clean up environment variables
-        env::remove_var("WESTON_IVI_SOCKET_PATH");
-        env::remove_var("WESTON_IVI_MAX_CONNECTIONS");
-        env::remove_var("WESTON_IVI_ID_START");
     }
 }
@@ -0,0 +1,165 @@
+//! Size-based rotation of the plugin log file
+//!
+//! The logger owns its file handle, so rotation uses the copy-and-truncate
+//! approach: the log is copied to `<file>.1` (shifting older copies up to
+//! `<file>.<keep>`) and then truncated in place. The logger opens the file in
+//! append mode, so it keeps writing from the start of the truncated file.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How often the log file size is checked
+const ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Path of the `index`-th rotated copy of a log file
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Rotate a log file, keeping at most `keep` rotated copies
+///
+/// `<file>.1` is the most recent copy. With `keep == 0` the log is only
+/// truncated. Does nothing if the log file does not exist.
+pub fn rotate_log_file(path: &Path, keep: usize) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    if keep > 0 {
+        let _ = fs::remove_file(rotated_path(path, keep));
+        for index in (1..keep).rev() {
+            let from = rotated_path(path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(path, index + 1))?;
+            }
+        }
+        fs::copy(path, rotated_path(path, 1))?;
+    }
+
+    fs::OpenOptions::new().write(true).open(path)?.set_len(0)
+}
+
+/// Background thread that rotates the log file once it exceeds a size limit
+///
+/// The thread stops when the rotator is dropped.
+pub struct LogRotator {
+    stop_flag: Arc<AtomicBool>,
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl LogRotator {
+    /// Start watching `path`, rotating it whenever it grows beyond `max_size` bytes
+    pub fn start(path: PathBuf, max_size: u64, keep: usize) -> Self {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let thread_stop_flag = Arc::clone(&stop_flag);
+
+        let thread_handle = thread::spawn(move || {
+            while !thread_stop_flag.load(Ordering::Relaxed) {
+                let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                if size > max_size {
+                    // Logging here would write to the file being rotated
+                    let _ = rotate_log_file(&path, keep);
+                }
+                thread::park_timeout(ROTATION_CHECK_INTERVAL);
+            }
+        });
+
+        Self {
+            stop_flag,
+            thread_handle: Some(thread_handle),
+        }
+    }
+}
+
+impl Drop for LogRotator {
+    fn drop(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.thread_handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ivi-log-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("controller.log")
+    }
+
+    #[test]
+    fn test_rotate_log_file_keeps_newest_copies() {
+        let path = temp_log("keep");
+
+        for content in ["first", "second", "third"] {
+            fs::write(&path, content).unwrap();
+            rotate_log_file(&path, 2).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "third");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "second"
+        );
+        assert!(!rotated_path(&path, 3).exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_log_file_without_copies_truncates() {
+        let path = temp_log("truncate");
+        fs::write(&path, "old").unwrap();
+
+        rotate_log_file(&path, 0).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        assert!(!rotated_path(&path, 1).exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_missing_log_file() {
+        let path = temp_log("missing");
+        assert!(rotate_log_file(&path, 3).is_ok());
+        assert!(!rotated_path(&path, 1).exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_log_rotator_rotates_oversized_file() {
+        let path = temp_log("rotator");
+        fs::write(&path, "x".repeat(64)).unwrap();
+
+        let rotator = LogRotator::start(path.clone(), 16, 1);
+        let mut rotated = false;
+        for _ in 0..50 {
+            if rotated_path(&path, 1).exists() {
+                rotated = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        drop(rotator);
+
+        assert!(rotated);
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
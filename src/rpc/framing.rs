@@ -32,6 +32,13 @@ use std::io::{self, Read, Write};
 /// Maximum message size (64MB) for DOS protection
 pub const MAX_MESSAGE_SIZE: u32 = 64 * 1024 * 1024;
 
+/// Payload bytes reserved when a header is read
+///
+/// Larger payloads grow the buffer as their bytes arrive, so a header
+/// announcing a large message does not allocate memory the client never
+/// sends.
+const INITIAL_PAYLOAD_CAPACITY: usize = 64 * 1024;
+
 /// Result of attempting to read a frame
 #[derive(Debug, PartialEq)]
 pub enum FrameReadResult {
@@ -186,7 +193,9 @@ impl FrameReader {
                         self.state = ReadState::WaitingForPayload {
                             expected_len: msg_len,
                             bytes_read: 0,
-                            buffer: Vec::with_capacity(msg_len as usize),
+                            buffer: Vec::with_capacity(
+                                (msg_len as usize).min(INITIAL_PAYLOAD_CAPACITY),
+                            ),
                         };
                     }
                 }
@@ -294,6 +303,7 @@ pub fn write_frame<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use std::io::Cursor;
 
     #[test]
@@ -424,4 +434,73 @@ mod tests {
         let message = reader.read_frame(&mut cursor).unwrap();
         assert_eq!(message, FrameReadResult::Complete(b"test".to_vec()));
     }
+
+    #[test]
+    fn test_large_header_does_not_reserve_payload() {
+        let mut reader = FrameReader::new();
+        let mut cursor = Cursor::new(MAX_MESSAGE_SIZE.to_be_bytes());
+        assert_eq!(
+            reader.read_frame(&mut cursor).unwrap(),
+            FrameReadResult::Eof
+        );
+
+        match &reader.state {
+            ReadState::WaitingForPayload { buffer, .. } => {
+                assert!(buffer.capacity() <= INITIAL_PAYLOAD_CAPACITY)
+            }
+            state => panic!("unexpected state {:?}", state),
+        }
+    }
+
+    /// Feed `data` to a reader in chunks of `chunk` bytes, as a socket might
+    /// deliver it, and collect the frames read until the data runs out or
+    /// the reader fails
+    fn read_chunked(reader: &mut FrameReader, data: &[u8], chunk: usize) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        for piece in data.chunks(chunk) {
+            let mut cursor = Cursor::new(piece);
+            loop {
+                match reader.read_frame(&mut cursor) {
+                    Ok(FrameReadResult::Complete(frame)) => frames.push(frame),
+                    Ok(_) => break,
+                    Err(_) => return frames,
+                }
+            }
+        }
+        frames
+    }
+
+    proptest! {
+        #[test]
+        fn prop_read_frame_survives_arbitrary_bytes(
+            data in prop::collection::vec(any::<u8>(), 0..8192),
+            chunk in 1usize..64,
+        ) {
+            let mut reader = FrameReader::new();
+            for frame in read_chunked(&mut reader, &data, chunk) {
+                prop_assert!(!frame.is_empty());
+                prop_assert!(frame.len() <= MAX_MESSAGE_SIZE as usize);
+            }
+
+            // A partial payload holds no more than what was received, up to
+            // the initial reservation
+            if let ReadState::WaitingForPayload { buffer, .. } = &reader.state {
+                prop_assert!(buffer.capacity() <= INITIAL_PAYLOAD_CAPACITY.max(data.len()));
+            }
+        }
+
+        #[test]
+        fn prop_frames_round_trip_in_any_chunking(
+            messages in prop::collection::vec(prop::collection::vec(any::<u8>(), 1..512), 0..8),
+            chunk in 1usize..32,
+        ) {
+            let mut data = Vec::new();
+            for message in &messages {
+                write_frame(&mut data, message).unwrap();
+            }
+
+            let mut reader = FrameReader::new();
+            prop_assert_eq!(read_chunked(&mut reader, &data, chunk), messages);
+        }
+    }
 }
@@ -1,25 +1,96 @@
 // RPC request handler
 
-use super::protocol::{EventType, RpcError, RpcMethod, RpcRequest, RpcResponse};
+use super::framing;
+use super::idempotency::{
+    self, KeyReuse, RecentResults, IDEMPOTENCY_KEY_TTL, MAX_IDEMPOTENCY_KEYS,
+};
+use super::protocol::{
+    EventType, LayerTransition, RpcError, RpcMethod, RpcNotification, RpcRequest, RpcResponse,
+    METHOD_NAMES, MONITORING_METHOD_NAMES, PROTOCOL_VERSION, SCHEMA_VERSION,
+};
+use super::rate::{RateMeter, RATE_WINDOW};
 use super::transport::{ClientId, MessageHandler, Transport, TransportError};
-use crate::controller::state::{StateManager, SurfaceState};
+use crate::controller::staging::{StagedChanges, SurfaceChange};
+use crate::controller::state::{LayerState, StateManager, SurfaceState, SurfaceType};
 use crate::controller::subscriptions::SubscriptionManager;
 use crate::controller::validation;
+use crate::ffi::bindings::ivi_layout_api::IviLayoutApi;
 use crate::ffi::bindings::ivi_surface::IviSurface;
 use crate::ffi::bindings::weston_output_m::ScreenInfo;
-use crate::ffi::bindings::Rectangle;
+use crate::ffi::bindings::{IviLayoutTransitionType, Orientation, Rectangle};
 #[allow(unused)]
 use jlogger_tracing::{jdebug, jerror, jinfo, jtrace, jwarn, JloggerBuilder, LevelFilter};
 use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+/// Maximum consecutive delivery attempts for a client whose socket buffer is full
+const MAX_SEND_RETRIES: u32 = 5;
+
+/// Default pause between two passes of the notification delivery loop
+pub const DEFAULT_NOTIFY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Default window in which the commits of `auto_commit` requests are coalesced
+pub const DEFAULT_AUTO_COMMIT_DEBOUNCE: Duration = Duration::ZERO;
+
+/// Surfaces and layers changed by `auto_commit` requests whose commit is deferred
+#[derive(Default)]
+struct DeferredCommit {
+    /// When the first deferred change was made, `None` while nothing is deferred
+    since: Option<Instant>,
+    surface_ids: Vec<u32>,
+    layer_ids: Vec<u32>,
+}
+
+/// Z-order changes waiting for their commit to be recorded
+///
+/// The IVI layout does not report z-order, so it is recorded from the
+/// requests that changed it rather than re-read after the commit.
+#[derive(Default)]
+struct PendingZOrders {
+    /// Z-order set by set_surface_z_order, by surface
+    surfaces: Vec<(u32, i32)>,
+    /// Layers whose render order was changed by a restack
+    layer_ids: Vec<u32>,
+}
 
 /// Handles RPC requests and generates responses
 pub struct RpcHandler {
     state_manager: Arc<Mutex<StateManager>>,
     transport: Arc<Mutex<Option<Box<dyn Transport>>>>,
+    /// Optional read-only transport that only serves monitoring methods
+    metrics_transport: Arc<Mutex<Option<Box<dyn Transport>>>>,
     subscription_manager: Arc<Mutex<SubscriptionManager>>,
+    /// Event types that are never delivered, reported back on subscribe
+    disabled_events: Mutex<HashSet<EventType>>,
+    /// Whether the controller runs without per-surface and per-layer property listeners
+    coarse_events: AtomicBool,
+    /// Uids of the client processes allowed to call admin methods
+    admin_uids: Mutex<Vec<u32>>,
+    /// Surface changes waiting for a commit
+    staged_changes: Mutex<StagedChanges>,
+    /// Whether a change was made without `auto_commit` since the last commit
+    uncommitted: AtomicBool,
+    /// Layers changed without `auto_commit` since the last commit
+    uncommitted_layers: Mutex<Vec<u32>>,
+    /// Z-order changes recorded by the next commit
+    pending_z_orders: Mutex<PendingZOrders>,
+    /// Window in which `auto_commit` commits are coalesced, zero to commit at once
+    auto_commit_debounce: Mutex<Duration>,
+    /// `auto_commit` changes waiting for the debounce window to pass
+    deferred_commit: Mutex<DeferredCommit>,
+    /// Results of recent mutations, by `idempotency_key`
+    recent_results: Mutex<RecentResults>,
+    started_at: Instant,
+    requests_handled: AtomicU64,
+    requests_failed: AtomicU64,
+    /// IVI commits made by this controller
+    commit_rate: Mutex<RateMeter>,
+    /// Repaints of each output, by output name
+    repaint_rates: Mutex<HashMap<String, RateMeter>>,
 }
 
 impl RpcHandler {
@@ -28,7 +99,26 @@ impl RpcHandler {
         Arc::new(Self {
             state_manager,
             transport: Arc::new(Mutex::new(None)),
+            metrics_transport: Arc::new(Mutex::new(None)),
             subscription_manager: Arc::new(Mutex::new(SubscriptionManager::new())),
+            disabled_events: Mutex::new(HashSet::new()),
+            coarse_events: AtomicBool::new(false),
+            admin_uids: Mutex::new(Vec::new()),
+            staged_changes: Mutex::new(StagedChanges::new()),
+            uncommitted: AtomicBool::new(false),
+            uncommitted_layers: Mutex::new(Vec::new()),
+            pending_z_orders: Mutex::new(PendingZOrders::default()),
+            auto_commit_debounce: Mutex::new(Duration::ZERO),
+            deferred_commit: Mutex::new(DeferredCommit::default()),
+            recent_results: Mutex::new(RecentResults::new(
+                IDEMPOTENCY_KEY_TTL,
+                MAX_IDEMPOTENCY_KEYS,
+            )),
+            started_at: Instant::now(),
+            requests_handled: AtomicU64::new(0),
+            requests_failed: AtomicU64::new(0),
+            commit_rate: Mutex::new(RateMeter::new(RATE_WINDOW, Instant::now())),
+            repaint_rates: Mutex::new(HashMap::new()),
         })
     }
 
@@ -37,6 +127,44 @@ impl RpcHandler {
         Arc::clone(&self.subscription_manager)
     }
 
+    /// Set the event types that the notification bridge never delivers
+    ///
+    /// `subscribe` lists requested event types that are disabled as `ignored`.
+    pub fn set_disabled_events(&self, disabled_events: &[EventType]) {
+        *self.disabled_events.lock().unwrap() = disabled_events.iter().copied().collect();
+    }
+
+    /// Set the maximum number of distinct subscriptions per client
+    pub fn set_max_subscriptions(&self, max_subscriptions: usize) {
+        self.subscription_manager
+            .lock()
+            .unwrap()
+            .set_max_subscriptions(max_subscriptions);
+    }
+
+    /// Record whether property listeners are disabled, for `get_capabilities`
+    pub fn set_coarse_events(&self, coarse_events: bool) {
+        self.coarse_events.store(coarse_events, Ordering::Relaxed);
+    }
+
+    /// Set the uids of the client processes allowed to call admin methods
+    ///
+    /// With no admin uid, admin methods are refused to every client.
+    pub fn set_admin_uids(&self, admin_uids: &[u32]) {
+        *self.admin_uids.lock().unwrap() = admin_uids.to_vec();
+    }
+
+    /// Count a repaint of an output, for the repaint rate in `get_metrics`
+    pub fn record_repaint(&self, output_name: &str) {
+        let now = Instant::now();
+        self.repaint_rates
+            .lock()
+            .unwrap()
+            .entry(output_name.to_string())
+            .or_insert_with(|| RateMeter::new(RATE_WINDOW, now))
+            .record(now);
+    }
+
     /// Register a transport implementation
     pub fn register_transport(
         self: &Arc<Self>,
@@ -57,20 +185,51 @@ impl RpcHandler {
         Ok(())
     }
 
-    /// Start the registered transport
+    /// Register a read-only transport for monitoring
+    ///
+    /// Clients of this transport can only call the methods in
+    /// `MONITORING_METHOD_NAMES`; every other method is rejected.
+    pub fn register_metrics_transport(
+        self: &Arc<Self>,
+        mut transport: Box<dyn Transport>,
+    ) -> Result<(), TransportError> {
+        let handler = MonitoringMessageHandler {
+            rpc_handler: Arc::clone(self),
+        };
+        transport.register_handler(Box::new(handler));
+
+        *self.metrics_transport.lock().unwrap() = Some(transport);
+
+        Ok(())
+    }
+
+    /// Start the registered transport, and the monitoring transport if any
     pub fn start_transport(&self) -> Result<(), TransportError> {
         let mut transport_lock = self.transport.lock().unwrap();
         if let Some(transport) = transport_lock.as_mut() {
-            transport.start()
+            transport.start()?;
         } else {
-            Err(TransportError::InitError(
+            return Err(TransportError::InitError(
                 "No transport registered".to_string(),
-            ))
+            ));
+        }
+        drop(transport_lock);
+
+        if let Some(transport) = self.metrics_transport.lock().unwrap().as_mut() {
+            transport.start()?;
         }
+
+        Ok(())
     }
 
-    /// Stop the registered transport
+    /// Stop the registered transport, and the monitoring transport if any
     pub fn stop_transport(&self) -> Result<(), TransportError> {
+        if let Some(transport) = self.metrics_transport.lock().unwrap().as_mut() {
+            if let Err(e) = transport.stop() {
+                jwarn!("Failed to stop monitoring transport: {:?}", e);
+            }
+        }
+
         let mut transport_lock = self.transport.lock().unwrap();
         if let Some(transport) = transport_lock.as_mut() {
             transport.stop()
@@ -79,74 +238,217 @@ impl RpcHandler {
         }
     }
 
+    /// Coalesce the commits of `auto_commit` requests made within `window`
+    ///
+    /// The changes of all `auto_commit` requests received during a window are
+    /// committed at once by the transport loop, on the same thread that
+    /// handles requests, once the window has passed. Those requests are
+    /// answered with `"committed": false` and `"deferred": true`. Any other
+    /// request, an explicit `commit` included, first commits the deferred
+    /// changes, so reads never see a change as uncommitted. A zero window,
+    /// the default, commits each `auto_commit` request before it is answered.
+    pub fn start_auto_commit_debounce(&self, window: Duration) {
+        *self.auto_commit_debounce.lock().unwrap() = window;
+        if !window.is_zero() {
+            jinfo!("Coalescing auto_commit commits within {:?}", window);
+        }
+    }
+
     /// Start the notification delivery loop in a background thread
     /// This should be called after register_transport() and start_transport()
-    pub fn start_notification_delivery(self: &Arc<Self>) {
+    ///
+    /// Queued notifications are delivered every `interval`. A zero interval
+    /// polls in a tight loop that only yields the CPU between passes, which
+    /// gives the lowest latency at the cost of a busy core.
+    pub fn start_notification_delivery(self: &Arc<Self>, interval: Duration) {
         let subscription_manager = Arc::clone(&self.subscription_manager);
         let transport = Arc::clone(&self.transport);
 
-        jinfo!("Starting notification delivery loop");
+        jinfo!(
+            "Starting notification delivery loop, interval {:?}",
+            interval
+        );
 
         thread::spawn(move || {
+            let mut send_retries = HashMap::new();
+
             loop {
-                // Small sleep to avoid busy-waiting
-                thread::sleep(Duration::from_millis(10));
-
-                // Get connected clients
-                let transport_lock = transport.lock().unwrap();
-                if let Some(ref t) = *transport_lock {
-                    let clients = t.get_connected_clients();
-                    drop(transport_lock);
-
-                    // Drain and send notifications for each client
-                    for client_id in clients {
-                        let notifications = subscription_manager
-                            .lock()
-                            .unwrap()
-                            .drain_notifications(&client_id);
-
-                        if notifications.is_empty() {
-                            continue;
-                        }
+                if interval.is_zero() {
+                    thread::yield_now();
+                } else {
+                    thread::sleep(interval);
+                }
+
+                Self::deliver_notifications(&subscription_manager, &transport, &mut send_retries);
+            }
+        });
+
+        jinfo!("Notification delivery loop started");
+    }
+
+    /// Drain pending notifications for all connected clients and send them
+    ///
+    /// Identical payloads are grouped so each one goes out in a single
+    /// `send_to_clients` call while holding the transport lock once. A client
+    /// only joins a batch that comes after the last batch it was added to, so
+    /// per-client delivery order is preserved.
+    ///
+    /// If a send to a client would block, that notification and all later
+    /// ones for the client are requeued and retried on the next call, up to
+    /// `MAX_SEND_RETRIES` consecutive times. A client that turns out to be
+    /// disconnected has its subscriptions removed.
+    fn deliver_notifications(
+        subscription_manager: &Mutex<SubscriptionManager>,
+        transport: &Mutex<Option<Box<dyn Transport>>>,
+        send_retries: &mut HashMap<ClientId, u32>,
+    ) {
+        let clients = match transport.lock().unwrap().as_ref() {
+            Some(t) => t.get_connected_clients(),
+            None => return,
+        };
+
+        let mut batches: Vec<(Vec<u8>, RpcNotification, Vec<ClientId>)> = Vec::new();
+
+        for client_id in clients {
+            let notifications = subscription_manager
+                .lock()
+                .unwrap()
+                .drain_notifications(&client_id);
+
+            if notifications.is_empty() {
+                continue;
+            }
+
+            jtrace!(
+                "Sending {} notifications to client {}",
+                notifications.len(),
+                client_id
+            );
 
-                        jtrace!(
-                            "Sending {} notifications to client {}",
-                            notifications.len(),
-                            client_id
+            let mut next_batch = 0;
+            for notification in notifications {
+                let json = match serde_json::to_vec(&notification) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        jerror!(
+                            "Failed to serialize notification for client {}: {:?}",
+                            client_id,
+                            e
                         );
+                        continue;
+                    }
+                };
+
+                match batches[next_batch..]
+                    .iter()
+                    .position(|(payload, _, _)| *payload == json)
+                {
+                    Some(offset) => {
+                        next_batch += offset;
+                        batches[next_batch].2.push(client_id.clone());
+                    }
+                    None => {
+                        next_batch = batches.len();
+                        batches.push((json, notification, vec![client_id.clone()]));
+                    }
+                }
+                next_batch += 1;
+            }
+        }
+
+        if batches.is_empty() {
+            return;
+        }
 
-                        for notification in notifications {
-                            // Serialize notification to JSON
-                            match serde_json::to_vec(&notification) {
-                                Ok(json) => {
-                                    // Transport handles length-prefix framing
-                                    // Send to client
-                                    let transport_lock = transport.lock().unwrap();
-                                    if let Some(ref t) = *transport_lock {
-                                        if let Err(e) = t.send(&client_id, &json) {
-                                            jwarn!(
-                                                "Failed to send notification to client {}: {:?}",
-                                                client_id,
-                                                e
-                                            );
-                                        }
-                                    }
+        // Notifications held back for clients whose socket buffer is full
+        let mut blocked: HashMap<ClientId, Vec<RpcNotification>> = HashMap::new();
+        let mut disconnected: HashSet<ClientId> = HashSet::new();
+
+        {
+            // Transport handles length-prefix framing
+            let transport_lock = transport.lock().unwrap();
+            let t = match transport_lock.as_ref() {
+                Some(t) => t,
+                None => return,
+            };
+
+            for (payload, notification, targets) in batches {
+                let mut ready = Vec::new();
+                for client_id in targets {
+                    if let Some(pending) = blocked.get_mut(&client_id) {
+                        pending.push(notification.clone());
+                    } else if !disconnected.contains(&client_id) {
+                        ready.push(client_id);
+                    }
+                }
+
+                if ready.is_empty() {
+                    continue;
+                }
+
+                let ready: Vec<&ClientId> = ready.iter().collect();
+                match t.send_to_clients(&ready, &payload) {
+                    Ok(()) => {}
+                    Err(TransportError::PartialSendError(failures)) => {
+                        for (client_id, e) in failures {
+                            match e {
+                                TransportError::WouldBlock(_) => {
+                                    blocked.insert(client_id, vec![notification.clone()]);
                                 }
-                                Err(e) => {
-                                    jerror!(
-                                        "Failed to serialize notification for client {}: {:?}",
-                                        client_id,
-                                        e
-                                    );
+                                TransportError::Disconnected(_) => {
+                                    disconnected.insert(client_id);
                                 }
+                                e => jwarn!(
+                                    "Failed to send notification to client {}: {:?}",
+                                    client_id,
+                                    e
+                                ),
                             }
                         }
                     }
+                    Err(e) => jwarn!(
+                        "Failed to send notification to {} client(s): {:?}",
+                        ready.len(),
+                        e
+                    ),
                 }
             }
-        });
+        }
 
-        jinfo!("Notification delivery loop started");
+        let subscription_manager = subscription_manager.lock().unwrap();
+
+        for client_id in disconnected {
+            jinfo!(
+                "Client {} disconnected during notification delivery",
+                client_id
+            );
+            subscription_manager.remove_client(&client_id);
+        }
+
+        // Only consecutive failures count towards the retry limit
+        send_retries.retain(|client_id, _| blocked.contains_key(client_id));
+
+        for (client_id, pending) in blocked {
+            let attempts = send_retries.entry(client_id.clone()).or_insert(0);
+            *attempts += 1;
+
+            if *attempts > MAX_SEND_RETRIES {
+                jwarn!(
+                    "Dropping {} notifications for client {} after {} retries",
+                    pending.len(),
+                    client_id,
+                    MAX_SEND_RETRIES
+                );
+                send_retries.remove(&client_id);
+            } else {
+                jdebug!(
+                    "Send to client {} would block, retrying {} notifications",
+                    client_id,
+                    pending.len()
+                );
+                subscription_manager.requeue_notifications(&client_id, pending);
+            }
+        }
     }
 
     /// Handle an RPC request
@@ -158,19 +460,159 @@ impl RpcHandler {
             request.id
         );
 
+        self.requests_handled.fetch_add(1, Ordering::Relaxed);
+
         // Parse the method from the request
         let method = match RpcMethod::from_request(&request) {
             Ok(m) => m,
             Err(e) => {
                 jwarn!("Invalid RPC method: {}, error: {}", request.method, e);
-                return RpcResponse::error(request.id, e);
+                self.requests_failed.fetch_add(1, Ordering::Relaxed);
+                return RpcResponse::error(request.id, e).with_method(&request.method);
             }
         };
 
-        // Route to the appropriate handler
-        let result = match method {
-            RpcMethod::ListSurfaces => self.handle_list_surfaces(),
-            RpcMethod::GetSurface { id } => self.handle_get_surface(id),
+        // A dry run checks the request and answers without applying it
+        match self.dry_run(&request, &method) {
+            Ok(false) => {}
+            Ok(true) => {
+                return match self.validate(&method) {
+                    Ok(()) => {
+                        let mut result = request.params.clone();
+                        result["dry_run"] = json!(true);
+                        result["valid"] = json!(true);
+                        RpcResponse::success(request.id, result).with_method(&request.method)
+                    }
+                    Err(e) => {
+                        self.requests_failed.fetch_add(1, Ordering::Relaxed);
+                        RpcResponse::error(request.id, e).with_method(&request.method)
+                    }
+                };
+            }
+            Err(e) => {
+                self.requests_failed.fetch_add(1, Ordering::Relaxed);
+                return RpcResponse::error(request.id, e).with_method(&request.method);
+            }
+        }
+
+        let leaves_uncommitted = method.auto_commit() == Some(false);
+        let configured_layer = method.configured_layer();
+
+        // A retried mutation gets the result of the first attempt instead of
+        // being applied again
+        let idempotency_key = match self.idempotency_key(&request, &method) {
+            Ok(key) => key,
+            Err(e) => {
+                self.requests_failed.fetch_add(1, Ordering::Relaxed);
+                return RpcResponse::error(request.id, e).with_method(&request.method);
+            }
+        };
+        // Only keyed requests are remembered, so only their params are hashed
+        let params_hash = match idempotency_key {
+            Some(_) => idempotency::params_hash(&request.params),
+            None => 0,
+        };
+        if let Some(key) = &idempotency_key {
+            let cached = self.recent_results.lock().unwrap().get(
+                key,
+                &request.method,
+                params_hash,
+                Instant::now(),
+            );
+            match cached {
+                Ok(Some(result)) => {
+                    jinfo!(
+                        "Replaying result of {} for idempotency key {}",
+                        request.method,
+                        key
+                    );
+                    return RpcResponse::success(request.id, result).with_method(&request.method);
+                }
+                Ok(None) => {}
+                Err(reuse) => {
+                    self.requests_failed.fetch_add(1, Ordering::Relaxed);
+                    let error = RpcError::invalid_params(match reuse {
+                        KeyReuse::OtherMethod(first_method) => format!(
+                            "idempotency_key {} was already used for {}",
+                            key, first_method
+                        ),
+                        KeyReuse::OtherParams => {
+                            format!("idempotency_key {} was already used with other params", key)
+                        }
+                    });
+                    return RpcResponse::error(request.id, error).with_method(&request.method);
+                }
+            }
+        }
+
+        // Only back-to-back auto_commit changes share a debounced commit
+        let auto_commit = method.auto_commit() == Some(true);
+        if !auto_commit {
+            self.flush_deferred_commit();
+        }
+
+        let mut result = self.dispatch(client_id, method);
+
+        // The commit of this request still waits for the debounce window
+        if auto_commit && self.commit_deferred() {
+            if let Ok(serde_json::Value::Object(fields)) = &mut result {
+                if fields.contains_key("committed") {
+                    fields.insert("committed".to_string(), json!(false));
+                    fields.insert("deferred".to_string(), json!(true));
+                }
+            }
+        }
+
+        if leaves_uncommitted && result.is_ok() {
+            self.mark_uncommitted(configured_layer);
+        }
+
+        if let (Some(key), Ok(value)) = (&idempotency_key, &result) {
+            self.recent_results.lock().unwrap().insert(
+                key,
+                &request.method,
+                params_hash,
+                value.clone(),
+                Instant::now(),
+            );
+        }
+
+        // Generate response
+        match result {
+            Ok(value) => {
+                jdebug!("RPC request successful: id={}", request.id);
+                RpcResponse::success(request.id, value).with_method(&request.method)
+            }
+            Err(error) => {
+                jerror!("RPC request failed: id={}, error: {}", request.id, error);
+                self.requests_failed.fetch_add(1, Ordering::Relaxed);
+                RpcResponse::error(request.id, error).with_method(&request.method)
+            }
+        }
+    }
+
+    /// Remember a change made without `auto_commit` for the next commit
+    fn mark_uncommitted(&self, layer_id: Option<u32>) {
+        self.uncommitted.store(true, Ordering::SeqCst);
+        if let Some(id) = layer_id {
+            let mut layers = self.uncommitted_layers.lock().unwrap();
+            if !layers.contains(&id) {
+                layers.push(id);
+            }
+        }
+    }
+
+    /// Route a parsed request to its handler
+    fn dispatch(
+        &self,
+        client_id: &ClientId,
+        method: RpcMethod,
+    ) -> Result<serde_json::Value, RpcError> {
+        match method {
+            RpcMethod::ListSurfaces { offset, limit } => self.handle_list_surfaces(offset, limit),
+            RpcMethod::GetSurface { id, live: false } => self.handle_get_surface(id),
+            RpcMethod::GetSurface { id, live: true } => self.handle_get_surface_live(id),
+            RpcMethod::SurfaceExists { id } => self.handle_surface_exists(id),
             RpcMethod::SetSurfaceSourceRectangle {
                 id,
                 x,
@@ -189,25 +631,71 @@ impl RpcHandler {
             } => {
                 self.handle_set_surface_destination_rectangle(id, x, y, width, height, auto_commit)
             }
+            RpcMethod::SetSurfaceRectangles {
+                id,
+                src,
+                dest,
+                auto_commit,
+            } => self.handle_set_surface_rectangles(id, src, dest, auto_commit),
+            RpcMethod::SetSurfacePosition {
+                id,
+                x,
+                y,
+                relative,
+                auto_commit,
+            } => self.handle_set_surface_position(id, x, y, relative, auto_commit),
+            RpcMethod::SetSurfaceSize {
+                id,
+                width,
+                height,
+                auto_commit,
+            } => self.handle_set_surface_size(id, width, height, auto_commit),
             RpcMethod::SetSurfaceVisibility {
                 id,
                 visible,
                 auto_commit,
             } => self.handle_set_surface_visibility(id, visible, auto_commit),
+            RpcMethod::SetSurfacesVisibility {
+                ids,
+                visible,
+                auto_commit,
+            } => self.handle_set_surfaces_visibility(&ids, visible, auto_commit),
+            RpcMethod::SetSurfacesOpacity {
+                ids,
+                opacity,
+                auto_commit,
+            } => self.handle_set_surfaces_opacity(&ids, opacity, auto_commit),
             RpcMethod::SetSurfaceOpacity {
                 id,
                 opacity,
                 auto_commit,
             } => self.handle_set_surface_opacity(id, opacity, auto_commit),
+            RpcMethod::SetSurfaceOrientation {
+                id,
+                degrees,
+                auto_commit,
+            } => self.handle_set_surface_orientation(id, degrees, auto_commit),
             RpcMethod::SetSurfaceZOrder {
                 id,
                 z_order,
                 auto_commit,
             } => self.handle_set_surface_z_order(id, z_order, auto_commit),
+            RpcMethod::BringSurfaceToFront { id, auto_commit } => {
+                self.handle_restack_surface(id, true, auto_commit)
+            }
+            RpcMethod::SendSurfaceToBack { id, auto_commit } => {
+                self.handle_restack_surface(id, false, auto_commit)
+            }
+            RpcMethod::GetSurfaceZIndex { id } => self.handle_get_surface_z_index(id),
             RpcMethod::SetSurfaceFocus { id, auto_commit } => {
                 self.handle_set_surface_focus(id, auto_commit)
             }
-            RpcMethod::Commit => self.handle_commit(),
+            RpcMethod::GetFocus => self.handle_get_focus(),
+            RpcMethod::ClearFocus => self.handle_clear_focus(),
+            RpcMethod::Commit { surface_id } => self.handle_commit(surface_id),
+            RpcMethod::Batch { operations, commit } => {
+                self.handle_batch(client_id, operations, commit)
+            }
 
             // Subscription methods
             RpcMethod::Subscribe { event_types } => self.handle_subscribe(client_id, event_types),
@@ -215,6 +703,14 @@ impl RpcHandler {
                 self.handle_unsubscribe(client_id, event_types)
             }
             RpcMethod::ListSubscriptions => self.handle_list_subscriptions(client_id),
+            RpcMethod::GetAllSubscriptions => self.handle_get_all_subscriptions(client_id),
+            RpcMethod::ClearSubscriptions {
+                client_id: target,
+                all,
+            } => self.handle_clear_subscriptions(client_id, target, all),
+
+            // Client methods
+            RpcMethod::ListClients => self.handle_list_clients(),
 
             // Layer methods
             RpcMethod::ListLayers => self.handle_list_layers(),
@@ -228,6 +724,7 @@ impl RpcHandler {
                 self.handle_destroy_layer(id, auto_commit)
             }
             RpcMethod::GetLayer { id } => self.handle_get_layer(id),
+            RpcMethod::LayerExists { id } => self.handle_layer_exists(id),
             RpcMethod::SetLayerSourceRectangle {
                 id,
                 x,
@@ -244,6 +741,13 @@ impl RpcHandler {
                 height,
                 auto_commit,
             } => self.handle_set_layer_destination_rectangle(id, x, y, width, height, auto_commit),
+            RpcMethod::SetLayerPosition {
+                id,
+                x,
+                y,
+                relative,
+                auto_commit,
+            } => self.handle_set_layer_position(id, x, y, relative, auto_commit),
             RpcMethod::SetLayerVisibility {
                 id,
                 visible,
@@ -252,8 +756,16 @@ impl RpcHandler {
             RpcMethod::SetLayerOpacity {
                 id,
                 opacity,
+                relative,
+                auto_commit,
+            } => self.handle_set_layer_opacity(id, opacity, relative, auto_commit),
+            RpcMethod::SetLayerTransition {
+                id,
+                transition,
+                duration,
                 auto_commit,
-            } => self.handle_set_layer_opacity(id, opacity, auto_commit),
+            } => self.handle_set_layer_transition(id, transition, duration, auto_commit),
+            RpcMethod::CancelLayerTransition { id } => self.handle_cancel_layer_transition(id),
             // Layer-surface assignment operations
             RpcMethod::SetLayerSurfaces {
                 layer_id,
@@ -271,9 +783,12 @@ impl RpcHandler {
                 auto_commit,
             } => self.handle_remove_surface_from_layer(layer_id, surface_id, auto_commit),
             RpcMethod::GetLayerSurfaces { layer_id } => self.handle_get_layer_surfaces(layer_id),
+            RpcMethod::GetLayerRenderOrder { id } => self.handle_get_layer_render_order(id),
             // Screen operations
             RpcMethod::ListScreens => self.handle_list_screens(),
             RpcMethod::GetScreen { name } => self.handle_get_screen(name),
+            RpcMethod::ListOutputs => self.handle_list_outputs(),
+            RpcMethod::GetOutput { index } => self.handle_get_output(index),
             RpcMethod::GetScreenLayers { screen_name } => {
                 self.handle_get_screen_layers(screen_name)
             }
@@ -288,105 +803,636 @@ impl RpcHandler {
                 layer_id,
                 auto_commit,
             } => self.handle_remove_layer_from_screen(screen_name, layer_id, auto_commit),
-        };
+            RpcMethod::GetScene => self.handle_get_scene(),
 
-        // Generate response
-        match result {
-            Ok(value) => {
-                jdebug!("RPC request successful: id={}", request.id);
-                RpcResponse::success(request.id, value)
-            }
-            Err(error) => {
-                jerror!("RPC request failed: id={}, error: {}", request.id, error);
-                RpcResponse::error(request.id, error)
-            }
+            // Monitoring methods
+            RpcMethod::Ping => Ok(json!({ "pong": true })),
+            RpcMethod::GetMetrics => self.handle_get_metrics(),
+            RpcMethod::Stats => self.handle_stats(),
+            RpcMethod::GetCapabilities => self.handle_get_capabilities(),
         }
     }
 
-    /// Handle list_surfaces request
-    fn handle_list_surfaces(&self) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let surfaces = state_manager.get_all_surfaces();
-
-        let surface_list: Vec<serde_json::Value> =
-            surfaces.iter().map(surface_state_to_json).collect();
-
-        Ok(json!({ "surfaces": surface_list }))
-    }
-
-    /// Handle get_surface request
-    fn handle_get_surface(&self, id: u32) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
+    /// Get the `idempotency_key` of a request, if it has one that applies
+    ///
+    /// Keys are only honoured for methods that change surfaces, layers or
+    /// screens, i.e. those taking `auto_commit`; on other methods they are
+    /// ignored, since repeating those is harmless.
+    fn idempotency_key(
+        &self,
+        request: &RpcRequest,
+        method: &RpcMethod,
+    ) -> Result<Option<String>, RpcError> {
+        let key = match request.params.get("idempotency_key") {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let key = key.as_str().filter(|key| !key.is_empty()).ok_or_else(|| {
+            RpcError::invalid_params("Invalid 'idempotency_key' parameter".to_string())
+        })?;
 
-        match state_manager.get_surface(id) {
-            Some(surface) => {
-                jdebug!("Retrieved surface {}", id);
-                Ok(surface_state_to_json(&surface))
-            }
-            None => {
-                jwarn!("Surface not found: {}", id);
-                Err(RpcError::surface_not_found(id))
-            }
+        if method.auto_commit().is_none() {
+            return Ok(None);
         }
+        Ok(Some(key.to_string()))
     }
 
-    fn id_to_surface(&self, id: u32) -> Option<IviSurface> {
-        let state_manager = self.state_manager.lock().unwrap();
-
-        let ivi_api = state_manager.ivi_api().clone();
-        ivi_api.get_surface_from_id(id)
-    }
-
-    fn commit_surface_changes(&self, id: u32) -> Result<(), RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager);
-
-        ivi_api
-            .commit_changes()
-            .map_err(|e| RpcError::internal_error(e.to_string()))?;
-
-        // Update internal state
-        let mut state_manager = self.state_manager.lock().unwrap();
-        state_manager.handle_surface_configured(id);
+    /// Whether a request asks for a dry run with `dry_run: true`
+    ///
+    /// Only methods that change the layout, i.e. those taking `auto_commit`,
+    /// `commit` and `clear_focus`, can be dry run; on other methods the flag
+    /// is ignored, since running them changes nothing.
+    fn dry_run(&self, request: &RpcRequest, method: &RpcMethod) -> Result<bool, RpcError> {
+        let dry_run = match request.params.get("dry_run") {
+            Some(dry_run) => dry_run.as_bool().ok_or_else(|| {
+                RpcError::invalid_params("Invalid 'dry_run' parameter".to_string())
+            })?,
+            None => return Ok(false),
+        };
 
-        Ok(())
+        let changes_layout = method.auto_commit().is_some()
+            || matches!(method, RpcMethod::Commit { .. } | RpcMethod::ClearFocus);
+        Ok(dry_run && changes_layout)
     }
 
-    fn id_to_layer(&self, id: u32) -> Option<crate::ffi::bindings::ivi_layer::IviLayer> {
-        let state_manager = self.state_manager.lock().unwrap();
-
-        let ivi_api = state_manager.ivi_api().clone();
-        ivi_api.get_layer_from_id(id)
-    }
+    /// Check a request the way its handler would, without applying it
+    ///
+    /// Covers the parameter ranges and whether the targeted surfaces and
+    /// layers are tracked. A request that passes can still fail when it is
+    /// applied, e.g. if the compositor rejects it.
+    fn validate(&self, method: &RpcMethod) -> Result<(), RpcError> {
+        if let RpcMethod::Batch { operations, .. } = method {
+            return operations
+                .iter()
+                .enumerate()
+                .try_for_each(|(index, operation)| {
+                    self.validate(operation).map_err(|e| RpcError {
+                        message: format!("operations[{}]: {}", index, e.message),
+                        ..e
+                    })
+                });
+        }
 
-    fn commit_layer_changes(&self, id: u32) -> Result<(), RpcError> {
+        let invalid = |e: validation::ValidationError| RpcError::invalid_params(e.to_string());
         let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager);
-
-        ivi_api
-            .commit_changes()
-            .map_err(|e| RpcError::internal_error(e.to_string()))?;
-
-        // Update internal state
-        let mut state_manager = self.state_manager.lock().unwrap();
-        state_manager.handle_layer_configured(id);
-
-        Ok(())
-    }
+        let surface = |id: u32| {
+            if state_manager.has_surface(id) {
+                Ok(())
+            } else {
+                Err(RpcError::surface_not_found(id))
+            }
+        };
+        let layer = |id: u32| {
+            if state_manager.has_layer(id) {
+                Ok(())
+            } else {
+                Err(RpcError::layer_not_found(id))
+            }
+        };
 
-    /// Handle set_surface_source_rectangle request
-    fn handle_set_surface_source_rectangle(
-        &self,
-        id: u32,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        jdebug!(
+        match method {
+            RpcMethod::SetSurfaceSourceRectangle {
+                id,
+                x,
+                y,
+                width,
+                height,
+                ..
+            }
+            | RpcMethod::SetSurfaceDestinationRectangle {
+                id,
+                x,
+                y,
+                width,
+                height,
+                ..
+            } => {
+                validation::validate_position(*x, *y).map_err(invalid)?;
+                validation::validate_size(*width, *height).map_err(invalid)?;
+                surface(*id)
+            }
+            RpcMethod::SetSurfaceRectangles { id, src, dest, .. } => {
+                for rect in [src, dest] {
+                    validation::validate_position(rect.x, rect.y).map_err(invalid)?;
+                    validation::validate_size(rect.width, rect.height).map_err(invalid)?;
+                }
+                surface(*id)
+            }
+            RpcMethod::SetSurfacePosition {
+                id, x, y, relative, ..
+            } => {
+                if !relative {
+                    validation::validate_position(*x, *y).map_err(invalid)?;
+                }
+                surface(*id)
+            }
+            RpcMethod::SetSurfaceSize {
+                id, width, height, ..
+            } => {
+                validation::validate_size(*width, *height).map_err(invalid)?;
+                surface(*id)
+            }
+            RpcMethod::SetSurfaceOpacity { id, opacity, .. } => {
+                validation::validate_opacity(*opacity).map_err(invalid)?;
+                surface(*id)
+            }
+            RpcMethod::SetSurfacesOpacity { ids, opacity, .. } => {
+                validation::validate_opacity(*opacity).map_err(invalid)?;
+                ids.iter().try_for_each(|&id| surface(id))
+            }
+            RpcMethod::SetSurfacesVisibility { ids, .. } => {
+                ids.iter().try_for_each(|&id| surface(id))
+            }
+            RpcMethod::SetSurfaceOrientation { id, degrees, .. } => {
+                Orientation::from_degrees(*degrees).map_err(RpcError::invalid_params)?;
+                surface(*id)
+            }
+            RpcMethod::SetSurfaceZOrder { id, z_order, .. } => {
+                let (min, max) = validation::Z_ORDER_RANGE.into_inner();
+                validation::validate_z_order(*z_order, min, max).map_err(invalid)?;
+                surface(*id)
+            }
+            RpcMethod::SetSurfaceVisibility { id, .. }
+            | RpcMethod::BringSurfaceToFront { id, .. }
+            | RpcMethod::SendSurfaceToBack { id, .. }
+            | RpcMethod::SetSurfaceFocus { id, .. } => surface(*id),
+            RpcMethod::Commit {
+                surface_id: Some(id),
+            } => surface(*id),
+            RpcMethod::CreateLayer { width, height, .. } => {
+                validation::validate_size(*width, *height).map_err(invalid)
+            }
+            RpcMethod::SetLayerSourceRectangle {
+                id,
+                x,
+                y,
+                width,
+                height,
+                ..
+            }
+            | RpcMethod::SetLayerDestinationRectangle {
+                id,
+                x,
+                y,
+                width,
+                height,
+                ..
+            } => {
+                validation::validate_position(*x, *y).map_err(invalid)?;
+                validation::validate_size(*width, *height).map_err(invalid)?;
+                layer(*id)
+            }
+            RpcMethod::SetLayerPosition {
+                id, x, y, relative, ..
+            } => {
+                if !relative {
+                    validation::validate_position(*x, *y).map_err(invalid)?;
+                }
+                layer(*id)
+            }
+            RpcMethod::SetLayerOpacity {
+                id,
+                opacity,
+                relative,
+                ..
+            } => {
+                if !relative {
+                    validation::validate_opacity(*opacity).map_err(invalid)?;
+                }
+                layer(*id)
+            }
+            RpcMethod::DestroyLayer { id, .. }
+            | RpcMethod::SetLayerVisibility { id, .. }
+            | RpcMethod::SetLayerTransition { id, .. } => layer(*id),
+            RpcMethod::SetLayerSurfaces {
+                layer_id,
+                surface_ids,
+                ..
+            } => {
+                layer(*layer_id)?;
+                surface_ids.iter().try_for_each(|&id| surface(id))
+            }
+            RpcMethod::AddSurfaceToLayer {
+                layer_id,
+                surface_id,
+                ..
+            }
+            | RpcMethod::RemoveSurfaceFromLayer {
+                layer_id,
+                surface_id,
+                ..
+            } => {
+                layer(*layer_id)?;
+                surface(*surface_id)
+            }
+            RpcMethod::AddLayersToScreen { layer_ids, .. } => {
+                layer_ids.iter().try_for_each(|&id| layer(id))
+            }
+            RpcMethod::RemoveLayerFromScreen { layer_id, .. } => layer(*layer_id),
+            _ => Ok(()),
+        }
+    }
+
+    /// Handle an RPC request received on the monitoring transport
+    ///
+    /// Only monitoring methods are served; anything else, in particular any
+    /// mutation, is answered with a method-not-found error.
+    pub fn handle_monitoring_request(
+        &self,
+        client_id: &ClientId,
+        request: RpcRequest,
+    ) -> RpcResponse {
+        if !MONITORING_METHOD_NAMES.contains(&request.method.as_str()) {
+            jwarn!(
+                "Rejecting method {} from monitoring client {}",
+                request.method,
+                client_id
+            );
+            return RpcResponse::error(
+                request.id,
+                RpcError::method_not_found(request.method.clone()),
+            )
+            .with_method(&request.method);
+        }
+
+        self.handle_request(client_id, request)
+    }
+
+    /// Handle get_metrics request
+    fn handle_get_metrics(&self) -> Result<serde_json::Value, RpcError> {
+        let (surfaces, layers) = {
+            let state_manager = self.state_manager.lock().unwrap();
+            (
+                state_manager.get_all_surfaces().len(),
+                state_manager.get_all_layers().len(),
+            )
+        };
+
+        let connected_clients = self
+            .transport
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|t| t.get_connected_clients().len())
+            .unwrap_or(0);
+
+        let now = Instant::now();
+        let (ivi_commits, commits_per_second) = {
+            let mut commit_rate = self.commit_rate.lock().unwrap();
+            (commit_rate.total(), round_rate(commit_rate.rate(now)))
+        };
+        let repaints_per_second: serde_json::Map<String, serde_json::Value> = self
+            .repaint_rates
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .map(|(output, meter)| (output.clone(), json!(round_rate(meter.rate(now)))))
+            .collect();
+
+        Ok(json!({
+            "uptime_ms": self.started_at.elapsed().as_millis() as u64,
+            "requests_handled": self.requests_handled.load(Ordering::Relaxed),
+            "requests_failed": self.requests_failed.load(Ordering::Relaxed),
+            "connected_clients": connected_clients,
+            "surfaces": surfaces,
+            "layers": layers,
+            "ivi_commits": ivi_commits,
+            "commits_per_second": commits_per_second,
+            "repaints_per_second": repaints_per_second,
+        }))
+    }
+
+    /// Handle stats request - a cheap summary of the controller state
+    ///
+    /// Only reads the tracked state and counters; the IVI layout is not
+    /// queried.
+    fn handle_stats(&self) -> Result<serde_json::Value, RpcError> {
+        let (surfaces, layers) = {
+            let state_manager = self.state_manager.lock().unwrap();
+            (state_manager.surface_count(), state_manager.layer_count())
+        };
+
+        let connected_clients = self
+            .transport
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|t| t.get_connected_clients().len())
+            .unwrap_or(0);
+
+        Ok(json!({
+            "surfaces": surfaces,
+            "layers": layers,
+            "connected_clients": connected_clients,
+            "subscriptions": self.subscription_manager.lock().unwrap().subscription_count(),
+            "pending_changes": self.staged_changes.lock().unwrap().len(),
+        }))
+    }
+
+    /// Handle get_capabilities request
+    ///
+    /// `coarse_events` tells clients that the controller was started with
+    /// `--coarse-events`: it has no per-surface or per-layer property listeners,
+    /// so property changes made outside this controller are only reported when
+    /// the surface is next configured, and layer changes not at all.
+    fn handle_get_capabilities(&self) -> Result<serde_json::Value, RpcError> {
+        let transport = if cfg!(feature = "enable-ipcon") {
+            "ipcon"
+        } else {
+            "unix"
+        };
+        let subscription_manager = self.subscription_manager.lock().unwrap();
+
+        Ok(json!({
+            "v": SCHEMA_VERSION,
+            "protocol": PROTOCOL_VERSION,
+            "version": env!("CARGO_PKG_VERSION"),
+            "transport": transport,
+            "methods": METHOD_NAMES,
+            "coarse_events": self.coarse_events.load(Ordering::Relaxed),
+            "max_frame_bytes": framing::MAX_MESSAGE_SIZE,
+            "max_subscriptions_per_client": subscription_manager.max_subscriptions(),
+            "notify_queue_depth": subscription_manager.buffer_size(),
+            "max_surface_size": validation::max_size(),
+        }))
+    }
+
+    /// Handle list_surfaces request
+    ///
+    /// Surfaces are ordered by ID so that consecutive pages are stable. Without
+    /// a `limit`, all surfaces from `offset` onwards are returned.
+    fn handle_list_surfaces(
+        &self,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let surfaces = state_manager.get_all_surfaces();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        let total = surfaces.len();
+        let surface_list: Vec<serde_json::Value> = surfaces
+            .iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|surface| surface_state_to_json(surface, is_surface_on_screen(&ivi_api, surface)))
+            .collect();
+
+        Ok(json!({ "v": SCHEMA_VERSION, "surfaces": surface_list, "total": total }))
+    }
+
+    /// Handle get_surface request
+    fn handle_get_surface(&self, id: u32) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+
+        match state_manager.get_surface(id) {
+            Some(surface) => {
+                drop(state_manager);
+                jdebug!("Retrieved surface {}", id);
+                let on_screen = is_surface_on_screen(&ivi_api, &surface);
+                Ok(surface_state_to_json(&surface, on_screen))
+            }
+            None => {
+                jwarn!("Surface not found: {}", id);
+                Err(RpcError::surface_not_found(id))
+            }
+        }
+    }
+
+    /// Handle get_surface request with `live` set
+    ///
+    /// Reads every property from the IVI API rather than the tracked state, so
+    /// a difference between the two points at a missed event. The fields the
+    /// IVI API does not know, such as the surface type and the z-order, are
+    /// taken from the tracked state when the surface is tracked.
+    fn handle_get_surface_live(&self, id: u32) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+        let tracked = state_manager.get_surface(id);
+        drop(state_manager);
+
+        let ivi_surface = ivi_api.get_surface_from_id(id).ok_or_else(|| {
+            jwarn!("Surface not found in IVI layout: {}", id);
+            RpcError::surface_not_found(id)
+        })?;
+
+        let mut surface = SurfaceState::from_ivi(&ivi_surface);
+        if let Some(tracked) = tracked {
+            surface.is_auto_assigned = tracked.is_auto_assigned;
+            surface.original_id = tracked.original_id;
+            surface.surface_type = tracked.surface_type;
+            surface.z_order = tracked.z_order;
+        }
+
+        jdebug!("Read surface {} from the IVI layout", id);
+        let on_screen = is_surface_on_screen(&ivi_api, &surface);
+        Ok(surface_state_to_json(&surface, on_screen))
+    }
+
+    /// Handle surface_exists request
+    ///
+    /// Checks the tracked state first and falls back to the IVI API for
+    /// surfaces the state manager has not picked up yet.
+    fn handle_surface_exists(&self, id: u32) -> Result<serde_json::Value, RpcError> {
+        let tracked = self.state_manager.lock().unwrap().has_surface(id);
+        let exists = tracked || self.id_to_surface(id).is_some();
+
+        jdebug!("Surface {} exists: {}", id, exists);
+        Ok(json!({ "exists": exists }))
+    }
+
+    fn id_to_surface(&self, id: u32) -> Option<IviSurface> {
+        let state_manager = self.state_manager.lock().unwrap();
+
+        let ivi_api = state_manager.ivi_api().clone();
+        ivi_api.get_surface_from_id(id)
+    }
+
+    /// Set a surface property, staging it unless `auto_commit` is set
+    ///
+    /// Staged changes are written to the IVI layout by `commit`, which allows
+    /// committing a single surface. An auto-committed change is applied at
+    /// once and replaces a staged change of the same property.
+    fn set_surface_property(
+        &self,
+        id: u32,
+        change: SurfaceChange,
+        auto_commit: bool,
+    ) -> Result<(), RpcError> {
+        self.set_surface_properties(id, &[change], auto_commit)
+    }
+
+    /// Stage several property changes of one surface, or apply them all
+    /// before a single commit
+    fn set_surface_properties(
+        &self,
+        id: u32,
+        changes: &[SurfaceChange],
+        auto_commit: bool,
+    ) -> Result<(), RpcError> {
+        let mut surface = self
+            .id_to_surface(id)
+            .ok_or_else(|| RpcError::surface_not_found(id))?;
+
+        if !auto_commit {
+            let mut staged = self.staged_changes.lock().unwrap();
+            for &change in changes {
+                staged.stage(id, change);
+            }
+            return Ok(());
+        }
+
+        for change in changes {
+            apply_surface_change(&mut surface, change)?;
+            self.staged_changes.lock().unwrap().discard(id, change);
+        }
+        self.auto_commit_changes(&[id], &[])
+    }
+
+    /// Commit pending IVI changes and emit notifications for what they changed
+    ///
+    /// This is the one path by which committed property changes reach the
+    /// state manager. Each listed surface and layer is re-read and compared
+    /// with its tracked state once, so every changed property produces
+    /// exactly one notification; the IVI property listeners firing after the
+    /// commit then find nothing left to report.
+    fn apply_and_notify(&self, surface_ids: &[u32], layer_ids: &[u32]) -> Result<(), RpcError> {
+        let ivi_api = self.state_manager.lock().unwrap().ivi_api().clone();
+
+        self.commit_ivi(&ivi_api)
+            .map_err(|e| RpcError::internal_error(e.to_string()))?;
+
+        let pending_z_orders = std::mem::take(&mut *self.pending_z_orders.lock().unwrap());
+        let mut state_manager = self.state_manager.lock().unwrap();
+        for &id in surface_ids {
+            state_manager.handle_surface_configured(id);
+        }
+        for &id in layer_ids {
+            state_manager.handle_layer_configured(id);
+        }
+        for (id, z_order) in pending_z_orders.surfaces {
+            state_manager.set_surface_z_order(id, z_order);
+        }
+        drop(state_manager);
+
+        self.record_z_orders(&pending_z_orders.layer_ids)
+    }
+
+    /// Commit the changes of an `auto_commit` request, or defer the commit
+    /// until the debounce window has passed when one is set
+    fn auto_commit_changes(&self, surface_ids: &[u32], layer_ids: &[u32]) -> Result<(), RpcError> {
+        if self.auto_commit_debounce.lock().unwrap().is_zero() {
+            return self.commit_with_pending(surface_ids, layer_ids);
+        }
+
+        let mut deferred = self.deferred_commit.lock().unwrap();
+        deferred.since.get_or_insert_with(Instant::now);
+        for &id in surface_ids {
+            if !deferred.surface_ids.contains(&id) {
+                deferred.surface_ids.push(id);
+            }
+        }
+        for &id in layer_ids {
+            if !deferred.layer_ids.contains(&id) {
+                deferred.layer_ids.push(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Commit at once, together with the changes deferred by the auto_commit
+    /// debounce
+    ///
+    /// For `auto_commit` requests that must see their commit made before
+    /// they update the tracked state.
+    fn commit_now(&self) -> Result<(), RpcError> {
+        let deferred = std::mem::take(&mut *self.deferred_commit.lock().unwrap());
+        self.commit_with_pending(&deferred.surface_ids, &deferred.layer_ids)
+    }
+
+    /// Commit for `auto_commit` requests, applying every pending change
+    ///
+    /// The IVI commit is global, so as with a `commit` without `id`, the
+    /// changes staged or left uncommitted by earlier requests are applied
+    /// and notified along with those of the `auto_commit` request.
+    fn commit_with_pending(&self, surface_ids: &[u32], layer_ids: &[u32]) -> Result<(), RpcError> {
+        let ivi_api = self.state_manager.lock().unwrap().ivi_api().clone();
+        let staged = self.staged_changes.lock().unwrap().take_all();
+        apply_staged_changes(&ivi_api, &staged)?;
+
+        let mut surface_ids = surface_ids.to_vec();
+        for (id, _) in staged {
+            if !surface_ids.contains(&id) {
+                surface_ids.push(id);
+            }
+        }
+        let mut layer_ids = layer_ids.to_vec();
+        self.uncommitted.store(false, Ordering::SeqCst);
+        for id in std::mem::take(&mut *self.uncommitted_layers.lock().unwrap()) {
+            if !layer_ids.contains(&id) {
+                layer_ids.push(id);
+            }
+        }
+
+        self.apply_and_notify(&surface_ids, &layer_ids)
+    }
+
+    /// Whether changes of `auto_commit` requests are waiting for their commit
+    fn commit_deferred(&self) -> bool {
+        self.deferred_commit.lock().unwrap().since.is_some()
+    }
+
+    /// Commit the changes deferred by the auto_commit debounce, if any
+    ///
+    /// The requests that made these changes have already been answered, so a
+    /// failing commit can only be logged.
+    fn flush_deferred_commit(&self) {
+        if !self.commit_deferred() {
+            return;
+        }
+
+        if let Err(e) = self.commit_now() {
+            jerror!("Failed to commit debounced auto_commit changes: {}", e);
+        }
+    }
+
+    /// Commit the deferred `auto_commit` changes once their debounce window
+    /// has passed
+    fn flush_expired_commit(&self) {
+        let window = *self.auto_commit_debounce.lock().unwrap();
+        let since = self.deferred_commit.lock().unwrap().since;
+        if matches!(since, Some(since) if since.elapsed() >= window) {
+            self.flush_deferred_commit();
+        }
+    }
+
+    /// Commit pending IVI changes and count the commit for `get_metrics`
+    fn commit_ivi(&self, ivi_api: &IviLayoutApi) -> Result<(), &'static str> {
+        ivi_api.commit_changes()?;
+        self.commit_rate.lock().unwrap().record(Instant::now());
+        Ok(())
+    }
+
+    fn id_to_layer(&self, id: u32) -> Option<crate::ffi::bindings::ivi_layer::IviLayer> {
+        let state_manager = self.state_manager.lock().unwrap();
+
+        let ivi_api = state_manager.ivi_api().clone();
+        ivi_api.get_layer_from_id(id)
+    }
+
+    /// Handle set_surface_source_rectangle request
+    fn handle_set_surface_source_rectangle(
+        &self,
+        id: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
             "Setting source region for surface {}: {}x{}@({}, {}) [auto_commit={}]",
             id,
             width,
@@ -401,24 +1447,20 @@ impl RpcHandler {
             jwarn!("Invalid position for surface {}: {}", id, e);
             RpcError::invalid_params(e.to_string())
         })?;
+        // Validate size now, as a staged change is only applied on commit
+        validation::validate_size(width, height)
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
 
-        let mut surface = self
-            .id_to_surface(id)
-            .ok_or_else(|| RpcError::surface_not_found(id))?;
-
-        surface
-            .set_source_rectangle(Rectangle {
+        self.set_surface_property(
+            id,
+            SurfaceChange::SourceRectangle(Rectangle {
                 x,
                 y,
                 width,
                 height,
-            })
-            .map_err(RpcError::internal_error)?;
-
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            self.commit_surface_changes(id)?;
-        }
+            }),
+            auto_commit,
+        )?;
 
         Ok(json!({ "success": true, "committed": auto_commit }))
     }
@@ -434,10 +1476,12 @@ impl RpcHandler {
         auto_commit: bool,
     ) -> Result<serde_json::Value, RpcError> {
         jdebug!(
-            "Setting size for surface {}: {}x{} [auto_commit={}]",
+            "Setting destination rectangle for surface {}: {}x{}@({}, {}) [auto_commit={}]",
             id,
             width,
             height,
+            x,
+            y,
             auto_commit
         );
 
@@ -447,27 +1491,164 @@ impl RpcHandler {
         validation::validate_size(width, height)
             .map_err(|e| RpcError::invalid_params(e.to_string()))?;
 
-        let mut surface = self
-            .id_to_surface(id)
-            .ok_or_else(|| RpcError::surface_not_found(id))?;
-
-        surface
-            .set_destination_rectangle(Rectangle {
+        self.set_surface_property(
+            id,
+            SurfaceChange::DestinationRectangle(Rectangle {
                 x,
                 y,
                 width,
                 height,
-            })
-            .map_err(RpcError::internal_error)?;
+            }),
+            auto_commit,
+        )?;
 
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            self.commit_surface_changes(id)?;
+        Ok(json!({ "success": true, "committed": auto_commit }))
+    }
+
+    /// Handle set_surface_rectangles request
+    ///
+    /// Sets the source and destination rectangles together, so no frame
+    /// shows the new crop with the old placement or the other way round.
+    /// Both rectangles are validated before either is changed.
+    fn handle_set_surface_rectangles(
+        &self,
+        id: u32,
+        src: Rectangle,
+        dest: Rectangle,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
+            "Setting rectangles for surface {}: src {:?}, dest {:?} [auto_commit={}]",
+            id,
+            src,
+            dest,
+            auto_commit
+        );
+
+        for rect in [&src, &dest] {
+            validation::validate_position(rect.x, rect.y)
+                .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+            validation::validate_size(rect.width, rect.height)
+                .map_err(|e| RpcError::invalid_params(e.to_string()))?;
         }
 
+        self.set_surface_properties(
+            id,
+            &[
+                SurfaceChange::SourceRectangle(src),
+                SurfaceChange::DestinationRectangle(dest),
+            ],
+            auto_commit,
+        )?;
+
         Ok(json!({ "success": true, "committed": auto_commit }))
     }
 
+    /// Handle set_surface_position request
+    ///
+    /// Moves the destination rectangle and keeps its size. A relative move
+    /// starts from the staged destination rectangle if there is one, so
+    /// consecutive moves accumulate even before they are committed.
+    fn handle_set_surface_position(
+        &self,
+        id: u32,
+        x: i32,
+        y: i32,
+        relative: bool,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        let current = self.staged_destination_rectangle(id)?;
+
+        let (x, y) = if relative {
+            (current.x.saturating_add(x), current.y.saturating_add(y))
+        } else {
+            (x, y)
+        };
+        validation::validate_position(x, y).map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        jdebug!(
+            "Moving surface {} to ({}, {}) [relative={}, auto_commit={}]",
+            id,
+            x,
+            y,
+            relative,
+            auto_commit
+        );
+
+        self.set_surface_property(
+            id,
+            SurfaceChange::DestinationRectangle(Rectangle { x, y, ..current }),
+            auto_commit,
+        )?;
+
+        Ok(json!({ "success": true, "x": x, "y": y, "committed": auto_commit }))
+    }
+
+    /// Handle set_surface_size request
+    ///
+    /// Resizes the destination rectangle and keeps its position, taken from
+    /// the staged destination rectangle if there is one, like
+    /// set_surface_position does.
+    fn handle_set_surface_size(
+        &self,
+        id: u32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        validation::validate_size(width, height)
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        let current = self.staged_destination_rectangle(id)?;
+
+        jdebug!(
+            "Resizing surface {} to {}x{} [auto_commit={}]",
+            id,
+            width,
+            height,
+            auto_commit
+        );
+
+        self.set_surface_property(
+            id,
+            SurfaceChange::DestinationRectangle(Rectangle {
+                width,
+                height,
+                ..current
+            }),
+            auto_commit,
+        )?;
+
+        Ok(json!({
+            "success": true,
+            "x": current.x,
+            "y": current.y,
+            "committed": auto_commit
+        }))
+    }
+
+    /// The destination rectangle a surface will have once committed
+    ///
+    /// This is the staged destination rectangle if there is one, otherwise
+    /// the current one.
+    fn staged_destination_rectangle(&self, id: u32) -> Result<Rectangle, RpcError> {
+        let staged = self
+            .staged_changes
+            .lock()
+            .unwrap()
+            .destination_rectangle(id);
+        match staged {
+            Some(rect) => Ok(rect),
+            None => match self.state_manager.lock().unwrap().get_surface(id) {
+                Some(surface) => Ok(surface.dest_rect),
+                None => {
+                    jwarn!("Surface not found: {}", id);
+                    Err(RpcError::surface_not_found(id))
+                }
+            },
+        }
+    }
+
     /// Handle set_surface_visibility request
     fn handle_set_surface_visibility(
         &self,
@@ -475,20 +1656,94 @@ impl RpcHandler {
         visible: bool,
         auto_commit: bool,
     ) -> Result<serde_json::Value, RpcError> {
-        let mut surface = self
-            .id_to_surface(id)
-            .ok_or_else(|| RpcError::surface_not_found(id))?;
+        self.set_surface_property(id, SurfaceChange::Visibility(visible), auto_commit)?;
 
-        surface
-            .set_visibility(visible)
-            .map_err(RpcError::internal_error)?;
+        Ok(json!({ "success": true, "committed": auto_commit }))
+    }
 
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            self.commit_surface_changes(id)?;
+    /// Handle set_surfaces_visibility request
+    ///
+    /// All surfaces must exist; otherwise nothing is changed. Visibility is a
+    /// per-surface property in the IVI layout, so hiding and re-showing
+    /// surfaces leaves the render order of their layers untouched.
+    fn handle_set_surfaces_visibility(
+        &self,
+        ids: &[u32],
+        visible: bool,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
+            "Setting visibility of surfaces {:?} to {} [auto_commit={}]",
+            ids,
+            visible,
+            auto_commit
+        );
+
+        self.set_surfaces_property(ids, SurfaceChange::Visibility(visible), auto_commit)
+    }
+
+    /// Handle set_surfaces_opacity request
+    ///
+    /// As with `set_surfaces_visibility`, all surfaces must exist; otherwise
+    /// nothing is changed.
+    fn handle_set_surfaces_opacity(
+        &self,
+        ids: &[u32],
+        opacity: f32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        validation::validate_opacity(opacity)
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        jdebug!(
+            "Setting opacity of surfaces {:?} to {} [auto_commit={}]",
+            ids,
+            opacity,
+            auto_commit
+        );
+
+        self.set_surfaces_property(ids, SurfaceChange::Opacity(opacity), auto_commit)
+    }
+
+    /// Set the same property on several surfaces, staging it unless `auto_commit` is set
+    ///
+    /// Every surface is checked before any is changed.
+    fn set_surfaces_property(
+        &self,
+        ids: &[u32],
+        change: SurfaceChange,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+
+        // Check every surface before changing any of them
+        if let Some(&missing) = ids.iter().find(|&&id| !state_manager.has_surface(id)) {
+            jwarn!("Surface not found: {}", missing);
+            return Err(RpcError::surface_not_found(missing));
         }
 
-        Ok(json!({ "success": true, "committed": auto_commit }))
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager); // Release lock before calling IVI API
+
+        if !auto_commit {
+            let mut staged = self.staged_changes.lock().unwrap();
+            for &id in ids {
+                staged.stage(id, change);
+            }
+            return Ok(json!({ "success": true, "count": ids.len(), "committed": false }));
+        }
+
+        for &id in ids {
+            let mut surface = ivi_api
+                .get_surface_from_id(id)
+                .ok_or_else(|| RpcError::surface_not_found(id))?;
+            apply_surface_change(&mut surface, &change)?;
+            self.staged_changes.lock().unwrap().discard(id, &change);
+        }
+
+        self.auto_commit_changes(ids, &[])?;
+
+        Ok(json!({ "success": true, "count": ids.len(), "committed": auto_commit }))
     }
 
     /// Handle set_surface_opacity request
@@ -502,18 +1757,30 @@ impl RpcHandler {
         validation::validate_opacity(opacity)
             .map_err(|e| RpcError::invalid_params(e.to_string()))?;
 
-        let mut surface = self
-            .id_to_surface(id)
-            .ok_or_else(|| RpcError::surface_not_found(id))?;
+        self.set_surface_property(id, SurfaceChange::Opacity(opacity), auto_commit)?;
 
-        surface
-            .set_opacity(opacity)
-            .map_err(RpcError::internal_error)?;
+        Ok(json!({ "success": true, "committed": auto_commit }))
+    }
 
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            self.commit_surface_changes(id)?;
-        }
+    /// Handle set_surface_orientation request
+    ///
+    /// Applying the change fails when the IVI layout API has no
+    /// `surface_set_orientation`, as with the Weston 13 headers.
+    fn handle_set_surface_orientation(
+        &self,
+        id: u32,
+        degrees: i32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        let orientation = Orientation::from_degrees(degrees).map_err(RpcError::invalid_params)?;
+        jdebug!(
+            "Setting orientation for surface {}: {} [auto_commit={}]",
+            id,
+            orientation,
+            auto_commit
+        );
+
+        self.set_surface_property(id, SurfaceChange::Orientation(orientation), auto_commit)?;
 
         Ok(json!({ "success": true, "committed": auto_commit }))
     }
@@ -525,47 +1792,174 @@ impl RpcHandler {
         z_order: i32,
         auto_commit: bool,
     ) -> Result<serde_json::Value, RpcError> {
-        // Validate z_order (using a reasonable range for now)
-        validation::validate_z_order(z_order, 0, 1000)
+        let (min, max) = validation::Z_ORDER_RANGE.into_inner();
+        validation::validate_z_order(z_order, min, max)
             .map_err(|e| RpcError::invalid_params(e.to_string()))?;
 
         let mut surface = self
             .id_to_surface(id)
             .ok_or_else(|| RpcError::surface_not_found(id))?;
 
-        surface
-            .set_z_order(z_order, 0, 1000)
+        let resolved_z_order = surface
+            .set_z_order(z_order, min, max)
             .map_err(RpcError::internal_error)?;
 
+        if resolved_z_order != z_order {
+            jdebug!(
+                "Z-order {} of surface {} resolved to {}",
+                z_order,
+                id,
+                resolved_z_order
+            );
+        }
+
+        // The render order is changed at once, but the z-order is only
+        // recorded once the change is committed
+        {
+            let mut pending = self.pending_z_orders.lock().unwrap();
+            pending.surfaces.retain(|(surface_id, _)| *surface_id != id);
+            pending.surfaces.push((id, resolved_z_order));
+        }
+
         // Commit changes only if auto_commit is true
         if auto_commit {
-            let state_manager = self.state_manager.lock().unwrap();
-            let ivi_api = state_manager.ivi_api().clone();
-            drop(state_manager);
+            self.auto_commit_changes(&[id], &[])?;
+        }
 
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(e.to_string()))?;
+        Ok(json!({
+            "success": true,
+            "z_order": resolved_z_order,
+            "committed": auto_commit
+        }))
+    }
 
-            // Update internal state and emit notification
-            // Capture old z-order first
-            let mut state_manager = self.state_manager.lock().unwrap();
-            let old_z = state_manager.get_surface(id).map(|s| s.z_order);
-            if let Some(mut surface_state) = state_manager.get_surface(id) {
-                surface_state.z_order = z_order;
-                state_manager.update_surface(id, surface_state);
+    /// Handle bring_surface_to_front and send_surface_to_back requests
+    ///
+    /// Moves the surface to the top or bottom of the render order of every
+    /// layer it is on, keeping the order of the other surfaces.
+    fn handle_restack_surface(
+        &self,
+        id: u32,
+        to_front: bool,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        if !self.state_manager.lock().unwrap().has_surface(id) {
+            return Err(RpcError::surface_not_found(id));
+        }
+
+        let surface = self
+            .id_to_surface(id)
+            .ok_or_else(|| RpcError::surface_not_found(id))?;
+
+        let layer_ids = if to_front {
+            surface.bring_to_front()
+        } else {
+            surface.send_to_back()
+        }
+        .map_err(RpcError::internal_error)?;
+
+        jdebug!(
+            "Moved surface {} to the {} of layers {:?}",
+            id,
+            if to_front { "front" } else { "back" },
+            layer_ids
+        );
+
+        // The other surfaces on those layers shift as well, so the z-order of
+        // all of them is recorded by the commit
+        {
+            let mut pending = self.pending_z_orders.lock().unwrap();
+            for &layer_id in &layer_ids {
+                if !pending.layer_ids.contains(&layer_id) {
+                    pending.layer_ids.push(layer_id);
+                }
             }
+        }
 
-            // Emit z-order change if we have an old value
-            if let Some(old_z_order) = old_z {
-                let nm = state_manager.notification_manager().clone();
-                drop(state_manager);
-                let nm = nm.lock().unwrap();
-                nm.emit_z_order_change(id, old_z_order, z_order);
+        if auto_commit {
+            self.auto_commit_changes(&[], &[])?;
+        }
+
+        Ok(json!({
+            "success": true,
+            "layers": layer_ids,
+            "committed": auto_commit
+        }))
+    }
+
+    /// Record the z-order of every surface on the given layers
+    ///
+    /// As for set_surface_z_order, the z-order of a surface is its index in the
+    /// render order of the first layer holding it.
+    fn record_z_orders(&self, layer_ids: &[u32]) -> Result<(), RpcError> {
+        let ivi_api = self.state_manager.lock().unwrap().ivi_api().clone();
+
+        let mut surface_ids: Vec<u32> = layer_ids
+            .iter()
+            .filter_map(|layer_id| ivi_api.get_layer_from_id(*layer_id))
+            .flat_map(|layer| layer.get_surfaces())
+            .map(|surface| surface.id())
+            .collect();
+        surface_ids.sort_unstable();
+        surface_ids.dedup();
+
+        for surface_id in surface_ids {
+            let surface = match ivi_api.get_surface_from_id(surface_id) {
+                Some(surface) => surface,
+                None => continue,
+            };
+            let layers = ivi_api
+                .get_layers_under_surface(&surface)
+                .map_err(|e| RpcError::internal_error(e.to_string()))?;
+            let z_order = layers.first().and_then(|layer| {
+                layer
+                    .get_surfaces()
+                    .iter()
+                    .position(|s| s.id() == surface_id)
+            });
+
+            if let Some(z_order) = z_order {
+                self.state_manager
+                    .lock()
+                    .unwrap()
+                    .set_surface_z_order(surface_id, z_order as i32);
             }
         }
+        Ok(())
+    }
 
-        Ok(json!({ "success": true, "committed": auto_commit }))
+    /// Handle get_surface_z_index request
+    ///
+    /// Returns the position of the surface in the render order of each layer
+    /// it is on, 0 being the bottommost, with the number of surfaces on that
+    /// layer.
+    fn handle_get_surface_z_index(&self, id: u32) -> Result<serde_json::Value, RpcError> {
+        if !self.state_manager.lock().unwrap().has_surface(id) {
+            return Err(RpcError::surface_not_found(id));
+        }
+
+        let surface = self
+            .id_to_surface(id)
+            .ok_or_else(|| RpcError::surface_not_found(id))?;
+        let ivi_api = self.state_manager.lock().unwrap().ivi_api().clone();
+        let layers = ivi_api
+            .get_layers_under_surface(&surface)
+            .map_err(|e| RpcError::internal_error(e.to_string()))?;
+
+        let z_indexes: Vec<serde_json::Value> = layers
+            .iter()
+            .filter_map(|layer| {
+                let surfaces = layer.get_surfaces();
+                let index = surfaces.iter().position(|s| s.id() == id)?;
+                Some(json!({
+                    "layer_id": layer.id(),
+                    "index": index,
+                    "count": surfaces.len(),
+                }))
+            })
+            .collect();
+
+        Ok(json!({ "id": id, "layers": z_indexes }))
     }
 
     /// Handle set_surface_focus request
@@ -588,18 +1982,24 @@ impl RpcHandler {
         surface
             .set_keyboard_focus()
             .map_err(RpcError::internal_error)?;
-        surface
-            .set_pointer_focus()
-            .map_err(RpcError::internal_error)?;
+        if let Err(e) = surface.set_pointer_focus() {
+            self.restore_keyboard_focus(id);
+            return Err(RpcError::internal_error(e));
+        }
+
+        // Commit changes only if auto_commit is true. The focused surface is
+        // recorded only once the IVI side has accepted the change, so a
+        // failed activation or commit leaves the previous focus in place.
+        if auto_commit {
+            self.commit_now()?;
+        }
+
+        self.state_manager
+            .lock()
+            .unwrap()
+            .set_focused_surface(Some(id));
 
-        // Commit changes only if auto_commit is true
         if auto_commit {
-            let state_manager = self.state_manager.lock().unwrap();
-            let ivi_api = state_manager.ivi_api().clone();
-            drop(state_manager);
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(e.to_string()))?;
             jinfo!("Focus set to surface {} and committed", id);
         } else {
             jinfo!("Focus set to surface {} (pending commit)", id);
@@ -608,40 +2008,202 @@ impl RpcHandler {
         Ok(json!({ "success": true, "committed": auto_commit }))
     }
 
-    /// Handle commit request - commits all pending changes
-    fn handle_commit(&self) -> Result<serde_json::Value, RpcError> {
-        jdebug!("Committing all pending changes");
+    /// Give keyboard focus back to the recorded focused surface after a
+    /// focus change failed half way, leaving it on surface `id`
+    ///
+    /// The IVI layout API cannot deactivate a surface, so when there is no
+    /// focus to restore, `id` keeps it and is recorded as focused.
+    fn restore_keyboard_focus(&self, id: u32) {
+        let previous = self.state_manager.lock().unwrap().get_focused_surface();
+        let restored = match previous.and_then(|previous| self.id_to_surface(previous)) {
+            Some(mut surface) => match surface.set_keyboard_focus() {
+                Ok(()) => true,
+                Err(e) => {
+                    jwarn!("Failed to restore keyboard focus: {}", e);
+                    false
+                }
+            },
+            None => false,
+        };
+
+        if !restored {
+            self.state_manager
+                .lock()
+                .unwrap()
+                .set_focused_surface(Some(id));
+        }
+    }
 
+    /// Handle get_focus request
+    fn handle_get_focus(&self) -> Result<serde_json::Value, RpcError> {
         let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager);
+        let focused = state_manager.get_focused_surface();
 
-        // Commit all pending changes
-        ivi_api
-            .commit_changes()
-            .map_err(|e| RpcError::internal_error(e.to_string()))?;
+        jdebug!("Focused surface: {:?}", focused);
+
+        Ok(json!({ "surface_id": focused }))
+    }
 
-        jinfo!("All pending changes committed");
+    /// Handle clear_focus request
+    ///
+    /// The IVI layout API has no way to deactivate a surface, so this only
+    /// clears the controller's record of the focused surface and emits a
+    /// FocusChanged notification if a surface was focused.
+    fn handle_clear_focus(&self) -> Result<serde_json::Value, RpcError> {
+        jdebug!("Clearing focus");
+
+        let mut state_manager = self.state_manager.lock().unwrap();
+        state_manager.set_focused_surface(None);
+
+        jinfo!("Focus cleared");
 
         Ok(json!({ "success": true }))
     }
 
-    /// Handle subscribe request - subscribe to event types
-    fn handle_subscribe(
-        &self,
-        client_id: &ClientId,
-        event_types: Vec<EventType>,
-    ) -> Result<serde_json::Value, RpcError> {
-        jinfo!(
-            "Client {} subscribing to {} event types",
-            client_id,
-            event_types.len()
-        );
+    /// Handle commit request - commits all pending changes
+    ///
+    /// With `surface_id`, only the staged changes of that surface are
+    /// applied; staged changes of other surfaces stay queued. Changes that
+    /// are not staged (layers, z-order, focus) are applied by every commit,
+    /// because the IVI layout API only has a global commit.
+    ///
+    /// When nothing was changed without `auto_commit` since the last commit,
+    /// the IVI commit is skipped, so defensive commits cause no repaint.
+    fn handle_commit(&self, surface_id: Option<u32>) -> Result<serde_json::Value, RpcError> {
+        let staged = match surface_id {
+            Some(id) => {
+                jdebug!("Committing pending changes of surface {}", id);
+                if !self.state_manager.lock().unwrap().has_surface(id) {
+                    return Err(RpcError::surface_not_found(id));
+                }
+                vec![(id, self.staged_changes.lock().unwrap().take_surface(id))]
+            }
+            None => {
+                jdebug!("Committing all pending changes");
+                self.staged_changes.lock().unwrap().take_all()
+            }
+        };
+
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        let uncommitted = self.uncommitted.swap(false, Ordering::SeqCst);
+        let layer_ids = std::mem::take(&mut *self.uncommitted_layers.lock().unwrap());
+        if !uncommitted && staged.iter().all(|(_, changes)| changes.is_empty()) {
+            jdebug!("Nothing pending, skipping IVI commit");
+            let commit_seq = ivi_api.commit_count();
+            return Ok(match surface_id {
+                Some(id) => json!({
+                    "success": true,
+                    "surface_id": id,
+                    "applied": 0,
+                    "commit_seq": commit_seq
+                }),
+                None => json!({ "success": true, "applied": 0, "commit_seq": commit_seq }),
+            });
+        }
+
+        let applied = apply_staged_changes(&ivi_api, &staged)?;
+
+        // Commit all pending changes
+        let surface_ids: Vec<u32> = staged.iter().map(|(id, _)| *id).collect();
+        self.apply_and_notify(&surface_ids, &layer_ids)?;
+        let commit_seq = ivi_api.commit_count();
+
+        match surface_id {
+            Some(id) => {
+                jinfo!("Pending changes of surface {} committed", id);
+                Ok(json!({
+                    "success": true,
+                    "surface_id": id,
+                    "applied": applied,
+                    "commit_seq": commit_seq
+                }))
+            }
+            None => {
+                jinfo!("All pending changes committed");
+                Ok(json!({ "success": true, "applied": applied, "commit_seq": commit_seq }))
+            }
+        }
+    }
+
+    /// Handle batch request - run several operations, then commit at most once
+    ///
+    /// Each operation runs as if it had been sent on its own without
+    /// `auto_commit`, so it leaves its changes for the next commit. One that
+    /// fails is reported in its slot of `results` and does not stop the
+    /// others; with `commit`, the changes of those that succeeded are
+    /// committed.
+    fn handle_batch(
+        &self,
+        client_id: &ClientId,
+        operations: Vec<RpcMethod>,
+        commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
+            "Running batch of {} operations [commit={}]",
+            operations.len(),
+            commit
+        );
+
+        let mut failed = 0;
+        let results: Vec<serde_json::Value> = operations
+            .into_iter()
+            .map(|operation| {
+                let configured_layer = operation.configured_layer();
+                match self.dispatch(client_id, operation) {
+                    Ok(result) => {
+                        self.mark_uncommitted(configured_layer);
+                        json!({ "success": true, "result": result })
+                    }
+                    Err(error) => {
+                        failed += 1;
+                        json!({ "success": false, "error": error })
+                    }
+                }
+            })
+            .collect();
+
+        if failed > 0 {
+            jwarn!("{} of {} batched operations failed", failed, results.len());
+        }
+
+        if commit {
+            self.handle_commit(None)?;
+        }
+
+        Ok(json!({ "results": results, "committed": commit }))
+    }
+
+    /// Handle subscribe request - subscribe to event types
+    fn handle_subscribe(
+        &self,
+        client_id: &ClientId,
+        event_types: Vec<EventType>,
+    ) -> Result<serde_json::Value, RpcError> {
+        jinfo!(
+            "Client {} subscribing to {} event types",
+            client_id,
+            event_types.len()
+        );
+
+        // Disabled events are still subscribed, they just never arrive
+        let ignored: Vec<EventType> = {
+            let disabled_events = self.disabled_events.lock().unwrap();
+            let mut ignored: Vec<EventType> = Vec::new();
+            for event_type in &event_types {
+                if disabled_events.contains(event_type) && !ignored.contains(event_type) {
+                    ignored.push(*event_type);
+                }
+            }
+            ignored
+        };
 
         let subscription_manager = self.subscription_manager.lock().unwrap();
         let subscribed = subscription_manager
             .subscribe(client_id, event_types)
-            .map_err(RpcError::internal_error)?;
+            .map_err(RpcError::invalid_params)?;
 
         jinfo!(
             "Client {} successfully subscribed to {} event types",
@@ -649,9 +2211,18 @@ impl RpcHandler {
             subscribed.len()
         );
 
+        if !ignored.is_empty() {
+            jwarn!(
+                "Client {} subscribed to disabled event types {:?}",
+                client_id,
+                ignored
+            );
+        }
+
         Ok(json!({
             "success": true,
-            "subscribed": subscribed
+            "subscribed": subscribed,
+            "ignored": ignored
         }))
     }
 
@@ -667,992 +2238,3802 @@ impl RpcHandler {
             event_types.len()
         );
 
-        let subscription_manager = self.subscription_manager.lock().unwrap();
-        let unsubscribed = subscription_manager
-            .unsubscribe(client_id, event_types)
-            .map_err(RpcError::internal_error)?;
+        let subscription_manager = self.subscription_manager.lock().unwrap();
+        let unsubscribed = subscription_manager
+            .unsubscribe(client_id, event_types)
+            .map_err(RpcError::internal_error)?;
+
+        jinfo!(
+            "Client {} successfully unsubscribed from {} event types",
+            client_id,
+            unsubscribed.len()
+        );
+
+        Ok(json!({
+            "success": true,
+            "unsubscribed": unsubscribed
+        }))
+    }
+
+    /// Handle list_subscriptions request - list all active subscriptions for a client
+    fn handle_list_subscriptions(
+        &self,
+        client_id: &ClientId,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!("Listing subscriptions for client {}", client_id);
+
+        let subscription_manager = self.subscription_manager.lock().unwrap();
+        let subscriptions = subscription_manager.get_subscriptions(client_id);
+
+        jdebug!(
+            "Client {} has {} active subscriptions",
+            client_id,
+            subscriptions.len()
+        );
+
+        Ok(json!({
+            "subscriptions": subscriptions
+        }))
+    }
+
+    /// Handle get_all_subscriptions request - list the subscriptions of every client
+    ///
+    /// Admin method: only clients whose process runs as one of the admin uids
+    /// may call it.
+    fn handle_get_all_subscriptions(
+        &self,
+        client_id: &ClientId,
+    ) -> Result<serde_json::Value, RpcError> {
+        self.require_admin(client_id, "get_all_subscriptions")?;
+
+        let all = self
+            .subscription_manager
+            .lock()
+            .unwrap()
+            .all_subscriptions();
+        jdebug!("Listing subscriptions of {} clients", all.len());
+
+        let subscriptions: serde_json::Map<String, serde_json::Value> = all
+            .into_iter()
+            .map(|(client_id, event_types)| {
+                let key = match client_id.unix_domain_id() {
+                    Some(id) => id.to_string(),
+                    None => client_id.to_string(),
+                };
+                (key, json!(event_types))
+            })
+            .collect();
+
+        Ok(json!({ "subscriptions": subscriptions }))
+    }
+
+    /// Handle clear_subscriptions request - drop the subscriptions of one or every client
+    ///
+    /// Drops the subscriptions and buffered notifications of `target`, the
+    /// caller when `None`, or of every client with `all`. Clearing another
+    /// client or every client is an admin method meant for recovery.
+    fn handle_clear_subscriptions(
+        &self,
+        client_id: &ClientId,
+        target: Option<u64>,
+        all: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        let target = target.map_or_else(|| client_id.clone(), ClientId::from_u64);
+
+        // Clearing its own subscriptions is no more than unsubscribing
+        if all || target != *client_id {
+            self.require_admin(client_id, "clear_subscriptions")?;
+        }
+
+        let subscription_manager = self.subscription_manager.lock().unwrap();
+        let cleared = if all {
+            subscription_manager.clear_all()
+        } else {
+            let had_subscriptions = subscription_manager
+                .all_subscriptions()
+                .contains_key(&target);
+            subscription_manager.remove_client(&target);
+            usize::from(had_subscriptions)
+        };
+
+        jinfo!(
+            "Client {} cleared the subscriptions of {} clients",
+            client_id,
+            cleared
+        );
+
+        Ok(json!({ "success": true, "cleared_clients": cleared }))
+    }
+
+    /// Fail unless the client process runs as one of the admin uids
+    fn require_admin(&self, client_id: &ClientId, method: &str) -> Result<(), RpcError> {
+        let credentials = self
+            .transport
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|transport| transport.client_credentials(client_id));
+
+        let admin_uids = self.admin_uids.lock().unwrap();
+        match credentials {
+            Some(credentials) if admin_uids.contains(&credentials.uid) => Ok(()),
+            _ => {
+                jwarn!("Refusing admin method {} to client {}", method, client_id);
+                Err(RpcError::permission_denied(method))
+            }
+        }
+    }
+
+    /// Handle list_clients request
+    ///
+    /// Reports each connected client with the uid, gid and pid of its process,
+    /// or `null` where the transport cannot identify the peer.
+    fn handle_list_clients(&self) -> Result<serde_json::Value, RpcError> {
+        let transport = self.transport.lock().unwrap();
+        let transport = transport
+            .as_ref()
+            .ok_or_else(|| RpcError::internal_error("No transport registered".to_string()))?;
+
+        let mut client_ids = transport.get_connected_clients();
+        client_ids.sort_by_key(|client_id| (client_id.unix_domain_id(), client_id.to_string()));
+
+        let clients: Vec<serde_json::Value> = client_ids
+            .iter()
+            .map(|client_id| {
+                let credentials = transport.client_credentials(client_id);
+                let id = match client_id.unix_domain_id() {
+                    Some(id) => json!(id),
+                    None => json!(client_id.to_string()),
+                };
+                json!({
+                    "id": id,
+                    "uid": credentials.map(|c| c.uid),
+                    "gid": credentials.map(|c| c.gid),
+                    "pid": credentials.map(|c| c.pid),
+                })
+            })
+            .collect();
+
+        jdebug!("Listing {} connected clients", clients.len());
+
+        Ok(json!({ "clients": clients }))
+    }
+
+    /// Handle list_layers request
+    fn handle_list_layers(&self) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let layers = state_manager.get_all_layers();
+
+        let layer_list: Vec<serde_json::Value> = layers.iter().map(layer_state_to_json).collect();
+
+        Ok(json!({ "v": SCHEMA_VERSION, "layers": layer_list }))
+    }
+
+    fn handle_create_layer(
+        &self,
+        id: u32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!("Creating new layer with size {}x{}", width, height);
+
+        // Validate size
+        validation::validate_size(width, height)
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        // Create the layer via IVI API
+        let layer = ivi_api
+            .layer_create_with_dimension(id, width, height)
+            .map_err(|e| RpcError::internal_error(e.to_string()))?;
+
+        jinfo!("Created new layer with ID {}", layer.id());
+
+        // Commit changes only if auto_commit is true
+        if auto_commit {
+            self.auto_commit_changes(&[], &[id])?;
+        }
+
+        Ok(json!({
+            "id": layer.id(),
+            "committed": auto_commit,
+        }))
+    }
+
+    /// Handle destroy_layer request
+    fn handle_destroy_layer(
+        &self,
+        id: u32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!("Destroying layer {} [auto_commit={}]", id, auto_commit);
+
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+
+        // Verify layer exists before attempting to destroy
+        if !state_manager.has_layer(id) {
+            jwarn!("Layer not found: {}", id);
+            return Err(RpcError::layer_not_found(id));
+        }
+
+        drop(state_manager);
+
+        // Get the layer from the IVI API
+        let layer = ivi_api
+            .get_layer_from_id(id)
+            .ok_or_else(|| RpcError::internal_error(format!("Failed to get IVI layer {}", id)))?;
+
+        // Destroy the layer (consumes self)
+        layer
+            .destroy()
+            .map_err(|e| RpcError::internal_error(format!("Failed to destroy layer: {}", e)))?;
+
+        jinfo!("Layer {} destroyed", id);
+
+        // Commit changes if auto_commit is true
+        if auto_commit {
+            self.commit_now()?;
+
+            // Update internal state - the layer is now destroyed
+            let mut state_manager = self.state_manager.lock().unwrap();
+            state_manager.handle_layer_destroyed(id);
+
+            jinfo!("Layer {} destruction committed", id);
+        }
+
+        Ok(json!({ "success": true, "committed": auto_commit }))
+    }
+
+    /// Handle get_layer request
+    fn handle_get_layer(&self, id: u32) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+
+        match state_manager.get_layer(id) {
+            Some(layer) => {
+                jdebug!("Retrieved layer {}", id);
+                Ok(layer_state_to_json(&layer))
+            }
+            None => {
+                jwarn!("Layer not found: {}", id);
+                Err(RpcError::layer_not_found(id))
+            }
+        }
+    }
+
+    /// Handle layer_exists request
+    ///
+    /// Checks the tracked state first and falls back to the IVI API for
+    /// layers the state manager has not picked up yet.
+    fn handle_layer_exists(&self, id: u32) -> Result<serde_json::Value, RpcError> {
+        let tracked = self.state_manager.lock().unwrap().has_layer(id);
+        let exists = tracked || self.id_to_layer(id).is_some();
+
+        jdebug!("Layer {} exists: {}", id, exists);
+        Ok(json!({ "exists": exists }))
+    }
+
+    /// Handle set_layer_source_rectangle request
+    fn handle_set_layer_source_rectangle(
+        &self,
+        id: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        validation::validate_position(x, y).map_err(|e| RpcError::invalid_params(e.to_string()))?;
+        validation::validate_size(width, height)
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        let mut layer = self
+            .id_to_layer(id)
+            .ok_or_else(|| RpcError::layer_not_found(id))?;
+
+        layer
+            .set_source_rectangle(Rectangle {
+                x,
+                y,
+                width,
+                height,
+            })
+            .map_err(RpcError::internal_error)?;
+
+        // Commit changes only if auto_commit is true
+        if auto_commit {
+            self.auto_commit_changes(&[], &[id])?;
+        }
+
+        Ok(json!({ "success": true, "committed": auto_commit }))
+    }
+
+    /// Handle set_layer_destination_rectangle request
+    fn handle_set_layer_destination_rectangle(
+        &self,
+        id: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        validation::validate_position(x, y).map_err(|e| RpcError::invalid_params(e.to_string()))?;
+        validation::validate_size(width, height)
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        let mut layer = self
+            .id_to_layer(id)
+            .ok_or_else(|| RpcError::layer_not_found(id))?;
+
+        layer
+            .set_destination_rectangle(Rectangle {
+                x,
+                y,
+                width,
+                height,
+            })
+            .map_err(RpcError::internal_error)?;
+
+        // Commit changes only if auto_commit is true
+        if auto_commit {
+            self.auto_commit_changes(&[], &[id])?;
+        }
+
+        Ok(json!({ "success": true, "committed": auto_commit }))
+    }
+
+    /// Handle set_layer_position request
+    ///
+    /// Moves the destination rectangle and keeps its size. A relative move
+    /// starts from the last known destination rectangle of the layer.
+    fn handle_set_layer_position(
+        &self,
+        id: u32,
+        x: i32,
+        y: i32,
+        relative: bool,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        let (current_x, current_y, width, height) =
+            match self.state_manager.lock().unwrap().get_layer(id) {
+                Some(layer) => layer.dest_rect,
+                None => {
+                    jwarn!("Layer not found: {}", id);
+                    return Err(RpcError::layer_not_found(id));
+                }
+            };
+
+        let (x, y) = if relative {
+            (current_x.saturating_add(x), current_y.saturating_add(y))
+        } else {
+            (x, y)
+        };
+        validation::validate_position(x, y).map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        jdebug!(
+            "Moving layer {} to ({}, {}) [relative={}, auto_commit={}]",
+            id,
+            x,
+            y,
+            relative,
+            auto_commit
+        );
+
+        let mut layer = self
+            .id_to_layer(id)
+            .ok_or_else(|| RpcError::layer_not_found(id))?;
+
+        layer
+            .set_destination_rectangle(Rectangle {
+                x,
+                y,
+                width,
+                height,
+            })
+            .map_err(RpcError::internal_error)?;
+
+        if auto_commit {
+            self.auto_commit_changes(&[], &[id])?;
+        }
+
+        Ok(json!({ "success": true, "x": x, "y": y, "committed": auto_commit }))
+    }
+
+    /// Handle set_layer_visibility request
+    fn handle_set_layer_visibility(
+        &self,
+        id: u32,
+        visible: bool,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
+            "Setting layer {} visibility to {} [auto_commit={}]",
+            id,
+            visible,
+            auto_commit
+        );
+
+        let state_manager = self.state_manager.lock().unwrap();
+
+        // Check if layer exists
+        if !state_manager.has_layer(id) {
+            jwarn!("Layer not found: {}", id);
+            return Err(RpcError::layer_not_found(id));
+        }
+
+        // Get the IVI API and update the layer
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager); // Release lock before calling IVI API
+
+        let mut layer = ivi_api
+            .get_layer_from_id(id)
+            .ok_or_else(|| RpcError::internal_error(format!("Failed to get IVI layer {}", id)))?;
+
+        layer
+            .set_visibility(visible)
+            .map_err(RpcError::internal_error)?;
+
+        // Commit changes only if auto_commit is true
+        if auto_commit {
+            self.auto_commit_changes(&[], &[id])?;
+        }
+
+        Ok(json!({ "success": true, "committed": auto_commit }))
+    }
+
+    /// Handle set_layer_transition request
+    ///
+    /// The transition animates the layer changes applied by the next commit.
+    fn handle_set_layer_transition(
+        &self,
+        id: u32,
+        transition: LayerTransition,
+        duration: u32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
+            "Setting layer {} transition to {:?} for {} ms [auto_commit={}]",
+            id,
+            transition,
+            duration,
+            auto_commit
+        );
+
+        let state_manager = self.state_manager.lock().unwrap();
+
+        // Check if layer exists
+        if !state_manager.has_layer(id) {
+            jwarn!("Layer not found: {}", id);
+            return Err(RpcError::layer_not_found(id));
+        }
+
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager); // Release lock before calling IVI API
+
+        let mut layer = ivi_api
+            .get_layer_from_id(id)
+            .ok_or_else(|| RpcError::internal_error(format!("Failed to get IVI layer {}", id)))?;
+
+        let transition_type = match transition {
+            LayerTransition::None => IviLayoutTransitionType::NoneTransition,
+            LayerTransition::Fade => IviLayoutTransitionType::LayerFade,
+            LayerTransition::Move => IviLayoutTransitionType::LayerMove,
+            LayerTransition::ViewOrder => IviLayoutTransitionType::LayerViewOrder,
+        };
+        layer
+            .set_transition(transition_type, duration)
+            .map_err(RpcError::internal_error)?;
+
+        // Commit changes only if auto_commit is true
+        if auto_commit {
+            self.auto_commit_changes(&[], &[])?;
+        }
+
+        Ok(json!({ "success": true, "committed": auto_commit }))
+    }
+
+    /// Handle cancel_layer_transition request
+    ///
+    /// Stops an in-progress move animation; the layer stays where the
+    /// animation left it.
+    fn handle_cancel_layer_transition(&self, id: u32) -> Result<serde_json::Value, RpcError> {
+        jdebug!("Cancelling move transition of layer {}", id);
+
+        let state_manager = self.state_manager.lock().unwrap();
+
+        // Check if layer exists
+        if !state_manager.has_layer(id) {
+            jwarn!("Layer not found: {}", id);
+            return Err(RpcError::layer_not_found(id));
+        }
+
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager); // Release lock before calling IVI API
+
+        let mut layer = ivi_api
+            .get_layer_from_id(id)
+            .ok_or_else(|| RpcError::internal_error(format!("Failed to get IVI layer {}", id)))?;
+
+        layer
+            .cancel_move_transition()
+            .map_err(RpcError::internal_error)?;
+
+        Ok(json!({ "success": true }))
+    }
+
+    /// Handle set_layer_opacity request
+    ///
+    /// With `relative`, `opacity` is a delta added to the layer's current
+    /// opacity and the result is clamped to [0.0, 1.0].
+    fn handle_set_layer_opacity(
+        &self,
+        id: u32,
+        opacity: f32,
+        relative: bool,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+
+        // Check if layer exists
+        let layer_state = match state_manager.get_layer(id) {
+            Some(layer_state) => layer_state,
+            None => {
+                jwarn!("Layer not found: {}", id);
+                return Err(RpcError::layer_not_found(id));
+            }
+        };
+
+        let opacity = if relative {
+            (layer_state.opacity + opacity).clamp(0.0, 1.0)
+        } else {
+            opacity
+        };
+
+        // Validate opacity
+        validation::validate_opacity(opacity)
+            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+
+        jdebug!(
+            "Setting layer {} opacity to {} [relative={}, auto_commit={}]",
+            id,
+            opacity,
+            relative,
+            auto_commit
+        );
+
+        // Get the IVI API and update the layer
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager); // Release lock before calling IVI API
+
+        let mut layer = ivi_api
+            .get_layer_from_id(id)
+            .ok_or_else(|| RpcError::internal_error(format!("Failed to get IVI layer {}", id)))?;
+
+        layer
+            .set_opacity(opacity)
+            .map_err(RpcError::internal_error)?;
+
+        // Commit changes only if auto_commit is true
+        if auto_commit {
+            self.auto_commit_changes(&[], &[id])?;
+        }
+
+        Ok(json!({ "success": true, "opacity": opacity, "committed": auto_commit }))
+    }
+
+    /// Handle set_layer_surfaces: Replace all surfaces on a layer
+    fn handle_set_layer_surfaces(
+        &self,
+        layer_id: u32,
+        surface_ids: Vec<u32>,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
+            "Setting layer {} surfaces to {:?} [auto_commit={}]",
+            layer_id,
+            surface_ids,
+            auto_commit
+        );
+
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+
+        // Get layer and verify it exists
+        let layer = ivi_api
+            .get_layer_from_id(layer_id)
+            .ok_or_else(|| RpcError::layer_not_found(layer_id))?;
+
+        // Build surface vector by getting each surface from ID
+        let surfaces: Vec<_> = surface_ids
+            .iter()
+            .filter_map(|&id| ivi_api.get_surface_from_id(id))
+            .collect();
+
+        // Verify all surfaces were found
+        if surfaces.len() != surface_ids.len() {
+            return Err(RpcError::internal_error(
+                "Some surfaces not found".to_string(),
+            ));
+        }
+
+        drop(state_manager);
+
+        // Build reference slice: first = bottommost, last = topmost
+        let surface_refs: Vec<&_> = surfaces.iter().collect();
+
+        // Set render order
+        ivi_api
+            .layer_set_render_order(&layer, &surface_refs)
+            .map_err(|e| RpcError::internal_error(format!("Failed to set render order: {}", e)))?;
+
+        // Commit if requested
+        if auto_commit {
+            self.auto_commit_changes(&[], &[])?;
+        }
+
+        Ok(json!({
+            "layer_id": layer_id,
+            "surface_ids": surface_ids,
+            "committed": auto_commit
+        }))
+    }
+
+    /// Handle add_surface_to_layer: Add a surface as topmost
+    fn handle_add_surface_to_layer(
+        &self,
+        layer_id: u32,
+        surface_id: u32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
+            "Adding surface {} to layer {} as topmost [auto_commit={}]",
+            surface_id,
+            layer_id,
+            auto_commit
+        );
+
+        let state_manager = self.state_manager.lock().unwrap();
+        if !state_manager.has_layer(layer_id) {
+            jwarn!("Layer not found: {}", layer_id);
+            return Err(RpcError::layer_not_found(layer_id));
+        }
+        let ivi_api = state_manager.ivi_api().clone();
+
+        // Get layer and verify it exists
+        let layer = ivi_api
+            .get_layer_from_id(layer_id)
+            .ok_or_else(|| RpcError::layer_not_found(layer_id))?;
+
+        // Get current surfaces on the layer
+        let mut surfaces = ivi_api.get_surfaces_on_layer(&layer);
+
+        // Get new surface and verify it exists
+        let new_surface = ivi_api
+            .get_surface_from_id(surface_id)
+            .ok_or_else(|| RpcError::surface_not_found(surface_id))?;
+
+        drop(state_manager);
+
+        // Append new surface to end (topmost position)
+        surfaces.push(new_surface);
+
+        // Build reference slice
+        let surface_refs: Vec<&_> = surfaces.iter().collect();
+
+        // Set render order
+        ivi_api
+            .layer_set_render_order(&layer, &surface_refs)
+            .map_err(|e| RpcError::internal_error(format!("Failed to set render order: {}", e)))?;
+
+        // Commit if requested
+        if auto_commit {
+            self.auto_commit_changes(&[], &[])?;
+        }
+
+        Ok(json!({
+            "layer_id": layer_id,
+            "surface_id": surface_id,
+            "committed": auto_commit
+        }))
+    }
+
+    /// Handle remove_surface_from_layer: Remove a surface from a layer
+    fn handle_remove_surface_from_layer(
+        &self,
+        layer_id: u32,
+        surface_id: u32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        jdebug!(
+            "Removing surface {} from layer {} [auto_commit={}]",
+            surface_id,
+            layer_id,
+            auto_commit
+        );
+
+        let state_manager = self.state_manager.lock().unwrap();
+        if !state_manager.has_layer(layer_id) {
+            jwarn!("Layer not found: {}", layer_id);
+            return Err(RpcError::layer_not_found(layer_id));
+        }
+        let ivi_api = state_manager.ivi_api().clone();
+
+        // Get layer and verify it exists
+        let layer = ivi_api
+            .get_layer_from_id(layer_id)
+            .ok_or_else(|| RpcError::layer_not_found(layer_id))?;
+
+        // Get surface to remove and verify it exists
+        let surface = ivi_api
+            .get_surface_from_id(surface_id)
+            .ok_or_else(|| RpcError::surface_not_found(surface_id))?;
+
+        drop(state_manager);
+
+        // Remove surface from layer
+        ivi_api
+            .layer_remove_surface(&layer, &surface)
+            .map_err(|e| {
+                RpcError::internal_error(format!("Failed to remove surface from layer: {}", e))
+            })?;
+
+        // Commit if requested
+        if auto_commit {
+            self.auto_commit_changes(&[], &[])?;
+        }
+
+        Ok(json!({
+            "layer_id": layer_id,
+            "surface_id": surface_id,
+            "committed": auto_commit
+        }))
+    }
+
+    /// Handle get_layer_surfaces: Get surfaces assigned to a layer
+    fn handle_get_layer_surfaces(&self, layer_id: u32) -> Result<serde_json::Value, RpcError> {
+        jdebug!("Getting surfaces for layer {}", layer_id);
+
+        let state_manager = self.state_manager.lock().unwrap();
+        if !state_manager.has_layer(layer_id) {
+            jwarn!("Layer not found: {}", layer_id);
+            return Err(RpcError::layer_not_found(layer_id));
+        }
+        let ivi_api = state_manager.ivi_api().clone();
+
+        // Get layer and verify it exists
+        let layer = ivi_api
+            .get_layer_from_id(layer_id)
+            .ok_or_else(|| RpcError::layer_not_found(layer_id))?;
+
+        drop(state_manager);
+
+        // Get surfaces on the layer
+        let surfaces = ivi_api.get_surfaces_on_layer(&layer);
+
+        // Extract surface IDs (order preserved: first = bottommost, last = topmost)
+        let surface_ids: Vec<u32> = surfaces.iter().map(|s| s.id()).collect();
+
+        Ok(json!({ "id": layer_id, "surface_ids": surface_ids }))
+    }
+
+    /// Handle get_layer_render_order request
+    ///
+    /// Returns the surface IDs of a layer in render order, bottommost first,
+    /// in the form accepted by `set_layer_surfaces`.
+    fn handle_get_layer_render_order(&self, id: u32) -> Result<serde_json::Value, RpcError> {
+        self.handle_get_layer_surfaces(id)
+    }
+}
+
+/// Convert a LayerState to JSON
+fn layer_state_to_json(layer: &LayerState) -> serde_json::Value {
+    json!({
+        "v": SCHEMA_VERSION,
+        "id": layer.id,
+        "src_rect": {
+            "x": layer.src_rect.0,
+            "y": layer.src_rect.1,
+            "width": layer.src_rect.2,
+            "height": layer.src_rect.3,
+        },
+        "dest_rect": {
+            "x": layer.dest_rect.0,
+            "y": layer.dest_rect.1,
+            "width": layer.dest_rect.2,
+            "height": layer.dest_rect.3,
+        },
+        "visibility": layer.visibility,
+        "opacity": layer.opacity,
+        "orientation": layer.orientation,
+    })
+}
+
+/// Round an event rate to two decimals for `get_metrics`
+fn round_rate(rate: f64) -> f64 {
+    (rate * 100.0).round() / 100.0
+}
+
+/// Check whether a surface is actually rendered on a screen
+///
+/// A surface is on screen only if it is visible, and at least one of the
+/// layers it belongs to is visible and assigned to a screen.
+fn is_surface_on_screen(ivi_api: &IviLayoutApi, surface: &SurfaceState) -> bool {
+    if !surface.visibility {
+        return false;
+    }
+
+    let ivi_surface = match ivi_api.get_surface_from_id(surface.id) {
+        Some(ivi_surface) => ivi_surface,
+        None => return false,
+    };
+
+    let layers = match ivi_api.get_layers_under_surface(&ivi_surface) {
+        Ok(layers) => layers,
+        Err(e) => {
+            jwarn!("Failed to get layers for surface {}: {}", surface.id, e);
+            return false;
+        }
+    };
+
+    layers.iter().any(|layer| {
+        layer.visibility()
+            && ivi_api
+                .get_screens_under_layer(layer)
+                .map(|screens| !screens.is_empty())
+                .unwrap_or(false)
+    })
+}
+
+/// Convert a SurfaceState to JSON
+pub(crate) fn surface_state_to_json(surface: &SurfaceState, on_screen: bool) -> serde_json::Value {
+    json!({
+        "v": SCHEMA_VERSION,
+        "id": surface.id,
+        "orig_size": {
+            "width": surface.orig_size.0,
+            "height": surface.orig_size.1,
+        },
+        "src_rect": {
+            "x": surface.src_rect.x,
+            "y": surface.src_rect.y,
+            "width": surface.src_rect.width,
+            "height": surface.src_rect.height,
+        },
+        "dest_rect": {
+            "x": surface.dest_rect.x,
+            "y": surface.dest_rect.y,
+            "width": surface.dest_rect.width,
+            "height": surface.dest_rect.height,
+        },
+        "visibility": surface.visibility,
+        "opacity": surface.opacity,
+        "orientation": surface.orientation,
+        "z_order": surface.z_order,
+        "on_screen": on_screen,
+        "surface_type": surface.surface_type.as_str(),
+    })
+}
+
+impl RpcHandler {
+    /// List all screens
+    fn handle_list_screens(&self) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        let screens = ivi_api.get_screens();
+        let screen_infos: Vec<serde_json::Value> = screens
+            .iter()
+            .map(|output| {
+                let info = ScreenInfo::from(output.clone());
+                json!({
+                    "name": info.name,
+                    "width": info.width,
+                    "height": info.height,
+                    "x": info.coord_global.x,
+                    "y": info.coord_global.y,
+                    "transform": info.transform.to_string(),
+                    "enabled": info.enabled,
+                    "scale": info.scale,
+                })
+            })
+            .collect();
+
+        Ok(json!({ "screens": screen_infos }))
+    }
+
+    /// Get a specific screen by name
+    fn handle_get_screen(&self, name: String) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        let screens = ivi_api.get_screens();
+        let screen = screens
+            .iter()
+            .find(|output| output.name() == Some(name.clone()))
+            .ok_or_else(|| RpcError::internal_error(format!("Screen '{}' not found", name)))?;
+
+        let screen_info = ScreenInfo::from(screen.clone());
+
+        Ok(json!({
+            "name": screen_info.name,
+            "width": screen_info.width,
+            "height": screen_info.height,
+            "x": screen_info.coord_global.x,
+            "y": screen_info.coord_global.y,
+            "transform": screen_info.transform.to_string(),
+            "enabled": screen_info.enabled,
+            "scale": screen_info.scale,
+        }))
+    }
+
+    /// Geometry of a compositor output, identified by its index in the output list
+    fn output_to_json(index: usize, info: ScreenInfo) -> serde_json::Value {
+        json!({
+            "index": index,
+            "name": info.name,
+            "width": info.width,
+            "height": info.height,
+            "x": info.coord_global.x as i32,
+            "y": info.coord_global.y as i32,
+        })
+    }
+
+    /// List the geometry of all compositor outputs
+    fn handle_list_outputs(&self) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        let outputs: Vec<serde_json::Value> = ivi_api
+            .get_screens()
+            .iter()
+            .enumerate()
+            .map(|(index, output)| Self::output_to_json(index, ScreenInfo::from(output.clone())))
+            .collect();
+
+        Ok(json!({ "outputs": outputs }))
+    }
+
+    /// Get the geometry of a compositor output by index
+    fn handle_get_output(&self, index: u32) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        let screens = ivi_api.get_screens();
+        let output = screens.get(index as usize).ok_or_else(|| {
+            RpcError::invalid_params(format!(
+                "Output index {} out of range ({} outputs)",
+                index,
+                screens.len()
+            ))
+        })?;
+
+        Ok(Self::output_to_json(
+            index as usize,
+            ScreenInfo::from(output.clone()),
+        ))
+    }
+
+    /// Get layers assigned to a screen
+    fn handle_get_screen_layers(&self, screen_name: String) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        let screens = ivi_api.get_screens();
+        let screen = screens
+            .iter()
+            .find(|output| output.name() == Some(screen_name.clone()))
+            .ok_or_else(|| {
+                RpcError::internal_error(format!("Screen '{}' not found", screen_name))
+            })?;
+
+        let layers;
+        unsafe {
+            layers = ivi_api
+                .get_layers_on_screen((*screen).clone().into())
+                .map_err(|e| RpcError::internal_error(format!("Failed to get layers: {}", e)))?;
+        }
+        let layer_ids: Vec<u32> = layers.iter().map(|layer| layer.id()).collect();
+
+        Ok(json!({ "layer_ids": layer_ids }))
+    }
+
+    /// Get the complete scene: surfaces, layers with their surface render
+    /// order, and screens with their layer render order
+    fn handle_get_scene(&self) -> Result<serde_json::Value, RpcError> {
+        jdebug!("Building scene snapshot");
+
+        let state_manager = self.state_manager.lock().unwrap();
+        let surfaces = state_manager.get_all_surfaces();
+        let layers = state_manager.get_all_layers();
+        let ivi_api = state_manager.ivi_api().clone();
+        drop(state_manager);
+
+        let surface_list: Vec<serde_json::Value> = surfaces
+            .iter()
+            .map(|surface| surface_state_to_json(surface, is_surface_on_screen(&ivi_api, surface)))
+            .collect();
+
+        let layer_list: Vec<serde_json::Value> = layers
+            .iter()
+            .map(|layer| {
+                let surface_ids: Vec<u32> = ivi_api
+                    .get_layer_from_id(layer.id)
+                    .map(|l| {
+                        ivi_api
+                            .get_surfaces_on_layer(&l)
+                            .iter()
+                            .map(|s| s.id())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let mut value = layer_state_to_json(layer);
+                value["surface_ids"] = json!(surface_ids);
+                value
+            })
+            .collect();
+
+        let mut screen_list = Vec::new();
+        for screen in ivi_api.get_screens() {
+            let name = match screen.name() {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let layers;
+            unsafe {
+                layers = ivi_api.get_layers_on_screen(screen.into()).map_err(|e| {
+                    RpcError::internal_error(format!("Failed to get layers: {}", e))
+                })?;
+            }
+            let layer_ids: Vec<u32> = layers.iter().map(|layer| layer.id()).collect();
+
+            screen_list.push(json!({ "name": name, "layer_ids": layer_ids }));
+        }
+
+        Ok(json!({
+            "v": SCHEMA_VERSION,
+            "surfaces": surface_list,
+            "layers": layer_list,
+            "screens": screen_list,
+        }))
+    }
+
+    /// Get screens assigned to a layer
+    fn handle_get_layer_screens(&self, layer_id: u32) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+
+        // Get the layer
+        let layer = ivi_api
+            .get_layer_from_id(layer_id)
+            .ok_or_else(|| RpcError::layer_not_found(layer_id))?;
+
+        drop(state_manager);
+
+        let screens = ivi_api
+            .get_screens_under_layer(&layer)
+            .map_err(|e| RpcError::internal_error(format!("Failed to get screens: {}", e)))?;
+
+        let screen_names: Vec<String> = screens.iter().filter_map(|output| output.name()).collect();
+
+        Ok(json!({ "screen_names": screen_names }))
+    }
+
+    /// Add layers to a screen (sets render order, replaces existing)
+    fn handle_add_layers_to_screen(
+        &self,
+        screen_name: String,
+        layer_ids: Vec<u32>,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+
+        // Find the screen
+        let screens = ivi_api.get_screens();
+        let screen = screens
+            .iter()
+            .find(|output| output.name() == Some(screen_name.clone()))
+            .ok_or_else(|| {
+                RpcError::internal_error(format!("Screen '{}' not found", screen_name))
+            })?;
+
+        // Verify all layers exist and build layer array
+        let layers: Vec<_> = layer_ids
+            .iter()
+            .filter_map(|&id| {
+                state_manager.get_layer(id)?;
+                ivi_api.get_layer_from_id(id)
+            })
+            .collect();
+
+        // Verify we got all layers
+        if layers.len() != layer_ids.len() {
+            return Err(RpcError::internal_error(
+                "Some layers not found".to_string(),
+            ));
+        }
+
+        drop(state_manager);
+
+        // Set render order - convert Vec<IviLayer> to &[&IviLayer]
+        let layer_refs: Vec<&_> = layers.iter().collect();
+        ivi_api
+            .screen_set_render_order((*screen).clone(), &layer_refs)
+            .map_err(|e| RpcError::internal_error(format!("Failed to set render order: {}", e)))?;
+
+        if auto_commit {
+            self.auto_commit_changes(&[], &[])?;
+        }
+
+        Ok(json!({
+            "screen_name": screen_name,
+            "layer_ids": layer_ids,
+            "committed": auto_commit
+        }))
+    }
+
+    /// Remove a layer from a screen
+    fn handle_remove_layer_from_screen(
+        &self,
+        screen_name: String,
+        layer_id: u32,
+        auto_commit: bool,
+    ) -> Result<serde_json::Value, RpcError> {
+        let state_manager = self.state_manager.lock().unwrap();
+        let ivi_api = state_manager.ivi_api().clone();
+
+        // Find the screen
+        let screens = ivi_api.get_screens();
+        let screen = screens
+            .iter()
+            .find(|output| output.name() == Some(screen_name.clone()))
+            .ok_or_else(|| {
+                RpcError::internal_error(format!("Screen '{}' not found", screen_name))
+            })?;
+
+        // Get the layer using get_layer_from_id
+        let layer = ivi_api
+            .get_layer_from_id(layer_id)
+            .ok_or_else(|| RpcError::layer_not_found(layer_id))?;
+
+        drop(state_manager);
+
+        // Remove layer from screen
+        ivi_api
+            .screen_remove_layer(screen.clone(), &layer)
+            .map_err(|e| RpcError::internal_error(format!("Failed to remove layer: {}", e)))?;
+
+        if auto_commit {
+            self.auto_commit_changes(&[], &[])?;
+        }
+
+        Ok(json!({
+            "screen_name": screen_name,
+            "layer_id": layer_id,
+            "committed": auto_commit
+        }))
+    }
+}
+
+/// Message handler implementation that bridges transport and RPC handler
+struct RpcMessageHandler {
+    rpc_handler: Arc<RpcHandler>,
+}
+
+impl MessageHandler for RpcMessageHandler {
+    fn handle_message(&self, client_id: &ClientId, data: &[u8]) {
+        serve_message(&self.rpc_handler.transport, client_id, data, |request| {
+            self.rpc_handler.handle_request(client_id, request)
+        });
+    }
+
+    fn handle_disconnect(&self, client_id: &ClientId) {
+        // Log the disconnection
+        jinfo!("Client {} disconnected", client_id);
+
+        // Clean up subscriptions for this client
+        self.rpc_handler
+            .subscription_manager
+            .lock()
+            .unwrap()
+            .remove_client(client_id);
+
+        jdebug!("Cleaned up subscriptions for client {}", client_id);
+    }
+
+    fn poll(&self) {
+        self.rpc_handler.flush_expired_commit();
+    }
+}
+
+/// Write staged surface changes to the IVI layout, returning how many were written
+///
+/// Changes of surfaces that have been destroyed since they were staged are
+/// dropped.
+fn apply_staged_changes(
+    ivi_api: &IviLayoutApi,
+    staged: &[(u32, Vec<SurfaceChange>)],
+) -> Result<usize, RpcError> {
+    let mut applied = 0;
+    for (id, changes) in staged {
+        let mut surface = match ivi_api.get_surface_from_id(*id) {
+            Some(surface) => surface,
+            None => {
+                jwarn!("Dropping staged changes of destroyed surface {}", id);
+                continue;
+            }
+        };
+        for change in changes {
+            apply_surface_change(&mut surface, change)?;
+            applied += 1;
+        }
+    }
+    Ok(applied)
+}
+
+/// Write a staged surface change to the IVI layout
+fn apply_surface_change(surface: &mut IviSurface, change: &SurfaceChange) -> Result<(), RpcError> {
+    match *change {
+        SurfaceChange::SourceRectangle(rect) => surface.set_source_rectangle(rect),
+        SurfaceChange::DestinationRectangle(rect) => surface.set_destination_rectangle(rect),
+        SurfaceChange::Visibility(visible) => surface.set_visibility(visible),
+        SurfaceChange::Opacity(opacity) => surface.set_opacity(opacity),
+        SurfaceChange::Orientation(orientation) => surface.set_orientation(orientation),
+    }
+    .map_err(RpcError::internal_error)
+}
+
+/// Message handler for the read-only monitoring transport
+struct MonitoringMessageHandler {
+    rpc_handler: Arc<RpcHandler>,
+}
+
+impl MessageHandler for MonitoringMessageHandler {
+    fn handle_message(&self, client_id: &ClientId, data: &[u8]) {
+        serve_message(
+            &self.rpc_handler.metrics_transport,
+            client_id,
+            data,
+            |request| {
+                self.rpc_handler
+                    .handle_monitoring_request(client_id, request)
+            },
+        );
+    }
+
+    fn handle_disconnect(&self, client_id: &ClientId) {
+        // Monitoring clients cannot subscribe, so there is nothing to clean up
+        jinfo!("Monitoring client {} disconnected", client_id);
+    }
+}
+
+/// Parse a request, handle it and send the response back over `transport`
+fn serve_message<F>(
+    transport: &Mutex<Option<Box<dyn Transport>>>,
+    client_id: &ClientId,
+    data: &[u8],
+    handle: F,
+) where
+    F: FnOnce(RpcRequest) -> RpcResponse,
+{
+    jtrace!("Received message from client {}", client_id);
+
+    // Parse the incoming message as an RPC request
+    let request = match RpcRequest::from_json(data) {
+        Ok(req) => req,
+        Err(e) => {
+            // If we can't parse the request, we can't send a proper response
+            // because we don't have a request ID
+            jerror!(
+                "Failed to parse RPC request from client {}: {:?}",
+                client_id,
+                e
+            );
+            return;
+        }
+    };
+
+    // Handle the request
+    let response = handle(request);
+
+    // Serialize the response
+    let response_data = match response.to_json() {
+        Ok(data) => data,
+        Err(e) => {
+            jerror!(
+                "Failed to serialize RPC response for client {}: {:?}",
+                client_id,
+                e
+            );
+            return;
+        }
+    };
+
+    // Send the response back to the client
+    jdebug!(
+        "Sending response to client {}, {} bytes",
+        client_id,
+        response_data.len()
+    );
+    let transport_lock = transport.lock().unwrap();
+    if let Some(transport) = transport_lock.as_ref() {
+        match transport.send(client_id, &response_data) {
+            Ok(_) => {
+                jdebug!("Successfully sent response to client {}", client_id);
+            }
+            Err(e) => {
+                jerror!(
+                    "Failed to send RPC response to client {}: {:?}",
+                    client_id,
+                    e
+                );
+            }
+        }
+    } else {
+        jwarn!(
+            "No transport available to send response to client {}",
+            client_id
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::notifications::{Notification, NotificationType};
+    use crate::ffi::bindings::{
+        f32_to_wl_fixed_t, ivi_layout_interface, ivi_layout_layer, ivi_layout_layer_properties,
+        ivi_layout_surface, ivi_layout_surface_properties, wl_fixed_t,
+    };
+    use crate::rpc::transport::ClientCredentials;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+    /// Mock transport for testing
+    struct MockTransport {
+        started: AtomicBool,
+        stopped: AtomicBool,
+        last_client_id: AtomicU64,
+        last_message: Mutex<Vec<u8>>,
+        handler: Mutex<Option<Box<dyn MessageHandler>>>,
+        connected_clients: Vec<ClientId>,
+        broadcasts: Arc<Mutex<Vec<(Vec<ClientId>, Vec<u8>)>>>,
+        /// Simulates a full socket buffer on every client
+        would_block: Arc<AtomicBool>,
+        credentials: HashMap<ClientId, ClientCredentials>,
+    }
+
+    impl MockTransport {
+        fn new() -> Self {
+            Self {
+                started: AtomicBool::new(false),
+                stopped: AtomicBool::new(false),
+                last_client_id: AtomicU64::new(0),
+                last_message: Mutex::new(Vec::new()),
+                handler: Mutex::new(None),
+                connected_clients: vec![ClientId::from_u64(1)],
+                broadcasts: Arc::new(Mutex::new(Vec::new())),
+                would_block: Arc::new(AtomicBool::new(false)),
+                credentials: HashMap::new(),
+            }
+        }
+    }
+
+    impl Transport for MockTransport {
+        fn start(&mut self) -> Result<(), TransportError> {
+            self.started.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn stop(&mut self) -> Result<(), TransportError> {
+            self.stopped.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn send(&self, client_id: &ClientId, data: &[u8]) -> Result<(), TransportError> {
+            if let Some(id) = client_id.unix_domain_id() {
+                self.last_client_id.store(id, Ordering::SeqCst);
+                *self.last_message.lock().unwrap() = data.to_vec();
+                Ok(())
+            } else {
+                Err(TransportError::SendError("Invalid client ID".to_string()))
+            }
+        }
+
+        fn send_to_clients(
+            &self,
+            client_ids: &[&ClientId],
+            data: &[u8],
+        ) -> Result<(), TransportError> {
+            if self.would_block.load(Ordering::SeqCst) {
+                return Err(TransportError::PartialSendError(
+                    client_ids
+                        .iter()
+                        .map(|&id| {
+                            let e = TransportError::WouldBlock("socket buffer full".to_string());
+                            (id.clone(), e)
+                        })
+                        .collect(),
+                ));
+            }
+
+            self.broadcasts.lock().unwrap().push((
+                client_ids.iter().map(|&id| id.clone()).collect(),
+                data.to_vec(),
+            ));
+            for &client_id in client_ids {
+                self.send(client_id, data)?;
+            }
+            Ok(())
+        }
+
+        fn get_connected_clients(&self) -> Vec<ClientId> {
+            self.connected_clients.clone()
+        }
+
+        fn client_credentials(&self, client_id: &ClientId) -> Option<ClientCredentials> {
+            self.credentials.get(client_id).copied()
+        }
+
+        fn register_handler(&mut self, handler: Box<dyn MessageHandler>) {
+            *self.handler.lock().unwrap() = Some(handler);
+        }
+    }
+
+    // Helper to create a mock state manager for testing
+    fn create_mock_state_manager() -> Arc<Mutex<StateManager>> {
+        // Create a mock IVI API with a null pointer (safe for testing as we won't call IVI functions)
+        let ivi_api = {
+            // For testing, we create a dummy API pointer
+            // This is safe because our tests don't actually call IVI functions
+            Arc::new(IviLayoutApi::from_raw(std::ptr::dangling()).unwrap())
+        };
+        Arc::new(Mutex::new(StateManager::new(ivi_api)))
+    }
+
+    #[test]
+    fn test_transport_registration() {
+        let state_manager = create_mock_state_manager();
+
+        // Create RPC handler
+        let rpc_handler = RpcHandler::new(state_manager);
+
+        // Create and register mock transport
+        let transport = Box::new(MockTransport::new());
+        let result = rpc_handler.register_transport(transport);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_transport_start_stop() {
+        let state_manager = create_mock_state_manager();
+
+        // Create RPC handler
+        let rpc_handler = RpcHandler::new(state_manager);
+
+        // Create and register mock transport
+        let transport = Box::new(MockTransport::new());
+        rpc_handler.register_transport(transport).unwrap();
+
+        // Start transport
+        let result = rpc_handler.start_transport();
+        assert!(result.is_ok());
+
+        // Stop transport
+        let result = rpc_handler.stop_transport();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_message_handler_integration() {
+        let state_manager = create_mock_state_manager();
+
+        // Create RPC handler
+        let rpc_handler = RpcHandler::new(state_manager);
+
+        // Create and register mock transport
+        let transport = Box::new(MockTransport::new());
+        rpc_handler.register_transport(transport).unwrap();
+
+        // Create a list_surfaces request
+        let request = RpcRequest::new(1, "list_surfaces".to_string(), json!({}));
+        let _request_data = request.to_json().unwrap();
+
+        // Verify the transport is registered
+        let transport_lock = rpc_handler.transport.lock().unwrap();
+        assert!(transport_lock.is_some());
+    }
+
+    #[test]
+    fn test_notification_broadcast_reaches_all_subscribers() {
+        let subscription_manager = Mutex::new(SubscriptionManager::new());
+        let mut mock = MockTransport::new();
+        mock.connected_clients = (1..=3).map(ClientId::from_u64).collect();
+        let broadcasts = Arc::clone(&mock.broadcasts);
+        let transport: Mutex<Option<Box<dyn Transport>>> = Mutex::new(Some(Box::new(mock)));
+
+        {
+            let manager = subscription_manager.lock().unwrap();
+            manager
+                .subscribe(&ClientId::from_u64(1), vec![EventType::SurfaceCreated])
+                .unwrap();
+            manager
+                .subscribe(&ClientId::from_u64(2), vec![EventType::SurfaceCreated])
+                .unwrap();
+            manager
+                .subscribe(&ClientId::from_u64(3), vec![EventType::LayerCreated])
+                .unwrap();
+
+            let notification = RpcNotification::new(
+                "notification".to_string(),
+                json!({"event_type": "SurfaceCreated", "surface_id": 1000}),
+            );
+            manager.queue_notification(EventType::SurfaceCreated, notification);
+        }
+
+        RpcHandler::deliver_notifications(&subscription_manager, &transport, &mut HashMap::new());
+
+        let broadcasts = broadcasts.lock().unwrap();
+        assert_eq!(broadcasts.len(), 1);
+
+        let mut targets = broadcasts[0].0.clone();
+        targets.sort_by_key(|id| id.unix_domain_id());
+        assert_eq!(targets, vec![ClientId::from_u64(1), ClientId::from_u64(2)]);
+    }
+
+    #[test]
+    fn test_interleaved_notifications_delivered_in_order() {
+        use crate::controller::notifications::{
+            GeometryType, NotificationManager, NotificationType,
+        };
+        use crate::rpc::notification_bridge::NotificationBridge;
+
+        let subscription_manager = Arc::new(Mutex::new(SubscriptionManager::new()));
+        let mut mock = MockTransport::new();
+        mock.connected_clients = vec![ClientId::from_u64(1), ClientId::from_u64(2)];
+        let broadcasts = Arc::clone(&mock.broadcasts);
+        let transport: Mutex<Option<Box<dyn Transport>>> = Mutex::new(Some(Box::new(mock)));
+
+        // Wire the notification manager to the subscription manager as the plugin does
+        let bridge = Arc::new(NotificationBridge::new(Arc::clone(&subscription_manager)));
+        let mut nm = NotificationManager::new();
+        for nt in [
+            NotificationType::GeometryChanged,
+            NotificationType::VisibilityChanged,
+        ] {
+            let bridge = Arc::clone(&bridge);
+            nm.register_callback(nt, Arc::new(move |n| bridge.handle_notification(n)));
+        }
+
+        {
+            let manager = subscription_manager.lock().unwrap();
+            manager
+                .subscribe(
+                    &ClientId::from_u64(1),
+                    vec![
+                        EventType::DestinationGeometryChanged,
+                        EventType::VisibilityChanged,
+                    ],
+                )
+                .unwrap();
+            manager
+                .subscribe(&ClientId::from_u64(2), vec![EventType::VisibilityChanged])
+                .unwrap();
+        }
+
+        // Rapid interleaved geometry and visibility changes on two surfaces
+        let mut expected = Vec::new();
+        for step in 0..5 {
+            for surface_id in [1000, 1001] {
+                let old_rect = Rectangle {
+                    x: step,
+                    y: 0,
+                    width: 100,
+                    height: 100,
+                };
+                let new_rect = Rectangle {
+                    x: step + 1,
+                    ..old_rect
+                };
+                nm.emit_geometry_change(surface_id, GeometryType::Destination, old_rect, new_rect);
+                expected.push(("DestinationGeometryChanged", surface_id));
+
+                nm.emit_visibility_change(surface_id, step % 2 == 0, step % 2 != 0);
+                expected.push(("VisibilityChanged", surface_id));
+            }
+        }
+
+        RpcHandler::deliver_notifications(&subscription_manager, &transport, &mut HashMap::new());
+
+        // Rebuild what each client received, in send order
+        let mut received: HashMap<ClientId, Vec<serde_json::Value>> = HashMap::new();
+        for (targets, payload) in broadcasts.lock().unwrap().iter() {
+            let notification: serde_json::Value = serde_json::from_slice(payload).unwrap();
+            for client_id in targets {
+                received
+                    .entry(client_id.clone())
+                    .or_default()
+                    .push(notification["params"].clone());
+            }
+        }
+
+        let describe = |params: &serde_json::Value| {
+            (
+                params["event_type"].as_str().unwrap().to_string(),
+                params["surface_id"].as_u64().unwrap() as u32,
+            )
+        };
 
-        jinfo!(
-            "Client {} successfully unsubscribed from {} event types",
-            client_id,
-            unsubscribed.len()
-        );
+        // Client 1 sees every event, none dropped or reordered
+        let client1: Vec<_> = received[&ClientId::from_u64(1)]
+            .iter()
+            .map(describe)
+            .collect();
+        let expected1: Vec<_> = expected
+            .iter()
+            .map(|(event, id)| (event.to_string(), *id))
+            .collect();
+        assert_eq!(client1, expected1);
 
-        Ok(json!({
-            "success": true,
-            "unsubscribed": unsubscribed
-        }))
+        // Client 2 only sees visibility events, still in emission order
+        let client2: Vec<_> = received[&ClientId::from_u64(2)]
+            .iter()
+            .map(describe)
+            .collect();
+        let expected2: Vec<_> = expected
+            .iter()
+            .filter(|(event, _)| *event == "VisibilityChanged")
+            .map(|(event, id)| (event.to_string(), *id))
+            .collect();
+        assert_eq!(client2, expected2);
+
+        // Geometry payloads arrive with their own values, in step order per surface
+        let xs: Vec<i64> = received[&ClientId::from_u64(1)]
+            .iter()
+            .filter(|p| p["event_type"] == "DestinationGeometryChanged" && p["surface_id"] == 1000)
+            .map(|p| p["new_rect"]["x"].as_i64().unwrap())
+            .collect();
+        assert_eq!(xs, vec![1, 2, 3, 4, 5]);
     }
 
-    /// Handle list_subscriptions request - list all active subscriptions for a client
-    fn handle_list_subscriptions(
-        &self,
-        client_id: &ClientId,
-    ) -> Result<serde_json::Value, RpcError> {
-        jdebug!("Listing subscriptions for client {}", client_id);
+    #[test]
+    fn test_notification_retried_when_socket_buffer_full() {
+        let subscription_manager = Mutex::new(SubscriptionManager::new());
+        let mock = MockTransport::new();
+        let broadcasts = Arc::clone(&mock.broadcasts);
+        let would_block = Arc::clone(&mock.would_block);
+        let transport: Mutex<Option<Box<dyn Transport>>> = Mutex::new(Some(Box::new(mock)));
+        let mut send_retries = HashMap::new();
+        let client_id = ClientId::from_u64(1);
+
+        {
+            let manager = subscription_manager.lock().unwrap();
+            manager
+                .subscribe(&client_id, vec![EventType::SurfaceCreated])
+                .unwrap();
+            for id in [1000, 1001] {
+                let notification = RpcNotification::new(
+                    "notification".to_string(),
+                    json!({"event_type": "SurfaceCreated", "surface_id": id}),
+                );
+                manager.queue_notification(EventType::SurfaceCreated, notification);
+            }
+        }
 
-        let subscription_manager = self.subscription_manager.lock().unwrap();
-        let subscriptions = subscription_manager.get_subscriptions(client_id);
+        // Full socket buffer: nothing is sent, notifications stay queued
+        would_block.store(true, Ordering::SeqCst);
+        RpcHandler::deliver_notifications(&subscription_manager, &transport, &mut send_retries);
+        assert!(broadcasts.lock().unwrap().is_empty());
+        assert_eq!(send_retries.get(&client_id), Some(&1));
+
+        // Buffer drained: both notifications are delivered in order
+        would_block.store(false, Ordering::SeqCst);
+        RpcHandler::deliver_notifications(&subscription_manager, &transport, &mut send_retries);
+        let broadcasts = broadcasts.lock().unwrap();
+        assert_eq!(broadcasts.len(), 2);
+        let first: serde_json::Value = serde_json::from_slice(&broadcasts[0].1).unwrap();
+        let second: serde_json::Value = serde_json::from_slice(&broadcasts[1].1).unwrap();
+        assert_eq!(first["params"]["surface_id"], 1000);
+        assert_eq!(second["params"]["surface_id"], 1001);
+        assert!(send_retries.is_empty());
+    }
 
-        jdebug!(
-            "Client {} has {} active subscriptions",
-            client_id,
-            subscriptions.len()
-        );
+    #[test]
+    fn test_notification_dropped_after_max_retries() {
+        let subscription_manager = Mutex::new(SubscriptionManager::new());
+        let mock = MockTransport::new();
+        mock.would_block.store(true, Ordering::SeqCst);
+        let transport: Mutex<Option<Box<dyn Transport>>> = Mutex::new(Some(Box::new(mock)));
+        let mut send_retries = HashMap::new();
+        let client_id = ClientId::from_u64(1);
+
+        {
+            let manager = subscription_manager.lock().unwrap();
+            manager
+                .subscribe(&client_id, vec![EventType::SurfaceCreated])
+                .unwrap();
+            let notification = RpcNotification::new(
+                "notification".to_string(),
+                json!({"event_type": "SurfaceCreated", "surface_id": 1000}),
+            );
+            manager.queue_notification(EventType::SurfaceCreated, notification);
+        }
 
-        Ok(json!({
-            "subscriptions": subscriptions
-        }))
+        for _ in 0..=MAX_SEND_RETRIES {
+            RpcHandler::deliver_notifications(&subscription_manager, &transport, &mut send_retries);
+        }
+
+        let remaining = subscription_manager
+            .lock()
+            .unwrap()
+            .drain_notifications(&client_id);
+        assert!(remaining.is_empty());
     }
 
-    /// Handle list_layers request
-    fn handle_list_layers(&self) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let layers = state_manager.get_all_layers();
+    #[test]
+    fn test_list_surfaces_pagination() {
+        let state_manager = create_mock_state_manager();
+        {
+            let mut sm = state_manager.lock().unwrap();
+            for id in [1002, 1000, 1001] {
+                let rect = Rectangle {
+                    x: 0,
+                    y: 0,
+                    width: 100,
+                    height: 100,
+                };
+                // Hidden surfaces skip the on-screen lookup through the IVI API
+                sm.add_surface(
+                    id,
+                    SurfaceState {
+                        id,
+                        orig_size: (100, 100),
+                        src_rect: rect,
+                        dest_rect: rect,
+                        visibility: false,
+                        opacity: 1.0,
+                        orientation: crate::ffi::bindings::Orientation::Normal,
+                        z_order: 0,
+                        is_auto_assigned: false,
+                        original_id: None,
+                        initialized: true,
+                        surface_type: SurfaceType::Ivi,
+                    },
+                );
+            }
+        }
+        let rpc_handler = RpcHandler::new(state_manager);
+        let client_id = ClientId::from_u64(1);
 
-        let layer_list: Vec<serde_json::Value> = layers
+        let request = RpcRequest::new(
+            1,
+            "list_surfaces".to_string(),
+            json!({ "offset": 1, "limit": 1 }),
+        );
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+        assert_eq!(result["v"], SCHEMA_VERSION);
+        assert_eq!(result["total"], 3);
+        assert_eq!(result["surfaces"].as_array().unwrap().len(), 1);
+        assert_eq!(result["surfaces"][0]["id"], 1001);
+        assert_eq!(result["surfaces"][0]["v"], SCHEMA_VERSION);
+
+        let request = RpcRequest::new(2, "list_surfaces".to_string(), json!({}));
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+        let ids: Vec<u64> = result["surfaces"]
+            .as_array()
+            .unwrap()
             .iter()
-            .map(|layer| {
-                json!({
-                    "id": layer.id,
-                    "src_rect": {
-                        "x": layer.src_rect.0,
-                        "y": layer.src_rect.1,
-                        "width": layer.src_rect.2,
-                        "height": layer.src_rect.3,
-                    },
-                    "dest_rect": {
-                        "x": layer.dest_rect.0,
-                        "y": layer.dest_rect.1,
-                        "width": layer.dest_rect.2,
-                        "height": layer.dest_rect.3,
-                    },
-                    "visibility": layer.visibility,
-                    "opacity": layer.opacity,
-                    "orientation": layer.orientation,
-                })
-            })
+            .map(|s| s["id"].as_u64().unwrap())
             .collect();
+        assert_eq!(ids, vec![1000, 1001, 1002]);
+    }
 
-        Ok(json!({ "layers": layer_list }))
+    #[test]
+    fn test_surface_and_layer_exists() {
+        let state_manager = create_mock_state_manager();
+        {
+            let mut sm = state_manager.lock().unwrap();
+            let rect = Rectangle {
+                x: 0,
+                y: 0,
+                width: 100,
+                height: 100,
+            };
+            sm.add_surface(
+                1000,
+                SurfaceState {
+                    id: 1000,
+                    orig_size: (100, 100),
+                    src_rect: rect,
+                    dest_rect: rect,
+                    visibility: false,
+                    opacity: 1.0,
+                    orientation: crate::ffi::bindings::Orientation::Normal,
+                    z_order: 0,
+                    is_auto_assigned: false,
+                    original_id: None,
+                    initialized: true,
+                    surface_type: SurfaceType::Ivi,
+                },
+            );
+            sm.add_layer(
+                2000,
+                LayerState {
+                    id: 2000,
+                    visibility: true,
+                    opacity: 1.0,
+                    src_rect: (0, 0, 100, 100),
+                    dest_rect: (0, 0, 100, 100),
+                    orientation: crate::ffi::bindings::Orientation::Normal,
+                },
+            );
+        }
+        let rpc_handler = RpcHandler::new(state_manager);
+        let client_id = ClientId::from_u64(1);
+
+        // Tracked objects are answered from state without querying the IVI API
+        let request = RpcRequest::new(1, "surface_exists".to_string(), json!({ "id": 1000 }));
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+        assert_eq!(result, json!({ "exists": true }));
+
+        let request = RpcRequest::new(2, "layer_exists".to_string(), json!({ "id": 2000 }));
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+        assert_eq!(result, json!({ "exists": true }));
+
+        let request = RpcRequest::new(3, "layer_exists".to_string(), json!({}));
+        let error = rpc_handler
+            .handle_request(&client_id, request)
+            .error
+            .unwrap();
+        assert_eq!(error.code, -32602);
     }
 
-    fn handle_create_layer(
-        &self,
-        id: u32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        jdebug!("Creating new layer with size {}x{}", width, height);
+    #[test]
+    fn test_list_clients_reports_credentials() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+
+        let mut mock = MockTransport::new();
+        mock.connected_clients = vec![ClientId::from_u64(2), ClientId::from_u64(1)];
+        mock.credentials.insert(
+            ClientId::from_u64(1),
+            ClientCredentials {
+                uid: 1000,
+                gid: 100,
+                pid: 4242,
+            },
+        );
+        rpc_handler.register_transport(Box::new(mock)).unwrap();
+
+        let request = RpcRequest::new(1, "list_clients".to_string(), json!({}));
+        let result = rpc_handler
+            .handle_request(&ClientId::from_u64(1), request)
+            .result
+            .unwrap();
+
+        assert_eq!(
+            result,
+            json!({
+                "clients": [
+                    { "id": 1, "uid": 1000, "gid": 100, "pid": 4242 },
+                    { "id": 2, "uid": null, "gid": null, "pid": null },
+                ]
+            })
+        );
+    }
 
-        // Validate size
-        validation::validate_size(width, height)
-            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+    #[test]
+    fn test_get_all_subscriptions_requires_admin() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        rpc_handler.set_admin_uids(&[0]);
+
+        let mut mock = MockTransport::new();
+        for (id, uid) in [(1, 0), (2, 1000)] {
+            mock.credentials.insert(
+                ClientId::from_u64(id),
+                ClientCredentials {
+                    uid,
+                    gid: uid,
+                    pid: 100 + uid as i32,
+                },
+            );
+        }
+        rpc_handler.register_transport(Box::new(mock)).unwrap();
 
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager);
+        let subscribe = |id: u64, event_type: &str| {
+            let request = RpcRequest::new(
+                1,
+                "subscribe".to_string(),
+                json!({ "event_types": [event_type] }),
+            );
+            assert!(rpc_handler
+                .handle_request(&ClientId::from_u64(id), request)
+                .error
+                .is_none());
+        };
+        subscribe(1, "SurfaceCreated");
+        subscribe(2, "LayerCreated");
 
-        // Create the layer via IVI API
-        let layer = ivi_api
-            .layer_create_with_dimension(id, width, height)
-            .map_err(|e| RpcError::internal_error(e.to_string()))?;
+        let request = |id: u64| {
+            let request = RpcRequest::new(1, "get_all_subscriptions".to_string(), json!({}));
+            rpc_handler.handle_request(&ClientId::from_u64(id), request)
+        };
 
-        jinfo!("Created new layer with ID {}", layer.id());
+        assert_eq!(
+            request(1).result.unwrap(),
+            json!({
+                "subscriptions": {
+                    "1": ["SurfaceCreated"],
+                    "2": ["LayerCreated"],
+                }
+            })
+        );
 
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            self.commit_layer_changes(id)?;
-        }
+        let error = request(2).error.unwrap();
+        assert_eq!(error.code, RpcError::PERMISSION_DENIED);
 
-        Ok(json!({
-            "id": layer.id(),
-            "committed": auto_commit,
-        }))
+        // Without admin uids, nobody may call it
+        rpc_handler.set_admin_uids(&[]);
+        assert_eq!(request(1).error.unwrap().code, RpcError::PERMISSION_DENIED);
     }
 
-    /// Handle destroy_layer request
-    fn handle_destroy_layer(
-        &self,
-        id: u32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        jdebug!("Destroying layer {} [auto_commit={}]", id, auto_commit);
-
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
-
-        // Verify layer exists before attempting to destroy
-        if !state_manager.has_layer(id) {
-            jwarn!("Layer not found: {}", id);
-            return Err(RpcError::layer_not_found(id));
+    #[test]
+    fn test_clear_subscriptions_requires_admin() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        rpc_handler.set_admin_uids(&[0]);
+
+        let mut mock = MockTransport::new();
+        for (id, uid) in [(1, 0), (2, 1000)] {
+            mock.credentials.insert(
+                ClientId::from_u64(id),
+                ClientCredentials {
+                    uid,
+                    gid: uid,
+                    pid: 100 + uid as i32,
+                },
+            );
         }
+        rpc_handler.register_transport(Box::new(mock)).unwrap();
 
-        drop(state_manager);
+        let subscribe = |id: u64| {
+            let request = RpcRequest::new(
+                1,
+                "subscribe".to_string(),
+                json!({ "event_types": ["SurfaceCreated"] }),
+            );
+            assert!(rpc_handler
+                .handle_request(&ClientId::from_u64(id), request)
+                .error
+                .is_none());
+        };
+        for id in 1..=3 {
+            subscribe(id);
+        }
 
-        // Get the layer from the IVI API
-        let layer = ivi_api
-            .get_layer_from_id(id)
-            .ok_or_else(|| RpcError::internal_error(format!("Failed to get IVI layer {}", id)))?;
+        let clear = |id: u64, params: serde_json::Value| {
+            let request = RpcRequest::new(1, "clear_subscriptions".to_string(), params);
+            rpc_handler.handle_request(&ClientId::from_u64(id), request)
+        };
+        let subscriber_count = || {
+            rpc_handler
+                .subscription_manager
+                .lock()
+                .unwrap()
+                .subscriber_count()
+        };
 
-        // Destroy the layer (consumes self)
-        layer
-            .destroy()
-            .map_err(|e| RpcError::internal_error(format!("Failed to destroy layer: {}", e)))?;
+        let error = clear(2, json!({ "all": true })).error.unwrap();
+        assert_eq!(error.code, RpcError::PERMISSION_DENIED);
+        let error = clear(2, json!({ "client_id": 3 })).error.unwrap();
+        assert_eq!(error.code, RpcError::PERMISSION_DENIED);
+        assert_eq!(subscriber_count(), 3);
 
-        jinfo!("Layer {} destroyed", id);
+        assert_eq!(
+            clear(1, json!({ "client_id": 3 })).result,
+            Some(json!({ "success": true, "cleared_clients": 1 }))
+        );
+        assert_eq!(subscriber_count(), 2);
 
-        // Commit changes if auto_commit is true
-        if auto_commit {
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(e.to_string()))?;
+        assert_eq!(
+            clear(1, json!({ "all": true })).result,
+            Some(json!({ "success": true, "cleared_clients": 2 }))
+        );
+        assert_eq!(subscriber_count(), 0);
 
-            // Update internal state - the layer is now destroyed
-            let mut state_manager = self.state_manager.lock().unwrap();
-            state_manager.handle_layer_destroyed(id);
+        assert_eq!(
+            clear(1, json!({ "client_id": "x" })).error.unwrap().code,
+            -32602
+        );
+    }
 
-            jinfo!("Layer {} destruction committed", id);
+    #[test]
+    fn test_clear_own_subscriptions_needs_no_admin() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        rpc_handler
+            .register_transport(Box::new(MockTransport::new()))
+            .unwrap();
+
+        let request = |id: u64, method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            rpc_handler.handle_request(&ClientId::from_u64(id), request)
+        };
+        for id in 1..=2 {
+            let response = request(
+                id,
+                "subscribe",
+                json!({ "event_types": ["SurfaceCreated"] }),
+            );
+            assert!(response.error.is_none());
         }
 
-        Ok(json!({ "success": true, "committed": auto_commit }))
+        // Without arguments only the caller's subscriptions are cleared
+        assert_eq!(
+            request(1, "clear_subscriptions", json!({})).result,
+            Some(json!({ "success": true, "cleared_clients": 1 }))
+        );
+        let all_subscriptions = rpc_handler
+            .subscription_manager
+            .lock()
+            .unwrap()
+            .all_subscriptions();
+        assert!(!all_subscriptions.contains_key(&ClientId::from_u64(1)));
+        assert!(all_subscriptions.contains_key(&ClientId::from_u64(2)));
+
+        // Naming itself is the same
+        assert_eq!(
+            request(2, "clear_subscriptions", json!({ "client_id": 2 })).result,
+            Some(json!({ "success": true, "cleared_clients": 1 }))
+        );
+
+        assert_eq!(
+            request(
+                1,
+                "clear_subscriptions",
+                json!({ "client_id": 1, "all": true })
+            )
+            .error
+            .unwrap()
+            .code,
+            -32602
+        );
     }
 
-    /// Handle get_layer request
-    fn handle_get_layer(&self, id: u32) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
+    #[test]
+    fn test_response_echoes_method() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
 
-        match state_manager.get_layer(id) {
-            Some(layer) => {
-                jdebug!("Retrieved layer {}", id);
-                Ok(json!({
-                    "id": layer.id,
-                    "src_rect": {
-                        "x": layer.src_rect.0,
-                        "y": layer.src_rect.1,
-                        "width": layer.src_rect.2,
-                        "height": layer.src_rect.3,
-                    },
-                    "dest_rect": {
-                        "x": layer.dest_rect.0,
-                        "y": layer.dest_rect.1,
-                        "width": layer.dest_rect.2,
-                        "height": layer.dest_rect.3,
-                    },
-                    "visibility": layer.visibility,
-                    "opacity": layer.opacity,
-                    "orientation": layer.orientation,
-                }))
-            }
-            None => {
-                jwarn!("Layer not found: {}", id);
-                Err(RpcError::invalid_params(format!("Layer {} not found", id)))
-            }
+        let request = RpcRequest::new(1, "get_focus".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.method.as_deref(), Some("get_focus"));
+
+        let request = RpcRequest::new(2, "get_surface".to_string(), json!({ "id": 1234 }));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert!(response.error.is_some());
+        assert_eq!(response.method.as_deref(), Some("get_surface"));
+
+        let request = RpcRequest::new(3, "no_such_method".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.method.as_deref(), Some("no_such_method"));
+
+        let json: serde_json::Value = serde_json::from_slice(&response.to_json().unwrap()).unwrap();
+        assert_eq!(json["method"], "no_such_method");
+    }
+
+    #[test]
+    fn test_get_surface_and_layer_not_found_errors_match() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(1, "get_surface".to_string(), json!({ "id": 1234 }));
+        let surface_error = rpc_handler
+            .handle_request(&client_id, request)
+            .error
+            .unwrap();
+
+        let request = RpcRequest::new(2, "get_layer".to_string(), json!({ "id": 2345 }));
+        let layer_error = rpc_handler
+            .handle_request(&client_id, request)
+            .error
+            .unwrap();
+
+        assert_eq!(surface_error.code, RpcError::NOT_FOUND);
+        assert_eq!(layer_error.code, RpcError::NOT_FOUND);
+        assert_eq!(
+            surface_error.data,
+            Some(json!({ "id": 1234, "kind": "surface" }))
+        );
+        assert_eq!(
+            layer_error.data,
+            Some(json!({ "id": 2345, "kind": "layer" }))
+        );
+    }
+
+    #[test]
+    fn test_create_and_destroy_layer_validation() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        for params in [
+            json!({ "id": 2000, "width": 0, "height": 1080 }),
+            json!({ "id": 2000, "width": 1920, "height": -1 }),
+            json!({ "width": 1920, "height": 1080 }),
+        ] {
+            let request = RpcRequest::new(1, "create_layer".to_string(), params.clone());
+            let error = rpc_handler
+                .handle_request(&client_id, request)
+                .error
+                .unwrap();
+            assert_eq!(error.code, -32602, "create_layer accepted {}", params);
         }
+
+        // Destroying an unknown layer is reported like any other missing layer
+        let request = RpcRequest::new(2, "destroy_layer".to_string(), json!({ "id": 2345 }));
+        let error = rpc_handler
+            .handle_request(&client_id, request)
+            .error
+            .unwrap();
+        assert_eq!(error.code, RpcError::NOT_FOUND);
+        assert_eq!(error.data, Some(json!({ "id": 2345, "kind": "layer" })));
     }
 
-    /// Handle set_layer_source_rectangle request
-    fn handle_set_layer_source_rectangle(
-        &self,
-        id: u32,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        validation::validate_position(x, y).map_err(|e| RpcError::invalid_params(e.to_string()))?;
-        validation::validate_size(width, height)
-            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+    #[test]
+    fn test_get_and_clear_focus() {
+        let state_manager = create_mock_state_manager();
+        let rpc_handler = RpcHandler::new(Arc::clone(&state_manager));
+        let client_id = ClientId::from_u64(1);
 
-        let mut layer = self
-            .id_to_layer(id)
-            .ok_or_else(|| RpcError::invalid_params(format!("Layer {} not found", id)))?;
+        let request = RpcRequest::new(1, "get_focus".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.result, Some(json!({ "surface_id": null })));
 
-        layer
-            .set_source_rectangle(Rectangle {
-                x,
-                y,
-                width,
-                height,
-            })
-            .map_err(RpcError::internal_error)?;
+        state_manager
+            .lock()
+            .unwrap()
+            .set_focused_surface(Some(1000));
 
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            self.commit_layer_changes(id)?;
-        }
+        let request = RpcRequest::new(2, "get_focus".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.result, Some(json!({ "surface_id": 1000 })));
 
-        Ok(json!({ "success": true, "committed": auto_commit }))
+        let request = RpcRequest::new(3, "clear_focus".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert!(response.error.is_none());
+        assert_eq!(state_manager.lock().unwrap().get_focused_surface(), None);
     }
 
-    /// Handle set_layer_destination_rectangle request
-    fn handle_set_layer_destination_rectangle(
-        &self,
-        id: u32,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        validation::validate_position(x, y).map_err(|e| RpcError::invalid_params(e.to_string()))?;
-        validation::validate_size(width, height)
-            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+    #[test]
+    fn test_monitoring_request_rejects_mutations() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(1, "commit".to_string(), json!({}));
+        let response = rpc_handler.handle_monitoring_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32601);
+        assert_eq!(response.method.as_deref(), Some("commit"));
+
+        let request = RpcRequest::new(2, "ping".to_string(), json!({}));
+        let response = rpc_handler.handle_monitoring_request(&client_id, request);
+        assert_eq!(response.result, Some(json!({ "pong": true })));
+    }
+
+    #[test]
+    fn test_get_metrics_counts_requests() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(1, "ping".to_string(), json!({}));
+        rpc_handler.handle_request(&client_id, request);
+        let request = RpcRequest::new(2, "no_such_method".to_string(), json!({}));
+        rpc_handler.handle_request(&client_id, request);
+
+        let request = RpcRequest::new(3, "get_metrics".to_string(), json!({}));
+        let result = rpc_handler
+            .handle_monitoring_request(&client_id, request)
+            .result
+            .unwrap();
+        assert_eq!(result["requests_handled"], 3);
+        assert_eq!(result["requests_failed"], 1);
+        assert_eq!(result["connected_clients"], 0);
+    }
+
+    #[test]
+    fn test_stats() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            let response = rpc_handler.handle_monitoring_request(&client_id, request);
+            response.result
+        };
 
-        let mut layer = self
-            .id_to_layer(id)
-            .ok_or_else(|| RpcError::invalid_params(format!("Layer {} not found", id)))?;
+        rpc_handler.handle_request(
+            &client_id,
+            RpcRequest::new(
+                1,
+                "subscribe".to_string(),
+                json!({ "event_types": ["SurfaceCreated", "LayerCreated"] }),
+            ),
+        );
+        rpc_handler.handle_request(
+            &client_id,
+            RpcRequest::new(
+                2,
+                "set_surface_opacity".to_string(),
+                json!({ "id": 1000, "opacity": 0.5 }),
+            ),
+        );
 
-        layer
-            .set_destination_rectangle(Rectangle {
-                x,
-                y,
-                width,
-                height,
+        assert_eq!(
+            request("stats", json!({})).unwrap(),
+            json!({
+                "surfaces": 1,
+                "layers": 0,
+                "connected_clients": 0,
+                "subscriptions": 2,
+                "pending_changes": 1,
             })
-            .map_err(RpcError::internal_error)?;
+        );
 
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            self.commit_layer_changes(id)?;
+        free_mock_surface();
+    }
+
+    #[test]
+    fn test_capabilities_methods_are_all_known() {
+        for method in METHOD_NAMES {
+            let request = RpcRequest::new(1, method.to_string(), json!({}));
+            if let Err(e) = RpcMethod::from_request(&request) {
+                assert_ne!(e.code, -32601, "{} is not a known method", method);
+            }
+        }
+        for method in MONITORING_METHOD_NAMES {
+            assert!(METHOD_NAMES.contains(method));
         }
+    }
 
-        Ok(json!({ "success": true, "committed": auto_commit }))
+    #[test]
+    fn test_capabilities_report_coarse_events() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(1, "get_capabilities".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.result.unwrap()["coarse_events"], false);
+
+        rpc_handler.set_coarse_events(true);
+        let request = RpcRequest::new(2, "get_capabilities".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.result.unwrap()["coarse_events"], true);
     }
 
-    /// Handle set_layer_visibility request
-    fn handle_set_layer_visibility(
-        &self,
-        id: u32,
-        visible: bool,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        jdebug!(
-            "Setting layer {} visibility to {} [auto_commit={}]",
-            id,
-            visible,
-            auto_commit
+    #[test]
+    fn test_capabilities_report_limits() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        rpc_handler.set_max_subscriptions(8);
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(1, "get_capabilities".to_string(), json!({}));
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+
+        assert_eq!(result["max_frame_bytes"], framing::MAX_MESSAGE_SIZE);
+        assert_eq!(result["max_subscriptions_per_client"], 8);
+        assert_eq!(
+            result["notify_queue_depth"],
+            crate::controller::subscriptions::DEFAULT_BUFFER_SIZE
         );
+        assert_eq!(result["max_surface_size"], validation::max_size());
+    }
 
-        let state_manager = self.state_manager.lock().unwrap();
+    #[test]
+    fn test_layer_transition_validation() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(
+            1,
+            "set_layer_transition".to_string(),
+            json!({ "id": 2000, "type": "spin", "duration": 300 }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        // Check if layer exists
-        if !state_manager.has_layer(id) {
-            jwarn!("Layer not found: {}", id);
-            return Err(RpcError::invalid_params(format!("Layer {} not found", id)));
-        }
+        let request = RpcRequest::new(
+            2,
+            "set_layer_transition".to_string(),
+            json!({ "id": 2000, "type": "move", "duration": 300 }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 2000, "kind": "layer" }))
+        );
 
-        // Get the IVI API and update the layer
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager); // Release lock before calling IVI API
+        let request = RpcRequest::new(
+            3,
+            "cancel_layer_transition".to_string(),
+            json!({ "id": 2000 }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 2000, "kind": "layer" }))
+        );
+    }
 
-        let mut layer = ivi_api
-            .get_layer_from_id(id)
-            .ok_or_else(|| RpcError::internal_error(format!("Failed to get IVI layer {}", id)))?;
+    #[test]
+    fn test_set_surfaces_visibility_checks_all_surfaces_first() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        // No IVI call is made when a surface is missing
+        let request = RpcRequest::new(
+            1,
+            "set_surfaces_visibility".to_string(),
+            json!({ "ids": [1000, 1001], "visible": false }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 1000, "kind": "surface" }))
+        );
 
-        layer
-            .set_visibility(visible)
-            .map_err(RpcError::internal_error)?;
+        let request = RpcRequest::new(
+            2,
+            "set_surfaces_visibility".to_string(),
+            json!({ "ids": [1000, "x"], "visible": false }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
 
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(e.to_string()))?;
+    thread_local! {
+        // Properties of the single surface the mock IVI layout knows
+        static MOCK_SURFACE: std::cell::Cell<*mut ivi_layout_surface_properties> =
+            const { std::cell::Cell::new(std::ptr::null_mut()) };
+        // Number of IVI commits made through the mock IVI layout
+        static COMMIT_COUNT: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
+    }
 
-            // Update internal state
-            let mut state_manager = self.state_manager.lock().unwrap();
-            state_manager.handle_layer_configured(id);
+    unsafe extern "C" fn mock_get_surface_from_id(id: u32) -> *mut ivi_layout_surface {
+        if id == 1000 {
+            std::ptr::NonNull::dangling().as_ptr()
+        } else {
+            std::ptr::null_mut()
         }
+    }
 
-        Ok(json!({ "success": true, "committed": auto_commit }))
+    unsafe extern "C" fn mock_get_id_of_surface(_surface: *mut ivi_layout_surface) -> u32 {
+        1000
     }
 
-    /// Handle set_layer_opacity request
-    fn handle_set_layer_opacity(
-        &self,
-        id: u32,
-        opacity: f32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        // Validate opacity
-        validation::validate_opacity(opacity)
-            .map_err(|e| RpcError::invalid_params(e.to_string()))?;
+    unsafe extern "C" fn mock_get_properties_of_surface(
+        _surface: *mut ivi_layout_surface,
+    ) -> *const ivi_layout_surface_properties {
+        MOCK_SURFACE.with(|props| props.get())
+    }
 
-        jdebug!(
-            "Setting layer {} opacity to {} [auto_commit={}]",
-            id,
-            opacity,
-            auto_commit
-        );
+    unsafe extern "C" fn mock_surface_set_visibility(
+        _surface: *mut ivi_layout_surface,
+        visible: bool,
+    ) -> i32 {
+        MOCK_SURFACE.with(|props| (*props.get()).visibility = visible);
+        0
+    }
 
-        let state_manager = self.state_manager.lock().unwrap();
+    unsafe extern "C" fn mock_surface_set_opacity(
+        _surface: *mut ivi_layout_surface,
+        opacity: wl_fixed_t,
+    ) -> i32 {
+        MOCK_SURFACE.with(|props| (*props.get()).opacity = opacity);
+        0
+    }
 
-        // Check if layer exists
-        if !state_manager.has_layer(id) {
-            jwarn!("Layer not found: {}", id);
-            return Err(RpcError::invalid_params(format!("Layer {} not found", id)));
-        }
+    unsafe extern "C" fn mock_surface_set_source_rectangle(
+        _surface: *mut ivi_layout_surface,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> i32 {
+        MOCK_SURFACE.with(|props| {
+            let props = &mut *props.get();
+            props.source_x = x;
+            props.source_y = y;
+            props.source_width = width;
+            props.source_height = height;
+        });
+        0
+    }
 
-        // Get the IVI API and update the layer
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager); // Release lock before calling IVI API
+    unsafe extern "C" fn mock_surface_set_destination_rectangle(
+        _surface: *mut ivi_layout_surface,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> i32 {
+        MOCK_SURFACE.with(|props| {
+            let props = &mut *props.get();
+            props.dest_x = x;
+            props.dest_y = y;
+            props.dest_width = width;
+            props.dest_height = height;
+        });
+        0
+    }
 
-        let mut layer = ivi_api
-            .get_layer_from_id(id)
-            .ok_or_else(|| RpcError::internal_error(format!("Failed to get IVI layer {}", id)))?;
+    unsafe extern "C" fn mock_get_layers_under_surface(
+        _surface: *mut ivi_layout_surface,
+        length: *mut i32,
+        _array: *mut *mut *mut ivi_layout_layer,
+    ) -> i32 {
+        *length = 0;
+        0
+    }
 
-        layer
-            .set_opacity(opacity)
-            .map_err(RpcError::internal_error)?;
+    unsafe extern "C" fn mock_commit_changes() -> i32 {
+        COMMIT_COUNT.with(|count| count.set(count.get() + 1));
+        0
+    }
 
-        // Commit changes only if auto_commit is true
-        if auto_commit {
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(e.to_string()))?;
+    /// State manager over the mock IVI layout, tracking surface 1000
+    ///
+    /// The mock surface must be released with `free_mock_surface`.
+    fn create_mock_ivi_state_manager() -> Arc<Mutex<StateManager>> {
+        let mut props: ivi_layout_surface_properties = unsafe { std::mem::zeroed() };
+        props.opacity = f32_to_wl_fixed_t(1.0);
+        MOCK_SURFACE.with(|cell| cell.set(Box::into_raw(Box::new(props))));
+        COMMIT_COUNT.with(|count| count.set(0));
+
+        let mut interface: ivi_layout_interface = unsafe { std::mem::zeroed() };
+        interface.get_surface_from_id = Some(mock_get_surface_from_id);
+        interface.get_id_of_surface = Some(mock_get_id_of_surface);
+        interface.get_properties_of_surface = Some(mock_get_properties_of_surface);
+        interface.surface_set_visibility = Some(mock_surface_set_visibility);
+        interface.surface_set_opacity = Some(mock_surface_set_opacity);
+        interface.get_layers_under_surface = Some(mock_get_layers_under_surface);
+        interface.surface_set_source_rectangle = Some(mock_surface_set_source_rectangle);
+        interface.surface_set_destination_rectangle = Some(mock_surface_set_destination_rectangle);
+        interface.commit_changes = Some(mock_commit_changes);
+        let interface: &'static ivi_layout_interface = Box::leak(Box::new(interface));
+
+        let ivi_api = Arc::new(IviLayoutApi::from_raw(interface).unwrap());
+        let state_manager = Arc::new(Mutex::new(StateManager::new(ivi_api)));
+        state_manager.lock().unwrap().handle_surface_created(1000);
+        state_manager
+    }
 
-            // Update internal state
-            let mut state_manager = self.state_manager.lock().unwrap();
-            state_manager.handle_layer_configured(id);
-        }
+    fn free_mock_surface() {
+        MOCK_SURFACE
+            .with(|cell| drop(unsafe { Box::from_raw(cell.replace(std::ptr::null_mut())) }));
+    }
 
-        Ok(json!({ "success": true, "committed": auto_commit }))
+    thread_local! {
+        // Surface IDs passed to the last layer_set_render_order
+        static RENDER_ORDER: std::cell::RefCell<Vec<u32>> =
+            const { std::cell::RefCell::new(Vec::new()) };
     }
 
-    /// Handle set_layer_surfaces: Replace all surfaces on a layer
-    fn handle_set_layer_surfaces(
-        &self,
-        layer_id: u32,
-        surface_ids: Vec<u32>,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        jdebug!(
-            "Setting layer {} surfaces to {:?} [auto_commit={}]",
-            layer_id,
-            surface_ids,
-            auto_commit
-        );
+    // The layered mock uses each object's ID as its handle
+    unsafe extern "C" fn layered_get_surface_from_id(id: u32) -> *mut ivi_layout_surface {
+        id as usize as *mut ivi_layout_surface
+    }
 
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
+    unsafe extern "C" fn layered_get_id_of_surface(surface: *mut ivi_layout_surface) -> u32 {
+        surface as usize as u32
+    }
 
-        // Get layer and verify it exists
-        let layer = ivi_api
-            .get_layer_from_id(layer_id)
-            .ok_or_else(|| RpcError::internal_error(format!("Layer {} not found", layer_id)))?;
+    unsafe extern "C" fn layered_get_id_of_layer(layer: *mut ivi_layout_layer) -> u32 {
+        layer as usize as u32
+    }
 
-        // Build surface vector by getting each surface from ID
-        let surfaces: Vec<_> = surface_ids
+    unsafe extern "C" fn layered_get_layers_under_surface(
+        _surface: *mut ivi_layout_surface,
+        length: *mut i32,
+        array: *mut *mut *mut ivi_layout_layer,
+    ) -> i32 {
+        let layers = vec![2000usize as *mut ivi_layout_layer];
+        *length = layers.len() as i32;
+        *array = Box::leak(layers.into_boxed_slice()).as_mut_ptr();
+        0
+    }
+
+    unsafe extern "C" fn layered_get_surfaces_on_layer(
+        _layer: *mut ivi_layout_layer,
+        length: *mut i32,
+        array: *mut *mut *mut ivi_layout_surface,
+    ) -> i32 {
+        // Deliberately not sorted by ID
+        let surfaces: Vec<*mut ivi_layout_surface> = [1001usize, 1000, 1002]
             .iter()
-            .filter_map(|&id| ivi_api.get_surface_from_id(id))
+            .map(|&id| id as *mut ivi_layout_surface)
             .collect();
+        *length = surfaces.len() as i32;
+        *array = Box::leak(surfaces.into_boxed_slice()).as_mut_ptr();
+        0
+    }
 
-        // Verify all surfaces were found
-        if surfaces.len() != surface_ids.len() {
-            return Err(RpcError::internal_error(
-                "Some surfaces not found".to_string(),
-            ));
-        }
-
-        drop(state_manager);
-
-        // Build reference slice: first = bottommost, last = topmost
-        let surface_refs: Vec<&_> = surfaces.iter().collect();
-
-        // Set render order
-        ivi_api
-            .layer_set_render_order(&layer, &surface_refs)
-            .map_err(|e| RpcError::internal_error(format!("Failed to set render order: {}", e)))?;
+    unsafe extern "C" fn layered_layer_set_render_order(
+        _layer: *mut ivi_layout_layer,
+        surfaces: *mut *mut ivi_layout_surface,
+        number: i32,
+    ) -> i32 {
+        let ids = std::slice::from_raw_parts(surfaces, number as usize)
+            .iter()
+            .map(|&surface| surface as usize as u32)
+            .collect();
+        RENDER_ORDER.with(|order| *order.borrow_mut() = ids);
+        0
+    }
 
-        // Commit if requested
-        if auto_commit {
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(e.to_string()))?;
-        }
+    #[test]
+    fn test_set_surface_z_order_reorders_layer() {
+        let mut interface: ivi_layout_interface = unsafe { std::mem::zeroed() };
+        interface.get_surface_from_id = Some(layered_get_surface_from_id);
+        interface.get_id_of_surface = Some(layered_get_id_of_surface);
+        interface.get_id_of_layer = Some(layered_get_id_of_layer);
+        interface.get_layers_under_surface = Some(layered_get_layers_under_surface);
+        interface.get_surfaces_on_layer = Some(layered_get_surfaces_on_layer);
+        interface.layer_set_render_order = Some(layered_layer_set_render_order);
+        let interface: &'static ivi_layout_interface = Box::leak(Box::new(interface));
+        let ivi_api = Arc::new(IviLayoutApi::from_raw(interface).unwrap());
+        let rpc_handler = RpcHandler::new(Arc::new(Mutex::new(StateManager::new(ivi_api))));
+        let client_id = ClientId::from_u64(1);
+        let set_z_order = |id: u32, z_order: i32| {
+            let request = RpcRequest::new(
+                1,
+                "set_surface_z_order".to_string(),
+                json!({ "id": id, "z_order": z_order }),
+            );
+            let result = rpc_handler.handle_request(&client_id, request).result;
+            let order = RENDER_ORDER.with(|order| order.take());
+            (result.unwrap()["z_order"].clone(), order)
+        };
 
-        Ok(json!({
-            "layer_id": layer_id,
-            "surface_ids": surface_ids,
-            "committed": auto_commit
-        }))
+        // The other surfaces keep their order, 1001 below 1000
+        assert_eq!(set_z_order(1002, 1), (json!(1), vec![1001, 1002, 1000]));
+        assert_eq!(set_z_order(1000, 0), (json!(0), vec![1000, 1001, 1002]));
+        // Beyond the surface count is clamped to topmost
+        assert_eq!(set_z_order(1001, 7), (json!(2), vec![1000, 1002, 1001]));
     }
 
-    /// Handle add_surface_to_layer: Add a surface as topmost
-    fn handle_add_surface_to_layer(
-        &self,
-        layer_id: u32,
-        surface_id: u32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        jdebug!(
-            "Adding surface {} to layer {} as topmost [auto_commit={}]",
-            surface_id,
-            layer_id,
-            auto_commit
-        );
+    thread_local! {
+        // Properties shared by every layer of the layered mock
+        static LAYER_PROPS: std::cell::Cell<*mut ivi_layout_layer_properties> =
+            const { std::cell::Cell::new(std::ptr::null_mut()) };
+    }
 
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
+    unsafe extern "C" fn layered_get_layer_from_id(id: u32) -> *mut ivi_layout_layer {
+        id as usize as *mut ivi_layout_layer
+    }
 
-        // Get layer and verify it exists
-        let layer = ivi_api
-            .get_layer_from_id(layer_id)
-            .ok_or_else(|| RpcError::internal_error(format!("Layer {} not found", layer_id)))?;
+    unsafe extern "C" fn layered_get_properties_of_layer(
+        _layer: *mut ivi_layout_layer,
+    ) -> *const ivi_layout_layer_properties {
+        LAYER_PROPS.with(|props| props.get())
+    }
 
-        // Get current surfaces on the layer
-        let mut surfaces = ivi_api.get_surfaces_on_layer(&layer);
+    unsafe extern "C" fn layered_layer_set_visibility(
+        _layer: *mut ivi_layout_layer,
+        visible: bool,
+    ) -> i32 {
+        LAYER_PROPS.with(|props| (*props.get()).visibility = visible);
+        0
+    }
 
-        // Get new surface and verify it exists
-        let new_surface = ivi_api
-            .get_surface_from_id(surface_id)
-            .ok_or_else(|| RpcError::internal_error(format!("Surface {} not found", surface_id)))?;
+    unsafe extern "C" fn layered_layer_set_opacity(
+        _layer: *mut ivi_layout_layer,
+        opacity: wl_fixed_t,
+    ) -> i32 {
+        LAYER_PROPS.with(|props| (*props.get()).opacity = opacity);
+        0
+    }
 
-        drop(state_manager);
+    /// State manager over the mock IVI layout, tracking layer 2000
+    ///
+    /// Returns the layer property notifications seen so far. The mock layer
+    /// must be released with `free_mock_layer`.
+    fn create_mock_layer_state_manager(
+    ) -> (Arc<Mutex<StateManager>>, Arc<Mutex<Vec<NotificationType>>>) {
+        let mut props: ivi_layout_layer_properties = unsafe { std::mem::zeroed() };
+        props.opacity = f32_to_wl_fixed_t(1.0);
+        LAYER_PROPS.with(|cell| cell.set(Box::into_raw(Box::new(props))));
+        COMMIT_COUNT.with(|count| count.set(0));
+
+        let mut interface: ivi_layout_interface = unsafe { std::mem::zeroed() };
+        interface.get_layer_from_id = Some(layered_get_layer_from_id);
+        interface.get_id_of_layer = Some(layered_get_id_of_layer);
+        interface.get_properties_of_layer = Some(layered_get_properties_of_layer);
+        interface.layer_set_visibility = Some(layered_layer_set_visibility);
+        interface.layer_set_opacity = Some(layered_layer_set_opacity);
+        interface.commit_changes = Some(mock_commit_changes);
+        let interface: &'static ivi_layout_interface = Box::leak(Box::new(interface));
+        let ivi_api = Arc::new(IviLayoutApi::from_raw(interface).unwrap());
+        let state_manager = Arc::new(Mutex::new(StateManager::new(ivi_api)));
+        state_manager.lock().unwrap().handle_layer_created(2000);
+
+        let seen: Arc<Mutex<Vec<NotificationType>>> = Arc::new(Mutex::new(Vec::new()));
+        {
+            let notification_manager = state_manager.lock().unwrap().notification_manager();
+            let mut notification_manager = notification_manager.lock().unwrap();
+            for notification_type in [
+                NotificationType::LayerVisibilityChanged,
+                NotificationType::LayerOpacityChanged,
+            ] {
+                let seen = Arc::clone(&seen);
+                notification_manager.register_callback(
+                    notification_type,
+                    Arc::new(move |n: &Notification| {
+                        seen.lock().unwrap().push(n.notification_type);
+                    }),
+                );
+            }
+        }
 
-        // Append new surface to end (topmost position)
-        surfaces.push(new_surface);
+        (state_manager, seen)
+    }
 
-        // Build reference slice
-        let surface_refs: Vec<&_> = surfaces.iter().collect();
+    fn free_mock_layer() {
+        LAYER_PROPS.with(|cell| drop(unsafe { Box::from_raw(cell.replace(std::ptr::null_mut())) }));
+    }
 
-        // Set render order
-        ivi_api
-            .layer_set_render_order(&layer, &surface_refs)
-            .map_err(|e| RpcError::internal_error(format!("Failed to set render order: {}", e)))?;
+    #[test]
+    fn test_batch_of_layer_changes_commits() {
+        let (state_manager, seen) = create_mock_layer_state_manager();
 
-        // Commit if requested
-        if auto_commit {
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(e.to_string()))?;
-        }
+        let rpc_handler = RpcHandler::new(state_manager);
+        let request = RpcRequest::new(
+            1,
+            "batch".to_string(),
+            json!({
+                "operations": [
+                    { "method": "set_layer_visibility", "params": { "id": 2000, "visible": true } },
+                    { "method": "set_layer_opacity", "params": { "id": 2000, "opacity": 0.5 } },
+                ],
+                "commit": true,
+            }),
+        );
+        let response = rpc_handler.handle_request(&ClientId::from_u64(1), request);
+
+        assert_eq!(response.result.unwrap()["committed"], true);
+        assert_eq!(COMMIT_COUNT.with(|count| count.get()), 1);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                NotificationType::LayerVisibilityChanged,
+                NotificationType::LayerOpacityChanged,
+            ]
+        );
 
-        Ok(json!({
-            "layer_id": layer_id,
-            "surface_id": surface_id,
-            "committed": auto_commit
-        }))
+        free_mock_layer();
     }
 
-    /// Handle remove_surface_from_layer: Remove a surface from a layer
-    fn handle_remove_surface_from_layer(
-        &self,
-        layer_id: u32,
-        surface_id: u32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        jdebug!(
-            "Removing surface {} from layer {} [auto_commit={}]",
-            surface_id,
-            layer_id,
-            auto_commit
+    #[test]
+    fn test_auto_commit_layer_setters_emit_one_notification_per_change() {
+        let (state_manager, seen) = create_mock_layer_state_manager();
+        let rpc_handler = RpcHandler::new(state_manager);
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            assert!(rpc_handler
+                .handle_request(&client_id, request)
+                .error
+                .is_none());
+            std::mem::take(&mut *seen.lock().unwrap())
+        };
+
+        let visible = json!({ "id": 2000, "visible": true, "auto_commit": true });
+        assert_eq!(
+            request("set_layer_visibility", visible.clone()),
+            vec![NotificationType::LayerVisibilityChanged]
         );
+        assert_eq!(request("set_layer_visibility", visible), vec![]);
 
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
+        let opacity = json!({ "id": 2000, "opacity": 0.5, "auto_commit": true });
+        assert_eq!(
+            request("set_layer_opacity", opacity.clone()),
+            vec![NotificationType::LayerOpacityChanged]
+        );
+        assert_eq!(request("set_layer_opacity", opacity), vec![]);
+        assert_eq!(COMMIT_COUNT.with(|count| count.get()), 4);
 
-        // Get layer and verify it exists
-        let layer = ivi_api
-            .get_layer_from_id(layer_id)
-            .ok_or_else(|| RpcError::internal_error(format!("Layer {} not found", layer_id)))?;
+        free_mock_layer();
+    }
 
-        // Get surface to remove and verify it exists
-        let surface = ivi_api
-            .get_surface_from_id(surface_id)
-            .ok_or_else(|| RpcError::internal_error(format!("Surface {} not found", surface_id)))?;
+    #[test]
+    fn test_set_surface_orientation() {
+        let state_manager = create_mock_ivi_state_manager();
+        let rpc_handler = RpcHandler::new(Arc::clone(&state_manager));
+        let client_id = ClientId::from_u64(1);
+        let request = |degrees: i32, auto_commit: bool| {
+            let request = RpcRequest::new(
+                1,
+                "set_surface_orientation".to_string(),
+                json!({ "id": 1000, "orientation": degrees, "auto_commit": auto_commit }),
+            );
+            rpc_handler.handle_request(&client_id, request)
+        };
+        let take_staged = || {
+            rpc_handler
+                .staged_changes
+                .lock()
+                .unwrap()
+                .take_surface(1000)
+        };
 
-        drop(state_manager);
+        assert_eq!(request(89, false).error.unwrap().code, -32602);
+        assert!(take_staged().is_empty());
 
-        // Remove surface from layer
-        ivi_api
-            .layer_remove_surface(&layer, &surface)
-            .map_err(|e| {
-                RpcError::internal_error(format!("Failed to remove surface from layer: {}", e))
-            })?;
+        assert!(request(90, false).error.is_none());
+        assert_eq!(
+            take_staged(),
+            vec![SurfaceChange::Orientation(Orientation::Rotate90)]
+        );
 
-        // Commit if requested
-        if auto_commit {
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(e.to_string()))?;
-        }
+        assert!(request(-90, false).error.is_none());
+        assert_eq!(
+            take_staged(),
+            vec![SurfaceChange::Orientation(Orientation::Rotate270)]
+        );
 
-        Ok(json!({
-            "layer_id": layer_id,
-            "surface_id": surface_id,
-            "committed": auto_commit
-        }))
+        // The mock layout has no surface_set_orientation
+        assert_eq!(request(180, true).error.unwrap().code, -32603);
+        assert_eq!(COMMIT_COUNT.with(|count| count.get()), 0);
+
+        free_mock_surface();
     }
 
-    /// Handle get_layer_surfaces: Get surfaces assigned to a layer
-    fn handle_get_layer_surfaces(&self, layer_id: u32) -> Result<serde_json::Value, RpcError> {
-        jdebug!("Getting surfaces for layer {}", layer_id);
+    #[test]
+    fn test_relative_moves_accumulate_before_commit() {
+        let state_manager = create_mock_ivi_state_manager();
+        let rpc_handler = RpcHandler::new(Arc::clone(&state_manager));
+        let client_id = ClientId::from_u64(1);
+        let start = state_manager
+            .lock()
+            .unwrap()
+            .get_surface(1000)
+            .unwrap()
+            .dest_rect;
+        let nudge = |dx: i32, dy: i32| {
+            let request = RpcRequest::new(
+                1,
+                "set_surface_position".to_string(),
+                json!({ "id": 1000, "dx": dx, "dy": dy, "relative": true }),
+            );
+            rpc_handler
+                .handle_request(&client_id, request)
+                .result
+                .unwrap()
+        };
 
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
+        let first = nudge(10, -10);
+        assert_eq!(first["x"], start.x + 10);
+        assert_eq!(first["y"], start.y - 10);
+
+        // The second move starts from the staged position of the first
+        let second = nudge(5, 20);
+        assert_eq!(second["x"], start.x + 15);
+        assert_eq!(second["y"], start.y + 10);
+        assert_eq!(
+            rpc_handler
+                .staged_changes
+                .lock()
+                .unwrap()
+                .destination_rectangle(1000),
+            Some(Rectangle {
+                x: start.x + 15,
+                y: start.y + 10,
+                ..start
+            })
+        );
 
-        // Get layer and verify it exists
-        let layer = ivi_api
-            .get_layer_from_id(layer_id)
-            .ok_or_else(|| RpcError::internal_error(format!("Layer {} not found", layer_id)))?;
+        free_mock_surface();
+    }
 
-        drop(state_manager);
+    #[test]
+    fn test_set_surface_size_keeps_staged_position() {
+        let state_manager = create_mock_ivi_state_manager();
+        let rpc_handler = RpcHandler::new(Arc::clone(&state_manager));
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            rpc_handler.handle_request(&client_id, request)
+        };
 
-        // Get surfaces on the layer
-        let surfaces = ivi_api.get_surfaces_on_layer(&layer);
+        let response = request(
+            "set_surface_size",
+            json!({ "id": 1000, "width": 0, "height": 480 }),
+        );
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        // Extract surface IDs (order preserved: first = bottommost, last = topmost)
-        let surface_ids: Vec<u32> = surfaces.iter().map(|s| s.id()).collect();
+        let response = request(
+            "set_surface_position",
+            json!({ "id": 1000, "x": 100, "y": 200 }),
+        );
+        assert!(response.error.is_none());
+
+        // The staged position is kept, not the committed one
+        let response = request(
+            "set_surface_size",
+            json!({ "id": 1000, "width": 640, "height": 480 }),
+        );
+        let result = response.result.unwrap();
+        assert_eq!(result["x"], 100);
+        assert_eq!(result["y"], 200);
+        assert_eq!(
+            rpc_handler
+                .staged_changes
+                .lock()
+                .unwrap()
+                .take_surface(1000),
+            vec![SurfaceChange::DestinationRectangle(Rectangle {
+                x: 100,
+                y: 200,
+                width: 640,
+                height: 480,
+            })]
+        );
 
-        Ok(json!({ "surface_ids": surface_ids }))
+        free_mock_surface();
     }
-}
 
-/// Convert a SurfaceState to JSON
-fn surface_state_to_json(surface: &SurfaceState) -> serde_json::Value {
-    json!({
-        "id": surface.id,
-        "orig_size": {
-            "width": surface.orig_size.0,
-            "height": surface.orig_size.1,
-        },
-        "src_rect": {
-            "x": surface.src_rect.x,
-            "y": surface.src_rect.y,
-            "width": surface.src_rect.width,
-            "height": surface.src_rect.height,
-        },
-        "dest_rect": {
-            "x": surface.dest_rect.x,
-            "y": surface.dest_rect.y,
-            "width": surface.dest_rect.width,
-            "height": surface.dest_rect.height,
-        },
-        "visibility": surface.visibility,
-        "opacity": surface.opacity,
-        "orientation": surface.orientation,
-        "z_order": surface.z_order,
-    })
-}
+    #[test]
+    fn test_dry_run_validates_without_applying() {
+        let state_manager = create_mock_ivi_state_manager();
+        let rpc_handler = RpcHandler::new(Arc::clone(&state_manager));
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            rpc_handler.handle_request(&client_id, request)
+        };
 
-impl RpcHandler {
-    /// List all screens
-    fn handle_list_screens(&self) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager);
+        let response = request(
+            "set_surface_opacity",
+            json!({ "id": 1000, "opacity": 0.5, "auto_commit": true, "dry_run": true }),
+        );
+        assert_eq!(
+            response.result,
+            Some(json!({
+                "id": 1000,
+                "opacity": 0.5,
+                "auto_commit": true,
+                "dry_run": true,
+                "valid": true
+            }))
+        );
+        assert!(rpc_handler
+            .staged_changes
+            .lock()
+            .unwrap()
+            .take_surface(1000)
+            .is_empty());
 
-        let screens = ivi_api.get_screens();
-        let screen_infos: Vec<serde_json::Value> = screens
-            .iter()
-            .map(|output| {
-                let info = ScreenInfo::from(output.clone());
-                json!({
-                    "name": info.name,
-                    "width": info.width,
-                    "height": info.height,
-                    "x": info.coord_global.x,
-                    "y": info.coord_global.y,
-                    "transform": info.transform.to_string(),
-                    "enabled": info.enabled,
-                    "scale": info.scale,
-                })
-            })
-            .collect();
+        let response = request(
+            "set_surface_opacity",
+            json!({ "id": 1000, "opacity": 1.5, "dry_run": true }),
+        );
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        Ok(json!({ "screens": screen_infos }))
-    }
+        let response = request(
+            "set_surface_visibility",
+            json!({ "id": 1234, "visible": true, "dry_run": true }),
+        );
+        assert_eq!(response.error.unwrap().code, RpcError::NOT_FOUND);
+
+        let response = request(
+            "batch",
+            json!({
+                "operations": [
+                    { "method": "set_surface_visibility", "params": { "id": 1000, "visible": true } },
+                    { "method": "set_layer_visibility", "params": { "id": 2000, "visible": true } }
+                ],
+                "commit": true,
+                "dry_run": true
+            }),
+        );
+        let error = response.error.unwrap();
+        assert_eq!(error.code, RpcError::NOT_FOUND);
+        assert!(error.message.starts_with("operations[1]: "));
 
-    /// Get a specific screen by name
-    fn handle_get_screen(&self, name: String) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager);
+        let response = request("commit", json!({ "dry_run": true }));
+        assert_eq!(response.result.unwrap()["valid"], json!(true));
+        assert_eq!(COMMIT_COUNT.with(|count| count.get()), 0);
 
-        let screens = ivi_api.get_screens();
-        let screen = screens
-            .iter()
-            .find(|output| output.name() == Some(name.clone()))
-            .ok_or_else(|| RpcError::internal_error(format!("Screen '{}' not found", name)))?;
+        let response = request("set_surface_focus", json!({ "id": 1000, "dry_run": "yes" }));
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        let screen_info = ScreenInfo::from(screen.clone());
+        // Methods that change nothing run as usual
+        let response = request("surface_exists", json!({ "id": 1000, "dry_run": true }));
+        assert!(response.result.unwrap().get("valid").is_none());
 
-        Ok(json!({
-            "name": screen_info.name,
-            "width": screen_info.width,
-            "height": screen_info.height,
-            "x": screen_info.coord_global.x,
-            "y": screen_info.coord_global.y,
-            "transform": screen_info.transform.to_string(),
-            "enabled": screen_info.enabled,
-            "scale": screen_info.scale,
-        }))
+        free_mock_surface();
     }
 
-    /// Get layers assigned to a screen
-    fn handle_get_screen_layers(&self, screen_name: String) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
-        drop(state_manager);
+    #[test]
+    fn test_set_surface_focus_failure_keeps_focus() {
+        // The mock layout has no surface_activate, so activation fails
+        let state_manager = create_mock_ivi_state_manager();
+
+        let seen = Arc::new(Mutex::new(0));
+        {
+            let nm = state_manager.lock().unwrap().notification_manager();
+            let seen = Arc::clone(&seen);
+            nm.lock().unwrap().register_callback(
+                NotificationType::FocusChanged,
+                Arc::new(move |_: &Notification| {
+                    *seen.lock().unwrap() += 1;
+                }),
+            );
+        }
 
-        let screens = ivi_api.get_screens();
-        let screen = screens
-            .iter()
-            .find(|output| output.name() == Some(screen_name.clone()))
-            .ok_or_else(|| {
-                RpcError::internal_error(format!("Screen '{}' not found", screen_name))
-            })?;
+        let rpc_handler = RpcHandler::new(Arc::clone(&state_manager));
+        let client_id = ClientId::from_u64(1);
 
-        let layers;
-        unsafe {
-            layers = ivi_api
-                .get_layers_on_screen((*screen).clone().into())
-                .map_err(|e| RpcError::internal_error(format!("Failed to get layers: {}", e)))?;
-        }
-        let layer_ids: Vec<u32> = layers.iter().map(|layer| layer.id()).collect();
+        let request = RpcRequest::new(
+            1,
+            "set_surface_focus".to_string(),
+            json!({ "id": 1000, "auto_commit": true }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32603);
 
-        Ok(json!({ "layer_ids": layer_ids }))
+        let request = RpcRequest::new(2, "get_focus".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.result, Some(json!({ "surface_id": null })));
+        assert_eq!(*seen.lock().unwrap(), 0);
+        assert_eq!(COMMIT_COUNT.with(|count| count.get()), 0);
+
+        free_mock_surface();
     }
 
-    /// Get screens assigned to a layer
-    fn handle_get_layer_screens(&self, layer_id: u32) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
+    #[test]
+    fn test_auto_commit_emits_one_notification_per_change() {
+        let state_manager = create_mock_ivi_state_manager();
+
+        let seen: Arc<Mutex<Vec<NotificationType>>> = Arc::new(Mutex::new(Vec::new()));
+        {
+            let nm = state_manager.lock().unwrap().notification_manager();
+            let mut nm = nm.lock().unwrap();
+            for nt in [
+                NotificationType::GeometryChanged,
+                NotificationType::VisibilityChanged,
+                NotificationType::OpacityChanged,
+                NotificationType::OrientationChanged,
+                NotificationType::ZOrderChanged,
+            ] {
+                let seen = Arc::clone(&seen);
+                nm.register_callback(
+                    nt,
+                    Arc::new(move |n: &Notification| {
+                        seen.lock().unwrap().push(n.notification_type);
+                    }),
+                );
+            }
+        }
 
-        // Get the layer
-        let layer = ivi_api
-            .get_layer_from_id(layer_id)
-            .ok_or_else(|| RpcError::layer_not_found(layer_id))?;
+        let rpc_handler = RpcHandler::new(Arc::clone(&state_manager));
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            assert!(rpc_handler
+                .handle_request(&client_id, request)
+                .error
+                .is_none());
+            std::mem::take(&mut *seen.lock().unwrap())
+        };
 
-        drop(state_manager);
+        let visible = json!({ "id": 1000, "visible": true, "auto_commit": true });
+        assert_eq!(
+            request("set_surface_visibility", visible.clone()),
+            vec![NotificationType::VisibilityChanged]
+        );
+        // Setting the same value again changes nothing
+        assert_eq!(request("set_surface_visibility", visible), vec![]);
+
+        assert_eq!(
+            request(
+                "set_surface_opacity",
+                json!({ "id": 1000, "opacity": 0.5, "auto_commit": true })
+            ),
+            vec![NotificationType::OpacityChanged]
+        );
+        assert_eq!(
+            request(
+                "set_surfaces_visibility",
+                json!({ "ids": [1000], "visible": false, "auto_commit": true })
+            ),
+            vec![NotificationType::VisibilityChanged]
+        );
 
-        let screens = ivi_api
-            .get_screens_under_layer(&layer)
-            .map_err(|e| RpcError::internal_error(format!("Failed to get screens: {}", e)))?;
+        // A staged change notifies once, when it is committed
+        assert_eq!(
+            request("set_surface_opacity", json!({ "id": 1000, "opacity": 1.0 })),
+            vec![]
+        );
+        assert_eq!(
+            request("commit", json!({})),
+            vec![NotificationType::OpacityChanged]
+        );
 
-        let screen_names: Vec<String> = screens.iter().filter_map(|output| output.name()).collect();
+        assert_eq!(
+            request(
+                "set_surface_size",
+                json!({ "id": 1000, "width": 300, "height": 200, "auto_commit": true })
+            ),
+            vec![NotificationType::GeometryChanged]
+        );
+        let position = json!({ "id": 1000, "x": 10, "y": 20, "auto_commit": true });
+        assert_eq!(
+            request("set_surface_position", position.clone()),
+            vec![NotificationType::GeometryChanged]
+        );
+        assert_eq!(request("set_surface_position", position), vec![]);
 
-        Ok(json!({ "screen_names": screen_names }))
+        // The mock surface is on no layer, so the z-order is taken as given
+        let z_order = json!({ "id": 1000, "z_order": 5, "auto_commit": true });
+        assert_eq!(
+            request("set_surface_z_order", z_order.clone()),
+            vec![NotificationType::ZOrderChanged]
+        );
+        assert_eq!(request("set_surface_z_order", z_order), vec![]);
+
+        free_mock_surface();
     }
 
-    /// Add layers to a screen (sets render order, replaces existing)
-    fn handle_add_layers_to_screen(
-        &self,
-        screen_name: String,
-        layer_ids: Vec<u32>,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
+    #[test]
+    fn test_batch_reports_each_operation() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |params: serde_json::Value| {
+            let request = RpcRequest::new(1, "batch".to_string(), params);
+            rpc_handler.handle_request(&client_id, request)
+        };
+        let dest_rect = || {
+            MOCK_SURFACE.with(|props| {
+                let props = unsafe { &*props.get() };
+                [
+                    props.dest_x,
+                    props.dest_y,
+                    props.dest_width,
+                    props.dest_height,
+                ]
+            })
+        };
 
-        // Find the screen
-        let screens = ivi_api.get_screens();
-        let screen = screens
-            .iter()
-            .find(|output| output.name() == Some(screen_name.clone()))
-            .ok_or_else(|| {
-                RpcError::internal_error(format!("Screen '{}' not found", screen_name))
-            })?;
+        // A malformed or unbatchable operation rejects the whole batch
+        for operation in [
+            json!({ "method": "set_surface_opacity", "params": { "id": 1000 } }),
+            json!({ "method": "get_surface", "params": { "id": 1000 } }),
+            json!({ "method": "batch", "params": { "operations": [] } }),
+            json!({ "params": {} }),
+        ] {
+            let response = request(json!({ "operations": [operation], "commit": true }));
+            assert_eq!(response.error.unwrap().code, -32602);
+        }
+        assert_eq!(COMMIT_COUNT.with(|count| count.get()), 0);
+
+        // A failing operation in the middle does not stop the others
+        let response = request(json!({
+            "operations": [
+                {
+                    "method": "set_surface_destination_rectangle",
+                    "params": { "id": 1000, "x": 10, "y": 20, "width": 300, "height": 200 },
+                },
+                { "method": "set_surface_opacity", "params": { "id": 9999, "opacity": 0.5 } },
+                {
+                    "method": "set_surface_visibility",
+                    "params": { "id": 1000, "visible": true, "auto_commit": true },
+                },
+            ],
+            "commit": true,
+        }));
+        let result = response.result.unwrap();
+        assert_eq!(result["committed"], true);
+        let results = result["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["success"], true);
+        assert_eq!(results[1]["success"], false);
+        assert_eq!(results[1]["error"]["code"], -32000);
+        assert_eq!(results[2]["success"], true);
+        assert_eq!(results[2]["result"]["committed"], false);
+
+        assert_eq!(dest_rect(), [10, 20, 300, 200]);
+        assert!(MOCK_SURFACE.with(|props| unsafe { (*props.get()).visibility }));
+        assert_eq!(COMMIT_COUNT.with(|count| count.get()), 1);
+
+        free_mock_surface();
+    }
 
-        // Verify all layers exist and build layer array
-        let layers: Vec<_> = layer_ids
-            .iter()
-            .filter_map(|&id| {
-                state_manager.get_layer(id)?;
-                ivi_api.get_layer_from_id(id)
+    #[test]
+    fn test_compact_rectangles() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            rpc_handler.handle_request(&client_id, request)
+        };
+        let rects = || {
+            MOCK_SURFACE.with(|props| {
+                let props = unsafe { &*props.get() };
+                (
+                    [
+                        props.source_x,
+                        props.source_y,
+                        props.source_width,
+                        props.source_height,
+                    ],
+                    [
+                        props.dest_x,
+                        props.dest_y,
+                        props.dest_width,
+                        props.dest_height,
+                    ],
+                )
             })
-            .collect();
+        };
 
-        // Verify we got all layers
-        if layers.len() != layer_ids.len() {
-            return Err(RpcError::internal_error(
-                "Some layers not found".to_string(),
-            ));
+        let response = request(
+            "set_surface_rectangles",
+            json!({
+                "id": 1000,
+                "src": [10, 20, 320, 240],
+                "dest": { "x": 100, "y": 200, "width": 640, "height": 480 },
+                "auto_commit": true,
+            }),
+        );
+        assert!(response.error.is_none());
+        assert_eq!(rects(), ([10, 20, 320, 240], [100, 200, 640, 480]));
+
+        // Single-rectangle setters take `rect` in either form
+        for rect in [
+            json!([1, 2, 30, 40]),
+            json!({ "x": 1, "y": 2, "width": 30, "height": 40 }),
+        ] {
+            let response = request(
+                "set_surface_destination_rectangle",
+                json!({ "id": 1000, "rect": rect, "auto_commit": true }),
+            );
+            assert!(response.error.is_none());
+            assert_eq!(rects().1, [1, 2, 30, 40]);
         }
 
-        drop(state_manager);
+        for rect in [json!([1, 2, 30]), json!([1, 2, 30, "40"]), json!(7)] {
+            let response = request(
+                "set_surface_source_rectangle",
+                json!({ "id": 1000, "rect": rect }),
+            );
+            assert_eq!(response.error.unwrap().code, -32602);
+        }
 
-        // Set render order - convert Vec<IviLayer> to &[&IviLayer]
-        let layer_refs: Vec<&_> = layers.iter().collect();
-        ivi_api
-            .screen_set_render_order((*screen).clone(), &layer_refs)
-            .map_err(|e| RpcError::internal_error(format!("Failed to set render order: {}", e)))?;
+        free_mock_surface();
+    }
+
+    #[test]
+    fn test_set_surface_rectangles_applies_both_in_one_commit() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            rpc_handler.handle_request(&client_id, request)
+        };
+        let commit_count = || COMMIT_COUNT.with(|count| count.get());
+        let rects = || {
+            MOCK_SURFACE.with(|props| {
+                let props = unsafe { &*props.get() };
+                (
+                    [
+                        props.source_x,
+                        props.source_y,
+                        props.source_width,
+                        props.source_height,
+                    ],
+                    [
+                        props.dest_x,
+                        props.dest_y,
+                        props.dest_width,
+                        props.dest_height,
+                    ],
+                )
+            })
+        };
+
+        // An invalid rectangle changes neither
+        let response = request(
+            "set_surface_rectangles",
+            json!({
+                "id": 1000,
+                "src": { "x": 0, "y": 0, "width": 320, "height": 240 },
+                "dest": { "x": 0, "y": 0, "width": 0, "height": 240 },
+            }),
+        );
+        assert_eq!(response.error.unwrap().code, -32602);
+        let response = request(
+            "set_surface_rectangles",
+            json!({ "id": 1000, "src": { "x": 0, "y": 0, "width": 320 } }),
+        );
+        assert_eq!(response.error.unwrap().code, -32602);
+        assert_eq!(request("commit", json!({})).result.unwrap()["applied"], 0);
+
+        // Staged, both rectangles are applied by one commit
+        let response = request(
+            "set_surface_rectangles",
+            json!({
+                "id": 1000,
+                "src": { "x": 10, "y": 20, "width": 320, "height": 240 },
+                "dest": { "x": 100, "y": 200, "width": 640, "height": 480 },
+            }),
+        );
+        assert_eq!(response.result.unwrap()["committed"], false);
+        assert_eq!(rects(), ([0, 0, 0, 0], [0, 0, 0, 0]));
+        assert_eq!(request("commit", json!({})).result.unwrap()["applied"], 2);
+        assert_eq!(rects(), ([10, 20, 320, 240], [100, 200, 640, 480]));
+        assert_eq!(commit_count(), 1);
+
+        // With auto_commit, they are applied together too
+        let response = request(
+            "set_surface_rectangles",
+            json!({
+                "id": 1000,
+                "src": { "x": 0, "y": 0, "width": 160, "height": 120 },
+                "dest": { "x": 0, "y": 0, "width": 1280, "height": 960 },
+                "auto_commit": true,
+            }),
+        );
+        assert_eq!(response.result.unwrap()["committed"], true);
+        assert_eq!(rects(), ([0, 0, 160, 120], [0, 0, 1280, 960]));
+        assert_eq!(commit_count(), 2);
 
-        if auto_commit {
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(format!("Failed to commit: {}", e)))?;
+        free_mock_surface();
+    }
+
+    #[test]
+    fn test_metrics_report_commit_and_repaint_rates() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        for opacity in [0.5, 0.25] {
+            let request = RpcRequest::new(
+                1,
+                "set_surface_opacity".to_string(),
+                json!({ "id": 1000, "opacity": opacity, "auto_commit": true }),
+            );
+            assert!(rpc_handler
+                .handle_request(&client_id, request)
+                .error
+                .is_none());
         }
+        rpc_handler.record_repaint("HDMI-A-1");
 
-        Ok(json!({
-            "screen_name": screen_name,
-            "layer_ids": layer_ids,
-            "committed": auto_commit
-        }))
+        let request = RpcRequest::new(2, "get_metrics".to_string(), json!({}));
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+
+        assert_eq!(result["ivi_commits"], 2);
+        assert!(result["commits_per_second"].as_f64().unwrap() > 0.0);
+        assert!(result["repaints_per_second"]["HDMI-A-1"].as_f64().unwrap() > 0.0);
+        assert_eq!(result["repaints_per_second"].as_object().unwrap().len(), 1);
+
+        free_mock_surface();
     }
 
-    /// Remove a layer from a screen
-    fn handle_remove_layer_from_screen(
-        &self,
-        screen_name: String,
-        layer_id: u32,
-        auto_commit: bool,
-    ) -> Result<serde_json::Value, RpcError> {
-        let state_manager = self.state_manager.lock().unwrap();
-        let ivi_api = state_manager.ivi_api().clone();
+    #[test]
+    fn test_live_get_surface_keeps_tracked_z_order() {
+        let state_manager = create_mock_ivi_state_manager();
+        state_manager.lock().unwrap().set_surface_z_order(1000, 7);
+        let rpc_handler = RpcHandler::new(Arc::clone(&state_manager));
+        let client_id = ClientId::from_u64(1);
+        let get_surface = |live: bool| {
+            let request = RpcRequest::new(
+                1,
+                "get_surface".to_string(),
+                json!({ "id": 1000, "live": live }),
+            );
+            rpc_handler
+                .handle_request(&client_id, request)
+                .result
+                .unwrap()
+        };
 
-        // Find the screen
-        let screens = ivi_api.get_screens();
-        let screen = screens
-            .iter()
-            .find(|output| output.name() == Some(screen_name.clone()))
-            .ok_or_else(|| {
-                RpcError::internal_error(format!("Screen '{}' not found", screen_name))
-            })?;
+        // The IVI layout does not report z-order, so a live read must not
+        // report a drift from the tracked one
+        assert_eq!(get_surface(false)["z_order"], 7);
+        assert_eq!(get_surface(true)["z_order"], 7);
 
-        // Get the layer using get_layer_from_id
-        let layer = ivi_api
-            .get_layer_from_id(layer_id)
-            .ok_or_else(|| RpcError::layer_not_found(layer_id))?;
+        free_mock_surface();
+    }
 
-        drop(state_manager);
+    #[test]
+    fn test_z_order_is_recorded_on_commit() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            let response = rpc_handler.handle_request(&client_id, request);
+            assert!(response.error.is_none());
+            response.result.unwrap()
+        };
 
-        // Remove layer from screen
-        ivi_api
-            .screen_remove_layer(screen.clone(), &layer)
-            .map_err(|e| RpcError::internal_error(format!("Failed to remove layer: {}", e)))?;
+        request(
+            "set_surface_z_order",
+            json!({ "id": 1000, "z_order": 5, "auto_commit": false }),
+        );
+        assert_eq!(request("get_surface", json!({ "id": 1000 }))["z_order"], 0);
 
-        if auto_commit {
-            ivi_api
-                .commit_changes()
-                .map_err(|e| RpcError::internal_error(format!("Failed to commit: {}", e)))?;
-        }
+        request("commit", json!({}));
+        assert_eq!(request("get_surface", json!({ "id": 1000 }))["z_order"], 5);
 
-        Ok(json!({
-            "screen_name": screen_name,
-            "layer_id": layer_id,
-            "committed": auto_commit
-        }))
+        free_mock_surface();
     }
-}
 
-/// Message handler implementation that bridges transport and RPC handler
-struct RpcMessageHandler {
-    rpc_handler: Arc<RpcHandler>,
-}
+    #[test]
+    fn test_commit_with_nothing_pending_skips_ivi_commit() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            let response = rpc_handler.handle_request(&client_id, request);
+            assert!(response.error.is_none());
+            response.result.unwrap()
+        };
+        let commit_count = || COMMIT_COUNT.with(|count| count.get());
+
+        // Nothing has been changed yet
+        let result = request("commit", json!({}));
+        assert_eq!(result["applied"], 0);
+        assert_eq!(result["commit_seq"], 0);
+        assert_eq!(commit_count(), 0);
+
+        request("set_surface_opacity", json!({ "id": 1000, "opacity": 0.5 }));
+        let result = request("commit", json!({}));
+        assert_eq!(result["applied"], 1);
+        assert_eq!(result["commit_seq"], 1);
+        assert_eq!(commit_count(), 1);
+
+        // A second commit in a row has nothing to apply
+        let result = request("commit", json!({}));
+        assert_eq!(result["success"], true);
+        assert_eq!(result["applied"], 0);
+        assert_eq!(result["commit_seq"], 1);
+        assert_eq!(commit_count(), 1);
+
+        // An unstaged change without auto_commit still needs a commit
+        request(
+            "set_surface_z_order",
+            json!({ "id": 1000, "z_order": 3, "auto_commit": false }),
+        );
+        request("commit", json!({}));
+        assert_eq!(commit_count(), 2);
 
-impl MessageHandler for RpcMessageHandler {
-    fn handle_message(&self, client_id: &ClientId, data: &[u8]) {
-        jtrace!("Received message from client {}", client_id);
+        free_mock_surface();
+    }
 
-        // Parse the incoming message as an RPC request
-        let request = match RpcRequest::from_json(data) {
-            Ok(req) => req,
-            Err(e) => {
-                // If we can't parse the request, we can't send a proper response
-                // because we don't have a request ID
-                jerror!(
-                    "Failed to parse RPC request from client {}: {:?}",
-                    client_id,
-                    e
-                );
-                return;
-            }
+    #[test]
+    fn test_auto_commit_applies_staged_changes() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            let response = rpc_handler.handle_request(&client_id, request);
+            assert!(response.error.is_none());
+            response.result.unwrap()
         };
+        let commit_count = || COMMIT_COUNT.with(|count| count.get());
+
+        request("set_surface_opacity", json!({ "id": 1000, "opacity": 0.5 }));
+        request(
+            "set_surface_visibility",
+            json!({ "id": 1000, "visible": false, "auto_commit": true }),
+        );
+        assert_eq!(commit_count(), 1);
 
-        // Handle the request
-        let response = self.rpc_handler.handle_request(client_id, request);
+        // The IVI commit is global, so the staged opacity went with it
+        let surface = request("get_surface", json!({ "id": 1000 }));
+        assert_eq!(surface["opacity"], 0.5);
+        assert_eq!(surface["visibility"], false);
+        assert_eq!(request("commit", json!({}))["applied"], 0);
+        assert_eq!(commit_count(), 1);
 
-        // Serialize the response
-        let response_data = match response.to_json() {
-            Ok(data) => data,
-            Err(e) => {
-                jerror!(
-                    "Failed to serialize RPC response for client {}: {:?}",
-                    client_id,
-                    e
-                );
-                return;
-            }
+        free_mock_surface();
+    }
+
+    #[test]
+    fn test_auto_commit_debounce_coalesces_commits() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        // A window long enough that the timer never fires during the test
+        rpc_handler.start_auto_commit_debounce(Duration::from_secs(3600));
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            let response = rpc_handler.handle_request(&client_id, request);
+            assert!(response.error.is_none());
+            response.result.unwrap()
         };
+        let commit_count = || COMMIT_COUNT.with(|count| count.get());
 
-        // Send the response back to the client
-        jdebug!(
-            "Sending response to client {}, {} bytes",
-            client_id,
-            response_data.len()
+        let result = request(
+            "set_surface_opacity",
+            json!({ "id": 1000, "opacity": 0.5, "auto_commit": true }),
         );
-        let transport_lock = self.rpc_handler.transport.lock().unwrap();
-        if let Some(transport) = transport_lock.as_ref() {
-            match transport.send(client_id, &response_data) {
-                Ok(_) => {
-                    jdebug!("Successfully sent response to client {}", client_id);
-                }
-                Err(e) => {
-                    jerror!(
-                        "Failed to send RPC response to client {}: {:?}",
-                        client_id,
-                        e
-                    );
-                }
-            }
-        } else {
-            jwarn!(
-                "No transport available to send response to client {}",
-                client_id
-            );
+        assert_eq!(result["committed"], false);
+        assert_eq!(result["deferred"], true);
+        request(
+            "set_surface_visibility",
+            json!({ "id": 1000, "visible": false, "auto_commit": true }),
+        );
+        assert_eq!(commit_count(), 0);
+
+        // The transport loop leaves the commit alone until the window has passed
+        RpcMessageHandler {
+            rpc_handler: Arc::clone(&rpc_handler),
         }
+        .poll();
+        assert_eq!(commit_count(), 0);
+
+        // A read commits the deferred changes first and sees them
+        let surface = request("get_surface", json!({ "id": 1000 }));
+        assert_eq!(commit_count(), 1);
+        assert_eq!(surface["opacity"], 0.5);
+        assert_eq!(surface["visibility"], false);
+
+        // An explicit commit flushes deferred changes as well
+        request(
+            "set_surface_opacity",
+            json!({ "id": 1000, "opacity": 0.25, "auto_commit": true }),
+        );
+        request("commit", json!({}));
+        assert_eq!(commit_count(), 2);
+
+        free_mock_surface();
     }
 
-    fn handle_disconnect(&self, client_id: &ClientId) {
-        // Log the disconnection
-        jinfo!("Client {} disconnected", client_id);
+    #[test]
+    fn test_auto_commit_debounce_flushed_by_transport_loop() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        rpc_handler.start_auto_commit_debounce(Duration::from_millis(1));
+        let message_handler = RpcMessageHandler {
+            rpc_handler: Arc::clone(&rpc_handler),
+        };
+        let client_id = ClientId::from_u64(1);
+        let commit_count = || COMMIT_COUNT.with(|count| count.get());
 
-        // Clean up subscriptions for this client
-        self.rpc_handler
-            .subscription_manager
-            .lock()
-            .unwrap()
-            .remove_client(client_id);
+        let request = RpcRequest::new(
+            1,
+            "set_surface_opacity".to_string(),
+            json!({ "id": 1000, "opacity": 0.5, "auto_commit": true }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.result.unwrap()["deferred"], true);
+        assert_eq!(commit_count(), 0);
 
-        jdebug!("Cleaned up subscriptions for client {}", client_id);
-    }
-}
+        thread::sleep(Duration::from_millis(5));
+        message_handler.poll();
+        assert_eq!(commit_count(), 1);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ffi::bindings::ivi_layout_api::IviLayoutApi;
-    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+        // Nothing is left to commit on the next pass
+        message_handler.poll();
+        assert_eq!(commit_count(), 1);
 
-    /// Mock transport for testing
-    struct MockTransport {
-        started: AtomicBool,
-        stopped: AtomicBool,
-        last_client_id: AtomicU64,
-        last_message: Mutex<Vec<u8>>,
-        handler: Mutex<Option<Box<dyn MessageHandler>>>,
+        free_mock_surface();
     }
 
-    impl MockTransport {
-        fn new() -> Self {
-            Self {
-                started: AtomicBool::new(false),
-                stopped: AtomicBool::new(false),
-                last_client_id: AtomicU64::new(0),
-                last_message: Mutex::new(Vec::new()),
-                handler: Mutex::new(None),
-            }
-        }
-    }
+    #[test]
+    fn test_idempotency_key_replays_mutation_result() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str, params: serde_json::Value| {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            rpc_handler.handle_request(&client_id, request)
+        };
+        let commit_count = || COMMIT_COUNT.with(|count| count.get());
+        let params = json!({
+            "id": 1000,
+            "dx": 10,
+            "dy": 20,
+            "relative": true,
+            "auto_commit": true,
+            "idempotency_key": "move-1",
+        });
 
-    impl Transport for MockTransport {
-        fn start(&mut self) -> Result<(), TransportError> {
-            self.started.store(true, Ordering::SeqCst);
-            Ok(())
-        }
+        let first = request("set_surface_position", params.clone());
+        assert!(first.error.is_none());
+        assert_eq!(commit_count(), 1);
 
-        fn stop(&mut self) -> Result<(), TransportError> {
-            self.stopped.store(true, Ordering::SeqCst);
-            Ok(())
-        }
+        // A retry, e.g. on a new connection, is not applied again
+        let retry = rpc_handler.handle_request(
+            &ClientId::from_u64(2),
+            RpcRequest::new(1, "set_surface_position".to_string(), params.clone()),
+        );
+        assert_eq!(retry.result, first.result);
+        assert_eq!(commit_count(), 1);
+
+        let mut other_params = params;
+        other_params["dx"] = json!(30);
+        let response = request("set_surface_position", other_params);
+        assert_eq!(response.error.unwrap().code, -32602);
+        assert_eq!(commit_count(), 1);
+
+        let response = request(
+            "set_surface_opacity",
+            json!({ "id": 1000, "opacity": 0.5, "idempotency_key": "move-1" }),
+        );
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        fn send(&self, client_id: &ClientId, data: &[u8]) -> Result<(), TransportError> {
-            if let Some(id) = client_id.unix_domain_id() {
-                self.last_client_id.store(id, Ordering::SeqCst);
-                *self.last_message.lock().unwrap() = data.to_vec();
-                Ok(())
-            } else {
-                Err(TransportError::SendError("Invalid client ID".to_string()))
-            }
-        }
+        let response = request(
+            "set_surface_opacity",
+            json!({ "id": 1000, "opacity": 0.5, "idempotency_key": 7 }),
+        );
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        fn send_to_clients(
-            &self,
-            client_ids: &[&ClientId],
-            data: &[u8],
-        ) -> Result<(), TransportError> {
-            for &client_id in client_ids {
-                self.send(client_id, data)?;
-            }
-            Ok(())
-        }
+        free_mock_surface();
+    }
 
-        fn get_connected_clients(&self) -> Vec<ClientId> {
-            vec![ClientId::from_u64(1)]
-        }
+    #[test]
+    fn test_set_surfaces_opacity_validation() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(
+            1,
+            "set_surfaces_opacity".to_string(),
+            json!({ "ids": [1000, 1001], "opacity": 1.5 }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        fn register_handler(&mut self, handler: Box<dyn MessageHandler>) {
-            *self.handler.lock().unwrap() = Some(handler);
-        }
+        let request = RpcRequest::new(
+            2,
+            "set_surfaces_opacity".to_string(),
+            json!({ "ids": [1000, 1001], "opacity": 0.5 }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 1000, "kind": "surface" }))
+        );
     }
 
-    // Helper to create a mock state manager for testing
-    fn create_mock_state_manager() -> Arc<Mutex<StateManager>> {
-        // Create a mock IVI API with a null pointer (safe for testing as we won't call IVI functions)
-        let ivi_api = {
-            // For testing, we create a dummy API pointer
-            // This is safe because our tests don't actually call IVI functions
-            Arc::new(IviLayoutApi::from_raw(std::ptr::dangling()).unwrap())
+    #[test]
+    fn test_commit_single_surface_validation() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(1, "commit".to_string(), json!({ "id": 1000 }));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 1000, "kind": "surface" }))
+        );
+
+        let request = RpcRequest::new(2, "commit".to_string(), json!({ "id": "all" }));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_subscribe_reports_disabled_events() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        rpc_handler.set_disabled_events(&[EventType::SurfaceCreated]);
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(
+            1,
+            "subscribe".to_string(),
+            json!({ "event_types": ["SurfaceCreated", "LayerCreated"] }),
+        );
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+        assert_eq!(result["ignored"], json!(["SurfaceCreated"]));
+
+        let request = RpcRequest::new(
+            2,
+            "subscribe".to_string(),
+            json!({ "event_types": ["LayerCreated"] }),
+        );
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+        assert_eq!(result["ignored"], json!([]));
+    }
+
+    #[test]
+    fn test_subscribe_all_event_types() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+        let request = |method: &str| {
+            let request = RpcRequest::new(1, method.to_string(), json!({ "event_types": "all" }));
+            let response = rpc_handler.handle_request(&client_id, request);
+            assert!(response.error.is_none());
         };
-        Arc::new(Mutex::new(StateManager::new(ivi_api)))
+        let subscriptions = || {
+            let request = RpcRequest::new(2, "list_subscriptions".to_string(), json!({}));
+            let result = rpc_handler
+                .handle_request(&client_id, request)
+                .result
+                .unwrap();
+            result["subscriptions"].as_array().unwrap().len()
+        };
+
+        request("subscribe");
+        assert_eq!(subscriptions(), EventType::ALL.len());
+
+        request("unsubscribe");
+        assert_eq!(subscriptions(), 0);
+
+        let request = RpcRequest::new(3, "subscribe".to_string(), json!({ "event_types": "some" }));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
     }
 
     #[test]
-    fn test_transport_registration() {
-        let state_manager = create_mock_state_manager();
+    fn test_get_layer_render_order_validation() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(
+            1,
+            "get_layer_render_order".to_string(),
+            json!({ "id": 2000 }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 2000, "kind": "layer" }))
+        );
 
-        // Create RPC handler
-        let rpc_handler = RpcHandler::new(state_manager);
+        let request = RpcRequest::new(2, "get_layer_render_order".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
 
-        // Create and register mock transport
-        let transport = Box::new(MockTransport::new());
-        let result = rpc_handler.register_transport(transport);
+    #[test]
+    fn test_add_and_remove_surface_layer_validation() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        for (id, method) in [
+            (1, "add_surface_to_layer"),
+            (2, "remove_surface_from_layer"),
+        ] {
+            let request = RpcRequest::new(
+                id,
+                method.to_string(),
+                json!({ "layer_id": 2000, "surface_id": 1000 }),
+            );
+            let response = rpc_handler.handle_request(&client_id, request);
+            assert_eq!(
+                response.error.unwrap().data,
+                Some(json!({ "id": 2000, "kind": "layer" })),
+                "{}",
+                method
+            );
 
-        assert!(result.is_ok());
+            let request = RpcRequest::new(id, method.to_string(), json!({ "layer_id": 2000 }));
+            let response = rpc_handler.handle_request(&client_id, request);
+            assert_eq!(response.error.unwrap().code, -32602, "{}", method);
+        }
     }
 
     #[test]
-    fn test_transport_start_stop() {
-        let state_manager = create_mock_state_manager();
+    fn test_restack_surface_validation() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        for (id, method) in [(1, "bring_surface_to_front"), (2, "send_surface_to_back")] {
+            let request = RpcRequest::new(id, method.to_string(), json!({ "id": 1000 }));
+            let response = rpc_handler.handle_request(&client_id, request);
+            assert_eq!(
+                response.error.unwrap().data,
+                Some(json!({ "id": 1000, "kind": "surface" }))
+            );
+        }
 
-        // Create RPC handler
-        let rpc_handler = RpcHandler::new(state_manager);
+        let request = RpcRequest::new(3, "bring_surface_to_front".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
 
-        // Create and register mock transport
-        let transport = Box::new(MockTransport::new());
-        rpc_handler.register_transport(transport).unwrap();
+    #[test]
+    fn test_get_surface_z_index() {
+        let rpc_handler = RpcHandler::new(create_mock_ivi_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        // The mocked surface is not on any layer
+        let request = RpcRequest::new(1, "get_surface_z_index".to_string(), json!({ "id": 1000 }));
+        let result = rpc_handler
+            .handle_request(&client_id, request)
+            .result
+            .unwrap();
+        assert_eq!(result, json!({ "id": 1000, "layers": [] }));
+
+        let request = RpcRequest::new(2, "get_surface_z_index".to_string(), json!({ "id": 1001 }));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 1001, "kind": "surface" }))
+        );
 
-        // Start transport
-        let result = rpc_handler.start_transport();
-        assert!(result.is_ok());
+        let request = RpcRequest::new(3, "get_surface_z_index".to_string(), json!({}));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        // Stop transport
-        let result = rpc_handler.stop_transport();
-        assert!(result.is_ok());
+        free_mock_surface();
     }
 
     #[test]
-    fn test_message_handler_integration() {
-        let state_manager = create_mock_state_manager();
+    fn test_set_surface_position_validation() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
+
+        let request = RpcRequest::new(
+            1,
+            "set_surface_position".to_string(),
+            json!({ "id": 1000, "dx": 5, "dy": -5, "relative": true }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 1000, "kind": "surface" }))
+        );
 
-        // Create RPC handler
-        let rpc_handler = RpcHandler::new(state_manager);
+        // A relative move needs dx/dy, not x/y
+        let request = RpcRequest::new(
+            2,
+            "set_surface_position".to_string(),
+            json!({ "id": 1000, "x": 5, "y": -5, "relative": true }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
 
-        // Create and register mock transport
-        let transport = Box::new(MockTransport::new());
-        rpc_handler.register_transport(transport).unwrap();
+        let request = RpcRequest::new(
+            3,
+            "set_layer_position".to_string(),
+            json!({ "id": 2000, "dx": 5, "dy": -5, "relative": true }),
+        );
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(
+            response.error.unwrap().data,
+            Some(json!({ "id": 2000, "kind": "layer" }))
+        );
+    }
 
-        // Create a list_surfaces request
-        let request = RpcRequest::new(1, "list_surfaces".to_string(), json!({}));
-        let _request_data = request.to_json().unwrap();
+    #[test]
+    fn test_get_output_requires_index() {
+        let rpc_handler = RpcHandler::new(create_mock_state_manager());
+        let client_id = ClientId::from_u64(1);
 
-        // Verify the transport is registered
-        let transport_lock = rpc_handler.transport.lock().unwrap();
-        assert!(transport_lock.is_some());
+        let request = RpcRequest::new(1, "get_output".to_string(), json!({ "index": -1 }));
+        let response = rpc_handler.handle_request(&client_id, request);
+        assert_eq!(response.error.unwrap().code, -32602);
     }
 }
@@ -0,0 +1,216 @@
+// Results of recent requests, replayed for retried idempotency keys
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// How long the result of a request with an `idempotency_key` is kept
+pub const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(60);
+
+/// Maximum number of idempotency keys remembered at once
+pub const MAX_IDEMPOTENCY_KEYS: usize = 1024;
+
+/// Why a key cannot replay the result remembered for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyReuse {
+    /// The key was used for this other method
+    OtherMethod(String),
+    /// The key was used for the same method with other params
+    OtherParams,
+}
+
+/// Hash of request params, to tell a retry from a different request
+/// reusing its key
+pub fn params_hash(params: &serde_json::Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    params.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Result of a request, remembered under its idempotency key
+#[derive(Debug)]
+struct RecentResult {
+    method: String,
+    params_hash: u64,
+    result: serde_json::Value,
+    seen_at: Instant,
+}
+
+/// Bounded map from idempotency keys to the results of recent requests
+///
+/// Entries expire after the TTL. When full, the oldest entry is dropped to
+/// make room, so a burst of keyed requests can shorten how long each key is
+/// remembered but never grows the map.
+#[derive(Debug)]
+pub struct RecentResults {
+    ttl: Duration,
+    capacity: usize,
+    results: HashMap<String, RecentResult>,
+    /// Keys in insertion order, oldest first
+    order: VecDeque<String>,
+}
+
+impl RecentResults {
+    /// Create an empty map keeping up to `capacity` keys for `ttl` each
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            results: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Look up the result remembered for `key`
+    ///
+    /// Returns `Err` if `key` was used for a different method, or for the
+    /// same method with params of a different [`params_hash`].
+    pub fn get(
+        &mut self,
+        key: &str,
+        method: &str,
+        params_hash: u64,
+        now: Instant,
+    ) -> Result<Option<serde_json::Value>, KeyReuse> {
+        self.expire(now);
+        match self.results.get(key) {
+            Some(recent) if recent.method != method => {
+                Err(KeyReuse::OtherMethod(recent.method.clone()))
+            }
+            Some(recent) if recent.params_hash != params_hash => Err(KeyReuse::OtherParams),
+            Some(recent) => Ok(Some(recent.result.clone())),
+            None => Ok(None),
+        }
+    }
+
+    /// Remember the result of a request made with `key` at `now`
+    pub fn insert(
+        &mut self,
+        key: &str,
+        method: &str,
+        params_hash: u64,
+        result: serde_json::Value,
+        now: Instant,
+    ) {
+        self.expire(now);
+        if self.results.contains_key(key) {
+            return;
+        }
+        while self.order.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.results.remove(&oldest);
+                }
+                None => return,
+            }
+        }
+
+        self.order.push_back(key.to_string());
+        self.results.insert(
+            key.to_string(),
+            RecentResult {
+                method: method.to_string(),
+                params_hash,
+                result,
+                seen_at: now,
+            },
+        );
+    }
+
+    /// Number of keys currently remembered
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Whether no key is remembered
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(oldest) = self.order.front() {
+            let expired = match self.results.get(oldest) {
+                Some(recent) => now.duration_since(recent.seen_at) > self.ttl,
+                None => true,
+            };
+            if !expired {
+                break;
+            }
+            self.results.remove(oldest);
+            self.order.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_replays_result_until_expired() {
+        let start = Instant::now();
+        let mut recent = RecentResults::new(Duration::from_secs(60), 16);
+
+        assert_eq!(recent.get("a", "set_surface_position", 1, start), Ok(None));
+        recent.insert("a", "set_surface_position", 1, json!({ "x": 10 }), start);
+
+        let later = start + Duration::from_secs(30);
+        assert_eq!(
+            recent.get("a", "set_surface_position", 1, later),
+            Ok(Some(json!({ "x": 10 })))
+        );
+
+        let expired = start + Duration::from_secs(61);
+        assert_eq!(
+            recent.get("a", "set_surface_position", 1, expired),
+            Ok(None)
+        );
+        assert!(recent.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_key_reused_for_other_method() {
+        let now = Instant::now();
+        let mut recent = RecentResults::new(Duration::from_secs(60), 16);
+
+        recent.insert("a", "set_surface_position", 1, json!({}), now);
+        assert_eq!(
+            recent.get("a", "set_layer_opacity", 1, now),
+            Err(KeyReuse::OtherMethod("set_surface_position".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_rejects_key_reused_with_other_params() {
+        let now = Instant::now();
+        let mut recent = RecentResults::new(Duration::from_secs(60), 16);
+        let first = params_hash(&json!({ "id": 1000, "x": 10 }));
+        let second = params_hash(&json!({ "id": 1000, "x": 20 }));
+
+        recent.insert("a", "set_surface_position", first, json!({}), now);
+        assert_eq!(
+            recent.get("a", "set_surface_position", first, now),
+            Ok(Some(json!({})))
+        );
+        assert_eq!(
+            recent.get("a", "set_surface_position", second, now),
+            Err(KeyReuse::OtherParams)
+        );
+    }
+
+    #[test]
+    fn test_drops_oldest_key_when_full() {
+        let now = Instant::now();
+        let mut recent = RecentResults::new(Duration::from_secs(60), 2);
+
+        recent.insert("a", "commit", 1, json!(1), now);
+        recent.insert("b", "commit", 1, json!(2), now);
+        recent.insert("c", "commit", 1, json!(3), now);
+
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent.get("a", "commit", 1, now), Ok(None));
+        assert_eq!(recent.get("c", "commit", 1, now), Ok(Some(json!(3))));
+    }
+}
@@ -2,12 +2,14 @@
 
 pub mod framing;
 pub mod handler;
+pub mod idempotency;
 pub mod notification_bridge;
 pub mod protocol;
+pub mod rate;
 pub mod transport;
 
 pub use framing::{write_frame, FrameReadResult, FrameReader, MAX_MESSAGE_SIZE};
 pub use handler::RpcHandler;
 pub use notification_bridge::NotificationBridge;
 pub use protocol::{RpcError, RpcMethod, RpcRequest, RpcResponse};
-pub use transport::{ClientId, MessageHandler, Transport, TransportError};
+pub use transport::{ClientCredentials, ClientId, MessageHandler, Transport, TransportError};
@@ -6,13 +6,16 @@ use crate::controller::notifications::{
     OrientationChangeNotification, VisibilityChangeNotification, ZOrderChangeNotification,
 };
 use crate::controller::subscriptions::SubscriptionManager;
+use crate::rpc::handler::surface_state_to_json;
 use crate::rpc::protocol::{EventType, RpcNotification};
 use serde_json::json;
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 
 /// Bridges internal notifications to RPC client delivery
 pub struct NotificationBridge {
     subscription_manager: Arc<Mutex<SubscriptionManager>>,
+    disabled_events: HashSet<EventType>,
 }
 
 impl NotificationBridge {
@@ -20,6 +23,18 @@ impl NotificationBridge {
     pub fn new(subscription_manager: Arc<Mutex<SubscriptionManager>>) -> Self {
         Self {
             subscription_manager,
+            disabled_events: HashSet::new(),
+        }
+    }
+
+    /// Create a new notification bridge that never delivers the given event types
+    pub fn with_disabled_events(
+        subscription_manager: Arc<Mutex<SubscriptionManager>>,
+        disabled_events: Vec<EventType>,
+    ) -> Self {
+        Self {
+            subscription_manager,
+            disabled_events: disabled_events.into_iter().collect(),
         }
     }
 
@@ -28,11 +43,14 @@ impl NotificationBridge {
     fn convert_notification(&self, notification: &Notification) -> (EventType, RpcNotification) {
         let (event_type, params) = match &notification.data {
             // Surface events
-            NotificationData::SurfaceCreated { surface_id } => (
+            // A surface is not on any layer yet when it is created, so it
+            // cannot be on screen
+            NotificationData::SurfaceCreated { state } => (
                 EventType::SurfaceCreated,
                 json!({
                     "event_type": "SurfaceCreated",
-                    "surface_id": surface_id
+                    "surface_id": state.id,
+                    "surface": surface_state_to_json(state, false),
                 }),
             ),
 
@@ -68,6 +86,42 @@ impl NotificationBridge {
                 }),
             ),
 
+            NotificationData::RepaintComplete {
+                output_id,
+                output_name,
+                commit_seq,
+            } => (
+                EventType::RepaintComplete,
+                json!({
+                    "event_type": "RepaintComplete",
+                    "output": output_name,
+                    "output_id": output_id,
+                    "commit_seq": commit_seq,
+                }),
+            ),
+
+            NotificationData::InputPanelShown {
+                surface_id,
+                target_surface_id,
+                overlay,
+            } => (
+                EventType::InputPanelShown,
+                json!({
+                    "event_type": "InputPanelShown",
+                    "surface_id": surface_id,
+                    "target_surface_id": target_surface_id,
+                    "overlay": overlay,
+                }),
+            ),
+
+            NotificationData::InputPanelHidden { surface_id } => (
+                EventType::InputPanelHidden,
+                json!({
+                    "event_type": "InputPanelHidden",
+                    "surface_id": surface_id,
+                }),
+            ),
+
             NotificationData::SurfaceDestroyed { surface_id } => (
                 EventType::SurfaceDestroyed,
                 json!({
@@ -218,10 +272,7 @@ impl NotificationBridge {
             ),
         };
 
-        let rpc_notification = RpcNotification {
-            method: "notification".to_string(),
-            params,
-        };
+        let rpc_notification = RpcNotification::new("notification".to_string(), params);
 
         (event_type, rpc_notification)
     }
@@ -231,6 +282,10 @@ impl NotificationBridge {
     pub fn handle_notification(&self, notification: &Notification) {
         let (event_type, rpc_notification) = self.convert_notification(notification);
 
+        if self.disabled_events.contains(&event_type) {
+            return;
+        }
+
         self.subscription_manager
             .lock()
             .unwrap()
@@ -242,10 +297,38 @@ impl NotificationBridge {
 mod tests {
     use super::*;
     use crate::controller::notifications::NotificationType;
+    use crate::controller::state::{SurfaceState, SurfaceType};
     use crate::ffi::bindings::Orientation;
     use crate::ffi::Rectangle;
     use crate::rpc::ClientId;
 
+    fn test_surface_state(id: u32) -> SurfaceState {
+        SurfaceState {
+            id,
+            orig_size: (1920, 1080),
+            src_rect: Rectangle {
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            dest_rect: Rectangle {
+                x: 100,
+                y: 50,
+                width: 800,
+                height: 600,
+            },
+            visibility: false,
+            opacity: 1.0,
+            orientation: Orientation::Normal,
+            z_order: 0,
+            is_auto_assigned: false,
+            original_id: None,
+            initialized: true,
+            surface_type: SurfaceType::Ivi,
+        }
+    }
+
     #[test]
     fn test_convert_surface_created() {
         let subscription_manager = Arc::new(Mutex::new(SubscriptionManager::new()));
@@ -253,7 +336,9 @@ mod tests {
 
         let notification = Notification {
             notification_type: NotificationType::SurfaceCreated,
-            data: NotificationData::SurfaceCreated { surface_id: 1000 },
+            data: NotificationData::SurfaceCreated {
+                state: test_surface_state(1000),
+            },
         };
 
         let (event_type, rpc_notification) = bridge.convert_notification(&notification);
@@ -261,12 +346,27 @@ mod tests {
         assert_eq!(event_type, EventType::SurfaceCreated);
         assert_eq!(rpc_notification.method, "notification");
 
+        let frame: serde_json::Value =
+            serde_json::from_slice(&rpc_notification.to_json().unwrap()).unwrap();
+        assert_eq!(frame["type"], "notification");
+        assert!(frame.get("id").is_none());
+
         let params = rpc_notification.params.as_object().unwrap();
         assert_eq!(
             params.get("event_type").unwrap().as_str().unwrap(),
             "SurfaceCreated"
         );
         assert_eq!(params.get("surface_id").unwrap().as_u64().unwrap(), 1000);
+
+        let surface = params.get("surface").unwrap();
+        assert_eq!(surface["id"], 1000);
+        assert_eq!(surface["orig_size"]["width"], 1920);
+        assert_eq!(surface["dest_rect"]["x"], 100);
+        assert_eq!(surface["dest_rect"]["height"], 600);
+        assert_eq!(surface["visibility"], false);
+        assert_eq!(surface["orientation"], "Normal");
+        assert_eq!(surface["on_screen"], false);
+        assert_eq!(surface["surface_type"], "ivi");
     }
 
     #[test]
@@ -354,6 +454,57 @@ mod tests {
         assert_eq!(params.get("layer_id").unwrap().as_u64().unwrap(), 5000);
     }
 
+    #[test]
+    fn test_convert_repaint_complete() {
+        let subscription_manager = Arc::new(Mutex::new(SubscriptionManager::new()));
+        let bridge = NotificationBridge::new(subscription_manager);
+
+        let notification = Notification {
+            notification_type: NotificationType::RepaintComplete,
+            data: NotificationData::RepaintComplete {
+                output_id: 1,
+                output_name: "HDMI-A-1".to_string(),
+                commit_seq: 7,
+            },
+        };
+
+        let (event_type, rpc_notification) = bridge.convert_notification(&notification);
+
+        assert_eq!(event_type, EventType::RepaintComplete);
+        let params = rpc_notification.params.as_object().unwrap();
+        assert_eq!(
+            params.get("event_type").unwrap().as_str().unwrap(),
+            "RepaintComplete"
+        );
+        assert_eq!(params.get("output").unwrap().as_str().unwrap(), "HDMI-A-1");
+        assert_eq!(params.get("output_id").unwrap().as_u64().unwrap(), 1);
+        assert_eq!(params.get("commit_seq").unwrap().as_u64().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_convert_input_panel_shown() {
+        let subscription_manager = Arc::new(Mutex::new(SubscriptionManager::new()));
+        let bridge = NotificationBridge::new(subscription_manager);
+
+        let notification = Notification {
+            notification_type: NotificationType::InputPanelShown,
+            data: NotificationData::InputPanelShown {
+                surface_id: Some(3000),
+                target_surface_id: None,
+                overlay: false,
+            },
+        };
+
+        let (event_type, rpc_notification) = bridge.convert_notification(&notification);
+
+        assert_eq!(event_type, EventType::InputPanelShown);
+        let params = &rpc_notification.params;
+        assert_eq!(params["event_type"], "InputPanelShown");
+        assert_eq!(params["surface_id"], 3000);
+        assert!(params["target_surface_id"].is_null());
+        assert_eq!(params["overlay"], false);
+    }
+
     #[test]
     fn test_handle_notification_queues_to_manager() {
         let subscription_manager = Arc::new(Mutex::new(SubscriptionManager::new()));
@@ -370,7 +521,9 @@ mod tests {
         // Handle a surface created notification
         let notification = Notification {
             notification_type: NotificationType::SurfaceCreated,
-            data: NotificationData::SurfaceCreated { surface_id: 1000 },
+            data: NotificationData::SurfaceCreated {
+                state: test_surface_state(1000),
+            },
         };
 
         bridge.handle_notification(&notification);
@@ -385,6 +538,44 @@ mod tests {
         assert_eq!(notifications[0].method, "notification");
     }
 
+    #[test]
+    fn test_disabled_events_not_queued() {
+        let subscription_manager = Arc::new(Mutex::new(SubscriptionManager::new()));
+        let bridge = NotificationBridge::with_disabled_events(
+            Arc::clone(&subscription_manager),
+            vec![EventType::SurfaceCreated],
+        );
+        let client_id = ClientId::from_u64(1);
+
+        subscription_manager
+            .lock()
+            .unwrap()
+            .subscribe(
+                &client_id,
+                vec![EventType::SurfaceCreated, EventType::SurfaceDestroyed],
+            )
+            .unwrap();
+
+        bridge.handle_notification(&Notification {
+            notification_type: NotificationType::SurfaceCreated,
+            data: NotificationData::SurfaceCreated {
+                state: test_surface_state(1000),
+            },
+        });
+        bridge.handle_notification(&Notification {
+            notification_type: NotificationType::SurfaceDestroyed,
+            data: NotificationData::SurfaceDestroyed { surface_id: 1000 },
+        });
+
+        let notifications = subscription_manager
+            .lock()
+            .unwrap()
+            .drain_notifications(&client_id);
+
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].params["event_type"], "SurfaceDestroyed");
+    }
+
     #[test]
     fn test_orientation_to_string() {
         assert_eq!(Orientation::Normal.to_string(), "Normal");
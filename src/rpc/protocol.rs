@@ -1,7 +1,118 @@
 // RPC protocol definitions
 
+use crate::ffi::bindings::Rectangle;
 use serde::{Deserialize, Serialize};
 
+/// Schema version of the surface and layer JSON objects
+///
+/// Sent as the `"v"` field of every surface and layer object and of the
+/// responses listing them. Bump it whenever fields of those objects are
+/// added or changed.
+///
+/// - 1: first versioned schema
+/// - 2: surfaces carry `surface_type`
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Version of the wire protocol, reported by `get_capabilities`
+///
+/// - 1: notifications are told apart from responses by the missing `"id"`
+/// - 2: notifications also carry `"type": "notification"`
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Value of the `"type"` field of notification frames
+pub const NOTIFICATION_FRAME_TYPE: &str = "notification";
+
+/// Names of all RPC methods, as reported by `get_capabilities`
+pub const METHOD_NAMES: &[&str] = &[
+    "list_surfaces",
+    "get_surface",
+    "surface_exists",
+    "set_surface_source_rectangle",
+    "set_surface_destination_rectangle",
+    "set_surface_rectangles",
+    "set_surface_position",
+    "set_surface_size",
+    "set_layer_position",
+    "set_surface_visibility",
+    "set_surfaces_visibility",
+    "set_surface_opacity",
+    "set_surfaces_opacity",
+    "set_surface_orientation",
+    "set_surface_z_order",
+    "bring_surface_to_front",
+    "send_surface_to_back",
+    "get_surface_z_index",
+    "set_surface_focus",
+    "get_focus",
+    "clear_focus",
+    "commit",
+    "batch",
+    "subscribe",
+    "unsubscribe",
+    "list_subscriptions",
+    "get_all_subscriptions",
+    "clear_subscriptions",
+    "list_clients",
+    "list_layers",
+    "create_layer",
+    "destroy_layer",
+    "get_layer",
+    "layer_exists",
+    "set_layer_source_rectangle",
+    "set_layer_destination_rectangle",
+    "set_layer_visibility",
+    "set_layer_opacity",
+    "set_layer_transition",
+    "cancel_layer_transition",
+    "set_layer_surfaces",
+    "add_surface_to_layer",
+    "remove_surface_from_layer",
+    "get_layer_surfaces",
+    "get_layer_render_order",
+    "list_screens",
+    "get_screen",
+    "list_outputs",
+    "get_output",
+    "get_screen_layers",
+    "get_layer_screens",
+    "add_layers_to_screen",
+    "remove_layer_from_screen",
+    "get_scene",
+    "ping",
+    "get_metrics",
+    "stats",
+    "get_capabilities",
+];
+
+/// Read-only methods served on the monitoring socket
+pub const MONITORING_METHOD_NAMES: &[&str] = &["ping", "get_metrics", "stats", "get_capabilities"];
+
+/// Layer transition animations accepted by `set_layer_transition`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerTransition {
+    /// No animation
+    None,
+    /// Fade the layer in or out
+    Fade,
+    /// Animate changes of the destination rectangle
+    Move,
+    /// Animate changes of the surface render order
+    ViewOrder,
+}
+
+impl LayerTransition {
+    /// Parse a transition name as used in the `type` parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(LayerTransition::None),
+            "fade" => Some(LayerTransition::Fade),
+            "move" => Some(LayerTransition::Move),
+            "view_order" => Some(LayerTransition::ViewOrder),
+            _ => None,
+        }
+    }
+}
+
 /// Event types for client subscriptions
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EventType {
@@ -23,6 +134,37 @@ pub enum EventType {
     LayerDestroyed,
     LayerVisibilityChanged,
     LayerOpacityChanged,
+
+    // Output events
+    RepaintComplete,
+
+    // Input panel events
+    InputPanelShown,
+    InputPanelHidden,
+}
+
+impl EventType {
+    /// Every event type, subscribed to with `"event_types": "all"`
+    pub const ALL: [EventType; 18] = [
+        EventType::SurfaceCreated,
+        EventType::SurfaceContentReady,
+        EventType::SurfaceContentSizeChanged,
+        EventType::SurfaceDestroyed,
+        EventType::SourceGeometryChanged,
+        EventType::DestinationGeometryChanged,
+        EventType::VisibilityChanged,
+        EventType::OpacityChanged,
+        EventType::OrientationChanged,
+        EventType::ZOrderChanged,
+        EventType::FocusChanged,
+        EventType::LayerCreated,
+        EventType::LayerDestroyed,
+        EventType::LayerVisibilityChanged,
+        EventType::LayerOpacityChanged,
+        EventType::RepaintComplete,
+        EventType::InputPanelShown,
+        EventType::InputPanelHidden,
+    ];
 }
 
 /// RPC request structure
@@ -44,6 +186,7 @@ impl RpcRequest {
         serde_json::from_slice(data).map_err(|e| RpcError {
             code: -32700, // Parse error
             message: format!("Failed to parse request: {}", e),
+            data: None,
         })
     }
 
@@ -52,6 +195,7 @@ impl RpcRequest {
         serde_json::to_vec(self).map_err(|e| RpcError {
             code: -32603, // Internal error
             message: format!("Failed to serialize request: {}", e),
+            data: None,
         })
     }
 }
@@ -60,6 +204,10 @@ impl RpcRequest {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RpcResponse {
     pub id: u64,
+    /// Method of the request this responds to, echoed to help clients route
+    /// and check responses. Absent if the request could not be parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -71,6 +219,7 @@ impl RpcResponse {
     pub fn success(id: u64, result: serde_json::Value) -> Self {
         Self {
             id,
+            method: None,
             result: Some(result),
             error: None,
         }
@@ -80,16 +229,24 @@ impl RpcResponse {
     pub fn error(id: u64, error: RpcError) -> Self {
         Self {
             id,
+            method: None,
             result: None,
             error: Some(error),
         }
     }
 
+    /// Echo the method of the originating request
+    pub fn with_method(mut self, method: &str) -> Self {
+        self.method = Some(method.to_string());
+        self
+    }
+
     /// Parse an RPC response from JSON bytes
     pub fn from_json(data: &[u8]) -> Result<Self, RpcError> {
         serde_json::from_slice(data).map_err(|e| RpcError {
             code: -32700, // Parse error
             message: format!("Failed to parse response: {}", e),
+            data: None,
         })
     }
 
@@ -98,6 +255,7 @@ impl RpcResponse {
         serde_json::to_vec(self).map_err(|e| RpcError {
             code: -32603, // Internal error
             message: format!("Failed to serialize response: {}", e),
+            data: None,
         })
     }
 }
@@ -105,14 +263,154 @@ impl RpcResponse {
 /// RPC notification structure (JSON-RPC 2.0 notification - no id field)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RpcNotification {
+    /// Always `NOTIFICATION_FRAME_TYPE`, so clients can route the frame
+    /// without relying on the missing `"id"`
+    #[serde(rename = "type", default = "notification_frame_type")]
+    pub frame_type: String,
     pub method: String,
     pub params: serde_json::Value,
 }
 
+/// Extract the `event_types` parameter, a list of event types or `"all"`
+fn event_types_param(params: &serde_json::Value) -> Result<Vec<EventType>, RpcError> {
+    let value = params
+        .get("event_types")
+        .ok_or_else(|| RpcError::invalid_params("Missing 'event_types' parameter".to_string()))?;
+
+    if value.as_str() == Some("all") {
+        return Ok(EventType::ALL.to_vec());
+    }
+
+    serde_json::from_value(value.clone())
+        .map_err(|_| RpcError::invalid_params("Invalid 'event_types' parameter".to_string()))
+}
+
+/// Read a JSON number as a `u32` id
+///
+/// Negative, fractional and out-of-range numbers yield `None` rather than
+/// being truncated into a different, possibly existing, id.
+fn as_u32(value: &serde_json::Value) -> Option<u32> {
+    value.as_u64().and_then(|v| u32::try_from(v).ok())
+}
+
+/// Read a JSON number as an `i32` coordinate or size
+///
+/// Fractional and out-of-range numbers yield `None` rather than being
+/// wrapped into a different, possibly valid, value.
+fn as_i32(value: &serde_json::Value) -> Option<i32> {
+    value.as_i64().and_then(|v| i32::try_from(v).ok())
+}
+
+/// Read a rectangle parameter, either a `{"x", "y", "width", "height"}`
+/// object or the compact `[x, y, width, height]` array
+fn rectangle_param(params: &serde_json::Value, name: &str) -> Result<Rectangle, RpcError> {
+    let invalid = |field: Option<&str>| {
+        let name = match field {
+            Some(field) => format!("{}.{}", name, field),
+            None => name.to_string(),
+        };
+        RpcError::invalid_params(format!("Missing or invalid '{}' parameter", name))
+    };
+
+    match params.get(name) {
+        Some(serde_json::Value::Array(values)) if values.len() == 4 => {
+            let mut rect = [0; 4];
+            for (slot, value) in rect.iter_mut().zip(values) {
+                *slot = as_i32(value).ok_or_else(|| invalid(None))?;
+            }
+            let [x, y, width, height] = rect;
+            Ok(Rectangle {
+                x,
+                y,
+                width,
+                height,
+            })
+        }
+        Some(rect) if rect.is_object() => {
+            let field = |field: &str| {
+                rect.get(field)
+                    .and_then(as_i32)
+                    .ok_or_else(|| invalid(Some(field)))
+            };
+            Ok(Rectangle {
+                x: field("x")?,
+                y: field("y")?,
+                width: field("width")?,
+                height: field("height")?,
+            })
+        }
+        _ => Err(invalid(None)),
+    }
+}
+
+/// Read the rectangle of a single-rectangle setter, given either as a
+/// `rect` parameter (see [`rectangle_param`]) or as top-level `x`, `y`,
+/// `width` and `height` parameters
+fn rect_or_fields_param(params: &serde_json::Value) -> Result<Rectangle, RpcError> {
+    if params.get("rect").is_some() {
+        return rectangle_param(params, "rect");
+    }
+
+    let field = |field: &str| {
+        params.get(field).and_then(as_i32).ok_or_else(|| {
+            RpcError::invalid_params(format!("Missing or invalid '{}' parameter", field))
+        })
+    };
+
+    Ok(Rectangle {
+        x: field("x")?,
+        y: field("y")?,
+        width: field("width")?,
+        height: field("height")?,
+    })
+}
+
+/// Parse one `{"method", "params"}` operation of a `batch` request
+///
+/// Only methods taking `auto_commit` can be batched, and it is forced off so
+/// that the batch commits at most once.
+fn batch_operation(
+    id: u64,
+    index: usize,
+    operation: &serde_json::Value,
+) -> Result<RpcMethod, RpcError> {
+    let invalid =
+        |message: &str| RpcError::invalid_params(format!("operations[{}]: {}", index, message));
+
+    let method = operation
+        .get("method")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| invalid("Missing or invalid 'method'"))?;
+    let mut params = match operation.get("params") {
+        None => serde_json::json!({}),
+        Some(params) if params.is_object() => params.clone(),
+        Some(_) => return Err(invalid("Invalid 'params'")),
+    };
+    params["auto_commit"] = serde_json::Value::Bool(false);
+
+    let parsed = RpcMethod::from_request(&RpcRequest::new(id, method.to_string(), params))
+        .map_err(|e| invalid(&e.message))?;
+    match parsed {
+        RpcMethod::Batch { .. } => Err(invalid("batch cannot be nested")),
+        _ if parsed.auto_commit().is_none() => {
+            Err(invalid(&format!("{} cannot be batched", method)))
+        }
+        _ => Ok(parsed),
+    }
+}
+
+fn notification_frame_type() -> String {
+    NOTIFICATION_FRAME_TYPE.to_string()
+}
+
 impl RpcNotification {
     /// Create a new RPC notification
     pub fn new(method: String, params: serde_json::Value) -> Self {
-        Self { method, params }
+        Self {
+            frame_type: notification_frame_type(),
+            method,
+            params,
+        }
     }
 
     /// Serialize to JSON bytes
@@ -120,6 +418,7 @@ impl RpcNotification {
         serde_json::to_vec(self).map_err(|e| RpcError {
             code: -32603, // Internal error
             message: format!("Failed to serialize notification: {}", e),
+            data: None,
         })
     }
 }
@@ -129,6 +428,8 @@ impl RpcNotification {
 pub struct RpcError {
     pub code: i32,
     pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
 }
 
 impl std::fmt::Display for RpcError {
@@ -140,48 +441,63 @@ impl std::fmt::Display for RpcError {
 impl std::error::Error for RpcError {}
 
 impl RpcError {
+    /// Error code for a surface or layer that does not exist
+    pub const NOT_FOUND: i32 = -32000;
+
+    /// Error code for an admin method called by a client that is not an admin
+    pub const PERMISSION_DENIED: i32 = -32010;
+
     /// Create a new RPC error
     pub fn new(code: i32, message: String) -> Self {
-        Self { code, message }
+        Self {
+            code,
+            message,
+            data: None,
+        }
     }
 
     /// Create an invalid parameters error
     pub fn invalid_params(message: String) -> Self {
-        Self {
-            code: -32602,
-            message,
-        }
+        Self::new(-32602, message)
     }
 
     /// Create a method not found error
     pub fn method_not_found(method: String) -> Self {
-        Self {
-            code: -32601,
-            message: format!("Method not found: {}", method),
-        }
+        Self::new(-32601, format!("Method not found: {}", method))
     }
 
     /// Create an internal error
     pub fn internal_error(message: String) -> Self {
-        Self {
-            code: -32603,
-            message,
-        }
+        Self::new(-32603, message)
+    }
+
+    /// Create a permission denied error
+    pub fn permission_denied(method: &str) -> Self {
+        Self::new(
+            Self::PERMISSION_DENIED,
+            format!("Permission denied: {} is an admin method", method),
+        )
     }
 
     /// Create a surface not found error
+    ///
+    /// The error data carries `{"id": id, "kind": "surface"}`.
     pub fn surface_not_found(id: u32) -> Self {
-        Self {
-            code: -32000,
-            message: format!("Surface not found: {}", id),
-        }
+        Self::not_found("surface", format!("Surface not found: {}", id), id)
     }
 
     /// Create a layer not found error
+    ///
+    /// The error data carries `{"id": id, "kind": "layer"}`.
     pub fn layer_not_found(id: u32) -> Self {
+        Self::not_found("layer", format!("Layer not found: {}", id), id)
+    }
+
+    fn not_found(kind: &str, message: String, id: u32) -> Self {
         Self {
-            code: -32000,
-            message: format!("Layer not found: {}", id),
+            code: Self::NOT_FOUND,
+            message,
+            data: Some(serde_json::json!({ "id": id, "kind": kind })),
         }
     }
 }
@@ -190,9 +506,17 @@ impl RpcError {
 #[derive(Debug, Clone, PartialEq)]
 pub enum RpcMethod {
     // Surface methods
-    ListSurfaces,
+    ListSurfaces {
+        offset: usize,
+        limit: Option<usize>,
+    },
     GetSurface {
         id: u32,
+        /// Read the properties from the IVI API instead of the tracked state
+        live: bool,
+    },
+    SurfaceExists {
+        id: u32,
     },
     SetSurfaceSourceRectangle {
         id: u32,
@@ -210,26 +534,83 @@ pub enum RpcMethod {
         height: i32,
         auto_commit: bool,
     },
+    SetSurfaceRectangles {
+        id: u32,
+        src: Rectangle,
+        dest: Rectangle,
+        auto_commit: bool,
+    },
+    SetSurfacePosition {
+        id: u32,
+        x: i32,
+        y: i32,
+        relative: bool,
+        auto_commit: bool,
+    },
+    SetSurfaceSize {
+        id: u32,
+        width: i32,
+        height: i32,
+        auto_commit: bool,
+    },
     SetSurfaceVisibility {
         id: u32,
         visible: bool,
         auto_commit: bool,
     },
+    SetSurfacesVisibility {
+        ids: Vec<u32>,
+        visible: bool,
+        auto_commit: bool,
+    },
+    SetSurfacesOpacity {
+        ids: Vec<u32>,
+        opacity: f32,
+        auto_commit: bool,
+    },
     SetSurfaceOpacity {
         id: u32,
         opacity: f32,
         auto_commit: bool,
     },
+    SetSurfaceOrientation {
+        id: u32,
+        /// Clockwise rotation in degrees, a multiple of 90
+        degrees: i32,
+        auto_commit: bool,
+    },
     SetSurfaceZOrder {
         id: u32,
         z_order: i32,
         auto_commit: bool,
     },
+    BringSurfaceToFront {
+        id: u32,
+        auto_commit: bool,
+    },
+    SendSurfaceToBack {
+        id: u32,
+        auto_commit: bool,
+    },
+    GetSurfaceZIndex {
+        id: u32,
+    },
     SetSurfaceFocus {
         id: u32,
         auto_commit: bool,
     },
-    Commit,
+    GetFocus,
+    ClearFocus,
+    Commit {
+        /// Only apply the staged changes of this surface
+        surface_id: Option<u32>,
+    },
+    Batch {
+        /// Operations to run in order, all with `auto_commit` off
+        operations: Vec<RpcMethod>,
+        /// Commit once after the last operation
+        commit: bool,
+    },
 
     // Subscription methods
     Subscribe {
@@ -239,12 +620,25 @@ pub enum RpcMethod {
         event_types: Vec<EventType>,
     },
     ListSubscriptions,
+    GetAllSubscriptions,
+    ClearSubscriptions {
+        /// Client whose subscriptions to clear, the caller when `None`
+        client_id: Option<u64>,
+        /// Clear the subscriptions of every client
+        all: bool,
+    },
+
+    // Client methods
+    ListClients,
 
     // Layer methods
     ListLayers,
     GetLayer {
         id: u32,
     },
+    LayerExists {
+        id: u32,
+    },
     CreateLayer {
         id: u32,
         width: i32,
@@ -271,6 +665,13 @@ pub enum RpcMethod {
         height: i32,
         auto_commit: bool,
     },
+    SetLayerPosition {
+        id: u32,
+        x: i32,
+        y: i32,
+        relative: bool,
+        auto_commit: bool,
+    },
     SetLayerVisibility {
         id: u32,
         visible: bool,
@@ -279,8 +680,18 @@ pub enum RpcMethod {
     SetLayerOpacity {
         id: u32,
         opacity: f32,
+        relative: bool,
+        auto_commit: bool,
+    },
+    SetLayerTransition {
+        id: u32,
+        transition: LayerTransition,
+        duration: u32,
         auto_commit: bool,
     },
+    CancelLayerTransition {
+        id: u32,
+    },
     // Layer-surface assignment operations
     SetLayerSurfaces {
         layer_id: u32,
@@ -300,11 +711,18 @@ pub enum RpcMethod {
     GetLayerSurfaces {
         layer_id: u32,
     },
+    GetLayerRenderOrder {
+        id: u32,
+    },
     // Screen operations
     ListScreens,
     GetScreen {
         name: String,
     },
+    ListOutputs,
+    GetOutput {
+        index: u32,
+    },
     GetScreenLayers {
         screen_name: String,
     },
@@ -321,74 +739,149 @@ pub enum RpcMethod {
         layer_id: u32,
         auto_commit: bool,
     },
+    // Scene operations
+    GetScene,
+
+    // Monitoring methods
+    Ping,
+    GetMetrics,
+    Stats,
+    GetCapabilities,
 }
 
 impl RpcMethod {
+    /// The `auto_commit` flag of a method that changes the layout
+    ///
+    /// Returns `None` for methods that change nothing or always commit.
+    pub fn auto_commit(&self) -> Option<bool> {
+        match self {
+            RpcMethod::SetSurfaceSourceRectangle { auto_commit, .. }
+            | RpcMethod::SetSurfaceDestinationRectangle { auto_commit, .. }
+            | RpcMethod::SetSurfaceRectangles { auto_commit, .. }
+            | RpcMethod::SetSurfacePosition { auto_commit, .. }
+            | RpcMethod::SetSurfaceSize { auto_commit, .. }
+            | RpcMethod::SetSurfaceVisibility { auto_commit, .. }
+            | RpcMethod::SetSurfacesVisibility { auto_commit, .. }
+            | RpcMethod::SetSurfacesOpacity { auto_commit, .. }
+            | RpcMethod::SetSurfaceOpacity { auto_commit, .. }
+            | RpcMethod::SetSurfaceOrientation { auto_commit, .. }
+            | RpcMethod::SetSurfaceZOrder { auto_commit, .. }
+            | RpcMethod::BringSurfaceToFront { auto_commit, .. }
+            | RpcMethod::SendSurfaceToBack { auto_commit, .. }
+            | RpcMethod::SetSurfaceFocus { auto_commit, .. }
+            | RpcMethod::CreateLayer { auto_commit, .. }
+            | RpcMethod::DestroyLayer { auto_commit, .. }
+            | RpcMethod::SetLayerSourceRectangle { auto_commit, .. }
+            | RpcMethod::SetLayerDestinationRectangle { auto_commit, .. }
+            | RpcMethod::SetLayerPosition { auto_commit, .. }
+            | RpcMethod::SetLayerVisibility { auto_commit, .. }
+            | RpcMethod::SetLayerOpacity { auto_commit, .. }
+            | RpcMethod::SetLayerTransition { auto_commit, .. }
+            | RpcMethod::SetLayerSurfaces { auto_commit, .. }
+            | RpcMethod::AddSurfaceToLayer { auto_commit, .. }
+            | RpcMethod::RemoveSurfaceFromLayer { auto_commit, .. }
+            | RpcMethod::AddLayersToScreen { auto_commit, .. }
+            | RpcMethod::RemoveLayerFromScreen { auto_commit, .. } => Some(*auto_commit),
+            RpcMethod::Batch { commit, .. } => Some(*commit),
+            _ => None,
+        }
+    }
+
+    /// Layer whose properties the method changes, re-read by the commit that
+    /// applies the change
+    pub fn configured_layer(&self) -> Option<u32> {
+        match self {
+            RpcMethod::CreateLayer { id, .. }
+            | RpcMethod::SetLayerSourceRectangle { id, .. }
+            | RpcMethod::SetLayerDestinationRectangle { id, .. }
+            | RpcMethod::SetLayerPosition { id, .. }
+            | RpcMethod::SetLayerVisibility { id, .. }
+            | RpcMethod::SetLayerOpacity { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+
     /// Parse an RPC method from a request
     pub fn from_request(request: &RpcRequest) -> Result<Self, RpcError> {
         match request.method.as_str() {
-            "list_surfaces" => Ok(RpcMethod::ListSurfaces),
+            "list_surfaces" => {
+                let offset = match request.params.get("offset") {
+                    Some(v) => v.as_u64().ok_or_else(|| {
+                        RpcError::invalid_params("Invalid 'offset' parameter".to_string())
+                    })? as usize,
+                    None => 0,
+                };
+                let limit = match request.params.get("limit") {
+                    Some(v) => Some(v.as_u64().ok_or_else(|| {
+                        RpcError::invalid_params("Invalid 'limit' parameter".to_string())
+                    })? as usize),
+                    None => None,
+                };
+                Ok(RpcMethod::ListSurfaces { offset, limit })
+            }
 
             "get_surface" => {
-                let id = request
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let live = request
                     .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
-                Ok(RpcMethod::GetSurface { id })
+                    .get("live")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                Ok(RpcMethod::GetSurface { id, live })
             }
 
-            "set_surface_source_rectangle" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
-
-                let x = request
-                    .params
-                    .get("x")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'x' parameter".to_string())
-                    })? as i32;
+            "surface_exists" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                Ok(RpcMethod::SurfaceExists { id })
+            }
 
-                let y = request
+            "set_surface_source_rectangle" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = rect_or_fields_param(&request.params)?;
+                let auto_commit = request
                     .params
-                    .get("y")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'y' parameter".to_string())
-                    })? as i32;
+                    .get("auto_commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
 
-                let width = request
-                    .params
-                    .get("width")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'width' parameter".to_string())
-                    })? as i32;
-                let height = request
-                    .params
-                    .get("height")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params(
-                            "Missing or invalid 'height' parameter".to_string(),
-                        )
-                    })? as i32;
+                Ok(RpcMethod::SetSurfaceSourceRectangle {
+                    id,
+                    x,
+                    y,
+                    width,
+                    height,
+                    auto_commit,
+                })
+            }
 
+            "set_surface_destination_rectangle" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = rect_or_fields_param(&request.params)?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
 
-                Ok(RpcMethod::SetSurfaceSourceRectangle {
+                Ok(RpcMethod::SetSurfaceDestinationRectangle {
                     id,
                     x,
                     y,
@@ -398,69 +891,126 @@ impl RpcMethod {
                 })
             }
 
-            "set_surface_destination_rectangle" => {
-                let id = request
+            "set_surface_rectangles" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let src = rectangle_param(&request.params, "src")?;
+                let dest = rectangle_param(&request.params, "dest")?;
+                let auto_commit = request
                     .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
-                let x = request
+                    .get("auto_commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+
+                Ok(RpcMethod::SetSurfaceRectangles {
+                    id,
+                    src,
+                    dest,
+                    auto_commit,
+                })
+            }
+
+            "set_surface_position" | "set_layer_position" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let relative = request
                     .params
-                    .get("x")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'x' parameter".to_string())
-                    })? as i32;
-                let y = request
+                    .get("relative")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                // A relative move takes the offset as dx/dy
+                let (x_name, y_name) = if relative { ("dx", "dy") } else { ("x", "y") };
+                let x = request.params.get(x_name).and_then(as_i32).ok_or_else(|| {
+                    RpcError::invalid_params(format!("Missing or invalid '{}' parameter", x_name))
+                })?;
+                let y = request.params.get(y_name).and_then(as_i32).ok_or_else(|| {
+                    RpcError::invalid_params(format!("Missing or invalid '{}' parameter", y_name))
+                })?;
+                let auto_commit = request
                     .params
-                    .get("y")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'y' parameter".to_string())
-                    })? as i32;
-                let width = request
+                    .get("auto_commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                if request.method == "set_surface_position" {
+                    Ok(RpcMethod::SetSurfacePosition {
+                        id,
+                        x,
+                        y,
+                        relative,
+                        auto_commit,
+                    })
+                } else {
+                    Ok(RpcMethod::SetLayerPosition {
+                        id,
+                        x,
+                        y,
+                        relative,
+                        auto_commit,
+                    })
+                }
+            }
+
+            "set_surface_size" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let field = |field: &str| {
+                    request.params.get(field).and_then(as_i32).ok_or_else(|| {
+                        RpcError::invalid_params(format!(
+                            "Missing or invalid '{}' parameter",
+                            field
+                        ))
+                    })
+                };
+                let auto_commit = request
                     .params
-                    .get("width")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'width' parameter".to_string())
-                    })? as i32;
-                let height = request
+                    .get("auto_commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                Ok(RpcMethod::SetSurfaceSize {
+                    id,
+                    width: field("width")?,
+                    height: field("height")?,
+                    auto_commit,
+                })
+            }
+
+            "set_surface_visibility" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let visible = request
                     .params
-                    .get("height")
-                    .and_then(|v| v.as_i64())
+                    .get("visible")
+                    .and_then(|v| v.as_bool())
                     .ok_or_else(|| {
                         RpcError::invalid_params(
-                            "Missing or invalid 'height' parameter".to_string(),
+                            "Missing or invalid 'visible' parameter".to_string(),
                         )
-                    })? as i32;
-
+                    })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
-
-                Ok(RpcMethod::SetSurfaceDestinationRectangle {
+                Ok(RpcMethod::SetSurfaceVisibility {
                     id,
-                    x,
-                    y,
-                    width,
-                    height,
+                    visible,
                     auto_commit,
                 })
             }
 
-            "set_surface_visibility" => {
-                let id = request
+            "set_surfaces_visibility" => {
+                let ids = request
                     .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
+                    .get("ids")
+                    .and_then(|v| v.as_array())
+                    .and_then(|ids| ids.iter().map(as_u32).collect::<Option<Vec<u32>>>())
                     .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
+                        RpcError::invalid_params("Missing or invalid 'ids' parameter".to_string())
+                    })?;
                 let visible = request
                     .params
                     .get("visible")
@@ -475,21 +1025,17 @@ impl RpcMethod {
                     .get("auto_commit")
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
-                Ok(RpcMethod::SetSurfaceVisibility {
-                    id,
+                Ok(RpcMethod::SetSurfacesVisibility {
+                    ids,
                     visible,
                     auto_commit,
                 })
             }
 
             "set_surface_opacity" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
                 let opacity = request
                     .params
                     .get("opacity")
@@ -511,23 +1057,74 @@ impl RpcMethod {
                 })
             }
 
-            "set_surface_z_order" => {
-                let id = request
+            "set_surface_orientation" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let degrees = request
                     .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
+                    .get("orientation")
+                    .and_then(as_i32)
                     .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
+                        RpcError::invalid_params(
+                            "Missing or invalid 'orientation' parameter".to_string(),
+                        )
+                    })?;
+                let auto_commit = request
+                    .params
+                    .get("auto_commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                Ok(RpcMethod::SetSurfaceOrientation {
+                    id,
+                    degrees,
+                    auto_commit,
+                })
+            }
+
+            "set_surfaces_opacity" => {
+                let ids = request
+                    .params
+                    .get("ids")
+                    .and_then(|v| v.as_array())
+                    .and_then(|ids| ids.iter().map(as_u32).collect::<Option<Vec<u32>>>())
+                    .ok_or_else(|| {
+                        RpcError::invalid_params("Missing or invalid 'ids' parameter".to_string())
+                    })?;
+                let opacity = request
+                    .params
+                    .get("opacity")
+                    .and_then(|v| v.as_f64())
+                    .ok_or_else(|| {
+                        RpcError::invalid_params(
+                            "Missing or invalid 'opacity' parameter".to_string(),
+                        )
+                    })?;
+                let auto_commit = request
+                    .params
+                    .get("auto_commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                Ok(RpcMethod::SetSurfacesOpacity {
+                    ids,
+                    opacity: opacity as f32,
+                    auto_commit,
+                })
+            }
+
+            "set_surface_z_order" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
                 let z_order = request
                     .params
                     .get("z_order")
-                    .and_then(|v| v.as_i64())
+                    .and_then(as_i32)
                     .ok_or_else(|| {
                         RpcError::invalid_params(
                             "Missing or invalid 'z_order' parameter".to_string(),
                         )
-                    })? as i32;
+                    })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -540,89 +1137,140 @@ impl RpcMethod {
                 })
             }
 
-            "set_surface_focus" => {
-                let id = request
+            "bring_surface_to_front" | "send_surface_to_back" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let auto_commit = request
                     .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })?;
+                    .get("auto_commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                if request.method == "bring_surface_to_front" {
+                    Ok(RpcMethod::BringSurfaceToFront { id, auto_commit })
+                } else {
+                    Ok(RpcMethod::SendSurfaceToBack { id, auto_commit })
+                }
+            }
+
+            "get_surface_z_index" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                Ok(RpcMethod::GetSurfaceZIndex { id })
+            }
+
+            "set_surface_focus" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
-                Ok(RpcMethod::SetSurfaceFocus {
-                    id: id as u32,
-                    auto_commit,
-                })
+                Ok(RpcMethod::SetSurfaceFocus { id, auto_commit })
             }
 
-            "commit" => Ok(RpcMethod::Commit),
+            "get_focus" => Ok(RpcMethod::GetFocus),
+
+            "clear_focus" => Ok(RpcMethod::ClearFocus),
+
+            "commit" => {
+                let surface_id = match request.params.get("id") {
+                    None | Some(serde_json::Value::Null) => None,
+                    Some(v) => Some(as_u32(v).ok_or_else(|| {
+                        RpcError::invalid_params("Invalid 'id' parameter".to_string())
+                    })?),
+                };
+                Ok(RpcMethod::Commit { surface_id })
+            }
+
+            "batch" => {
+                let operations = request
+                    .params
+                    .get("operations")
+                    .and_then(|v| v.as_array())
+                    .ok_or_else(|| {
+                        RpcError::invalid_params(
+                            "Missing or invalid 'operations' parameter".to_string(),
+                        )
+                    })?
+                    .iter()
+                    .enumerate()
+                    .map(|(index, operation)| batch_operation(request.id, index, operation))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let commit = request
+                    .params
+                    .get("commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                Ok(RpcMethod::Batch { operations, commit })
+            }
 
             // Subscription methods
             "subscribe" => {
-                let event_types: Vec<EventType> = serde_json::from_value(
-                    request
-                        .params
-                        .get("event_types")
-                        .ok_or_else(|| {
-                            RpcError::invalid_params("Missing 'event_types' parameter".to_string())
-                        })?
-                        .clone(),
-                )
-                .map_err(|_| {
-                    RpcError::invalid_params("Invalid 'event_types' parameter".to_string())
-                })?;
+                let event_types = event_types_param(&request.params)?;
                 Ok(RpcMethod::Subscribe { event_types })
             }
 
             "unsubscribe" => {
-                let event_types: Vec<EventType> = serde_json::from_value(
-                    request
-                        .params
-                        .get("event_types")
-                        .ok_or_else(|| {
-                            RpcError::invalid_params("Missing 'event_types' parameter".to_string())
-                        })?
-                        .clone(),
-                )
-                .map_err(|_| {
-                    RpcError::invalid_params("Invalid 'event_types' parameter".to_string())
-                })?;
+                let event_types = event_types_param(&request.params)?;
                 Ok(RpcMethod::Unsubscribe { event_types })
             }
 
             "list_subscriptions" => Ok(RpcMethod::ListSubscriptions),
+            "get_all_subscriptions" => Ok(RpcMethod::GetAllSubscriptions),
+
+            "clear_subscriptions" => {
+                let client_id = match request.params.get("client_id") {
+                    None | Some(serde_json::Value::Null) => None,
+                    Some(v) => Some(v.as_u64().ok_or_else(|| {
+                        RpcError::invalid_params("Invalid 'client_id' parameter".to_string())
+                    })?),
+                };
+                let all = match request.params.get("all") {
+                    None | Some(serde_json::Value::Null) => false,
+                    Some(v) => v.as_bool().ok_or_else(|| {
+                        RpcError::invalid_params("Invalid 'all' parameter".to_string())
+                    })?,
+                };
+                if all && client_id.is_some() {
+                    return Err(RpcError::invalid_params(
+                        "'client_id' and 'all' are mutually exclusive".to_string(),
+                    ));
+                }
+                Ok(RpcMethod::ClearSubscriptions { client_id, all })
+            }
+
+            // Client methods
+            "list_clients" => Ok(RpcMethod::ListClients),
 
             // Layer methods
             "list_layers" => Ok(RpcMethod::ListLayers),
 
             "create_layer" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
                 let width = request
                     .params
                     .get("width")
-                    .and_then(|v| v.as_u64())
+                    .and_then(as_i32)
+                    .filter(|v| *v >= 0)
                     .ok_or_else(|| {
                         RpcError::invalid_params("Missing or invalid 'width' parameter".to_string())
-                    })? as i32;
+                    })?;
                 let height = request
                     .params
                     .get("height")
-                    .and_then(|v| v.as_u64())
+                    .and_then(as_i32)
+                    .filter(|v| *v >= 0)
                     .ok_or_else(|| {
                         RpcError::invalid_params(
                             "Missing or invalid 'height' parameter".to_string(),
                         )
-                    })? as i32;
+                    })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -637,13 +1285,9 @@ impl RpcMethod {
             }
 
             "destroy_layer" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -653,59 +1297,35 @@ impl RpcMethod {
             }
 
             "get_layer" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
                 Ok(RpcMethod::GetLayer { id })
             }
 
+            "layer_exists" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                Ok(RpcMethod::LayerExists { id })
+            }
+
             "set_layer_source_rectangle" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
-                let x = request
-                    .params
-                    .get("x")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'x' parameter".to_string())
-                    })? as i32;
-                let y = request
-                    .params
-                    .get("y")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'y' parameter".to_string())
-                    })? as i32;
-                let width = request
-                    .params
-                    .get("width")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'width' parameter".to_string())
-                    })? as i32;
-                let height = request
-                    .params
-                    .get("height")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params(
-                            "Missing or invalid 'height' parameter".to_string(),
-                        )
-                    })? as i32;
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = rect_or_fields_param(&request.params)?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
+
                 Ok(RpcMethod::SetLayerSourceRectangle {
                     id,
                     x,
@@ -717,48 +1337,21 @@ impl RpcMethod {
             }
 
             "set_layer_destination_rectangle" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
-                let x = request
-                    .params
-                    .get("x")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'x' parameter".to_string())
-                    })? as i32;
-                let y = request
-                    .params
-                    .get("y")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'y' parameter".to_string())
-                    })? as i32;
-                let width = request
-                    .params
-                    .get("width")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'width' parameter".to_string())
-                    })? as i32;
-                let height = request
-                    .params
-                    .get("height")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params(
-                            "Missing or invalid 'height' parameter".to_string(),
-                        )
-                    })? as i32;
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let Rectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                } = rect_or_fields_param(&request.params)?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
+
                 Ok(RpcMethod::SetLayerDestinationRectangle {
                     id,
                     x,
@@ -770,13 +1363,9 @@ impl RpcMethod {
             }
 
             "set_layer_visibility" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })?;
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
                 let visible = request
                     .params
                     .get("visible")
@@ -792,20 +1381,16 @@ impl RpcMethod {
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
                 Ok(RpcMethod::SetLayerVisibility {
-                    id: id as u32,
+                    id,
                     visible,
                     auto_commit,
                 })
             }
 
             "set_layer_opacity" => {
-                let id = request
-                    .params
-                    .get("id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
-                    })? as u32;
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
                 let opacity = request
                     .params
                     .get("opacity")
@@ -815,6 +1400,11 @@ impl RpcMethod {
                             "Missing or invalid 'opacity' parameter".to_string(),
                         )
                     })? as f32;
+                let relative = request
+                    .params
+                    .get("relative")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -823,21 +1413,68 @@ impl RpcMethod {
                 Ok(RpcMethod::SetLayerOpacity {
                     id,
                     opacity,
+                    relative,
                     auto_commit,
                 })
             }
 
-            // Layer-surface assignment operations
-            "set_layer_surfaces" => {
-                let layer_id = request
+            "set_layer_transition" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                let transition = request
                     .params
-                    .get("layer_id")
-                    .and_then(|v| v.as_u64())
+                    .get("type")
+                    .and_then(|v| v.as_str())
+                    .and_then(LayerTransition::from_name)
                     .ok_or_else(|| {
                         RpcError::invalid_params(
-                            "Missing or invalid 'layer_id' parameter".to_string(),
+                            "Missing or invalid 'type' parameter, expected one of: none, fade, move, view_order"
+                                .to_string(),
                         )
-                    })? as u32;
+                    })?;
+                let duration =
+                    request
+                        .params
+                        .get("duration")
+                        .and_then(as_u32)
+                        .ok_or_else(|| {
+                            RpcError::invalid_params(
+                                "Missing or invalid 'duration' parameter".to_string(),
+                            )
+                        })?;
+                let auto_commit = request
+                    .params
+                    .get("auto_commit")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                Ok(RpcMethod::SetLayerTransition {
+                    id,
+                    transition,
+                    duration,
+                    auto_commit,
+                })
+            }
+
+            "cancel_layer_transition" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                Ok(RpcMethod::CancelLayerTransition { id })
+            }
+
+            // Layer-surface assignment operations
+            "set_layer_surfaces" => {
+                let layer_id =
+                    request
+                        .params
+                        .get("layer_id")
+                        .and_then(as_u32)
+                        .ok_or_else(|| {
+                            RpcError::invalid_params(
+                                "Missing or invalid 'layer_id' parameter".to_string(),
+                            )
+                        })?;
                 let surface_ids = request
                     .params
                     .get("surface_ids")
@@ -848,8 +1485,11 @@ impl RpcMethod {
                         )
                     })?
                     .iter()
-                    .filter_map(|v| v.as_u64().map(|n| n as u32))
-                    .collect();
+                    .map(as_u32)
+                    .collect::<Option<Vec<u32>>>()
+                    .ok_or_else(|| {
+                        RpcError::invalid_params("Invalid surface_id in array".to_string())
+                    })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -863,24 +1503,25 @@ impl RpcMethod {
             }
 
             "add_surface_to_layer" => {
-                let layer_id = request
-                    .params
-                    .get("layer_id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params(
-                            "Missing or invalid 'layer_id' parameter".to_string(),
-                        )
-                    })? as u32;
+                let layer_id =
+                    request
+                        .params
+                        .get("layer_id")
+                        .and_then(as_u32)
+                        .ok_or_else(|| {
+                            RpcError::invalid_params(
+                                "Missing or invalid 'layer_id' parameter".to_string(),
+                            )
+                        })?;
                 let surface_id = request
                     .params
                     .get("surface_id")
-                    .and_then(|v| v.as_u64())
+                    .and_then(as_u32)
                     .ok_or_else(|| {
                         RpcError::invalid_params(
                             "Missing or invalid 'surface_id' parameter".to_string(),
                         )
-                    })? as u32;
+                    })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -894,24 +1535,25 @@ impl RpcMethod {
             }
 
             "remove_surface_from_layer" => {
-                let layer_id = request
-                    .params
-                    .get("layer_id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params(
-                            "Missing or invalid 'layer_id' parameter".to_string(),
-                        )
-                    })? as u32;
+                let layer_id =
+                    request
+                        .params
+                        .get("layer_id")
+                        .and_then(as_u32)
+                        .ok_or_else(|| {
+                            RpcError::invalid_params(
+                                "Missing or invalid 'layer_id' parameter".to_string(),
+                            )
+                        })?;
                 let surface_id = request
                     .params
                     .get("surface_id")
-                    .and_then(|v| v.as_u64())
+                    .and_then(as_u32)
                     .ok_or_else(|| {
                         RpcError::invalid_params(
                             "Missing or invalid 'surface_id' parameter".to_string(),
                         )
-                    })? as u32;
+                    })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -925,18 +1567,26 @@ impl RpcMethod {
             }
 
             "get_layer_surfaces" => {
-                let layer_id = request
-                    .params
-                    .get("layer_id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params(
-                            "Missing or invalid 'layer_id' parameter".to_string(),
-                        )
-                    })? as u32;
+                let layer_id =
+                    request
+                        .params
+                        .get("layer_id")
+                        .and_then(as_u32)
+                        .ok_or_else(|| {
+                            RpcError::invalid_params(
+                                "Missing or invalid 'layer_id' parameter".to_string(),
+                            )
+                        })?;
                 Ok(RpcMethod::GetLayerSurfaces { layer_id })
             }
 
+            "get_layer_render_order" => {
+                let id = request.params.get("id").and_then(as_u32).ok_or_else(|| {
+                    RpcError::invalid_params("Missing or invalid 'id' parameter".to_string())
+                })?;
+                Ok(RpcMethod::GetLayerRenderOrder { id })
+            }
+
             // Screen operations
             "list_screens" => Ok(RpcMethod::ListScreens),
 
@@ -952,6 +1602,19 @@ impl RpcMethod {
                 Ok(RpcMethod::GetScreen { name })
             }
 
+            "list_outputs" => Ok(RpcMethod::ListOutputs),
+
+            "get_output" => {
+                let index = request
+                    .params
+                    .get("index")
+                    .and_then(as_u32)
+                    .ok_or_else(|| {
+                        RpcError::invalid_params("Missing or invalid 'index' parameter".to_string())
+                    })?;
+                Ok(RpcMethod::GetOutput { index })
+            }
+
             "get_screen_layers" => {
                 let screen_name = request
                     .params
@@ -967,15 +1630,16 @@ impl RpcMethod {
             }
 
             "get_layer_screens" => {
-                let layer_id = request
-                    .params
-                    .get("layer_id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params(
-                            "Missing or invalid 'layer_id' parameter".to_string(),
-                        )
-                    })? as u32;
+                let layer_id =
+                    request
+                        .params
+                        .get("layer_id")
+                        .and_then(as_u32)
+                        .ok_or_else(|| {
+                            RpcError::invalid_params(
+                                "Missing or invalid 'layer_id' parameter".to_string(),
+                            )
+                        })?;
                 Ok(RpcMethod::GetLayerScreens { layer_id })
             }
 
@@ -1001,14 +1665,11 @@ impl RpcMethod {
                     })?
                     .iter()
                     .map(|v| {
-                        v.as_u64().ok_or_else(|| {
+                        as_u32(v).ok_or_else(|| {
                             RpcError::invalid_params("Invalid layer_id in array".to_string())
                         })
                     })
-                    .collect::<Result<Vec<_>, _>>()?
-                    .into_iter()
-                    .map(|v| v as u32)
-                    .collect();
+                    .collect::<Result<Vec<_>, _>>()?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -1032,15 +1693,16 @@ impl RpcMethod {
                         )
                     })?
                     .to_string();
-                let layer_id = request
-                    .params
-                    .get("layer_id")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        RpcError::invalid_params(
-                            "Missing or invalid 'layer_id' parameter".to_string(),
-                        )
-                    })? as u32;
+                let layer_id =
+                    request
+                        .params
+                        .get("layer_id")
+                        .and_then(as_u32)
+                        .ok_or_else(|| {
+                            RpcError::invalid_params(
+                                "Missing or invalid 'layer_id' parameter".to_string(),
+                            )
+                        })?;
                 let auto_commit = request
                     .params
                     .get("auto_commit")
@@ -1053,7 +1715,212 @@ impl RpcMethod {
                 })
             }
 
+            // Scene operations
+            "get_scene" => Ok(RpcMethod::GetScene),
+
+            // Monitoring methods
+            "ping" => Ok(RpcMethod::Ping),
+            "get_metrics" => Ok(RpcMethod::GetMetrics),
+            "stats" => Ok(RpcMethod::Stats),
+            "get_capabilities" => Ok(RpcMethod::GetCapabilities),
+
             _ => Err(RpcError::method_not_found(request.method.clone())),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Parameter names used by the RPC methods, so that generated params
+    /// reach the parsing code instead of being ignored
+    const PARAM_NAMES: &[&str] = &[
+        "id",
+        "ids",
+        "x",
+        "y",
+        "dx",
+        "dy",
+        "width",
+        "height",
+        "rect",
+        "src",
+        "dest",
+        "relative",
+        "visible",
+        "opacity",
+        "orientation",
+        "z_order",
+        "auto_commit",
+        "event_types",
+        "client_id",
+        "layer_id",
+        "surface_id",
+        "surface_ids",
+        "layer_ids",
+        "screen_name",
+        "name",
+        "index",
+        "offset",
+        "limit",
+        "live",
+        "transition",
+        "duration",
+        "operations",
+        "method",
+        "params",
+        "commit",
+    ];
+
+    fn json_value() -> impl Strategy<Value = serde_json::Value> {
+        let leaf = prop_oneof![
+            Just(serde_json::Value::Null),
+            any::<bool>().prop_map(serde_json::Value::from),
+            any::<i64>().prop_map(serde_json::Value::from),
+            any::<u64>().prop_map(serde_json::Value::from),
+            any::<f64>().prop_map(serde_json::Value::from),
+            prop::sample::select(METHOD_NAMES).prop_map(serde_json::Value::from),
+            ".{0,8}".prop_map(serde_json::Value::from),
+        ];
+        leaf.prop_recursive(4, 64, 6, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..6).prop_map(serde_json::Value::from),
+                prop::collection::vec((prop::sample::select(PARAM_NAMES), inner), 0..6).prop_map(
+                    |fields| {
+                        serde_json::Value::Object(
+                            fields
+                                .into_iter()
+                                .map(|(name, value)| (name.to_string(), value))
+                                .collect(),
+                        )
+                    }
+                ),
+            ]
+        })
+    }
+
+    fn parse(method: &str, params: serde_json::Value) -> Result<RpcMethod, RpcError> {
+        let frame = RpcRequest::new(1, method.to_string(), params)
+            .to_json()
+            .unwrap();
+        RpcMethod::from_request(&RpcRequest::from_json(&frame).unwrap())
+    }
+
+    #[test]
+    fn test_ids_round_trip_at_u32_bounds() {
+        for id in [0, u32::MAX] {
+            assert_eq!(
+                parse("get_surface", serde_json::json!({ "id": id })).unwrap(),
+                RpcMethod::GetSurface { id, live: false }
+            );
+            assert_eq!(
+                parse(
+                    "set_layer_visibility",
+                    serde_json::json!({ "id": id, "visible": true })
+                )
+                .unwrap(),
+                RpcMethod::SetLayerVisibility {
+                    id,
+                    visible: true,
+                    auto_commit: false,
+                }
+            );
+
+            let frame = RpcResponse::success(1, serde_json::json!({ "id": id }))
+                .to_json()
+                .unwrap();
+            let response = RpcResponse::from_json(&frame).unwrap();
+            assert_eq!(response.result.unwrap()["id"].as_u64(), Some(id as u64));
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_ids_are_rejected() {
+        let too_large = u32::MAX as u64 + 1;
+        for bad in [
+            serde_json::json!(too_large),
+            serde_json::json!(-1),
+            serde_json::json!(1.5),
+        ] {
+            for (method, params) in [
+                ("get_surface", serde_json::json!({ "id": bad })),
+                ("set_surface_focus", serde_json::json!({ "id": bad })),
+                (
+                    "set_surfaces_visibility",
+                    serde_json::json!({ "ids": [1000, bad], "visible": true }),
+                ),
+                (
+                    "set_layer_surfaces",
+                    serde_json::json!({ "layer_id": 2000, "surface_ids": [bad] }),
+                ),
+                ("commit", serde_json::json!({ "id": bad })),
+            ] {
+                let e = parse(method, params).unwrap_err();
+                assert_eq!(e.code, -32602, "{} accepted id {}", method, bad);
+            }
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_coordinates_are_rejected() {
+        // Would wrap to 10 if truncated to i32
+        let too_large = serde_json::json!(u32::MAX as u64 + 11);
+        for (method, params) in [
+            (
+                "set_surface_position",
+                serde_json::json!({ "id": 1000, "x": too_large, "y": 0 }),
+            ),
+            (
+                "set_surface_destination_rectangle",
+                serde_json::json!({ "id": 1000, "x": too_large, "y": 0, "width": 10, "height": 10 }),
+            ),
+            (
+                "set_surface_source_rectangle",
+                serde_json::json!({ "id": 1000, "rect": [too_large, 0, 10, 10] }),
+            ),
+            (
+                "set_surface_size",
+                serde_json::json!({ "id": 1000, "width": too_large, "height": 10 }),
+            ),
+            (
+                "set_surface_z_order",
+                serde_json::json!({ "id": 1000, "z_order": too_large }),
+            ),
+            (
+                "create_layer",
+                serde_json::json!({ "id": 2000, "width": too_large, "height": 10 }),
+            ),
+        ] {
+            let e = parse(method, params).unwrap_err();
+            assert_eq!(e.code, -32602, "{} accepted {}", method, too_large);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_from_json_survives_arbitrary_bytes(
+            data in prop::collection::vec(any::<u8>(), 0..1024),
+        ) {
+            if let Err(e) = RpcRequest::from_json(&data) {
+                prop_assert_eq!(e.code, -32700);
+            }
+        }
+
+        #[test]
+        fn prop_from_request_rejects_bad_params_with_rpc_error(
+            method in prop::sample::select(METHOD_NAMES),
+            params in json_value(),
+        ) {
+            let request = RpcRequest::new(1, method.to_string(), params);
+            let frame = serde_json::to_vec(&request).unwrap();
+            let request = RpcRequest::from_json(&frame).unwrap();
+
+            if let Err(e) = RpcMethod::from_request(&request) {
+                prop_assert_ne!(e.code, -32601, "{} is a known method", method);
+                prop_assert!(!e.message.is_empty());
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+// Event rate measurement for get_metrics
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Time over which `get_metrics` averages commit and repaint rates
+pub const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Counts events and their rate over a sliding time window
+#[derive(Debug)]
+pub struct RateMeter {
+    window: Duration,
+    started_at: Instant,
+    /// Times of the events inside the window, oldest first
+    events: VecDeque<Instant>,
+    total: u64,
+}
+
+impl RateMeter {
+    /// Create a meter that starts counting at `now`
+    pub fn new(window: Duration, now: Instant) -> Self {
+        Self {
+            window,
+            started_at: now,
+            events: VecDeque::new(),
+            total: 0,
+        }
+    }
+
+    /// Record an event that happened at `now`
+    pub fn record(&mut self, now: Instant) {
+        self.events.push_back(now);
+        self.total += 1;
+        self.expire(now);
+    }
+
+    /// Number of events recorded since the meter was created
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Events per second over the window ending at `now`
+    ///
+    /// Until a full window has passed, the rate is taken over the time since
+    /// the meter was created.
+    pub fn rate(&mut self, now: Instant) -> f64 {
+        self.expire(now);
+        let span = now.duration_since(self.started_at).min(self.window);
+        if span.is_zero() {
+            return 0.0;
+        }
+        self.events.len() as f64 / span.as_secs_f64()
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(&oldest) = self.events.front() {
+            if now.duration_since(oldest) <= self.window {
+                break;
+            }
+            self.events.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_over_full_window() {
+        let start = Instant::now();
+        let mut meter = RateMeter::new(Duration::from_secs(1), start);
+
+        // 60 events spread over two seconds; only the last second counts
+        for i in 0..60 {
+            meter.record(start + Duration::from_millis(i * 2000 / 60));
+        }
+        let rate = meter.rate(start + Duration::from_secs(2));
+
+        assert_eq!(meter.total(), 60);
+        assert!((rate - 30.0).abs() <= 1.0, "rate was {}", rate);
+    }
+
+    #[test]
+    fn test_rate_before_full_window() {
+        let start = Instant::now();
+        let mut meter = RateMeter::new(Duration::from_secs(5), start);
+
+        for i in 0..10 {
+            meter.record(start + Duration::from_millis(i * 100));
+        }
+
+        assert_eq!(meter.rate(start + Duration::from_secs(1)), 10.0);
+        assert_eq!(meter.rate(start), 0.0);
+    }
+
+    #[test]
+    fn test_rate_drops_to_zero_when_idle() {
+        let start = Instant::now();
+        let mut meter = RateMeter::new(Duration::from_secs(1), start);
+
+        meter.record(start);
+        assert_eq!(meter.rate(start + Duration::from_secs(3)), 0.0);
+        assert_eq!(meter.total(), 1);
+    }
+}
@@ -81,6 +81,14 @@ impl std::fmt::Display for ClientId {
     }
 }
 
+/// Operating system credentials of a connected client process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientCredentials {
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: i32,
+}
+
 /// Transport error types
 #[derive(Debug, Error)]
 pub enum TransportError {
@@ -95,6 +103,19 @@ pub enum TransportError {
 
     #[error("Initialization error: {0}")]
     InitError(String),
+
+    /// The send would block (e.g. the client's socket buffer is full).
+    /// Nothing was written, so the same data can be retried later.
+    #[error("Send would block: {0}")]
+    WouldBlock(String),
+
+    /// The client went away while sending (e.g. broken pipe)
+    #[error("Client disconnected: {0}")]
+    Disconnected(String),
+
+    /// Sending to some of the clients of a `send_to_clients` call failed
+    #[error("Send failed for {} client(s)", .0.len())]
+    PartialSendError(Vec<(ClientId, TransportError)>),
 }
 
 /// Transport trait for pluggable communication mechanisms
@@ -135,11 +156,22 @@ pub trait Transport: Send + Sync {
     /// * `data` - The raw bytes to send (same data to all clients)
     ///
     /// Implementations may use multicast or unicast depending on the transport.
+    /// Per-client failures should be reported with
+    /// `TransportError::PartialSendError` so callers can retry or drop
+    /// individual clients.
     fn send_to_clients(&self, client_ids: &[&ClientId], data: &[u8]) -> Result<(), TransportError>;
 
     /// Get a list of all currently connected client IDs
     fn get_connected_clients(&self) -> Vec<ClientId>;
 
+    /// Get the credentials of a connected client's process
+    ///
+    /// Returns `None` if the client is unknown or the transport cannot
+    /// identify the peer process.
+    fn client_credentials(&self, _client_id: &ClientId) -> Option<ClientCredentials> {
+        None
+    }
+
     /// Register a message handler for processing incoming messages
     ///
     /// This should be called before `start()`. The handler will be invoked
@@ -172,4 +204,11 @@ pub trait MessageHandler: Send + Sync {
     ///
     /// * `client_id` - The client that disconnected
     fn handle_disconnect(&self, client_id: &ClientId);
+
+    /// Do periodic work on the transport thread
+    ///
+    /// Called on every pass of the transport event loop, whether or not a
+    /// message arrived, so work that must not race the handling of messages
+    /// can be scheduled without a thread of its own. The default does nothing.
+    fn poll(&self) {}
 }
@@ -123,6 +123,8 @@ impl IpconTransport {
                 break;
             }
 
+            handler.poll();
+
             let msg;
             {
                 // Mutex lock fails if another thread panicked while holding the lock, so we handle
@@ -30,6 +30,26 @@
 //!
 //! **Note**: Only one transport can be active at a time.
 
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Default UNIX domain socket path of the plugin and the client
+///
+/// `/tmp/weston-ivi-controller.sock` unless `WESTON_IVI_DEFAULT_SOCKET_PATH`
+/// was set when building.
+pub const DEFAULT_SOCKET_PATH: &str = env!("WESTON_IVI_DEFAULT_SOCKET_PATH");
+
+/// Name of the Linux abstract socket a socket path refers to, if any
+///
+/// A path starting with `@`, such as `@weston-ivi-controller`, names a socket
+/// in the abstract namespace instead of a file. Abstract sockets disappear
+/// with the last open descriptor, so they never leave a stale socket file
+/// behind, and are reachable from every process in the same network
+/// namespace.
+pub fn abstract_socket_name(socket_path: &Path) -> Option<&[u8]> {
+    socket_path.as_os_str().as_bytes().strip_prefix(b"@")
+}
+
 #[cfg(not(feature = "enable-ipcon"))]
 pub mod unix_socket;
 
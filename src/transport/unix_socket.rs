@@ -3,26 +3,89 @@
 #[allow(unused)]
 use jlogger_tracing::{jdebug, jerror, jinfo, jwarn, JloggerBuilder, LevelFilter};
 use std::collections::HashMap;
-use std::io;
-use std::os::unix::net::{UnixListener, UnixStream};
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::net::Shutdown;
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{SocketAddr, UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
 use crate::rpc::framing::{write_frame, FrameReadResult, FrameReader};
-use crate::rpc::transport::{ClientId, MessageHandler, Transport, TransportError};
+use crate::rpc::transport::{
+    ClientCredentials, ClientId, MessageHandler, Transport, TransportError,
+};
+use crate::transport::abstract_socket_name;
+
+/// Bind a listener at a socket path, or in the abstract namespace if the
+/// path starts with `@`
+fn bind_listener(socket_path: &Path) -> io::Result<UnixListener> {
+    match abstract_socket_name(socket_path) {
+        Some(name) => UnixListener::bind_addr(&SocketAddr::from_abstract_name(name)?),
+        None => UnixListener::bind(socket_path),
+    }
+}
+
+/// Map an I/O error from writing a frame to a `TransportError`
+fn send_error(e: io::Error) -> TransportError {
+    match e.kind() {
+        io::ErrorKind::WouldBlock => TransportError::WouldBlock(e.to_string()),
+        io::ErrorKind::BrokenPipe
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::NotConnected => TransportError::Disconnected(e.to_string()),
+        _ => TransportError::SendError(format!("Failed to send frame: {}", e)),
+    }
+}
+
+/// Read the credentials of the process at the other end of a stream (`SO_PEERCRED`)
+fn peer_credentials(stream: &UnixStream) -> io::Result<ClientCredentials> {
+    let mut ucred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    // SAFETY: `ucred` and `len` are valid for writes and `len` holds the size of `ucred`
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut ucred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(ClientCredentials {
+        uid: ucred.uid,
+        gid: ucred.gid,
+        pid: ucred.pid,
+    })
+}
 
 /// Configuration for UNIX domain socket transport
 pub struct UnixSocketConfig {
     pub socket_path: PathBuf,
     pub max_connections: usize,
+    /// Uids allowed to connect; empty allows every uid
+    pub allowed_uids: Vec<u32>,
 }
 
 /// Client connection state
 struct ClientConnection {
     stream: UnixStream,
     frame_reader: FrameReader,
+    credentials: Option<ClientCredentials>,
+    /// Unwritten tail of a partially written frame
+    pending_output: Vec<u8>,
 }
 
 /// Shared state for the transport
@@ -31,6 +94,7 @@ struct TransportState {
     next_client_id: u64,
     handler: Option<Arc<dyn MessageHandler>>,
     running: bool,
+    allowed_uids: Vec<u32>,
 }
 
 /// UNIX domain socket transport implementation
@@ -43,6 +107,7 @@ pub struct UnixSocketTransport {
 impl UnixSocketTransport {
     /// Create a new UNIX socket transport
     pub fn new(config: UnixSocketConfig) -> Self {
+        let allowed_uids = config.allowed_uids.clone();
         Self {
             config,
             state: Arc::new(Mutex::new(TransportState {
@@ -50,6 +115,7 @@ impl UnixSocketTransport {
                 next_client_id: 1,
                 handler: None,
                 running: false,
+                allowed_uids,
             })),
             listener_thread: None,
         }
@@ -62,20 +128,48 @@ impl UnixSocketTransport {
     ) -> io::Result<()> {
         match listener.accept() {
             Ok((stream, _addr)) => {
+                let credentials = match peer_credentials(&stream) {
+                    Ok(credentials) => Some(credentials),
+                    Err(e) => {
+                        jwarn!("Failed to read peer credentials: {}", e);
+                        None
+                    }
+                };
+
+                let mut state_lock = state.lock().unwrap();
+
+                // Reject connections from uids outside the allowlist
+                if !state_lock.allowed_uids.is_empty()
+                    && !credentials.is_some_and(|c| state_lock.allowed_uids.contains(&c.uid))
+                {
+                    drop(state_lock);
+                    jwarn!(
+                        "Rejecting connection from disallowed peer {:?}",
+                        credentials
+                    );
+                    let _ = stream.shutdown(Shutdown::Both);
+                    return Ok(());
+                }
+
                 // Set non-blocking mode
                 stream.set_nonblocking(true)?;
 
-                let mut state_lock = state.lock().unwrap();
                 let client_id = state_lock.next_client_id;
                 state_lock.next_client_id += 1;
 
-                jinfo!("New client connected: {}", client_id);
+                jinfo!(
+                    "New client connected: {} (credentials: {:?})",
+                    client_id,
+                    credentials
+                );
 
                 state_lock.clients.insert(
                     client_id,
                     ClientConnection {
                         stream,
                         frame_reader: FrameReader::new(),
+                        credentials,
+                        pending_output: Vec::new(),
                     },
                 );
 
@@ -129,6 +223,51 @@ impl UnixSocketTransport {
         }
     }
 
+    /// Write as much of the unwritten tail of a frame as the stream takes
+    ///
+    /// Returns `WouldBlock` if part of it is still left.
+    fn flush_pending_output(connection: &mut ClientConnection) -> io::Result<()> {
+        while !connection.pending_output.is_empty() {
+            match connection.stream.write(&connection.pending_output) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+                Ok(n) => {
+                    connection.pending_output.drain(..n);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a complete frame to a non-blocking client stream
+    ///
+    /// Never blocks, as it is called with the transport state locked. If the
+    /// stream takes only part of the frame, the rest is kept and written by
+    /// the event loop. Until then further frames are dropped with
+    /// `WouldBlock`, so frames never interleave; `WouldBlock` therefore
+    /// always means that nothing of `data` was written.
+    fn write_frame_nonblocking(connection: &mut ClientConnection, data: &[u8]) -> io::Result<()> {
+        Self::flush_pending_output(connection)?;
+
+        let mut frame = Vec::with_capacity(4 + data.len());
+        write_frame(&mut frame, data)?;
+
+        let written = loop {
+            match connection.stream.write(&frame) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+
+        if written < frame.len() {
+            connection.pending_output = frame.split_off(written);
+        }
+
+        Ok(())
+    }
+
     /// Main event loop for handling connections
     fn event_loop(listener: UnixListener, state: Arc<Mutex<TransportState>>) {
         listener.set_nonblocking(true).unwrap();
@@ -153,6 +292,15 @@ impl UnixSocketTransport {
                 let mut state_lock = state.lock().unwrap();
 
                 for (&client_id, connection) in state_lock.clients.iter_mut() {
+                    // Finish a frame the client was not ready to take in full
+                    match Self::flush_pending_output(connection) {
+                        Err(e) if e.kind() != io::ErrorKind::WouldBlock => {
+                            disconnected_clients.push(client_id);
+                            continue;
+                        }
+                        _ => {}
+                    }
+
                     match Self::read_from_client(connection) {
                         Ok((alive, messages)) => {
                             // Store messages if any
@@ -204,6 +352,10 @@ impl UnixSocketTransport {
                 }
             }
 
+            if let Some(ref handler) = handler {
+                handler.poll();
+            }
+
             // Small sleep to avoid busy-waiting
             thread::sleep(Duration::from_millis(10));
         }
@@ -217,8 +369,9 @@ impl Transport for UnixSocketTransport {
             self.config.socket_path
         );
 
-        // Remove existing socket file if it exists
-        if self.config.socket_path.exists() {
+        // Remove existing socket file if it exists; abstract sockets have none
+        let is_abstract = abstract_socket_name(&self.config.socket_path).is_some();
+        if !is_abstract && self.config.socket_path.exists() {
             jdebug!("Removing existing socket file");
             std::fs::remove_file(&self.config.socket_path).map_err(|e| {
                 jerror!("Failed to remove existing socket: {}", e);
@@ -227,7 +380,7 @@ impl Transport for UnixSocketTransport {
         }
 
         // Create the UNIX domain socket
-        let listener = UnixListener::bind(&self.config.socket_path).map_err(|e| {
+        let listener = bind_listener(&self.config.socket_path).map_err(|e| {
             jerror!("Failed to bind socket: {}", e);
             TransportError::InitError(format!("Failed to bind socket: {}", e))
         })?;
@@ -268,7 +421,9 @@ impl Transport for UnixSocketTransport {
         }
 
         // Clean up the socket file
-        if self.config.socket_path.exists() {
+        if abstract_socket_name(&self.config.socket_path).is_none()
+            && self.config.socket_path.exists()
+        {
             jdebug!("Removing socket file");
             std::fs::remove_file(&self.config.socket_path).map_err(|e| {
                 jerror!("Failed to remove socket: {}", e);
@@ -292,11 +447,7 @@ impl Transport for UnixSocketTransport {
         })?;
 
         if let Some(connection) = state.clients.get_mut(&client_id) {
-            // Use the shared framing module to write the frame
-            write_frame(&mut connection.stream, data)
-                .map_err(|e| TransportError::SendError(format!("Failed to send frame: {}", e)))?;
-
-            Ok(())
+            Self::write_frame_nonblocking(connection, data).map_err(send_error)
         } else {
             Err(TransportError::SendError(format!(
                 "Client {} not found",
@@ -310,11 +461,10 @@ impl Transport for UnixSocketTransport {
         let mut errors = Vec::new();
 
         for &client_id in client_ids {
-            if let Some(client_id) = client_id.unix_domain_id() {
-                if let Some(connection) = state.clients.get_mut(&client_id) {
-                    // Use the shared framing module (best-effort delivery)
-                    if let Err(e) = write_frame(&mut connection.stream, data) {
-                        errors.push((client_id, e));
+            if let Some(id) = client_id.unix_domain_id() {
+                if let Some(connection) = state.clients.get_mut(&id) {
+                    if let Err(e) = Self::write_frame_nonblocking(connection, data) {
+                        errors.push((client_id.clone(), send_error(e)));
                     }
                 }
             } else {
@@ -328,9 +478,8 @@ impl Transport for UnixSocketTransport {
         if errors.is_empty() {
             Ok(())
         } else {
-            // Log errors but don't fail - best-effort delivery
             jdebug!("Failed to send to {} clients", errors.len());
-            Ok(())
+            Err(TransportError::PartialSendError(errors))
         }
     }
 
@@ -343,6 +492,14 @@ impl Transport for UnixSocketTransport {
             .collect()
     }
 
+    fn client_credentials(&self, client_id: &ClientId) -> Option<ClientCredentials> {
+        let state = self.state.lock().unwrap();
+        state
+            .clients
+            .get(&client_id.unix_domain_id()?)
+            .and_then(|connection| connection.credentials)
+    }
+
     fn register_handler(&mut self, handler: Box<dyn MessageHandler>) {
         let mut state = self.state.lock().unwrap();
         state.handler = Some(Arc::from(handler));
@@ -352,7 +509,7 @@ impl Transport for UnixSocketTransport {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::rpc::transport::{ClientId, MessageHandler};
+    use crate::rpc::transport::{ClientId, MessageHandler, TransportError};
     use std::io::Write;
     use std::os::unix::net::UnixStream;
     use std::sync::{Arc, Mutex};
@@ -378,6 +535,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unix_socket_abstract() {
+        let name = format!("test_ivi_socket_abstract_{}", std::process::id());
+        let config = UnixSocketConfig {
+            socket_path: PathBuf::from(format!("@{}", name)),
+            max_connections: 10,
+            allowed_uids: Vec::new(),
+        };
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let handler = TestHandler {
+            messages: Arc::clone(&messages),
+            disconnects: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        let mut transport = UnixSocketTransport::new(config);
+        transport.register_handler(Box::new(handler));
+        transport.start().expect("Failed to start transport");
+        thread::sleep(Duration::from_millis(100));
+
+        // No socket file is created
+        assert!(!Path::new(&format!("@{}", name)).exists());
+
+        let addr = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+        let mut client = UnixStream::connect_addr(&addr).expect("Failed to connect");
+        write_frame(&mut client, b"abstract").expect("Failed to write message");
+        thread::sleep(Duration::from_millis(100));
+
+        assert_eq!(messages.lock().unwrap()[0].1, b"abstract");
+
+        drop(client);
+        transport.stop().expect("Failed to stop transport");
+
+        // The name is released once the listener is closed
+        assert!(UnixStream::connect_addr(&addr).is_err());
+    }
+
     #[test]
     fn test_unix_socket_basic() {
         let socket_path = PathBuf::from("/tmp/test_ivi_socket_basic");
@@ -388,6 +582,7 @@ mod tests {
         let config = UnixSocketConfig {
             socket_path: socket_path.clone(),
             max_connections: 10,
+            allowed_uids: Vec::new(),
         };
 
         let messages = Arc::new(Mutex::new(Vec::new()));
@@ -454,6 +649,7 @@ mod tests {
         let config = UnixSocketConfig {
             socket_path: socket_path.clone(),
             max_connections: 10,
+            allowed_uids: Vec::new(),
         };
 
         let messages = Arc::new(Mutex::new(Vec::new()));
@@ -503,4 +699,145 @@ mod tests {
         // Clean up
         let _ = std::fs::remove_file(&socket_path);
     }
+
+    #[test]
+    fn test_send_would_block_when_buffer_full() {
+        let socket_path = PathBuf::from("/tmp/test_ivi_socket_would_block");
+
+        // Clean up any existing socket
+        let _ = std::fs::remove_file(&socket_path);
+
+        let config = UnixSocketConfig {
+            socket_path: socket_path.clone(),
+            max_connections: 10,
+            allowed_uids: Vec::new(),
+        };
+
+        let mut transport = UnixSocketTransport::new(config);
+        transport.start().expect("Failed to start transport");
+        thread::sleep(Duration::from_millis(100));
+
+        // Connect a client that never reads
+        let _client = UnixStream::connect(&socket_path).expect("Failed to connect");
+        thread::sleep(Duration::from_millis(100));
+
+        let client_id = transport.get_connected_clients()[0].clone();
+
+        // Keep sending small frames until the socket buffer is full
+        let mut result = Ok(());
+        for _ in 0..100_000 {
+            result = transport.send(&client_id, b"notification");
+            if result.is_err() {
+                break;
+            }
+        }
+        assert!(matches!(result, Err(TransportError::WouldBlock(_))));
+
+        // The same failure is reported per client by send_to_clients
+        match transport.send_to_clients(&[&client_id], b"notification") {
+            Err(TransportError::PartialSendError(failures)) => {
+                assert_eq!(failures.len(), 1);
+                assert!(matches!(failures[0].1, TransportError::WouldBlock(_)));
+            }
+            other => panic!("Expected PartialSendError, got {:?}", other),
+        }
+
+        transport.stop().expect("Failed to stop transport");
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn test_partial_frame_is_completed_by_event_loop() {
+        let socket_path = PathBuf::from("/tmp/test_ivi_socket_partial_frame");
+        let _ = std::fs::remove_file(&socket_path);
+
+        let config = UnixSocketConfig {
+            socket_path: socket_path.clone(),
+            max_connections: 10,
+            allowed_uids: Vec::new(),
+        };
+        let mut transport = UnixSocketTransport::new(config);
+        transport.start().expect("Failed to start transport");
+        thread::sleep(Duration::from_millis(100));
+
+        let mut client = UnixStream::connect(&socket_path).expect("Failed to connect");
+        thread::sleep(Duration::from_millis(100));
+        let client_id = transport.get_connected_clients()[0].clone();
+
+        // The frame does not fit in the socket buffer, yet send returns at once
+        let large = vec![b'x'; 4 * 1024 * 1024];
+        transport.send(&client_id, &large).unwrap();
+
+        // A frame sent before the rest has been written is dropped
+        assert!(matches!(
+            transport.send(&client_id, b"dropped"),
+            Err(TransportError::WouldBlock(_))
+        ));
+
+        let mut reader = FrameReader::new();
+        let mut read_frame = || loop {
+            match reader.read_frame(&mut client).unwrap() {
+                FrameReadResult::Complete(frame) => break frame,
+                FrameReadResult::NeedMore => continue,
+                FrameReadResult::Eof => panic!("transport closed the connection"),
+            }
+        };
+        assert_eq!(read_frame(), large);
+
+        transport.send(&client_id, b"next").unwrap();
+        assert_eq!(read_frame(), b"next");
+
+        transport.stop().expect("Failed to stop transport");
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn test_client_credentials_and_uid_allowlist() {
+        let socket_path = PathBuf::from("/tmp/test_ivi_socket_peercred");
+        let _ = std::fs::remove_file(&socket_path);
+
+        // SAFETY: getuid/getpid have no preconditions
+        let (uid, pid) = unsafe { (libc::getuid(), libc::getpid()) };
+
+        let config = UnixSocketConfig {
+            socket_path: socket_path.clone(),
+            max_connections: 10,
+            allowed_uids: vec![uid],
+        };
+        let mut transport = UnixSocketTransport::new(config);
+        transport.start().expect("Failed to start transport");
+        thread::sleep(Duration::from_millis(100));
+
+        let _client = UnixStream::connect(&socket_path).expect("Failed to connect");
+        thread::sleep(Duration::from_millis(100));
+
+        let clients = transport.get_connected_clients();
+        assert_eq!(clients.len(), 1);
+        let credentials = transport.client_credentials(&clients[0]).unwrap();
+        assert_eq!(credentials.uid, uid);
+        assert_eq!(credentials.pid, pid);
+
+        transport.stop().expect("Failed to stop transport");
+
+        // A transport that does not allow our uid closes the connection right away
+        let config = UnixSocketConfig {
+            socket_path: socket_path.clone(),
+            max_connections: 10,
+            allowed_uids: vec![uid.wrapping_add(1)],
+        };
+        let mut transport = UnixSocketTransport::new(config);
+        transport.start().expect("Failed to start transport");
+        thread::sleep(Duration::from_millis(100));
+
+        let mut client = UnixStream::connect(&socket_path).expect("Failed to connect");
+        client
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        let mut buf = [0u8; 1];
+        assert_eq!(std::io::Read::read(&mut client, &mut buf).unwrap(), 0);
+        assert!(transport.get_connected_clients().is_empty());
+
+        transport.stop().expect("Failed to stop transport");
+        let _ = std::fs::remove_file(&socket_path);
+    }
 }